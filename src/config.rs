@@ -38,6 +38,9 @@ pub struct Config {
     #[serde(default)]
     pub doh: DohConfig,
 
+    #[serde(default)]
+    pub doq: DoqConfig,
+
     #[serde(default)]
     pub forwarder: Vec<ForwarderConfig>,
 
@@ -129,6 +132,9 @@ impl Config {
         if self.limits.max_udp_packet_size == 0 || self.limits.max_udp_packet_size > 65535 {
             return Err(SCloudException::SCLOUD_CONFIG_INVALID_DNS_LIMITS);
         }
+        if self.server.tcp_idle_timeout_secs == Some(0) {
+            return Err(SCloudException::SCLOUD_CONFIG_INVALID_DNS_LIMITS);
+        }
 
         let mut listener_names = HashSet::new();
         for l in &self.listener {
@@ -141,6 +147,7 @@ impl Config {
             if l.port == 0 {
                 return Err(SCloudException::SCLOUD_CONFIG_INVALID_LISTENER_PORT);
             }
+            l.socket_addr()?;
             if l.protocols.is_empty() {
                 return Err(SCloudException::SCLOUD_CONFIG_INVALID_LISTENER_PROTOCOLS);
             }
@@ -165,6 +172,9 @@ impl Config {
             if self.doh.paths.is_empty() {
                 return Err(SCloudException::SCLOUD_CONFIG_INVALID_DOH);
             }
+            if self.doh.max_concurrent_streams == Some(0) || self.doh.idle_timeout_secs == Some(0) {
+                return Err(SCloudException::SCLOUD_CONFIG_INVALID_DOH);
+            }
             if self.doh.terminate_tls {
                 if self
                     .doh
@@ -189,6 +199,35 @@ impl Config {
             }
         }
 
+        if self.doq.enabled {
+            if self.doq.bind.parse::<std::net::SocketAddr>().is_err() {
+                return Err(SCloudException::SCLOUD_CONFIG_INVALID_DOQ);
+            }
+            if self.doq.max_concurrent_streams == Some(0) || self.doq.idle_timeout_secs == Some(0) {
+                return Err(SCloudException::SCLOUD_CONFIG_INVALID_DOQ);
+            }
+            if self
+                .doq
+                .tls_cert_path
+                .as_deref()
+                .unwrap_or("")
+                .trim()
+                .is_empty()
+            {
+                return Err(SCloudException::SCLOUD_CONFIG_TLS_MISSING_CERT);
+            }
+            if self
+                .doq
+                .tls_key_path
+                .as_deref()
+                .unwrap_or("")
+                .trim()
+                .is_empty()
+            {
+                return Err(SCloudException::SCLOUD_CONFIG_TLS_MISSING_KEY);
+            }
+        }
+
         if self.recursion.enabled {
             if self.recursion.allowed_acl.trim().is_empty() {
                 return Err(SCloudException::SCLOUD_CONFIG_UNKNOWN_ACL_REFERENCE);
@@ -340,6 +379,41 @@ impl Config {
         Ok(())
     }
 
+    /// Listeners to bind: the `listener` entries, or UDP+TCP listeners on
+    /// `server.bind_port` of every IPv4 and IPv6 address when there is none.
+    pub(crate) fn listeners(&self) -> Vec<ListenerConfig> {
+        if !self.listener.is_empty() {
+            return self.listener.clone();
+        }
+        [("default-v4", "0.0.0.0"), ("default-v6", "::")]
+            .into_iter()
+            .map(|(name, address)| ListenerConfig {
+                name: name.to_string(),
+                address: address.to_string(),
+                port: self.server.bind_port,
+                protocols: vec![Protocol::UDP, Protocol::TCP],
+                recursion_allowed: true,
+                acl: String::new(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// UDP payload size advertised in the OPT record of responses:
+    /// `server.max_udp_payload` capped by
+    /// `amplification_mitigation.max_response_size_udp`, or `None` when
+    /// EDNS is disabled.
+    pub(crate) fn edns_udp_size(&self) -> Option<u16> {
+        if !self.server.enable_edns {
+            return None;
+        }
+        let size = self
+            .server
+            .max_udp_payload
+            .min(self.amplification_mitigation.max_response_size_udp);
+        Some(u16::try_from(size).unwrap_or(u16::MAX))
+    }
+
     /// Get the address of a specific forwarder by index value
     #[allow(unused)]
     pub(crate) fn try_get_forwarder_addr_by_index(
@@ -392,6 +466,7 @@ impl Default for Config {
             acl: Vec::new(),
             listener: Vec::new(),
             doh: DohConfig::default(),
+            doq: DoqConfig::default(),
             forwarder: Vec::new(),
             root_hints: RootHintsConfig::default(),
             cache: CacheConfig::default(),
@@ -425,6 +500,9 @@ pub struct ServerConfig {
     pub enable_edns: bool,
     pub enable_tcp: bool,
     pub enable_dnssec: bool,
+    /// seconds without a query before a TCP or DoT connection is closed
+    #[serde(default)]
+    pub tcp_idle_timeout_secs: Option<u64>,
 
     pub bind_port: u16,
 }
@@ -442,6 +520,7 @@ impl Default for ServerConfig {
             enable_edns: true,
             enable_tcp: true,
             enable_dnssec: false,
+            tcp_idle_timeout_secs: None,
             bind_port: 53,
         }
     }
@@ -452,6 +531,8 @@ pub struct WorkersConfig {
     pub tcp_acceptor: u16,
     #[serde(default)]
     pub doh_acceptor: u16,
+    #[serde(default)]
+    pub doq_acceptor: u16,
     pub decoder: u16,
     pub query_dispatcher: u16,
     pub cache_lookup: u16,
@@ -469,6 +550,7 @@ impl Default for WorkersConfig {
         WorkersConfig {
             tcp_acceptor: 1,
             doh_acceptor: 1,
+            doq_acceptor: 1,
             decoder: 5,
             query_dispatcher: 3,
             cache_lookup: 3,
@@ -628,6 +710,30 @@ pub struct ListenerConfig {
     pub tls_key_path: Option<String>,
 }
 
+impl ListenerConfig {
+    /// Address and port to bind. IPv6 addresses may be written with or
+    /// without brackets (`::1` or `[::1]`).
+    pub(crate) fn socket_addr(&self) -> Result<std::net::SocketAddr, SCloudException> {
+        let ip = self
+            .address
+            .trim()
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<std::net::IpAddr>()
+            .map_err(|_| SCloudException::SCLOUD_CONFIG_INVALID_LISTENER_ADDRESS)?;
+        Ok(std::net::SocketAddr::new(ip, self.port))
+    }
+
+    pub(crate) fn has_protocol(&self, protocol: Protocol) -> bool {
+        self.protocols.contains(&protocol)
+    }
+
+    /// Whether the listener serves DNS over TLS rather than plain DNS.
+    pub(crate) fn is_tls(&self) -> bool {
+        self.enable_tls.unwrap_or(false)
+    }
+}
+
 impl Default for ListenerConfig {
     fn default() -> Self {
         ListenerConfig {
@@ -645,7 +751,7 @@ impl Default for ListenerConfig {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Protocol {
     UDP,
@@ -664,8 +770,17 @@ pub struct DohConfig {
     pub tls_key_path: Option<String>,
     #[serde(default)]
     pub paths: Vec<String>,
+    /// paths answering `GET ?name=&type=` with `application/dns-json`
+    #[serde(default)]
+    pub json_paths: Vec<String>,
     #[serde(default)]
     pub allowed_origins: Vec<String>,
+    /// HTTP/2 streams a client may have open at once on one connection
+    #[serde(default)]
+    pub max_concurrent_streams: Option<u32>,
+    /// seconds without traffic before a connection is closed
+    #[serde(default)]
+    pub idle_timeout_secs: Option<u64>,
 }
 
 impl Default for DohConfig {
@@ -677,7 +792,39 @@ impl Default for DohConfig {
             tls_cert_path: None,
             tls_key_path: None,
             paths: vec!["/dns-query".to_string()],
+            json_paths: vec!["/resolve".to_string()],
             allowed_origins: Vec::new(),
+            max_concurrent_streams: None,
+            idle_timeout_secs: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DoqConfig {
+    pub enabled: bool,
+    pub bind: String,
+    #[serde(default)]
+    pub tls_cert_path: Option<String>,
+    #[serde(default)]
+    pub tls_key_path: Option<String>,
+    /// query streams a client may have open at once on one connection
+    #[serde(default)]
+    pub max_concurrent_streams: Option<u32>,
+    /// seconds without traffic before a connection is closed
+    #[serde(default)]
+    pub idle_timeout_secs: Option<u64>,
+}
+
+impl Default for DoqConfig {
+    fn default() -> Self {
+        DoqConfig {
+            enabled: false,
+            bind: "0.0.0.0:853".to_string(),
+            tls_cert_path: None,
+            tls_key_path: None,
+            max_concurrent_streams: None,
+            idle_timeout_secs: None,
         }
     }
 }
@@ -841,7 +988,7 @@ pub struct ZoneConfig {
     #[serde(default)]
     pub forwarders: Vec<String>,
     #[serde(default)]
-    pub forward_policy: Option<String>,
+    pub forward_policy: Option<ForwardPolicy>,
 }
 
 impl Default for ZoneConfig {
@@ -1,13 +1,25 @@
+use crate::config::CacheConfig;
+use crate::dns::packet::DNSPacket;
+use crate::dns::packet::additional::AdditionalSection;
+use crate::dns::packet::answer::AnswerSection;
+use crate::dns::packet::authority::AuthoritySection;
+use crate::dns::packet::header::Header;
+use crate::dns::q_class::DNSClass;
 use crate::dns::q_type::DNSRecordType;
-use crate::dns::records::DNSRecord;
+use crate::dns::records::rdata::RData;
+use crate::log_warn;
+use dashmap::DashMap;
+use once_cell::sync::OnceCell;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
 
-use crate::dns::q_class::DNSClass;
-use std::time::Instant;
+/// EDNS(0) pseudo-record, never cached (RFC 6891 section 6.1.1).
+const OPT_TYPE: DNSRecordType = DNSRecordType::OPT;
+
+static SHARED: OnceCell<Arc<DnsCache>> = OnceCell::new();
 
-// TODO: light LRU system
-// Should make a real LRU system for v2 (handle millions of entries)
-// Should link them in a HashMap (max_entries: 150_000)
-#[allow(unused)]
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub(crate) struct CacheKey {
     pub name: String,
@@ -15,10 +27,384 @@ pub(crate) struct CacheKey {
     pub rclass: DNSClass,
 }
 
-#[allow(unused)]
+impl CacheKey {
+    /// Key of the first question of `packet`.
+    ///
+    /// Names are compared case-insensitively (RFC 4343), so the name is
+    /// lowercased and stored without its trailing dot.
+    pub(crate) fn from_question(packet: &DNSPacket) -> Option<CacheKey> {
+        let question = packet.questions.first()?;
+        Some(CacheKey {
+            name: question.q_name.trim_end_matches('.').to_ascii_lowercase(),
+            rtype: question.q_type,
+            rclass: question.q_class,
+        })
+    }
+}
+
+/// A cached response, without its header and question.
 #[derive(Clone, Debug)]
 pub(crate) struct CacheEntry {
-    pub records: Vec<DNSRecord>,
+    pub rcode: u8,
+    pub aa: bool,
+    pub ra: bool,
+    pub answers: Vec<AnswerSection>,
+    pub authorities: Vec<AuthoritySection>,
+    pub additionals: Vec<AdditionalSection>,
+    pub inserted_at: Instant,
     pub expires_at: Instant,
     pub last_access: Instant,
+    tick: u64, // key of the entry in the LRU index
+}
+
+impl CacheEntry {
+    pub(crate) fn is_expired(&self, now: Instant) -> bool {
+        now >= self.expires_at
+    }
+}
+
+/// How the cache makes room once `max_entries` is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EvictionPolicy {
+    /// Exact LRU: every hit moves the entry to the back of an ordered index,
+    /// and each insertion into a full cache evicts the front one.
+    Lru,
+    /// Approximate LRU: hits only stamp the entry, and a full cache drops
+    /// its least recently used eighth in a single pass. Cheaper under
+    /// contention, at the cost of evicting in batches.
+    LightLru,
+}
+
+impl EvictionPolicy {
+    /// Parse the `cache.eviction_policy` value of the configuration.
+    pub(crate) fn parse(value: &str) -> Option<EvictionPolicy> {
+        match value.to_ascii_lowercase().as_str() {
+            "lru" => Some(EvictionPolicy::Lru),
+            "light-lru" => Some(EvictionPolicy::LightLru),
+            _ => None,
+        }
+    }
+}
+
+/// Response cache shared by the CACHE_LOOKUP and CACHE_WRITER workers.
+///
+/// Entries live in a sharded map, so lookups of different names do not
+/// contend. An entry lives for the smallest TTL of its records, capped by
+/// `max_ttl_seconds`.
+#[derive(Debug)]
+pub(crate) struct DnsCache {
+    enabled: bool,
+    max_entries: usize,
+    max_ttl: u32,
+    negative_ttl: u32, // 0 disables negative caching
+    policy: EvictionPolicy,
+    entries: DashMap<CacheKey, CacheEntry>,
+    lru: Mutex<BTreeMap<u64, CacheKey>>, // `Lru` policy only, oldest first
+    clock: AtomicU64,
+    evictions: AtomicU64,
+    expirations: AtomicU64,
+}
+
+impl DnsCache {
+    pub(crate) fn new(cfg: &CacheConfig) -> DnsCache {
+        let policy = EvictionPolicy::parse(&cfg.eviction_policy).unwrap_or_else(|| {
+            log_warn!(
+                "cache: unknown eviction policy {:?}, using lru",
+                cfg.eviction_policy
+            );
+            EvictionPolicy::Lru
+        });
+        DnsCache {
+            enabled: cfg.enabled && cfg.max_entries > 0,
+            max_entries: cfg.max_entries,
+            max_ttl: u32::try_from(cfg.max_ttl_seconds).unwrap_or(u32::MAX),
+            negative_ttl: u32::try_from(cfg.negative_ttl_seconds).unwrap_or(u32::MAX),
+            policy,
+            entries: DashMap::new(),
+            lru: Mutex::new(BTreeMap::new()),
+            clock: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+            expirations: AtomicU64::new(0),
+        }
+    }
+
+    /// The cache of the process, built from `cfg` on first use.
+    pub(crate) fn shared(cfg: &CacheConfig) -> Arc<DnsCache> {
+        SHARED.get_or_init(|| Arc::new(DnsCache::new(cfg))).clone()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn max_entries(&self) -> usize {
+        self.max_entries
+    }
+
+    /// Entries dropped to make room since the cache was created.
+    pub(crate) fn evictions(&self) -> u64 {
+        self.evictions.load(Ordering::Relaxed)
+    }
+
+    /// Entries dropped because their TTL ran out since the cache was created.
+    pub(crate) fn expirations(&self) -> u64 {
+        self.expirations.load(Ordering::Relaxed)
+    }
+
+    #[allow(unused)]
+    pub(crate) fn policy(&self) -> EvictionPolicy {
+        self.policy
+    }
+
+    /// Answer `query` from the cache.
+    ///
+    /// The response gets the ID and question of `query`, and the TTL of
+    /// every record is decremented by the time spent in the cache. A cached
+    /// NXDOMAIN or NODATA comes back with its RCODE and the zone SOA in the
+    /// authority section. Expired entries are removed on the way.
+    pub(crate) fn get(&self, query: &DNSPacket) -> Option<DNSPacket> {
+        if !self.enabled {
+            return None;
+        }
+        let key = CacheKey::from_question(query)?;
+        let now = Instant::now();
+
+        let mut entry = self.entries.get_mut(&key)?;
+        if entry.is_expired(now) {
+            let tick = entry.tick;
+            drop(entry);
+            if self
+                .entries
+                .remove_if(&key, |_, e| e.is_expired(now))
+                .is_some()
+            {
+                self.index().remove(&tick);
+                self.expirations.fetch_add(1, Ordering::Relaxed);
+            }
+            return None;
+        }
+
+        entry.last_access = now;
+        if self.policy == EvictionPolicy::Lru {
+            let tick = self.next_tick();
+            let mut index = self.index();
+            index.remove(&entry.tick);
+            index.insert(tick, key);
+            entry.tick = tick;
+        }
+
+        let elapsed = now.duration_since(entry.inserted_at).as_secs();
+        let elapsed = u32::try_from(elapsed).unwrap_or(u32::MAX);
+
+        let mut response = DNSPacket::new_response(query, entry.rcode);
+        response.header.aa = entry.aa;
+        response.header.ra = entry.ra;
+        response.answers = entry.answers.clone();
+        response.authorities = entry.authorities.clone();
+        response.additionals = entry.additionals.clone();
+        drop(entry);
+
+        for answer in response.answers.iter_mut() {
+            answer.ttl = answer.ttl.saturating_sub(elapsed);
+        }
+        for authority in response.authorities.iter_mut() {
+            authority.ttl = authority.ttl.saturating_sub(elapsed);
+        }
+        for additional in response.additionals.iter_mut() {
+            additional.ttl = additional.ttl.saturating_sub(elapsed);
+        }
+        response.sync_counts();
+        Some(response)
+    }
+
+    /// Store `response` under the key of its question.
+    ///
+    /// Positive answers live for their smallest TTL, capped by
+    /// `max_ttl_seconds`. NXDOMAIN and NODATA answers are cached as RFC 2308
+    /// describes: they need the zone SOA in the authority section and live
+    /// for the smallest of its TTL and MINIMUM field, capped by
+    /// `negative_ttl_seconds`. A truncated response, any other RCODE or a
+    /// zero TTL is skipped. Returns whether the response was stored.
+    pub(crate) fn insert(&self, response: &DNSPacket) -> bool {
+        if !self.enabled || !response.header.qr || response.header.tc {
+            return false;
+        }
+        let negative = match response.header.rcode {
+            Header::RCODE_NXDOMAIN => true,
+            Header::RCODE_NOERROR => response.answers.is_empty(),
+            _ => return false,
+        };
+        let Some(key) = CacheKey::from_question(response) else {
+            return false;
+        };
+
+        let mut additionals: Vec<AdditionalSection> = response
+            .additionals
+            .iter()
+            .filter(|a| a.q_type != OPT_TYPE && !negative)
+            .cloned()
+            .collect();
+        let cap = if negative {
+            match negative_ttl(response) {
+                Some(ttl) => ttl.min(self.negative_ttl),
+                None => return false,
+            }
+        } else {
+            self.max_ttl
+        };
+        let ttl = response
+            .answers
+            .iter()
+            .map(|a| a.ttl)
+            .chain(response.authorities.iter().map(|a| a.ttl))
+            .chain(additionals.iter().map(|a| a.ttl))
+            .min()
+            .unwrap_or(0)
+            .min(cap);
+        if ttl == 0 {
+            return false;
+        }
+
+        if !self.entries.contains_key(&key) {
+            self.make_room();
+        }
+
+        // clients must not keep the records longer than we do
+        let mut answers = response.answers.clone();
+        answers.iter_mut().for_each(|a| a.ttl = a.ttl.min(cap));
+        let mut authorities = response.authorities.clone();
+        authorities.iter_mut().for_each(|a| a.ttl = a.ttl.min(cap));
+        additionals.iter_mut().for_each(|a| a.ttl = a.ttl.min(cap));
+
+        let now = Instant::now();
+        let tick = self.next_tick();
+        let entry = CacheEntry {
+            rcode: response.header.rcode,
+            aa: response.header.aa,
+            ra: response.header.ra,
+            answers,
+            authorities,
+            additionals,
+            inserted_at: now,
+            expires_at: now + Duration::from_secs(ttl as u64),
+            last_access: now,
+            tick,
+        };
+        let previous = self.entries.insert(key.clone(), entry);
+        if self.policy == EvictionPolicy::Lru {
+            let mut index = self.index();
+            if let Some(previous) = previous {
+                index.remove(&previous.tick);
+            }
+            index.insert(tick, key);
+        }
+        true
+    }
+
+    /// Drop every expired entry and return how many were dropped.
+    pub(crate) fn remove_expired(&self) -> usize {
+        let now = Instant::now();
+        let mut ticks = Vec::new();
+        self.entries.retain(|_, e| {
+            if e.is_expired(now) {
+                ticks.push(e.tick);
+                return false;
+            }
+            true
+        });
+        if self.policy == EvictionPolicy::Lru && !ticks.is_empty() {
+            let mut index = self.index();
+            for tick in &ticks {
+                index.remove(tick);
+            }
+        }
+        self.expirations
+            .fetch_add(ticks.len() as u64, Ordering::Relaxed);
+        ticks.len()
+    }
+
+    /// Evict the least recently used entries until at most `target` are
+    /// left, and return how many were evicted.
+    pub(crate) fn shrink_to(&self, target: usize) -> usize {
+        let evicted = match self.policy {
+            EvictionPolicy::Lru => {
+                let mut evicted = 0;
+                while self.entries.len() > target {
+                    // the index lock is released before touching the map
+                    let Some((tick, key)) = self.index().pop_first() else {
+                        break;
+                    };
+                    if self
+                        .entries
+                        .remove_if(&key, |_, e| e.tick == tick)
+                        .is_some()
+                    {
+                        evicted += 1;
+                    }
+                }
+                evicted
+            }
+            EvictionPolicy::LightLru => {
+                self.evict_least_recent(self.entries.len().saturating_sub(target))
+            }
+        };
+        self.evictions.fetch_add(evicted as u64, Ordering::Relaxed);
+        evicted
+    }
+
+    /// Evict entries until there is room for a new one.
+    fn make_room(&self) {
+        if self.entries.len() < self.max_entries {
+            return;
+        }
+        match self.policy {
+            EvictionPolicy::Lru => self.shrink_to(self.max_entries - 1),
+            EvictionPolicy::LightLru => {
+                let batch = (self.max_entries / 8).max(1);
+                self.shrink_to(self.max_entries.saturating_sub(batch))
+            }
+        };
+    }
+
+    /// Drop the `count` least recently used entries in one pass and return
+    /// how many were dropped.
+    fn evict_least_recent(&self, count: usize) -> usize {
+        if count == 0 {
+            return 0;
+        }
+        let mut accesses: Vec<(Instant, CacheKey)> = self
+            .entries
+            .iter()
+            .map(|e| (e.last_access, e.key().clone()))
+            .collect();
+        if accesses.len() > count {
+            accesses.select_nth_unstable_by_key(count, |(at, _)| *at);
+            accesses.truncate(count);
+        }
+        accesses
+            .into_iter()
+            .filter(|(_, key)| self.entries.remove(key).is_some())
+            .count()
+    }
+
+    fn next_tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn index(&self) -> MutexGuard<'_, BTreeMap<u64, CacheKey>> {
+        self.lru.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// TTL of a negative answer: the smallest of the SOA TTL and of its MINIMUM
+/// field (RFC 2308 section 5), or `None` without a SOA to take it from.
+fn negative_ttl(response: &DNSPacket) -> Option<u32> {
+    let soa = response
+        .authorities
+        .iter()
+        .find(|a| a.q_type == DNSRecordType::SOA)?;
+    let RData::SOA { minimum, .. } = soa.rdata else {
+        return None;
+    };
+    Some(soa.ttl.min(minimum))
 }
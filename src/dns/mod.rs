@@ -1,9 +1,9 @@
-mod cache;
+pub(crate) mod cache;
 pub(crate) mod packet;
 pub(crate) mod q_class;
 pub(crate) mod q_name;
 pub(crate) mod q_type;
 pub(crate) mod records;
 pub(crate) mod resolver;
-mod tests;
+pub(crate) mod tests;
 pub(crate) mod zones;
@@ -1,6 +1,8 @@
+use crate::dns::packet::writer::MessageWriter;
 use crate::dns::q_class::DNSClass;
 use crate::dns::q_name::parse_qname;
 use crate::dns::q_type::DNSRecordType;
+use crate::dns::records::rdata::RData;
 use crate::exceptions::SCloudException;
 
 #[derive(PartialEq, Debug, Clone)]
@@ -9,8 +11,7 @@ pub(crate) struct AdditionalSection {
     pub(crate) q_type: DNSRecordType,
     pub(crate) q_class: DNSClass,
     pub(crate) ttl: u32,
-    pub rdlength: u16,
-    pub rdata: Vec<u8>,
+    pub(crate) rdata: RData,
 }
 
 impl AdditionalSection {
@@ -21,6 +22,7 @@ impl AdditionalSection {
     /// use crate::dns::packet::additional::AdditionalSection;
     /// use crate::dns::q_type::DNSRecordType;
     /// use crate::dns::q_class::DNSClass;
+    /// use crate::dns::records::rdata::RData;
     ///
     /// // Record A in additional section (ns1.example.com → 192.0.2.1)
     /// let raw_additional: Vec<u8> = vec![
@@ -42,40 +44,40 @@ impl AdditionalSection {
     /// assert_eq!(additional.q_type, DNSRecordType::A);
     /// assert_eq!(additional.q_class, DNSClass::IN);
     /// assert_eq!(additional.ttl, 300);
-    /// assert_eq!(additional.rdata, vec![192, 0, 2, 1]);
+    /// assert_eq!(additional.rdata, RData::A("192.0.2.1".parse().unwrap()));
     /// assert_eq!(consumed, raw_additional.len());
     /// ```
     pub(crate) fn from_bytes(
         buf: &[u8],
         offset: usize,
     ) -> Result<(AdditionalSection, usize), SCloudException> {
-        let (q_name, consumed_name) = parse_qname(buf, offset).unwrap();
+        let (q_name, consumed_name) = parse_qname(buf, offset)?;
         let mut pos = consumed_name;
 
         if buf.len() < pos + 10 {
             return Err(SCloudException::SCLOUD_ADDITIONAL_DESERIALIZATION_FAILED_BUF_TOO_SHORT);
         }
 
-        let q_type = DNSRecordType::try_from(u16::from_be_bytes([buf[pos], buf[pos + 1]])).unwrap();
+        let q_type = DNSRecordType::try_from(u16::from_be_bytes([buf[pos], buf[pos + 1]]))?;
         pos += 2;
 
-        let q_class = DNSClass::try_from(u16::from_be_bytes([buf[pos], buf[pos + 1]])).unwrap();
+        let q_class = DNSClass::try_from(u16::from_be_bytes([buf[pos], buf[pos + 1]]))?;
         pos += 2;
 
         let ttl = u32::from_be_bytes([buf[pos], buf[pos + 1], buf[pos + 2], buf[pos + 3]]);
         pos += 4;
 
-        let rdlength = u16::from_be_bytes([buf[pos], buf[pos + 1]]);
+        let rdlength = u16::from_be_bytes([buf[pos], buf[pos + 1]]) as usize;
         pos += 2;
 
-        if buf.len() < pos + rdlength as usize {
+        if buf.len() < pos + rdlength {
             return Err(
                 SCloudException::SCLOUD_ADDITIONAL_DESERIALIZATION_FAILED_RDATA_OUT_OF_BOUNDS,
             );
         }
 
-        let rdata = buf[pos..pos + rdlength as usize].to_vec();
-        pos += rdlength as usize;
+        let rdata = RData::decode(q_type, buf, pos, rdlength)?;
+        pos += rdlength;
 
         Ok((
             AdditionalSection {
@@ -83,10 +85,9 @@ impl AdditionalSection {
                 q_type,
                 q_class,
                 ttl,
-                rdlength,
                 rdata,
             },
-            pos,
+            pos - offset,
         ))
     }
 
@@ -97,14 +98,14 @@ impl AdditionalSection {
     /// use crate::dns::packet::additional::AdditionalSection;
     /// use crate::dns::q_type::DNSRecordType;
     /// use crate::dns::q_class::DNSClass;
+    /// use crate::dns::records::rdata::RData;
     ///
     /// let additional = AdditionalSection {
     ///     q_name: "ns1.example.com".to_string(),
     ///     q_type: DNSRecordType::A,
     ///     q_class: DNSClass::IN,
     ///     ttl: 300,
-    ///     rdlength: 4,
-    ///     rdata: vec![192, 0, 2, 1],
+    ///     rdata: RData::A("192.0.2.1".parse().unwrap()),
     /// };
     ///
     /// let bytes = additional.to_bytes().unwrap();
@@ -112,32 +113,26 @@ impl AdditionalSection {
     /// // NAME + TYPE + CLASS + TTL + RDLENGTH + RDATA
     /// assert!(bytes.len() > 20);
     /// ```
+    #[allow(unused)]
     pub(crate) fn to_bytes(&self) -> Result<Vec<u8>, SCloudException> {
-        let mut buf: Vec<u8> = Vec::new();
-
-        for label in self.q_name.split('.') {
-            let len = label.len();
-            if len > 63 {
-                return Err(
-                    SCloudException::SCLOUD_ADDITIONAL_DESERIALIZATION_FAILED_QNAME_TOO_LONG,
-                );
-            }
-            buf.push(len as u8);
-            buf.extend_from_slice(label.as_bytes());
-        }
-        buf.push(0x00);
+        let mut w = MessageWriter::uncompressed();
+        self.write(&mut w)?;
+        Ok(w.finish())
+    }
 
-        let qtype_u16 =
-            u16::try_from(self.q_type).expect("Cannot convert AdditionalSection q_type to u16");
-        buf.extend_from_slice(&qtype_u16.to_be_bytes());
+    /// Append the record to a message being serialized.
+    pub(crate) fn write(&self, w: &mut MessageWriter) -> Result<(), SCloudException> {
+        w.write_name(&self.q_name).map_err(|_| {
+            SCloudException::SCLOUD_ADDITIONAL_DESERIALIZATION_FAILED_QNAME_TOO_LONG
+        })?;
 
-        let qclass_u16 = u16::try_from(self.q_class).unwrap();
-        buf.extend_from_slice(&qclass_u16.to_be_bytes());
+        let qtype_u16 = u16::try_from(self.q_type)?;
+        w.put_u16(qtype_u16);
 
-        buf.extend_from_slice(&self.ttl.to_be_bytes());
-        buf.extend_from_slice(&self.rdlength.to_be_bytes());
-        buf.extend_from_slice(&self.rdata);
+        let qclass_u16 = u16::try_from(self.q_class)?;
+        w.put_u16(qclass_u16);
 
-        Ok(buf)
+        w.put_u32(self.ttl);
+        w.write_rdata(&self.rdata)
     }
 }
@@ -1,6 +1,8 @@
+use crate::dns::packet::writer::MessageWriter;
 use crate::dns::q_class::DNSClass;
 use crate::dns::q_name::parse_qname;
 use crate::dns::q_type::DNSRecordType;
+use crate::dns::records::rdata::RData;
 use crate::exceptions::SCloudException;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -9,8 +11,7 @@ pub struct AnswerSection {
     pub r_type: DNSRecordType,
     pub r_class: DNSClass,
     pub ttl: u32,
-    pub rdlength: u16,
-    pub rdata: Vec<u8>,
+    pub rdata: RData,
 }
 
 impl AnswerSection {
@@ -21,6 +22,7 @@ impl AnswerSection {
     /// use crate::dns::answer::AnswerSection;
     /// use crate::dns::q_type::DNSRecordType;
     /// use crate::dns::q_class::DNSClass;
+    /// use crate::dns::records::rdata::RData;
     ///
     /// // A Answer for example.com → 93.184.216.34
     /// let answer = AnswerSection {
@@ -28,8 +30,7 @@ impl AnswerSection {
     ///     r_type: DNSRecordType::A,
     ///     r_class: DNSClass::IN,
     ///     ttl: 300,
-    ///     rdlength: 4,
-    ///     rdata: vec![93, 184, 216, 34],
+    ///     rdata: RData::A("93.184.216.34".parse().unwrap()),
     /// };
     ///
     /// let bytes = answer.to_bytes().unwrap();
@@ -37,32 +38,26 @@ impl AnswerSection {
     /// // NAME + TYPE + CLASS + TTL + RDLENGTH + RDATA
     /// assert!(bytes.len() > 12);
     /// ```
+    #[allow(unused)]
     pub fn to_bytes(&self) -> Result<Vec<u8>, SCloudException> {
-        let mut buf = Vec::new();
-
-        // Encode NAME
-        for label in self.q_name.split('.') {
-            let len = label.len();
-            if len > 63 {
-                return Err(SCloudException::SCLOUD_ANSWER_DESERIALIZATION_FAILED_LABEL_TOO_LONG);
-            }
-            buf.push(len as u8);
-            buf.extend_from_slice(label.as_bytes());
-        }
-        buf.push(0x00);
+        let mut w = MessageWriter::uncompressed();
+        self.write(&mut w)?;
+        Ok(w.finish())
+    }
 
-        let rtype_u16 =
-            u16::try_from(self.r_type).expect("Cannot convert AnswerSection q_type to u16");
-        buf.extend_from_slice(&rtype_u16.to_be_bytes());
+    /// Append the record to a message being serialized.
+    pub(crate) fn write(&self, w: &mut MessageWriter) -> Result<(), SCloudException> {
+        w.write_name(&self.q_name)
+            .map_err(|_| SCloudException::SCLOUD_ANSWER_DESERIALIZATION_FAILED_LABEL_TOO_LONG)?;
 
-        let rclass_u16 = u16::try_from(self.r_class).unwrap();
-        buf.extend_from_slice(&rclass_u16.to_be_bytes());
+        let rtype_u16 = u16::try_from(self.r_type)?;
+        w.put_u16(rtype_u16);
 
-        buf.extend_from_slice(&self.ttl.to_be_bytes());
-        buf.extend_from_slice(&self.rdlength.to_be_bytes());
-        buf.extend_from_slice(&self.rdata);
+        let rclass_u16 = u16::try_from(self.r_class)?;
+        w.put_u16(rclass_u16);
 
-        Ok(buf)
+        w.put_u32(self.ttl);
+        w.write_rdata(&self.rdata)
     }
 
     /// Deserialize one AnswerSection and return (section, consumed_bytes)
@@ -72,6 +67,7 @@ impl AnswerSection {
     /// use crate::dns::answer::AnswerSection;
     /// use crate::dns::q_type::DNSRecordType;
     /// use crate::dns::q_class::DNSClass;
+    /// use crate::dns::records::rdata::RData;
     ///
     /// // example.com A 93.184.216.34
     /// let raw_answer: Vec<u8> = vec![
@@ -91,36 +87,36 @@ impl AnswerSection {
     /// assert_eq!(answer.r_type, DNSRecordType::A);
     /// assert_eq!(answer.r_class, DNSClass::IN);
     /// assert_eq!(answer.ttl, 300);
-    /// assert_eq!(answer.rdata, vec![93, 184, 216, 34]);
+    /// assert_eq!(answer.rdata, RData::A("93.184.216.34".parse().unwrap()));
     /// assert_eq!(consumed, raw_answer.len());
     /// ```
     pub(crate) fn from_bytes(
         buf: &[u8],
         offset: usize,
     ) -> Result<(AnswerSection, usize), SCloudException> {
-        let (q_name, mut pos) = parse_qname(buf, offset).unwrap();
+        let (q_name, mut pos) = parse_qname(buf, offset)?;
 
         if pos + 10 > buf.len() {
             return Err(SCloudException::SCLOUD_IMPOSSIBLE_PARSE_ANSWER_HEADER_TOO_SHORT);
         }
 
-        let r_type = DNSRecordType::try_from(u16::from_be_bytes([buf[pos], buf[pos + 1]])).unwrap();
+        let r_type = DNSRecordType::try_from(u16::from_be_bytes([buf[pos], buf[pos + 1]]))?;
         pos += 2;
 
-        let r_class = DNSClass::try_from(u16::from_be_bytes([buf[pos], buf[pos + 1]])).unwrap();
+        let r_class = DNSClass::try_from(u16::from_be_bytes([buf[pos], buf[pos + 1]]))?;
         pos += 2;
 
         let ttl = u32::from_be_bytes([buf[pos], buf[pos + 1], buf[pos + 2], buf[pos + 3]]);
         pos += 4;
 
-        let rdlength = u16::from_be_bytes([buf[pos], buf[pos + 1]]);
+        let rdlength = u16::from_be_bytes([buf[pos], buf[pos + 1]]) as usize;
         pos += 2;
 
-        if pos + rdlength as usize > buf.len() {
+        if pos + rdlength > buf.len() {
             return Err(SCloudException::SCLOUD_IMPOSSIBLE_PARSE_ANSWER_RDATA_OUT_OF_BOUNDS);
         }
-        let rdata = buf[pos..pos + rdlength as usize].to_vec();
-        pos += rdlength as usize;
+        let rdata = RData::decode(r_type, buf, pos, rdlength)?;
+        pos += rdlength;
 
         Ok((
             AnswerSection {
@@ -128,7 +124,6 @@ impl AnswerSection {
                 r_type,
                 r_class,
                 ttl,
-                rdlength,
                 rdata,
             },
             pos - offset,
@@ -1,6 +1,8 @@
+use crate::dns::packet::writer::MessageWriter;
 use crate::dns::q_class::DNSClass;
 use crate::dns::q_name::parse_qname;
 use crate::dns::q_type::DNSRecordType;
+use crate::dns::records::rdata::RData;
 use crate::exceptions::SCloudException;
 
 #[derive(PartialEq, Debug, Clone)]
@@ -9,17 +11,18 @@ pub(crate) struct AuthoritySection {
     pub(crate) q_type: DNSRecordType,
     pub(crate) q_class: DNSClass,
     pub(crate) ttl: u32,
-    pub(crate) ns_name: String,
+    pub(crate) rdata: RData,
 }
 
 impl AuthoritySection {
-    /// Deserialize one AuthoritySection (NS record) and return (section, consumed_bytes)
+    /// Deserialize one AuthoritySection and return (section, consumed_bytes)
     ///
     /// # Exemple :
     /// ```
     /// use crate::dns::packet::authority::AuthoritySection;
     /// use crate::dns::q_type::DNSRecordType;
     /// use crate::dns::q_class::DNSClass;
+    /// use crate::dns::records::rdata::RData;
     ///
     /// // example.com NS ns1.example.com
     /// let raw_authority: Vec<u8> = vec![
@@ -43,23 +46,23 @@ impl AuthoritySection {
     /// assert_eq!(authority.q_type, DNSRecordType::NS);
     /// assert_eq!(authority.q_class, DNSClass::IN);
     /// assert_eq!(authority.ttl, 3600);
-    /// assert_eq!(authority.ns_name, "ns1.example.com");
+    /// assert_eq!(authority.rdata, RData::NS("ns1.example.com".to_string()));
     /// assert_eq!(consumed, raw_authority.len());
     /// ```
     pub(crate) fn from_bytes(
         buf: &[u8],
         offset: usize,
     ) -> Result<(AuthoritySection, usize), SCloudException> {
-        let (q_name, mut pos) = parse_qname(buf, offset).unwrap();
+        let (q_name, mut pos) = parse_qname(buf, offset)?;
 
         if buf.len() < pos + 10 {
             return Err(SCloudException::SCLOUD_AUTHORITY_DESERIALIZATION_FAILED_BUF_TOO_SHORT);
         }
 
-        let q_type = DNSRecordType::try_from(u16::from_be_bytes([buf[pos], buf[pos + 1]])).unwrap();
+        let q_type = DNSRecordType::try_from(u16::from_be_bytes([buf[pos], buf[pos + 1]]))?;
         pos += 2;
 
-        let q_class = DNSClass::try_from(u16::from_be_bytes([buf[pos], buf[pos + 1]])).unwrap();
+        let q_class = DNSClass::try_from(u16::from_be_bytes([buf[pos], buf[pos + 1]]))?;
         pos += 2;
 
         let ttl = u32::from_be_bytes([buf[pos], buf[pos + 1], buf[pos + 2], buf[pos + 3]]);
@@ -74,8 +77,7 @@ impl AuthoritySection {
             );
         }
 
-        let (ns_name, _) = parse_qname(buf, pos).unwrap();
-
+        let rdata = RData::decode(q_type, buf, pos, rdlength as usize)?;
         pos += rdlength as usize;
 
         Ok((
@@ -84,26 +86,27 @@ impl AuthoritySection {
                 q_type,
                 q_class,
                 ttl,
-                ns_name,
+                rdata,
             },
             pos - offset,
         ))
     }
 
-    /// Serialize the AuthoritySection (NS record) into bytes
+    /// Serialize the AuthoritySection into bytes
     ///
     /// # Exemple :
     /// ```
     /// use crate::dns::packet::authority::AuthoritySection;
     /// use crate::dns::q_type::DNSRecordType;
     /// use crate::dns::q_class::DNSClass;
+    /// use crate::dns::records::rdata::RData;
     ///
     /// let authority = AuthoritySection {
     ///     q_name: "example.com".to_string(),
     ///     q_type: DNSRecordType::NS,
     ///     q_class: DNSClass::IN,
     ///     ttl: 3600,
-    ///     ns_name: "ns1.example.com".to_string(),
+    ///     rdata: RData::NS("ns1.example.com".to_string()),
     /// };
     ///
     /// let bytes = authority.to_bytes().unwrap();
@@ -111,34 +114,24 @@ impl AuthoritySection {
     /// // NAME + TYPE + CLASS + TTL + RDLENGTH + RDATA
     /// assert!(bytes.len() > 20);
     /// ```
+    #[allow(unused)]
     pub(crate) fn to_bytes(&self) -> Result<Vec<u8>, SCloudException> {
-        let mut buf = Vec::new();
-
-        for label in self.q_name.split('.') {
-            buf.push(label.len() as u8);
-            buf.extend_from_slice(label.as_bytes());
-        }
-        buf.push(0x00);
-
-        let qtype_u16 =
-            u16::try_from(self.q_type).expect("Cannot convert AuthoritySection q_type to u16");
-        buf.extend_from_slice(&qtype_u16.to_be_bytes());
-
-        let qclass_u16 = u16::try_from(self.q_class).unwrap();
-        buf.extend_from_slice(&qclass_u16.to_be_bytes());
+        let mut w = MessageWriter::uncompressed();
+        self.write(&mut w)?;
+        Ok(w.finish())
+    }
 
-        buf.extend_from_slice(&self.ttl.to_be_bytes());
+    /// Append the record to a message being serialized.
+    pub(crate) fn write(&self, w: &mut MessageWriter) -> Result<(), SCloudException> {
+        w.write_name(&self.q_name)?;
 
-        let mut rdata = Vec::new();
-        for label in self.ns_name.split('.') {
-            rdata.push(label.len() as u8);
-            rdata.extend_from_slice(label.as_bytes());
-        }
-        rdata.push(0x00);
+        let qtype_u16 = u16::try_from(self.q_type)?;
+        w.put_u16(qtype_u16);
 
-        buf.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
-        buf.extend_from_slice(&rdata);
+        let qclass_u16 = u16::try_from(self.q_class)?;
+        w.put_u16(qclass_u16);
 
-        Ok(buf)
+        w.put_u32(self.ttl);
+        w.write_rdata(&self.rdata)
     }
 }
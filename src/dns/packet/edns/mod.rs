@@ -0,0 +1,204 @@
+use crate::dns::packet::DNSPacket;
+use crate::dns::packet::writer::MessageWriter;
+use crate::dns::q_name::parse_qname;
+use crate::exceptions::SCloudException;
+
+const OPT_TYPE: u16 = 41;
+// DO bit of the OPT TTL flags (RFC 3225)
+const FLAG_DO: u16 = 0x8000;
+
+/// EDNS(0) information of a message, carried on the wire by the OPT
+/// pseudo-record of the additional section (RFC 6891).
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct Edns {
+    /// largest UDP payload the sender can reassemble
+    pub(crate) udp_size: u16,
+    /// upper 8 bits of the 12-bit extended RCODE
+    pub(crate) ext_rcode: u8,
+    pub(crate) version: u8,
+    /// DNSSEC OK (RFC 3225)
+    pub(crate) dnssec_ok: bool,
+    pub(crate) options: Vec<EdnsOption>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct EdnsOption {
+    pub(crate) code: u16,
+    pub(crate) data: Vec<u8>,
+}
+
+impl Edns {
+    /// Payload size every client accepts, with or without EDNS.
+    pub(crate) const MIN_UDP_SIZE: u16 = 512;
+    /// Only version of EDNS defined so far.
+    pub(crate) const VERSION: u8 = 0;
+    /// Extended RCODE 16: EDNS version not implemented (RFC 6891 section 9).
+    pub(crate) const RCODE_BADVERS: u16 = 16;
+
+    /// Version 0 EDNS advertising `udp_size`, without flags nor options.
+    pub(crate) fn new(udp_size: u16) -> Edns {
+        Edns {
+            udp_size,
+            ext_rcode: 0,
+            version: Edns::VERSION,
+            dnssec_ok: false,
+            options: Vec::new(),
+        }
+    }
+
+    /// Whether the record starting at `buf[offset..]` is an OPT record.
+    pub(crate) fn is_opt(buf: &[u8], offset: usize) -> Result<bool, SCloudException> {
+        let (_, pos) = parse_qname(buf, offset)?;
+        Ok(buf.get(pos..pos + 2) == Some(&OPT_TYPE.to_be_bytes()[..]))
+    }
+
+    /// Deserialize the OPT record at `buf[offset..]` and return (edns,
+    /// consumed_bytes).
+    ///
+    /// # Exemple :
+    /// ```
+    /// let raw_opt: Vec<u8> = vec![
+    ///     0x00,                   // root owner
+    ///     0x00, 0x29,             // TYPE = OPT
+    ///     0x04, 0xd0,             // UDP payload size = 1232
+    ///     0x00, 0x00, 0x80, 0x00, // extended RCODE 0, version 0, DO
+    ///     0x00, 0x00,             // RDLENGTH = 0
+    /// ];
+    ///
+    /// let (edns, consumed) = Edns::from_bytes(&raw_opt, 0).unwrap();
+    ///
+    /// assert_eq!(edns.udp_size, 1232);
+    /// assert!(edns.dnssec_ok);
+    /// assert_eq!(consumed, raw_opt.len());
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `SCLOUD_EDNS_MALFORMED` if the owner is not the root, the
+    /// record is not an OPT record or its options overflow the RDATA.
+    pub(crate) fn from_bytes(buf: &[u8], offset: usize) -> Result<(Edns, usize), SCloudException> {
+        let malformed = || SCloudException::SCLOUD_EDNS_MALFORMED;
+        let (name, mut pos) = parse_qname(buf, offset)?;
+        if !name.is_empty() {
+            return Err(malformed());
+        }
+
+        let fixed = buf.get(pos..pos + 10).ok_or_else(malformed)?;
+        if u16::from_be_bytes([fixed[0], fixed[1]]) != OPT_TYPE {
+            return Err(malformed());
+        }
+        let udp_size = u16::from_be_bytes([fixed[2], fixed[3]]);
+        let ext_rcode = fixed[4];
+        let version = fixed[5];
+        let flags = u16::from_be_bytes([fixed[6], fixed[7]]);
+        let rdlength = u16::from_be_bytes([fixed[8], fixed[9]]) as usize;
+        pos += 10;
+
+        let mut rdata = buf.get(pos..pos + rdlength).ok_or_else(malformed)?;
+        pos += rdlength;
+
+        let mut options = Vec::new();
+        while !rdata.is_empty() {
+            let (head, rest) = rdata.split_first_chunk::<4>().ok_or_else(malformed)?;
+            let code = u16::from_be_bytes([head[0], head[1]]);
+            let len = u16::from_be_bytes([head[2], head[3]]) as usize;
+            let data = rest.get(..len).ok_or_else(malformed)?;
+            options.push(EdnsOption {
+                code,
+                data: data.to_vec(),
+            });
+            rdata = &rest[len..];
+        }
+
+        Ok((
+            Edns {
+                udp_size,
+                ext_rcode,
+                version,
+                dnssec_ok: flags & FLAG_DO != 0,
+                options,
+            },
+            pos - offset,
+        ))
+    }
+
+    /// Append the OPT record to a message being serialized.
+    ///
+    /// # Errors
+    /// Returns `SCLOUD_EDNS_MALFORMED` if the options do not fit in 65535
+    /// bytes.
+    pub(crate) fn write(&self, w: &mut MessageWriter) -> Result<(), SCloudException> {
+        let mut rdata = Vec::new();
+        for option in &self.options {
+            let len = u16::try_from(option.data.len())
+                .map_err(|_| SCloudException::SCLOUD_EDNS_MALFORMED)?;
+            rdata.extend_from_slice(&option.code.to_be_bytes());
+            rdata.extend_from_slice(&len.to_be_bytes());
+            rdata.extend_from_slice(&option.data);
+        }
+        let rdlength =
+            u16::try_from(rdata.len()).map_err(|_| SCloudException::SCLOUD_EDNS_MALFORMED)?;
+
+        w.put_u8(0x00);
+        w.put_u16(OPT_TYPE);
+        w.put_u16(self.udp_size);
+        w.put_u8(self.ext_rcode);
+        w.put_u8(self.version);
+        w.put_u16(if self.dnssec_ok { FLAG_DO } else { 0 });
+        w.put_u16(rdlength);
+        w.put(&rdata);
+        Ok(())
+    }
+}
+
+impl DNSPacket {
+    /// Largest response to this query that can be sent over UDP, given
+    /// the payload size `udp_size` the server advertises (`None` when EDNS
+    /// is disabled): the smaller of the two sizes, never more than `cap`.
+    ///
+    /// Without an OPT record in both the query and the response, that is
+    /// 512 bytes (RFC 1035 section 4.2.1); never less in any case.
+    pub(crate) fn max_udp_response_size(&self, udp_size: Option<u16>, cap: usize) -> usize {
+        let (Some(udp_size), Some(edns)) = (udp_size, self.edns.as_ref()) else {
+            return usize::from(Edns::MIN_UDP_SIZE);
+        };
+        usize::from(edns.udp_size.min(udp_size))
+            .min(cap)
+            .max(usize::from(Edns::MIN_UDP_SIZE))
+    }
+
+    /// Set the EDNS part of this response to `query` (RFC 6891 section 7).
+    ///
+    /// A response only carries an OPT record when the query had one and
+    /// `udp_size` is set (EDNS enabled). It then advertises `udp_size`,
+    /// echoes the DO bit and keeps the extended RCODE of the response; the
+    /// options of an upstream reply are not passed on. A query using an
+    /// EDNS version above 0 gets an empty BADVERS response.
+    pub(crate) fn negotiate_edns(&mut self, query: &DNSPacket, udp_size: Option<u16>) {
+        let (Some(udp_size), Some(asked)) = (udp_size, query.edns.as_ref()) else {
+            self.edns = None;
+            self.sync_counts();
+            return;
+        };
+
+        let mut edns = Edns::new(udp_size.max(Edns::MIN_UDP_SIZE));
+        edns.dnssec_ok = asked.dnssec_ok;
+        if asked.version > Edns::VERSION {
+            self.answers.clear();
+            self.authorities.clear();
+            self.additionals.clear();
+            self.header.aa = false;
+            self.header.rcode = (Edns::RCODE_BADVERS & 0x0F) as u8;
+            edns.ext_rcode = (Edns::RCODE_BADVERS >> 4) as u8;
+        } else if let Some(current) = &self.edns {
+            edns.ext_rcode = current.ext_rcode;
+        }
+        self.edns = Some(edns);
+        self.sync_counts();
+    }
+
+    /// Full 12-bit RCODE: the header RCODE extended by the OPT record.
+    pub(crate) fn rcode(&self) -> u16 {
+        let high = self.edns.as_ref().map_or(0, |e| u16::from(e.ext_rcode));
+        (high << 4) | u16::from(self.header.rcode & 0x0F)
+    }
+}
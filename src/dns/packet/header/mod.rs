@@ -20,6 +20,17 @@ pub struct Header {
 impl Header {
     pub(crate) const DNS_HEADER_LEN: usize = 12;
 
+    // RCODE values (RFC 1035 section 4.1.1)
+    pub(crate) const RCODE_NOERROR: u8 = 0;
+    pub(crate) const RCODE_FORMERR: u8 = 1;
+    pub(crate) const RCODE_SERVFAIL: u8 = 2;
+    pub(crate) const RCODE_NXDOMAIN: u8 = 3;
+    pub(crate) const RCODE_REFUSED: u8 = 5;
+
+    // bits of `z` (RFC 2535 section 6.1, RFC 4035 section 3.2)
+    pub(crate) const Z_AD: u8 = 0b010; // authentic data
+    pub(crate) const Z_CD: u8 = 0b001; // checking disabled
+
     /// Serialize the DNS header into a byte array
     ///
     /// # Exemple :
@@ -65,6 +76,7 @@ impl Header {
         let mut flags2 = 0u8;
         flags2 |= (self.ra as u8 & 0x1) << 7;
         flags2 |= (self.z & 0x7) << 4;
+        flags2 |= self.rcode & 0xF;
 
         bytes[2] = flags1;
         bytes[3] = flags2;
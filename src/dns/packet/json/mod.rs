@@ -0,0 +1,85 @@
+use crate::dns::packet::DNSPacket;
+use crate::dns::packet::header::Header;
+use crate::dns::q_type::DNSRecordType;
+use crate::dns::records::rdata::RData;
+use serde_json::{Map, Value, json};
+
+impl DNSPacket {
+    /// Render the packet in the JSON format of the Google and Cloudflare
+    /// DoH JSON APIs (`application/dns-json`).
+    ///
+    /// Names are fully qualified, record types numeric and RDATA in
+    /// presentation format (see `RData`). Empty record sections are left
+    /// out.
+    ///
+    /// # Exemple :
+    /// ```
+    /// use crate::dns::packet::DNSPacket;
+    ///
+    /// let json = packet.to_json();
+    /// assert_eq!(json["Status"], 0);
+    /// assert_eq!(json["Answer"][0]["data"], "93.184.216.34");
+    /// ```
+    pub(crate) fn to_json(&self) -> Value {
+        let h = &self.header;
+        let mut out = Map::new();
+        out.insert("Status".into(), json!(self.rcode()));
+        out.insert("TC".into(), json!(h.tc));
+        out.insert("RD".into(), json!(h.rd));
+        out.insert("RA".into(), json!(h.ra));
+        out.insert("AD".into(), json!(h.z & Header::Z_AD != 0));
+        out.insert("CD".into(), json!(h.z & Header::Z_CD != 0));
+
+        let questions: Vec<Value> = self
+            .questions
+            .iter()
+            .map(|q| json!({ "name": fqdn(&q.q_name), "type": type_code(q.q_type) }))
+            .collect();
+        out.insert("Question".into(), Value::Array(questions));
+
+        let answers: Vec<Value> = self
+            .answers
+            .iter()
+            .map(|a| record(&a.q_name, a.r_type, a.ttl, &a.rdata))
+            .collect();
+        let authorities: Vec<Value> = self
+            .authorities
+            .iter()
+            .map(|a| record(&a.q_name, a.q_type, a.ttl, &a.rdata))
+            .collect();
+        let additionals: Vec<Value> = self
+            .additionals
+            .iter()
+            .map(|a| record(&a.q_name, a.q_type, a.ttl, &a.rdata))
+            .collect();
+
+        for (key, records) in [
+            ("Answer", answers),
+            ("Authority", authorities),
+            ("Additional", additionals),
+        ] {
+            if !records.is_empty() {
+                out.insert(key.into(), Value::Array(records));
+            }
+        }
+        Value::Object(out)
+    }
+}
+
+fn record(name: &str, rtype: DNSRecordType, ttl: u32, rdata: &RData) -> Value {
+    json!({
+        "name": fqdn(name),
+        "type": type_code(rtype),
+        "TTL": ttl,
+        "data": rdata.to_string(),
+    })
+}
+
+fn fqdn(name: &str) -> String {
+    let name = name.trim_end_matches('.');
+    format!("{}.", name)
+}
+
+fn type_code(rtype: DNSRecordType) -> u16 {
+    u16::try_from(rtype).unwrap_or_default()
+}
@@ -1,16 +1,22 @@
 use crate::dns::packet::additional::AdditionalSection;
 use crate::dns::packet::answer::AnswerSection;
 use crate::dns::packet::authority::AuthoritySection;
+use crate::dns::packet::edns::Edns;
 use crate::dns::packet::header::Header;
 use crate::dns::packet::question::QuestionSection;
+use crate::dns::packet::writer::MessageWriter;
 use crate::exceptions::SCloudException;
 use rand::random;
 
 pub(crate) mod additional;
 pub(crate) mod answer;
 pub(crate) mod authority;
+pub(crate) mod edns;
 pub(crate) mod header;
+pub(crate) mod json;
 pub(crate) mod question;
+pub(crate) mod truncate;
+pub(crate) mod writer;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct DNSPacket {
@@ -19,6 +25,8 @@ pub struct DNSPacket {
     pub answers: Vec<AnswerSection>,
     pub authorities: Vec<AuthoritySection>,
     pub additionals: Vec<AdditionalSection>,
+    /// OPT record of the additional section, kept apart from `additionals`
+    pub(crate) edns: Option<Edns>,
 }
 
 impl DNSPacket {
@@ -74,7 +82,18 @@ impl DNSPacket {
         }
 
         let mut additionals = Vec::new();
+        let mut edns = None;
         for _ in 0..header.arcount {
+            if Edns::is_opt(buf, pos)? {
+                // RFC 6891 section 6.1.1: at most one OPT record
+                if edns.is_some() {
+                    return Err(SCloudException::SCLOUD_EDNS_MALFORMED);
+                }
+                let (opt, consumed) = Edns::from_bytes(buf, pos)?;
+                pos += consumed;
+                edns = Some(opt);
+                continue;
+            }
             let (add, consumed) = AdditionalSection::from_bytes(&buf, pos)?;
             pos += consumed;
             additionals.push(add);
@@ -86,10 +105,12 @@ impl DNSPacket {
             answers,
             authorities,
             additionals,
+            edns,
         })
     }
 
-    /// Serialize the DNS packet into a byte array
+    /// Serialize the DNS packet into a byte array, compressing the names
+    /// (see `MessageWriter`)
     /// # Exemple :
     /// ```
     /// let packet = DNSPacket::new_query(&[QuestionSection {
@@ -104,30 +125,34 @@ impl DNSPacket {
     /// assert!(bytes.len() >= 12);
     /// ```
     pub fn to_bytes(&self) -> Result<Vec<u8>, SCloudException> {
-        let mut bytes = Vec::new();
+        let mut w = MessageWriter::new();
 
         if let Err(_) = self.header.to_bytes() {
             return Err(SCloudException::SCLOUD_HEADER_BYTES_EMPTY);
         }
-        bytes.extend_from_slice(&self.header.to_bytes()?);
+        w.put(&self.header.to_bytes()?);
 
         for q in &self.questions {
-            bytes.extend_from_slice(&q.to_bytes()?);
+            q.write(&mut w)?;
         }
 
         for ans in &self.answers {
-            bytes.extend_from_slice(&ans.to_bytes()?);
+            ans.write(&mut w)?;
         }
 
         for auth in &self.authorities {
-            bytes.extend_from_slice(&auth.to_bytes()?);
+            auth.write(&mut w)?;
         }
 
         for add in &self.additionals {
-            bytes.extend_from_slice(&add.to_bytes()?);
+            add.write(&mut w)?;
         }
 
-        Ok(bytes)
+        if let Some(edns) = &self.edns {
+            edns.write(&mut w)?;
+        }
+
+        Ok(w.finish())
     }
 
     /// Receive one or more `QuestionSection`, and return a new DNSPacket
@@ -166,6 +191,7 @@ impl DNSPacket {
     ///             answers: vec![],
     ///             authorities: vec![],
     ///             additionals: vec![],
+    ///             edns: None,
     ///         };
     /// ```
     pub fn new_query(question_section: &[QuestionSection]) -> DNSPacket {
@@ -189,6 +215,55 @@ impl DNSPacket {
             answers: vec![],
             authorities: vec![],
             additionals: vec![],
+            edns: None,
         }
     }
+
+    /// Build an empty response to `query` with the given RCODE.
+    ///
+    /// The ID, opcode, RD flag and question section are copied from the
+    /// query; the caller fills the other sections and `sync_counts` is
+    /// applied before serialization.
+    ///
+    /// # Exemple :
+    /// ```
+    /// let response = DNSPacket::new_response(&query, Header::RCODE_REFUSED);
+    ///
+    /// assert!(response.header.qr);
+    /// assert_eq!(response.header.id, query.header.id);
+    /// assert_eq!(response.questions, query.questions);
+    /// ```
+    pub fn new_response(query: &DNSPacket, rcode: u8) -> DNSPacket {
+        DNSPacket {
+            header: Header {
+                id: query.header.id,
+                qr: true,
+                opcode: query.header.opcode,
+                aa: false,
+                tc: false,
+                rd: query.header.rd,
+                ra: false,
+                // RFC 4035 section 3.1.6: CD is copied from the query
+                z: query.header.z & Header::Z_CD,
+                rcode,
+                qdcount: query.questions.len() as u16,
+                ancount: 0,
+                nscount: 0,
+                arcount: 0,
+            },
+            questions: query.questions.clone(),
+            answers: vec![],
+            authorities: vec![],
+            additionals: vec![],
+            edns: None,
+        }
+    }
+
+    /// Update the header counters from the actual section lengths.
+    pub fn sync_counts(&mut self) {
+        self.header.qdcount = self.questions.len() as u16;
+        self.header.ancount = self.answers.len() as u16;
+        self.header.nscount = self.authorities.len() as u16;
+        self.header.arcount = (self.additionals.len() + usize::from(self.edns.is_some())) as u16;
+    }
 }
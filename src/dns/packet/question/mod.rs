@@ -1,3 +1,4 @@
+use crate::dns::packet::writer::MessageWriter;
 use crate::dns::q_class::DNSClass;
 use crate::dns::q_name::parse_qname;
 use crate::dns::q_type::DNSRecordType;
@@ -31,28 +32,24 @@ impl QuestionSection {
     /// // QNAME + QTYPE + QCLASS
     /// assert!(bytes.len() > 6);
     /// ```
+    #[allow(unused)]
     pub fn to_bytes(&self) -> Result<Vec<u8>, SCloudException> {
-        let mut buf = Vec::with_capacity(self.q_name.len() + 5);
+        let mut w = MessageWriter::uncompressed();
+        self.write(&mut w)?;
+        Ok(w.finish())
+    }
 
-        // Encode QNAME
-        for label in self.q_name.split('.') {
-            let len = label.len();
-            if len > 63 {
-                return Err(SCloudException::SCLOUD_QUESTION_SERIALIZATION_FAILED_QNAME_TOO_LONG);
-            }
-            buf.push(len as u8);
-            buf.extend_from_slice(label.as_bytes());
-        }
-        buf.push(0x00);
+    /// Append the question to a message being serialized.
+    pub(crate) fn write(&self, w: &mut MessageWriter) -> Result<(), SCloudException> {
+        w.write_name(&self.q_name)?;
 
-        let q_type_u16 =
-            u16::try_from(self.q_type).expect("Cannot convert QuestionSection q_type to u16");
-        let q_class_u16 = u16::try_from(self.q_class).unwrap();
+        let q_type_u16 = u16::try_from(self.q_type)?;
+        let q_class_u16 = u16::try_from(self.q_class)?;
 
-        buf.extend_from_slice(&q_type_u16.to_be_bytes());
-        buf.extend_from_slice(&q_class_u16.to_be_bytes());
+        w.put_u16(q_type_u16);
+        w.put_u16(q_class_u16);
 
-        Ok(buf)
+        Ok(())
     }
 
     /// Deserialize the DNS question section from a byte array
@@ -83,15 +80,15 @@ impl QuestionSection {
         buf: &[u8],
         offset: usize,
     ) -> Result<(QuestionSection, usize), SCloudException> {
-        let (q_name, mut pos) = parse_qname(buf, offset).unwrap();
+        let (q_name, mut pos) = parse_qname(buf, offset)?;
 
         if buf.len() < pos + 4 {
             return Err(SCloudException::SCLOUD_QUESTION_DESERIALIZATION_FAILED);
         }
 
-        let q_type = DNSRecordType::try_from(u16::from_be_bytes([buf[pos], buf[pos + 1]])).unwrap();
+        let q_type = DNSRecordType::try_from(u16::from_be_bytes([buf[pos], buf[pos + 1]]))?;
 
-        let q_class = DNSClass::try_from(u16::from_be_bytes([buf[pos + 2], buf[pos + 3]])).unwrap();
+        let q_class = DNSClass::try_from(u16::from_be_bytes([buf[pos + 2], buf[pos + 3]]))?;
 
         pos += 4;
 
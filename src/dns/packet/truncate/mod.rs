@@ -0,0 +1,43 @@
+use crate::dns::packet::DNSPacket;
+use crate::exceptions::SCloudException;
+
+impl DNSPacket {
+    /// Serialize the response so that it fits in `max` bytes (RFC 2181
+    /// section 9).
+    ///
+    /// The additional section is dropped first, since it only holds
+    /// optional data. If the response still does not fit, the answer and
+    /// authority sections are emptied and TC is set so the client retries
+    /// over TCP. The question and the OPT record are always kept. The
+    /// packet is left as it was sent.
+    ///
+    /// # Exemple :
+    /// ```
+    /// let udp_max = query.max_udp_response_size(Some(4096), 4096);
+    /// let bytes = response.truncate_to(udp_max).unwrap();
+    ///
+    /// assert!(bytes.len() <= udp_max || response.header.tc);
+    /// ```
+    ///
+    /// # Errors
+    /// Same as `to_bytes`.
+    pub(crate) fn truncate_to(&mut self, max: usize) -> Result<Vec<u8>, SCloudException> {
+        let bytes = self.to_bytes()?;
+        if bytes.len() <= max {
+            return Ok(bytes);
+        }
+
+        self.additionals.clear();
+        self.sync_counts();
+        let bytes = self.to_bytes()?;
+        if bytes.len() <= max {
+            return Ok(bytes);
+        }
+
+        self.answers.clear();
+        self.authorities.clear();
+        self.header.tc = true;
+        self.sync_counts();
+        self.to_bytes()
+    }
+}
@@ -0,0 +1,125 @@
+use crate::dns::q_name::{label_bytes, split_label, strip_root, write_qname};
+use crate::dns::records::rdata::RData;
+use crate::exceptions::SCloudException;
+use std::collections::HashMap;
+
+// a compression pointer holds a 14-bit offset
+const MAX_POINTER_OFFSET: usize = 0x3FFF;
+
+/// Serializes a whole DNS message, compressing domain names (RFC 1035
+/// section 4.1.4).
+///
+/// Every name written with `write_name` is remembered by suffix, so a later
+/// name ending with an already written suffix is emitted as its own labels
+/// followed by a pointer to that suffix. Pointers are offsets from the start
+/// of the message: a writer must start with the header.
+///
+/// # Exemple :
+/// ```
+/// let mut w = MessageWriter::new();
+/// w.write_name("www.example.com").unwrap();
+/// w.write_name("mail.example.com").unwrap();
+///
+/// // "mail" followed by a pointer to "example.com" at offset 4
+/// assert_eq!(&w.finish()[17..], b"\x04mail\xc0\x04");
+/// ```
+#[derive(Debug, Default)]
+pub(crate) struct MessageWriter {
+    buf: Vec<u8>,
+    // suffix -> offset of its first label
+    suffixes: HashMap<String, u16>,
+    compress: bool,
+}
+
+impl MessageWriter {
+    /// Writer with name compression.
+    pub(crate) fn new() -> MessageWriter {
+        MessageWriter {
+            compress: true,
+            ..Default::default()
+        }
+    }
+
+    /// Writer that never compresses, for a part serialized on its own
+    /// (offsets would not be those of the final message).
+    pub(crate) fn uncompressed() -> MessageWriter {
+        MessageWriter::default()
+    }
+
+    pub(crate) fn put(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    pub(crate) fn put_u8(&mut self, v: u8) {
+        self.buf.push(v);
+    }
+
+    pub(crate) fn put_u16(&mut self, v: u16) {
+        self.buf.extend_from_slice(&v.to_be_bytes());
+    }
+
+    pub(crate) fn put_u32(&mut self, v: u32) {
+        self.buf.extend_from_slice(&v.to_be_bytes());
+    }
+
+    /// Write `name`, pointing at a previously written suffix when there is
+    /// one. Suffixes are matched with their case, so that a name is read
+    /// back exactly as written (e.g. a question using 0x20 randomization).
+    ///
+    /// # Errors
+    /// Same as `write_qname`.
+    pub(crate) fn write_name(&mut self, name: &str) -> Result<(), SCloudException> {
+        if !self.compress {
+            return write_qname(&mut self.buf, name);
+        }
+        // validate the whole name before writing any of it
+        write_qname(&mut Vec::new(), name)?;
+
+        let mut rest = strip_root(name);
+        while !rest.is_empty() {
+            if let Some(&offset) = self.suffixes.get(rest) {
+                self.put_u16(0xC000 | offset);
+                return Ok(());
+            }
+            if self.buf.len() <= MAX_POINTER_OFFSET {
+                self.suffixes
+                    .insert(rest.to_string(), self.buf.len() as u16);
+            }
+            let (label, tail) = split_label(rest);
+            self.buf.extend_from_slice(&label_bytes(label)?);
+            rest = tail;
+        }
+        self.buf.push(0x00);
+        Ok(())
+    }
+
+    /// Write `name` in full, without pointing at nor being pointed at by
+    /// other names. RFC 3597 section 4 only allows compression in the RDATA
+    /// of the types defined by RFC 1035.
+    ///
+    /// # Errors
+    /// Same as `write_qname`.
+    pub(crate) fn write_name_uncompressed(&mut self, name: &str) -> Result<(), SCloudException> {
+        write_qname(&mut self.buf, name)
+    }
+
+    /// Write RDLENGTH followed by the RDATA.
+    ///
+    /// # Errors
+    /// Returns `SCLOUD_RDATA_MALFORMED` if the RDATA is longer than 65535
+    /// bytes, or the error of `RData::encode`.
+    pub(crate) fn write_rdata(&mut self, rdata: &RData) -> Result<(), SCloudException> {
+        let start = self.buf.len();
+        self.put_u16(0);
+        rdata.encode(self)?;
+        let len = u16::try_from(self.buf.len() - start - 2)
+            .map_err(|_| SCloudException::SCLOUD_RDATA_MALFORMED)?;
+        self.buf[start..start + 2].copy_from_slice(&len.to_be_bytes());
+        Ok(())
+    }
+
+    /// The serialized message.
+    pub(crate) fn finish(self) -> Vec<u8> {
+        self.buf
+    }
+}
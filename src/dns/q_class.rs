@@ -13,6 +13,9 @@ use crate::exceptions::SCloudException;
 /// - NONE (0): Used in dynamic update (RFC 2136)
 /// - ANY  (255): Wildcard class (RFC 1035)
 ///
+/// Any other value is kept as `Unknown(u16)`, so a record of an unknown
+/// class goes through the server unchanged.
+///
 /// The enum supports conversion to and from the on-the-wire `u16`
 /// representation used in DNS packets.
 #[derive(Debug, Clone, Copy, PartialEq, Hash, Eq)]
@@ -23,6 +26,37 @@ pub(crate) enum DNSClass {
     HS,
     NONE,
     ANY,
+
+    /// Unknown DNS class, with its raw value.
+    Unknown(u16),
+}
+
+impl DNSClass {
+    /// Class from its mnemonic (`IN`, case-insensitive) or its RFC 3597
+    /// form (`CLASS1`).
+    ///
+    /// # Exemple :
+    /// ```
+    /// use crate::dns::q_class::DNSClass;
+    ///
+    /// assert_eq!(DNSClass::from_name("ch"), Some(DNSClass::CH));
+    /// assert_eq!(DNSClass::from_name("CLASS4096"), Some(DNSClass::Unknown(4096)));
+    /// assert_eq!(DNSClass::from_name("A"), None);
+    /// ```
+    pub(crate) fn from_name(name: &str) -> Option<DNSClass> {
+        match name.to_ascii_uppercase().as_str() {
+            "IN" => Some(DNSClass::IN),
+            "CS" => Some(DNSClass::CS),
+            "CH" => Some(DNSClass::CH),
+            "HS" => Some(DNSClass::HS),
+            "NONE" => Some(DNSClass::NONE),
+            "ANY" => Some(DNSClass::ANY),
+            upper => {
+                let v = upper.strip_prefix("CLASS")?.parse::<u16>().ok()?;
+                DNSClass::try_from(v).ok()
+            }
+        }
+    }
 }
 
 impl TryFrom<u16> for DNSClass {
@@ -30,9 +64,7 @@ impl TryFrom<u16> for DNSClass {
 
     /// Convert a raw DNS CLASS value (`u16`) into a `DNSClass`.
     ///
-    /// # Errors
-    /// Returns `SCLOUD_QCLASS_U16_FOR_DNSCLASS_UNKNOWN` if the value
-    /// does not match any known DNS class.
+    /// Unknown values are preserved using `DNSClass::Unknown`.
     fn try_from(v: u16) -> Result<DNSClass, Self::Error> {
         match v {
             0 => Ok(DNSClass::NONE),
@@ -41,7 +73,7 @@ impl TryFrom<u16> for DNSClass {
             3 => Ok(DNSClass::CH),
             4 => Ok(DNSClass::HS),
             255 => Ok(DNSClass::ANY),
-            _ => Err(SCloudException::SCLOUD_QCLASS_U16_FOR_DNSCLASS_UNKNOWN),
+            _ => Ok(DNSClass::Unknown(v)),
         }
     }
 }
@@ -51,11 +83,8 @@ impl TryFrom<DNSClass> for u16 {
 
     /// Convert a `DNSClass` into its DNS wire format (`u16`).
     ///
-    /// # Errors
-    /// This error should never occur unless an invalid enum variant
-    /// is introduced.
+    /// Unknown classes are returned as-is.
     fn try_from(c: DNSClass) -> Result<u16, Self::Error> {
-        #[allow(unreachable_patterns)]
         match c {
             DNSClass::NONE => Ok(0),
             DNSClass::IN => Ok(1),
@@ -63,18 +92,15 @@ impl TryFrom<DNSClass> for u16 {
             DNSClass::CH => Ok(3),
             DNSClass::HS => Ok(4),
             DNSClass::ANY => Ok(255),
-            _ => Err(SCloudException::SCLOUD_QCLASS_DNSCLASS_FOR_U16_UNKNOWN),
+            DNSClass::Unknown(v) => Ok(v),
         }
     }
 }
 
 impl From<&[u8; 2]> for DNSClass {
     /// Convert a 2-byte DNS wire representation into a `DNSClass`.
-    ///
-    /// # Panics
-    /// Panics if the value does not correspond to a known DNS class.
     fn from(bytes: &[u8; 2]) -> Self {
         let v = u16::from_be_bytes(*bytes);
-        DNSClass::try_from(v).unwrap()
+        DNSClass::try_from(v).unwrap_or(DNSClass::Unknown(v))
     }
 }
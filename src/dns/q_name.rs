@@ -1,4 +1,38 @@
+use crate::config::TuningConfig;
+use crate::dns::records::unescape;
 use crate::exceptions::SCloudException;
+use std::sync::OnceLock;
+
+/// Length limits enforced on every name read from a message, taken from
+/// `TuningConfig` (see `init_name_limits`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct NameLimits {
+    pub(crate) max_label_length: usize,
+    /// labels and the dots between them, escapes decoded
+    pub(crate) max_domain_length: usize,
+}
+
+impl Default for NameLimits {
+    /// The limits of RFC 1035 section 2.3.4.
+    fn default() -> Self {
+        NameLimits {
+            max_label_length: 63,
+            max_domain_length: 253,
+        }
+    }
+}
+
+static NAME_LIMITS: OnceLock<NameLimits> = OnceLock::new();
+
+/// Set the limits used by `parse_qname` from the configuration. Only the
+/// first call has an effect; names are parsed with the RFC 1035 limits
+/// until then.
+pub(crate) fn init_name_limits(tuning: &TuningConfig) {
+    let _ = NAME_LIMITS.set(NameLimits {
+        max_label_length: tuning.max_label_length,
+        max_domain_length: tuning.max_domain_length,
+    });
+}
 
 /// Parse a DNS QNAME from a DNS message buffer.
 ///
@@ -12,8 +46,10 @@ use crate::exceptions::SCloudException;
 ///
 /// # Returns
 /// * `(String, usize)`
-///   - Parsed domain name (e.g. "www.example.com")
-///   - Number of bytes consumed at the original position
+///   - Parsed domain name (e.g. "www.example.com"), in presentation format:
+///     a `.` or `\` inside a label is escaped with a `\`, and a byte
+///     outside printable ASCII is written `\DDD` (RFC 1035 section 5.1)
+///   - Position in the buffer right after the name
 ///
 /// # Errors
 /// Returns an error if:
@@ -26,61 +62,83 @@ use crate::exceptions::SCloudException;
 ///   to the position right after the pointer (not the expanded name).
 /// - The caller is responsible for passing the correct initial offset
 ///   (e.g. 12 for the first QNAME in a DNS packet).
-pub(crate) fn parse_qname(buf: &[u8], mut pos: usize) -> Result<(String, usize), SCloudException> {
-    let mut labels = Vec::new();
-    let mut _jumped = false;
-    let mut end_pos = pos;
+pub(crate) fn parse_qname(buf: &[u8], pos: usize) -> Result<(String, usize), SCloudException> {
+    let limits = NAME_LIMITS.get().copied().unwrap_or_default();
+    parse_qname_with_limits(buf, pos, &limits)
+}
+
+/// `parse_qname` with explicit length limits.
+///
+/// A compression pointer must point strictly before the labels it follows,
+/// which rejects forward pointers and guarantees that every loop ends.
+///
+/// # Errors
+/// Same as `parse_qname`, plus `SCLOUD_IMPOSSIBLE_PARSE_QNAME_COMPRESSION_FAILED`
+/// for a pointer that does not point backwards and
+/// `SCLOUD_IMPOSSIBLE_PARSE_QNAME_TOO_LONG` for a label or name over `limits`.
+pub(crate) fn parse_qname_with_limits(
+    buf: &[u8],
+    mut pos: usize,
+    limits: &NameLimits,
+) -> Result<(String, usize), SCloudException> {
+    let mut name = String::new();
+    // length of the name on the wire, without the length bytes
+    let mut name_len = 0;
+    let mut end_pos = None;
+    // start of the labels being read: the name itself, then each pointer target
+    let mut segment_start = pos;
 
     loop {
-        if pos >= buf.len() {
+        let Some(&len) = buf.get(pos) else {
             return Err(SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME_POS_GREATER_THAN_BUF);
-        }
+        };
 
-        let len = buf[pos];
-
-        // Compression 0xC0xx
-        if len & 0xC0 == 0xC0 {
-            if pos + 1 >= buf.len() {
-                return Err(SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME);
+        match len & 0xC0 {
+            // Compression 0xC0xx
+            0xC0 => {
+                let Some(&low) = buf.get(pos + 1) else {
+                    return Err(SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME);
+                };
+                let offset = (usize::from(len & 0x3F) << 8) | usize::from(low);
+                if offset >= segment_start {
+                    return Err(SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME_COMPRESSION_FAILED);
+                }
+                end_pos.get_or_insert(pos + 2);
+                segment_start = offset;
+                pos = offset;
+                continue;
             }
-
-            let offset = (((len as u16 & 0x3F) << 8) | buf[pos + 1] as u16) as usize;
-
-            if !_jumped {
-                end_pos = pos + 2;
-            }
-
-            _jumped = true;
-
-            let (name, _) = parse_qname(buf, offset)?;
-            labels.extend(name.split('.').map(|s| s.to_string()));
-            break;
+            0x00 => {}
+            // 0x40 and 0x80 label types are not in use (RFC 6891 section 5)
+            _ => return Err(SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME),
         }
 
         if len == 0 {
-            if !_jumped {
-                end_pos = pos + 1;
-            }
+            end_pos.get_or_insert(pos + 1);
             break;
         }
 
+        let len = usize::from(len);
         pos += 1;
-
-        if pos + len as usize > buf.len() {
+        let Some(label) = buf.get(pos..pos + len) else {
             return Err(
                 SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME_POS_AND_LEN_GREATER_THAN_BUF,
             );
-        }
+        };
 
-        let label = &buf[pos..pos + len as usize];
-        let s = String::from_utf8(label.to_vec())
-            .map_err(|_| SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME)?;
-
-        labels.push(s);
-        pos += len as usize;
+        let dot = usize::from(name_len > 0);
+        if len > limits.max_label_length || name_len + dot + len > limits.max_domain_length {
+            return Err(SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME_TOO_LONG);
+        }
+        if dot == 1 {
+            name.push('.');
+        }
+        push_label(&mut name, label);
+        name_len += dot + len;
+        pos += len;
     }
 
-    Ok((labels.join("."), end_pos))
+    Ok((name, end_pos.unwrap_or(pos)))
 }
 
 /// Parse a DNS QNAME at a specific offset and return only the name.
@@ -91,3 +149,143 @@ pub(crate) fn parse_qname_at(buf: &[u8], offset: usize) -> Result<String, SCloud
     let (name, _consumed) = parse_qname(&buf, offset)?;
     Ok(name)
 }
+
+/// Encode a domain name as an uncompressed sequence of DNS labels.
+///
+/// A trailing dot is accepted, and both `""` and `"."` encode the root name.
+/// Escapes are decoded, so that a name read by `parse_qname` is written
+/// back as it was on the wire.
+///
+/// # Exemple :
+/// ```
+/// let mut buf = Vec::new();
+/// write_qname(&mut buf, "example.com").unwrap();
+///
+/// assert_eq!(buf, b"\x07example\x03com\x00");
+/// ```
+///
+/// # Errors
+/// Returns `SCLOUD_QUESTION_SERIALIZATION_FAILED_QNAME_TOO_LONG` if a label
+/// is longer than 63 bytes, empty or badly escaped.
+pub(crate) fn write_qname(buf: &mut Vec<u8>, name: &str) -> Result<(), SCloudException> {
+    let mut rest = strip_root(name);
+    while !rest.is_empty() {
+        let (label, tail) = split_label(rest);
+        buf.extend_from_slice(&label_bytes(label)?);
+        rest = tail;
+    }
+    buf.push(0x00);
+    Ok(())
+}
+
+/// Wire form of a label in presentation format: its length byte followed
+/// by its bytes.
+///
+/// # Errors
+/// Returns `SCLOUD_QUESTION_SERIALIZATION_FAILED_QNAME_TOO_LONG` if the
+/// label is longer than 63 bytes, empty or badly escaped.
+pub(crate) fn label_bytes(label: &str) -> Result<Vec<u8>, SCloudException> {
+    let mut bytes = unescape(label)
+        .filter(|l| !l.is_empty() && l.len() <= 63)
+        .ok_or(SCloudException::SCLOUD_QUESTION_SERIALIZATION_FAILED_QNAME_TOO_LONG)?;
+    bytes.insert(0, bytes.len() as u8);
+    Ok(bytes)
+}
+
+/// First label of a name in presentation format and the rest of the name,
+/// split at the first dot that is not escaped.
+///
+/// # Exemple :
+/// ```
+/// assert_eq!(split_label("a\\.b.example"), ("a\\.b", "example"));
+/// assert_eq!(split_label("com"), ("com", ""));
+/// ```
+pub(crate) fn split_label(name: &str) -> (&str, &str) {
+    let bytes = name.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'.' => return (&name[..i], &name[i + 1..]),
+            _ => i += 1,
+        }
+    }
+    (name, "")
+}
+
+/// Presentation form of a name written in a zone file, the same as the
+/// one `parse_qname` gives for that name off the wire: escapes are
+/// decoded then written again, so `\065` becomes `A`, a byte outside
+/// printable ASCII `\DDD`, and `.` or `\` inside a label stay escaped. A
+/// trailing dot is kept.
+///
+/// Returns `None` for an empty label or a bad escape.
+///
+/// # Exemple :
+/// ```
+/// assert_eq!(normalize_name("\\119ww.a\\.b.").unwrap(), "www.a\\.b.");
+/// assert_eq!(normalize_name("café").unwrap(), "caf\\195\\169");
+/// assert!(normalize_name("a..b").is_none());
+/// ```
+pub(crate) fn normalize_name(name: &str) -> Option<String> {
+    let relative = strip_root(name);
+    let mut normalized = String::with_capacity(name.len());
+    let mut rest = relative;
+    while !rest.is_empty() {
+        let (label, tail) = split_label(rest);
+        let bytes = unescape(label).filter(|l| !l.is_empty())?;
+        if !normalized.is_empty() {
+            normalized.push('.');
+        }
+        push_label(&mut normalized, &bytes);
+        rest = tail;
+    }
+    if relative.len() < name.len() {
+        normalized.push('.');
+    }
+    Some(normalized)
+}
+
+/// `name` without its trailing dot, unless that dot is escaped.
+pub(crate) fn strip_root(name: &str) -> &str {
+    let Some(stripped) = name.strip_suffix('.') else {
+        return name;
+    };
+    if ends_with_escape(stripped) {
+        name
+    } else {
+        stripped
+    }
+}
+
+/// Whether `name` is `parent` or a name below it, both in presentation
+/// format without the trailing dot.
+pub(crate) fn is_subdomain(name: &str, parent: &str) -> bool {
+    if parent.is_empty() || name == parent {
+        return true;
+    }
+    // the dot before `parent` must not be escaped
+    name.strip_suffix(parent)
+        .and_then(|prefix| prefix.strip_suffix('.'))
+        .is_some_and(|prefix| !ends_with_escape(prefix))
+}
+
+/// Whether the character following `s` would be escaped: `s` ends with an
+/// odd number of `\`.
+fn ends_with_escape(s: &str) -> bool {
+    s.bytes().rev().take_while(|&b| b == b'\\').count() % 2 == 1
+}
+
+/// Append `label` to `name` in presentation format.
+pub(crate) fn push_label(name: &mut String, label: &[u8]) {
+    for &byte in label {
+        match byte {
+            b'.' | b'\\' => {
+                name.push('\\');
+                name.push(char::from(byte));
+            }
+            0x21..=0x7E => name.push(char::from(byte)),
+            _ => name.push_str(&format!("\\{:03}", byte)),
+        }
+    }
+}
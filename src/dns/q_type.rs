@@ -1,5 +1,6 @@
 use crate::exceptions::SCloudException;
 use std::convert::TryFrom;
+use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
 /// DNS Resource Record Types (QTYPE / TYPE).
@@ -51,6 +52,7 @@ pub(crate) enum DNSRecordType {
     NSEC3,
     NSEC3PARAM,
     OPENPGPKEY,
+    OPT,
     PTR,
     RP,
     RRSIG,
@@ -75,6 +77,40 @@ pub(crate) enum DNSRecordType {
     Unknown(u16),
 }
 
+impl DNSRecordType {
+    /// Record type from its mnemonic (`AAAA`, case-insensitive), its
+    /// RFC 3597 form (`TYPE28`) or its decimal value (`28`).
+    ///
+    /// # Exemple :
+    /// ```
+    /// use crate::dns::q_type::DNSRecordType;
+    ///
+    /// assert_eq!(DNSRecordType::from_name("mx"), Some(DNSRecordType::MX));
+    /// assert_eq!(DNSRecordType::from_name("TYPE28"), Some(DNSRecordType::AAAA));
+    /// assert_eq!(DNSRecordType::from_name("TYPE384"), Some(DNSRecordType::Unknown(384)));
+    /// assert_eq!(DNSRecordType::from_name("bogus"), None);
+    /// ```
+    pub(crate) fn from_name(name: &str) -> Option<DNSRecordType> {
+        let upper = name.to_ascii_uppercase();
+        let number = upper.strip_prefix("TYPE").unwrap_or(&upper);
+        if let Ok(v) = number.parse::<u16>() {
+            return DNSRecordType::try_from(v).ok();
+        }
+        DNSRecordType::iter()
+            .filter(|t| !matches!(t, DNSRecordType::Unknown(_)))
+            .find(|t| format!("{:?}", t) == upper)
+    }
+
+    /// Presentation name of the type `code`: its mnemonic, or `TYPE<code>`
+    /// for a type this enum does not know (RFC 3597 section 5).
+    pub(crate) fn name_of(code: u16) -> String {
+        match DNSRecordType::try_from(code) {
+            Ok(DNSRecordType::Unknown(_)) | Err(_) => format!("TYPE{}", code),
+            Ok(t) => format!("{:?}", t),
+        }
+    }
+}
+
 impl TryFrom<u16> for DNSRecordType {
     type Error = SCloudException;
 
@@ -114,6 +150,7 @@ impl TryFrom<u16> for DNSRecordType {
             50 => Ok(DNSRecordType::NSEC3),
             51 => Ok(DNSRecordType::NSEC3PARAM),
             61 => Ok(DNSRecordType::OPENPGPKEY),
+            41 => Ok(DNSRecordType::OPT),
             12 => Ok(DNSRecordType::PTR),
             17 => Ok(DNSRecordType::RP),
             46 => Ok(DNSRecordType::RRSIG),
@@ -130,7 +167,7 @@ impl TryFrom<u16> for DNSRecordType {
             16 => Ok(DNSRecordType::TXT),
             256 => Ok(DNSRecordType::URI),
             63 => Ok(DNSRecordType::ZONEMD),
-            _ => Err(SCloudException::SCLOUD_QTYPE_U16_FOR_DNSRECORDTYPE_UNKNOWN),
+            _ => Ok(DNSRecordType::Unknown(v)),
         }
     }
 }
@@ -174,6 +211,7 @@ impl TryFrom<DNSRecordType> for u16 {
             DNSRecordType::NSEC3 => Ok(50),
             DNSRecordType::NSEC3PARAM => Ok(51),
             DNSRecordType::OPENPGPKEY => Ok(61),
+            DNSRecordType::OPT => Ok(41),
             DNSRecordType::PTR => Ok(12),
             DNSRecordType::RP => Ok(17),
             DNSRecordType::RRSIG => Ok(46),
@@ -190,9 +228,7 @@ impl TryFrom<DNSRecordType> for u16 {
             DNSRecordType::TXT => Ok(16),
             DNSRecordType::URI => Ok(256),
             DNSRecordType::ZONEMD => Ok(63),
-            DNSRecordType::Unknown(_) => {
-                Err(SCloudException::SCLOUD_QTYPE_DNSRECORDTYPE_FOR_U16_UNKNOWN)
-            }
+            DNSRecordType::Unknown(v) => Ok(v),
         }
     }
 }
@@ -1,36 +1,89 @@
 use crate::dns::q_class::DNSClass;
 use crate::dns::q_type::DNSRecordType;
+use crate::dns::records::rdata::RData;
 
-/// A generic DNS resource record representation.
-///
-/// `DNSRecord` is a high-level abstraction used to represent DNS records
-/// independently of their wire format. It is typically produced after
-/// parsing DNS packets and can be used by resolvers, caches, or
-/// application-level logic.
-///
-/// Supported record types include:
-/// - A / AAAA
-/// - NS
-/// - MX
-/// - SRV
-/// - CAA
-/// - NAPTR
+pub(crate) mod rdata;
+
+/// A DNS resource record.
 ///
-/// Optional fields are populated depending on the record type.
+/// `DNSRecord` is the representation of a record independently of the
+/// message it was read from, typically a record of an authoritative zone.
+/// Its RDATA is typed (see `RData`).
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct DNSRecord {
     pub name: String,
     pub rtype: DNSRecordType,
     pub rclass: DNSClass,
     pub ttl: u32,
-    pub value: String,
-    pub priority: Option<u16>,       // MX, SRV
-    pub weight: Option<u16>,         // SRV
-    pub port: Option<u16>,           // SRV
-    pub flags: Option<u8>,           // NAPTR, CAA
-    pub tag: Option<String>,         // CAA
-    pub regex: Option<String>,       // NAPTR
-    pub replacement: Option<String>, // NAPTR
-    pub order: Option<u16>,          // NAPTR
-    pub preference: Option<u16>,     // NAPTR
+    pub rdata: RData,
+}
+
+/// Split a record value into its whitespace separated fields, keeping
+/// double-quoted strings together and unquoted.
+///
+/// Escapes (`\X`, `\DDD`) are kept as written, so that an escaped quote
+/// or blank does not end a field; see `unescape`.
+pub(crate) fn split_quoted(value: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut chars = value.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let mut field = String::new();
+        let quoted = c == '"';
+        if quoted {
+            chars.next();
+        }
+        while let Some(&c) = chars.peek() {
+            if (quoted && c == '"') || (!quoted && c.is_whitespace()) {
+                break;
+            }
+            chars.next();
+            field.push(c);
+            if c == '\\' {
+                field.extend(chars.next());
+            }
+        }
+        if quoted {
+            chars.next();
+        }
+        fields.push(field);
+    }
+
+    fields
+}
+
+/// Decode the escapes of a presentation field (RFC 1035 section 5.1):
+/// `\DDD` is the byte of decimal value DDD, `\X` is X itself.
+///
+/// Returns `None` for a `\DDD` above 255 or a trailing `\`.
+pub(crate) fn unescape(field: &str) -> Option<Vec<u8>> {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'\\' {
+            out.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+        let digits = bytes
+            .get(i + 1..i + 4)
+            .filter(|d| d.iter().all(u8::is_ascii_digit));
+        if let Some(digits) = digits {
+            let value = digits
+                .iter()
+                .fold(0u16, |v, d| v * 10 + u16::from(d - b'0'));
+            out.push(u8::try_from(value).ok()?);
+            i += 4;
+        } else {
+            out.push(*bytes.get(i + 1)?);
+            i += 2;
+        }
+    }
+    Some(out)
 }
@@ -0,0 +1,1328 @@
+use crate::dns::packet::writer::MessageWriter;
+use crate::dns::q_name::{normalize_name, parse_qname};
+use crate::dns::q_type::DNSRecordType;
+use crate::dns::records::{split_quoted, unescape};
+use crate::exceptions::SCloudException;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// RDATA of a resource record, decoded according to the record type.
+///
+/// Every section of a `DNSPacket` and every record of a zone holds its
+/// RDATA as an `RData`. Domain names are kept as read: names taken from the
+/// wire are absolute and have no trailing dot, names given to `from_text`
+/// may be relative: the zone parser makes them absolute (see `Zone::load`).
+///
+/// Types without a dedicated variant keep their raw RDATA in `Unknown` and
+/// are written back unchanged (RFC 3597).
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RData {
+    A(Ipv4Addr),
+    AAAA(Ipv6Addr),
+    NS(String),
+    CNAME(String),
+    PTR(String),
+    DNAME(String),
+    MX {
+        preference: u16,
+        exchange: String,
+    },
+    /// character-strings, without their length byte
+    TXT(Vec<Vec<u8>>),
+    SOA {
+        mname: String,
+        rname: String,
+        serial: u32,
+        refresh: u32,
+        retry: u32,
+        expire: u32,
+        minimum: u32,
+    },
+    SRV {
+        priority: u16,
+        weight: u16,
+        port: u16,
+        target: String,
+    },
+    CAA {
+        flags: u8,
+        tag: String,
+        value: Vec<u8>,
+    },
+    NAPTR {
+        order: u16,
+        preference: u16,
+        flags: Vec<u8>,
+        services: Vec<u8>,
+        regexp: Vec<u8>,
+        replacement: String,
+    },
+    SVCB(SvcbData),
+    HTTPS(SvcbData),
+    TLSA {
+        usage: u8,
+        selector: u8,
+        matching_type: u8,
+        data: Vec<u8>,
+    },
+    SSHFP {
+        algorithm: u8,
+        fp_type: u8,
+        fingerprint: Vec<u8>,
+    },
+    DS {
+        key_tag: u16,
+        algorithm: u8,
+        digest_type: u8,
+        digest: Vec<u8>,
+    },
+    DNSKEY {
+        flags: u16,
+        protocol: u8,
+        algorithm: u8,
+        public_key: Vec<u8>,
+    },
+    RRSIG {
+        type_covered: u16,
+        algorithm: u8,
+        labels: u8,
+        original_ttl: u32,
+        /// seconds since the epoch, modulo 2^32 (RFC 4034 section 3.1.5)
+        expiration: u32,
+        inception: u32,
+        key_tag: u16,
+        signer: String,
+        signature: Vec<u8>,
+    },
+    NSEC {
+        next: String,
+        types: Vec<u16>,
+    },
+    NSEC3 {
+        hash_algorithm: u8,
+        flags: u8,
+        iterations: u16,
+        salt: Vec<u8>,
+        next_hashed: Vec<u8>,
+        types: Vec<u16>,
+    },
+    NSEC3PARAM {
+        hash_algorithm: u8,
+        flags: u8,
+        iterations: u16,
+        salt: Vec<u8>,
+    },
+    Unknown(Vec<u8>),
+}
+
+/// RDATA shared by SVCB and HTTPS records (RFC 9460).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SvcbData {
+    pub(crate) priority: u16,
+    pub(crate) target: String,
+    /// SvcParams as (key, wire value), in increasing key order
+    pub(crate) params: Vec<(u16, Vec<u8>)>,
+}
+
+// RFC 9460 section 14.3.2 SvcParamKeys
+const SVC_PARAM_KEYS: [&str; 7] = [
+    "mandatory",
+    "alpn",
+    "no-default-alpn",
+    "port",
+    "ipv4hint",
+    "ech",
+    "ipv6hint",
+];
+
+impl RData {
+    /// Decode the `len` bytes of RDATA found at `buf[pos..]`.
+    ///
+    /// `buf` is the whole message, so that compressed names inside the
+    /// RDATA can be followed. The decoded names are uncompressed, so the
+    /// result no longer depends on the message it comes from.
+    ///
+    /// # Exemple :
+    /// ```
+    /// use crate::dns::q_type::DNSRecordType;
+    /// use crate::dns::records::rdata::RData;
+    ///
+    /// let rdata = RData::decode(DNSRecordType::A, &[93, 184, 216, 34], 0, 4).unwrap();
+    ///
+    /// assert_eq!(rdata, RData::A("93.184.216.34".parse().unwrap()));
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `SCLOUD_RDATA_MALFORMED` if the RDATA does not fit in the
+    /// message or does not match its type, or the error of `parse_qname`
+    /// for a malformed name.
+    pub(crate) fn decode(
+        rtype: DNSRecordType,
+        buf: &[u8],
+        pos: usize,
+        len: usize,
+    ) -> Result<RData, SCloudException> {
+        if pos + len > buf.len() {
+            return Err(SCloudException::SCLOUD_RDATA_MALFORMED);
+        }
+        let mut r = Reader {
+            buf,
+            pos,
+            end: pos + len,
+        };
+
+        let rdata = match rtype {
+            DNSRecordType::A => RData::A(Ipv4Addr::from(r.array::<4>()?)),
+            DNSRecordType::AAAA => RData::AAAA(Ipv6Addr::from(r.array::<16>()?)),
+            DNSRecordType::NS => RData::NS(r.name()?),
+            DNSRecordType::CNAME => RData::CNAME(r.name()?),
+            DNSRecordType::PTR => RData::PTR(r.name()?),
+            DNSRecordType::DNAME => RData::DNAME(r.name()?),
+            DNSRecordType::MX => RData::MX {
+                preference: r.u16()?,
+                exchange: r.name()?,
+            },
+            DNSRecordType::TXT => {
+                let mut strings = Vec::new();
+                while r.pos < r.end {
+                    strings.push(r.character_string()?);
+                }
+                RData::TXT(strings)
+            }
+            DNSRecordType::SOA => RData::SOA {
+                mname: r.name()?,
+                rname: r.name()?,
+                serial: r.u32()?,
+                refresh: r.u32()?,
+                retry: r.u32()?,
+                expire: r.u32()?,
+                minimum: r.u32()?,
+            },
+            DNSRecordType::SRV => RData::SRV {
+                priority: r.u16()?,
+                weight: r.u16()?,
+                port: r.u16()?,
+                target: r.name()?,
+            },
+            DNSRecordType::CAA => {
+                let flags = r.u8()?;
+                let tag = r.character_string()?;
+                RData::CAA {
+                    flags,
+                    tag: String::from_utf8(tag)
+                        .map_err(|_| SCloudException::SCLOUD_RDATA_MALFORMED)?,
+                    value: r.rest().to_vec(),
+                }
+            }
+            DNSRecordType::NAPTR => RData::NAPTR {
+                order: r.u16()?,
+                preference: r.u16()?,
+                flags: r.character_string()?,
+                services: r.character_string()?,
+                regexp: r.character_string()?,
+                replacement: r.name()?,
+            },
+            DNSRecordType::SVCB => RData::SVCB(r.svcb()?),
+            DNSRecordType::HTTPS => RData::HTTPS(r.svcb()?),
+            DNSRecordType::TLSA => RData::TLSA {
+                usage: r.u8()?,
+                selector: r.u8()?,
+                matching_type: r.u8()?,
+                data: r.rest().to_vec(),
+            },
+            DNSRecordType::SSHFP => RData::SSHFP {
+                algorithm: r.u8()?,
+                fp_type: r.u8()?,
+                fingerprint: r.rest().to_vec(),
+            },
+            DNSRecordType::DS => RData::DS {
+                key_tag: r.u16()?,
+                algorithm: r.u8()?,
+                digest_type: r.u8()?,
+                digest: r.rest().to_vec(),
+            },
+            DNSRecordType::DNSKEY => RData::DNSKEY {
+                flags: r.u16()?,
+                protocol: r.u8()?,
+                algorithm: r.u8()?,
+                public_key: r.rest().to_vec(),
+            },
+            DNSRecordType::RRSIG => RData::RRSIG {
+                type_covered: r.u16()?,
+                algorithm: r.u8()?,
+                labels: r.u8()?,
+                original_ttl: r.u32()?,
+                expiration: r.u32()?,
+                inception: r.u32()?,
+                key_tag: r.u16()?,
+                signer: r.name()?,
+                signature: r.rest().to_vec(),
+            },
+            DNSRecordType::NSEC => RData::NSEC {
+                next: r.name()?,
+                types: decode_type_bitmap(r.rest())?,
+            },
+            DNSRecordType::NSEC3 => RData::NSEC3 {
+                hash_algorithm: r.u8()?,
+                flags: r.u8()?,
+                iterations: r.u16()?,
+                salt: r.character_string()?,
+                next_hashed: r.character_string()?,
+                types: decode_type_bitmap(r.rest())?,
+            },
+            DNSRecordType::NSEC3PARAM => RData::NSEC3PARAM {
+                hash_algorithm: r.u8()?,
+                flags: r.u8()?,
+                iterations: r.u16()?,
+                salt: r.character_string()?,
+            },
+            _ => RData::Unknown(r.rest().to_vec()),
+        };
+
+        if r.pos != r.end {
+            return Err(SCloudException::SCLOUD_RDATA_MALFORMED);
+        }
+        Ok(rdata)
+    }
+
+    /// Append the wire format of the RDATA to `w`.
+    ///
+    /// Names of NS, CNAME, PTR, MX and SOA records may be compressed by
+    /// `w`; the other types always carry their names in full (RFC 3597
+    /// section 4).
+    ///
+    /// # Errors
+    /// Returns `SCLOUD_RDATA_MALFORMED` if a string is longer than 255
+    /// bytes, or the error of `write_qname` for an invalid name.
+    pub(crate) fn encode(&self, w: &mut MessageWriter) -> Result<(), SCloudException> {
+        match self {
+            RData::A(ip) => w.put(&ip.octets()),
+            RData::AAAA(ip) => w.put(&ip.octets()),
+            RData::NS(name) | RData::CNAME(name) | RData::PTR(name) => w.write_name(name)?,
+            RData::DNAME(name) => w.write_name_uncompressed(name)?,
+            RData::MX {
+                preference,
+                exchange,
+            } => {
+                w.put(&preference.to_be_bytes());
+                w.write_name(exchange)?;
+            }
+            RData::TXT(strings) => {
+                for s in strings {
+                    write_character_string(w, s)?;
+                }
+            }
+            RData::SOA {
+                mname,
+                rname,
+                serial,
+                refresh,
+                retry,
+                expire,
+                minimum,
+            } => {
+                w.write_name(mname)?;
+                w.write_name(rname)?;
+                for n in [serial, refresh, retry, expire, minimum] {
+                    w.put(&n.to_be_bytes());
+                }
+            }
+            RData::SRV {
+                priority,
+                weight,
+                port,
+                target,
+            } => {
+                for n in [priority, weight, port] {
+                    w.put(&n.to_be_bytes());
+                }
+                w.write_name_uncompressed(target)?;
+            }
+            RData::CAA { flags, tag, value } => {
+                w.put_u8(*flags);
+                write_character_string(w, tag.as_bytes())?;
+                w.put(value);
+            }
+            RData::NAPTR {
+                order,
+                preference,
+                flags,
+                services,
+                regexp,
+                replacement,
+            } => {
+                w.put(&order.to_be_bytes());
+                w.put(&preference.to_be_bytes());
+                for s in [flags, services, regexp] {
+                    write_character_string(w, s)?;
+                }
+                w.write_name_uncompressed(replacement)?;
+            }
+            RData::SVCB(svcb) | RData::HTTPS(svcb) => {
+                w.put(&svcb.priority.to_be_bytes());
+                w.write_name_uncompressed(&svcb.target)?;
+                for (key, value) in &svcb.params {
+                    let len = u16::try_from(value.len())
+                        .map_err(|_| SCloudException::SCLOUD_RDATA_MALFORMED)?;
+                    w.put(&key.to_be_bytes());
+                    w.put(&len.to_be_bytes());
+                    w.put(value);
+                }
+            }
+            RData::TLSA {
+                usage,
+                selector,
+                matching_type,
+                data,
+            } => {
+                w.put(&[*usage, *selector, *matching_type]);
+                w.put(data);
+            }
+            RData::SSHFP {
+                algorithm,
+                fp_type,
+                fingerprint,
+            } => {
+                w.put(&[*algorithm, *fp_type]);
+                w.put(fingerprint);
+            }
+            RData::DS {
+                key_tag,
+                algorithm,
+                digest_type,
+                digest,
+            } => {
+                w.put(&key_tag.to_be_bytes());
+                w.put(&[*algorithm, *digest_type]);
+                w.put(digest);
+            }
+            RData::DNSKEY {
+                flags,
+                protocol,
+                algorithm,
+                public_key,
+            } => {
+                w.put(&flags.to_be_bytes());
+                w.put(&[*protocol, *algorithm]);
+                w.put(public_key);
+            }
+            RData::RRSIG {
+                type_covered,
+                algorithm,
+                labels,
+                original_ttl,
+                expiration,
+                inception,
+                key_tag,
+                signer,
+                signature,
+            } => {
+                w.put(&type_covered.to_be_bytes());
+                w.put(&[*algorithm, *labels]);
+                for n in [original_ttl, expiration, inception] {
+                    w.put(&n.to_be_bytes());
+                }
+                w.put(&key_tag.to_be_bytes());
+                w.write_name_uncompressed(signer)?;
+                w.put(signature);
+            }
+            RData::NSEC { next, types } => {
+                w.write_name_uncompressed(next)?;
+                encode_type_bitmap(w, types);
+            }
+            RData::NSEC3 {
+                hash_algorithm,
+                flags,
+                iterations,
+                salt,
+                next_hashed,
+                types,
+            } => {
+                w.put(&[*hash_algorithm, *flags]);
+                w.put(&iterations.to_be_bytes());
+                write_character_string(w, salt)?;
+                write_character_string(w, next_hashed)?;
+                encode_type_bitmap(w, types);
+            }
+            RData::NSEC3PARAM {
+                hash_algorithm,
+                flags,
+                iterations,
+                salt,
+            } => {
+                w.put(&[*hash_algorithm, *flags]);
+                w.put(&iterations.to_be_bytes());
+                write_character_string(w, salt)?;
+            }
+            RData::Unknown(raw) => w.put(raw),
+        }
+        Ok(())
+    }
+
+    /// Wire format of the RDATA, names uncompressed.
+    ///
+    /// # Errors
+    /// Same as `encode`.
+    pub(crate) fn to_bytes(&self) -> Result<Vec<u8>, SCloudException> {
+        let mut w = MessageWriter::uncompressed();
+        self.encode(&mut w)?;
+        Ok(w.finish())
+    }
+
+    /// Parse the presentation format of an RDATA of type `rtype`, as found
+    /// in a zone file (RFC 1035 section 5.1).
+    ///
+    /// The generic `\# <length> <hex>` form of RFC 3597 is accepted for
+    /// every type. Names are kept as written, relative or not.
+    ///
+    /// # Exemple :
+    /// ```
+    /// let rdata = RData::from_text(DNSRecordType::MX, "10 mail").unwrap();
+    ///
+    /// assert_eq!(
+    ///     rdata,
+    ///     RData::MX { preference: 10, exchange: "mail".to_string() }
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `SCLOUD_ZONE_INVALID_RECORD_RDATA` if the text does not match
+    /// the record type, or if the type has no presentation format here.
+    #[allow(unused)]
+    pub(crate) fn from_text(rtype: DNSRecordType, text: &str) -> Result<RData, SCloudException> {
+        RData::from_fields(rtype, &split_quoted(text))
+    }
+
+    /// Same as `from_text`, for an RDATA already split into fields (e.g. by
+    /// the zone file tokenizer): quotes removed, escapes kept.
+    ///
+    /// # Errors
+    /// Same as `from_text`.
+    pub(crate) fn from_fields(
+        rtype: DNSRecordType,
+        fields: &[String],
+    ) -> Result<RData, SCloudException> {
+        let invalid = || SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA;
+        let mut f = Fields { fields, pos: 0 };
+
+        if fields.first().map(String::as_str) == Some("\\#") {
+            f.pos = 1;
+            let len: usize = f.number()?;
+            let raw = hex_decode(&f.rest().concat()).ok_or_else(invalid)?;
+            if raw.len() != len {
+                return Err(invalid());
+            }
+            return RData::decode(rtype, &raw, 0, len).map_err(|_| invalid());
+        }
+
+        let rdata = match rtype {
+            DNSRecordType::A => RData::A(f.next()?.parse().map_err(|_| invalid())?),
+            DNSRecordType::AAAA => RData::AAAA(f.next()?.parse().map_err(|_| invalid())?),
+            DNSRecordType::NS => RData::NS(f.name()?),
+            DNSRecordType::CNAME => RData::CNAME(f.name()?),
+            DNSRecordType::PTR => RData::PTR(f.name()?),
+            DNSRecordType::DNAME => RData::DNAME(f.name()?),
+            DNSRecordType::MX => RData::MX {
+                preference: f.number()?,
+                exchange: f.name()?,
+            },
+            DNSRecordType::TXT => {
+                let mut strings = Vec::new();
+                while f.pos < fields.len() {
+                    strings.push(f.string()?);
+                }
+                if strings.is_empty() {
+                    return Err(invalid());
+                }
+                RData::TXT(strings)
+            }
+            DNSRecordType::SOA => RData::SOA {
+                mname: f.name()?,
+                rname: f.name()?,
+                serial: f.number()?,
+                refresh: f.number()?,
+                retry: f.number()?,
+                expire: f.number()?,
+                minimum: f.number()?,
+            },
+            DNSRecordType::SRV => RData::SRV {
+                priority: f.number()?,
+                weight: f.number()?,
+                port: f.number()?,
+                target: f.name()?,
+            },
+            DNSRecordType::CAA => {
+                let flags = f.number()?;
+                let tag = f.next()?.to_string();
+                if tag.is_empty()
+                    || tag.len() > 255
+                    || !tag.bytes().all(|b| b.is_ascii_alphanumeric())
+                {
+                    return Err(invalid());
+                }
+                RData::CAA {
+                    flags,
+                    tag,
+                    value: unescape(f.next()?).ok_or_else(invalid)?,
+                }
+            }
+            DNSRecordType::NAPTR => RData::NAPTR {
+                order: f.number()?,
+                preference: f.number()?,
+                flags: f.string()?,
+                services: f.string()?,
+                regexp: f.string()?,
+                replacement: f.name()?,
+            },
+            DNSRecordType::SVCB => RData::SVCB(f.svcb()?),
+            DNSRecordType::HTTPS => RData::HTTPS(f.svcb()?),
+            DNSRecordType::TLSA => RData::TLSA {
+                usage: f.number()?,
+                selector: f.number()?,
+                matching_type: f.number()?,
+                data: f.hex()?,
+            },
+            DNSRecordType::SSHFP => RData::SSHFP {
+                algorithm: f.number()?,
+                fp_type: f.number()?,
+                fingerprint: f.hex()?,
+            },
+            DNSRecordType::DS => RData::DS {
+                key_tag: f.number()?,
+                algorithm: f.number()?,
+                digest_type: f.number()?,
+                digest: f.hex()?,
+            },
+            DNSRecordType::DNSKEY => RData::DNSKEY {
+                flags: f.number()?,
+                protocol: f.number()?,
+                algorithm: f.number()?,
+                public_key: f.base64()?,
+            },
+            DNSRecordType::RRSIG => RData::RRSIG {
+                type_covered: f.rtype()?,
+                algorithm: f.number()?,
+                labels: f.number()?,
+                original_ttl: f.number()?,
+                expiration: f.time()?,
+                inception: f.time()?,
+                key_tag: f.number()?,
+                signer: f.name()?,
+                signature: f.base64()?,
+            },
+            DNSRecordType::NSEC => RData::NSEC {
+                next: f.name()?,
+                types: f.types()?,
+            },
+            DNSRecordType::NSEC3 => RData::NSEC3 {
+                hash_algorithm: f.number()?,
+                flags: f.number()?,
+                iterations: f.number()?,
+                salt: f.salt()?,
+                next_hashed: base32hex_decode(f.next()?).ok_or_else(invalid)?,
+                types: f.types()?,
+            },
+            DNSRecordType::NSEC3PARAM => RData::NSEC3PARAM {
+                hash_algorithm: f.number()?,
+                flags: f.number()?,
+                iterations: f.number()?,
+                salt: f.salt()?,
+            },
+            _ => return Err(invalid()),
+        };
+
+        if f.pos < fields.len() {
+            return Err(invalid());
+        }
+        Ok(rdata)
+    }
+
+    /// The host name the record points at: target of NS, CNAME, PTR,
+    /// DNAME, MX, SRV, SVCB and HTTPS records.
+    pub(crate) fn target(&self) -> Option<&str> {
+        match self {
+            RData::NS(name) | RData::CNAME(name) | RData::PTR(name) | RData::DNAME(name) => {
+                Some(name)
+            }
+            RData::MX { exchange, .. } => Some(exchange),
+            RData::SRV { target, .. } => Some(target),
+            RData::SVCB(svcb) | RData::HTTPS(svcb) => Some(&svcb.target),
+            _ => None,
+        }
+    }
+
+    /// Replace every domain name of the RDATA by `f(name)`.
+    pub(crate) fn map_names(&mut self, f: impl Fn(&str) -> String) {
+        match self {
+            RData::NS(name)
+            | RData::CNAME(name)
+            | RData::PTR(name)
+            | RData::DNAME(name)
+            | RData::MX { exchange: name, .. }
+            | RData::SRV { target: name, .. }
+            | RData::NAPTR {
+                replacement: name, ..
+            }
+            | RData::RRSIG { signer: name, .. }
+            | RData::NSEC { next: name, .. } => *name = f(name),
+            RData::SVCB(svcb) | RData::HTTPS(svcb) => svcb.target = f(&svcb.target),
+            RData::SOA { mname, rname, .. } => {
+                *mname = f(mname);
+                *rname = f(rname);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Display for RData {
+    /// Presentation format of the RDATA (RFC 1035 section 5.1), names
+    /// fully qualified.
+    fn fmt(&self, out: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RData::A(ip) => write!(out, "{}", ip),
+            RData::AAAA(ip) => write!(out, "{}", ip),
+            RData::NS(name) | RData::CNAME(name) | RData::PTR(name) | RData::DNAME(name) => {
+                write!(out, "{}", fqdn(name))
+            }
+            RData::MX {
+                preference,
+                exchange,
+            } => write!(out, "{} {}", preference, fqdn(exchange)),
+            RData::TXT(strings) => {
+                let strings: Vec<String> = strings.iter().map(|s| quoted(s)).collect();
+                write!(out, "{}", strings.join(" "))
+            }
+            RData::SOA {
+                mname,
+                rname,
+                serial,
+                refresh,
+                retry,
+                expire,
+                minimum,
+            } => write!(
+                out,
+                "{} {} {} {} {} {} {}",
+                fqdn(mname),
+                fqdn(rname),
+                serial,
+                refresh,
+                retry,
+                expire,
+                minimum
+            ),
+            RData::SRV {
+                priority,
+                weight,
+                port,
+                target,
+            } => write!(out, "{} {} {} {}", priority, weight, port, fqdn(target)),
+            RData::CAA { flags, tag, value } => write!(out, "{} {} {}", flags, tag, quoted(value)),
+            RData::NAPTR {
+                order,
+                preference,
+                flags,
+                services,
+                regexp,
+                replacement,
+            } => write!(
+                out,
+                "{} {} {} {} {} {}",
+                order,
+                preference,
+                quoted(flags),
+                quoted(services),
+                quoted(regexp),
+                fqdn(replacement)
+            ),
+            RData::SVCB(svcb) | RData::HTTPS(svcb) => {
+                write!(out, "{} {}", svcb.priority, fqdn(&svcb.target))?;
+                for (key, value) in &svcb.params {
+                    write!(out, " {}", svc_param_to_text(*key, value))?;
+                }
+                Ok(())
+            }
+            RData::TLSA {
+                usage,
+                selector,
+                matching_type,
+                data,
+            } => write!(
+                out,
+                "{} {} {} {}",
+                usage,
+                selector,
+                matching_type,
+                hex_encode(data)
+            ),
+            RData::SSHFP {
+                algorithm,
+                fp_type,
+                fingerprint,
+            } => write!(out, "{} {} {}", algorithm, fp_type, hex_encode(fingerprint)),
+            RData::DS {
+                key_tag,
+                algorithm,
+                digest_type,
+                digest,
+            } => write!(
+                out,
+                "{} {} {} {}",
+                key_tag,
+                algorithm,
+                digest_type,
+                hex_encode(digest)
+            ),
+            RData::DNSKEY {
+                flags,
+                protocol,
+                algorithm,
+                public_key,
+            } => write!(
+                out,
+                "{} {} {} {}",
+                flags,
+                protocol,
+                algorithm,
+                STANDARD.encode(public_key)
+            ),
+            RData::RRSIG {
+                type_covered,
+                algorithm,
+                labels,
+                original_ttl,
+                expiration,
+                inception,
+                key_tag,
+                signer,
+                signature,
+            } => write!(
+                out,
+                "{} {} {} {} {} {} {} {} {}",
+                DNSRecordType::name_of(*type_covered),
+                algorithm,
+                labels,
+                original_ttl,
+                time_to_text(*expiration),
+                time_to_text(*inception),
+                key_tag,
+                fqdn(signer),
+                STANDARD.encode(signature)
+            ),
+            RData::NSEC { next, types } => {
+                write!(out, "{}", fqdn(next))?;
+                for t in types {
+                    write!(out, " {}", DNSRecordType::name_of(*t))?;
+                }
+                Ok(())
+            }
+            RData::NSEC3 {
+                hash_algorithm,
+                flags,
+                iterations,
+                salt,
+                next_hashed,
+                types,
+            } => {
+                write!(
+                    out,
+                    "{} {} {} {} {}",
+                    hash_algorithm,
+                    flags,
+                    iterations,
+                    salt_to_text(salt),
+                    base32hex_encode(next_hashed)
+                )?;
+                for t in types {
+                    write!(out, " {}", DNSRecordType::name_of(*t))?;
+                }
+                Ok(())
+            }
+            RData::NSEC3PARAM {
+                hash_algorithm,
+                flags,
+                iterations,
+                salt,
+            } => write!(
+                out,
+                "{} {} {} {}",
+                hash_algorithm,
+                flags,
+                iterations,
+                salt_to_text(salt)
+            ),
+            RData::Unknown(raw) if raw.is_empty() => write!(out, "\\# 0"),
+            RData::Unknown(raw) => write!(out, "\\# {} {}", raw.len(), hex_encode(raw)),
+        }
+    }
+}
+
+/// Cursor over the RDATA of one record inside a message.
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    end: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8], SCloudException> {
+        if self.pos + n > self.end {
+            return Err(SCloudException::SCLOUD_RDATA_MALFORMED);
+        }
+        let bytes = &self.buf[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], SCloudException> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> Result<u8, SCloudException> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, SCloudException> {
+        Ok(u16::from_be_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, SCloudException> {
+        Ok(u32::from_be_bytes(self.array()?))
+    }
+
+    fn rest(&mut self) -> &[u8] {
+        let bytes = &self.buf[self.pos..self.end];
+        self.pos = self.end;
+        bytes
+    }
+
+    fn character_string(&mut self) -> Result<Vec<u8>, SCloudException> {
+        let len = usize::from(self.u8()?);
+        Ok(self.take(len)?.to_vec())
+    }
+
+    fn name(&mut self) -> Result<String, SCloudException> {
+        if self.pos >= self.end {
+            return Err(SCloudException::SCLOUD_RDATA_MALFORMED);
+        }
+        let (name, next) = parse_qname(self.buf, self.pos)?;
+        if next > self.end {
+            return Err(SCloudException::SCLOUD_RDATA_MALFORMED);
+        }
+        self.pos = next;
+        Ok(name)
+    }
+
+    fn svcb(&mut self) -> Result<SvcbData, SCloudException> {
+        let priority = self.u16()?;
+        let target = self.name()?;
+        let mut params: Vec<(u16, Vec<u8>)> = Vec::new();
+        while self.pos < self.end {
+            let key = self.u16()?;
+            let len = usize::from(self.u16()?);
+            // RFC 9460 section 2.2: keys are in strictly increasing order
+            if params.last().is_some_and(|(last, _)| *last >= key) {
+                return Err(SCloudException::SCLOUD_RDATA_MALFORMED);
+            }
+            params.push((key, self.take(len)?.to_vec()));
+        }
+        Ok(SvcbData {
+            priority,
+            target,
+            params,
+        })
+    }
+}
+
+/// Cursor over the presentation fields of one RDATA.
+struct Fields<'a> {
+    fields: &'a [String],
+    pos: usize,
+}
+
+impl Fields<'_> {
+    fn next(&mut self) -> Result<&str, SCloudException> {
+        let field = self
+            .fields
+            .get(self.pos)
+            .ok_or(SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA)?;
+        self.pos += 1;
+        Ok(field)
+    }
+
+    fn rest(&mut self) -> &[String] {
+        let rest = &self.fields[self.pos.min(self.fields.len())..];
+        self.pos = self.fields.len();
+        rest
+    }
+
+    fn number<T: std::str::FromStr>(&mut self) -> Result<T, SCloudException> {
+        self.next()?
+            .parse()
+            .map_err(|_| SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA)
+    }
+
+    fn name(&mut self) -> Result<String, SCloudException> {
+        normalize_name(self.next()?).ok_or(SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA)
+    }
+
+    /// A character-string: at most 255 bytes once unescaped.
+    fn string(&mut self) -> Result<Vec<u8>, SCloudException> {
+        unescape(self.next()?)
+            .filter(|s| s.len() <= 255)
+            .ok_or(SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA)
+    }
+
+    /// A type mnemonic, or `TYPE<n>` for any code (RFC 3597 section 5).
+    fn rtype(&mut self) -> Result<u16, SCloudException> {
+        let s = self.next()?;
+        DNSRecordType::from_name(s)
+            .and_then(|t| u16::try_from(t).ok())
+            .ok_or(SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA)
+    }
+
+    fn types(&mut self) -> Result<Vec<u16>, SCloudException> {
+        let mut types = Vec::new();
+        while self.pos < self.fields.len() {
+            types.push(self.rtype()?);
+        }
+        Ok(types)
+    }
+
+    /// The remaining fields as one hex string, blanks allowed.
+    fn hex(&mut self) -> Result<Vec<u8>, SCloudException> {
+        hex_decode(&self.rest().concat()).ok_or(SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA)
+    }
+
+    /// The remaining fields as one base64 string, blanks allowed.
+    fn base64(&mut self) -> Result<Vec<u8>, SCloudException> {
+        STANDARD
+            .decode(self.rest().concat())
+            .map_err(|_| SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA)
+    }
+
+    /// `-` for no salt, hex otherwise (RFC 5155 section 3.3).
+    fn salt(&mut self) -> Result<Vec<u8>, SCloudException> {
+        match self.next()? {
+            "-" => Ok(Vec::new()),
+            s => hex_decode(s).ok_or(SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA),
+        }
+    }
+
+    /// `YYYYMMDDHHmmSS` in UTC, or seconds since the epoch (RFC 4034
+    /// section 3.2).
+    fn time(&mut self) -> Result<u32, SCloudException> {
+        let s = self.next()?;
+        text_to_time(s).ok_or(SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA)
+    }
+
+    fn svcb(&mut self) -> Result<SvcbData, SCloudException> {
+        let invalid = || SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA;
+        let priority = self.number()?;
+        let target = self.name()?;
+        let mut params = Vec::new();
+        for param in self.rest() {
+            let (key, value) = match param.split_once('=') {
+                Some((key, value)) => (key, value.trim_matches('"')),
+                None => (param.as_str(), ""),
+            };
+            let key = svc_param_key(key).ok_or_else(invalid)?;
+            let value = svc_param_from_text(key, value).ok_or_else(invalid)?;
+            params.push((key, value));
+        }
+        params.sort_by_key(|(key, _)| *key);
+        if params.windows(2).any(|w| w[0].0 == w[1].0) {
+            return Err(invalid());
+        }
+        Ok(SvcbData {
+            priority,
+            target,
+            params,
+        })
+    }
+}
+
+fn svc_param_key(name: &str) -> Option<u16> {
+    if let Some(i) = SVC_PARAM_KEYS.iter().position(|k| *k == name) {
+        return Some(i as u16);
+    }
+    name.strip_prefix("key")?.parse().ok()
+}
+
+fn svc_param_name(key: u16) -> String {
+    match SVC_PARAM_KEYS.get(usize::from(key)) {
+        Some(name) => name.to_string(),
+        None => format!("key{}", key),
+    }
+}
+
+fn svc_param_from_text(key: u16, value: &str) -> Option<Vec<u8>> {
+    let list = || value.split(',').filter(|v| !v.is_empty());
+    let mut out = Vec::new();
+    match key {
+        0 => {
+            for k in list() {
+                out.extend_from_slice(&svc_param_key(k)?.to_be_bytes());
+            }
+        }
+        1 => {
+            for id in list() {
+                out.push(u8::try_from(id.len()).ok()?);
+                out.extend_from_slice(id.as_bytes());
+            }
+        }
+        2 => {}
+        3 => out.extend_from_slice(&value.parse::<u16>().ok()?.to_be_bytes()),
+        4 => {
+            for ip in list() {
+                out.extend_from_slice(&ip.parse::<Ipv4Addr>().ok()?.octets());
+            }
+        }
+        5 => out = STANDARD.decode(value).ok()?,
+        6 => {
+            for ip in list() {
+                out.extend_from_slice(&ip.parse::<Ipv6Addr>().ok()?.octets());
+            }
+        }
+        _ => out.extend_from_slice(value.as_bytes()),
+    }
+    Some(out)
+}
+
+fn svc_param_to_text(key: u16, value: &[u8]) -> String {
+    let name = svc_param_name(key);
+    let text = match key {
+        0 if value.len().is_multiple_of(2) => Some(
+            value
+                .chunks(2)
+                .map(|k| svc_param_name(u16::from_be_bytes([k[0], k[1]])))
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        1 => {
+            let mut ids = Vec::new();
+            let mut rest = value;
+            while let Some((&len, tail)) = rest.split_first() {
+                let Some(id) = tail.get(..usize::from(len)) else {
+                    break;
+                };
+                ids.push(String::from_utf8_lossy(id).into_owned());
+                rest = &tail[usize::from(len)..];
+            }
+            rest.is_empty().then(|| ids.join(","))
+        }
+        2 if value.is_empty() => return name,
+        3 if value.len() == 2 => Some(u16::from_be_bytes([value[0], value[1]]).to_string()),
+        4 if value.len().is_multiple_of(4) => Some(
+            value
+                .chunks(4)
+                .map(|ip| Ipv4Addr::new(ip[0], ip[1], ip[2], ip[3]).to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        5 => Some(STANDARD.encode(value)),
+        6 if value.len().is_multiple_of(16) => Some(
+            value
+                .chunks(16)
+                .map(|ip| Ipv6Addr::from(<[u8; 16]>::try_from(ip).unwrap()).to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        _ => None,
+    };
+    match text {
+        Some(text) => format!("{}={}", name, text),
+        None => format!("key{}={}", key, quoted(value)),
+    }
+}
+
+fn write_character_string(w: &mut MessageWriter, s: &[u8]) -> Result<(), SCloudException> {
+    let len = u8::try_from(s.len()).map_err(|_| SCloudException::SCLOUD_RDATA_MALFORMED)?;
+    w.put_u8(len);
+    w.put(s);
+    Ok(())
+}
+
+/// RFC 4034 section 4.1.2 type bit maps.
+fn encode_type_bitmap(w: &mut MessageWriter, types: &[u16]) {
+    let mut types = types.to_vec();
+    types.sort_unstable();
+    types.dedup();
+    for window in types.chunk_by(|a, b| a >> 8 == b >> 8) {
+        let last = usize::from(window[window.len() - 1] as u8);
+        let mut bitmap = vec![0u8; last / 8 + 1];
+        for t in window {
+            let low = usize::from(*t as u8);
+            bitmap[low / 8] |= 0x80 >> (low % 8);
+        }
+        w.put_u8((window[0] >> 8) as u8);
+        w.put_u8(bitmap.len() as u8);
+        w.put(&bitmap);
+    }
+}
+
+fn decode_type_bitmap(mut bytes: &[u8]) -> Result<Vec<u16>, SCloudException> {
+    let mut types = Vec::new();
+    let mut previous = None;
+    while let [window, len, rest @ ..] = bytes {
+        let len = usize::from(*len);
+        // windows come in increasing order, each at most once
+        if len == 0 || len > 32 || rest.len() < len || previous >= Some(*window) {
+            return Err(SCloudException::SCLOUD_RDATA_MALFORMED);
+        }
+        previous = Some(*window);
+        for (i, byte) in rest[..len].iter().enumerate() {
+            for bit in 0..8 {
+                if byte & (0x80 >> bit) != 0 {
+                    types.push(u16::from(*window) << 8 | (i * 8 + bit) as u16);
+                }
+            }
+        }
+        bytes = &rest[len..];
+    }
+    if !bytes.is_empty() {
+        return Err(SCloudException::SCLOUD_RDATA_MALFORMED);
+    }
+    Ok(types)
+}
+
+fn fqdn(name: &str) -> String {
+    format!("{}.", name.trim_end_matches('.'))
+}
+
+/// RFC 1035 character-string in quotes, with `"` and `\` escaped and
+/// non-printable bytes as `\DDD`.
+fn quoted(s: &[u8]) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for &b in s {
+        match b {
+            b'"' | b'\\' => {
+                out.push('\\');
+                out.push(b as char);
+            }
+            0x20..=0x7e => out.push(b as char),
+            _ => out.push_str(&format!("\\{:03}", b)),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn salt_to_text(salt: &[u8]) -> String {
+    if salt.is_empty() {
+        "-".to_string()
+    } else {
+        hex_encode(salt)
+    }
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+fn hex_decode(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.is_ascii() {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
+
+const BASE32HEX: &[u8; 32] = b"0123456789abcdefghijklmnopqrstuv";
+
+/// RFC 4648 section 7 base32hex, unpadded as in NSEC3 records.
+fn base32hex_encode(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let (mut acc, mut bits) = (0u32, 0);
+    for &b in bytes {
+        acc = (acc << 8 | u32::from(b)) & 0xffff;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32HEX[(acc >> bits & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32HEX[(acc << (5 - bits) & 31) as usize] as char);
+    }
+    out
+}
+
+fn base32hex_decode(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let (mut acc, mut bits) = (0u32, 0);
+    for c in s.bytes() {
+        let v = BASE32HEX
+            .iter()
+            .position(|&d| d == c.to_ascii_lowercase())? as u32;
+        acc = (acc << 5 | v) & 0xffff;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+fn time_to_text(t: u32) -> String {
+    let secs = i64::from(t);
+    let (y, m, d) = civil_from_days(secs.div_euclid(86_400));
+    let s = secs.rem_euclid(86_400);
+    format!(
+        "{:04}{:02}{:02}{:02}{:02}{:02}",
+        y,
+        m,
+        d,
+        s / 3600,
+        s / 60 % 60,
+        s % 60
+    )
+}
+
+fn text_to_time(s: &str) -> Option<u32> {
+    if s.len() != 14 {
+        return s.parse().ok();
+    }
+    let n = |r: std::ops::Range<usize>| s.get(r)?.parse::<i64>().ok();
+    let (y, m, d) = (n(0..4)?, n(4..6)?, n(6..8)?);
+    let (hh, mm, ss) = (n(8..10)?, n(10..12)?, n(12..14)?);
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) || hh > 23 || mm > 59 || ss > 59 {
+        return None;
+    }
+    let secs = days_from_civil(y, m, d) * 86_400 + hh * 3600 + mm * 60 + ss;
+    // serial number arithmetic: the value wraps every 2^32 seconds
+    Some(secs.rem_euclid(1 << 32) as u32)
+}
+
+// days since 1970-01-01 of a proleptic Gregorian date, and back
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    (y, m, d)
+}
@@ -0,0 +1,275 @@
+use crate::config::{ForwardPolicy, ForwarderConfig};
+use crate::dns::packet::header::Header;
+use crate::exceptions::SCloudException;
+use crate::log_debug;
+use rand::seq::SliceRandom;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::time::{Instant, timeout, timeout_at};
+
+/// UDP payload size advertised to upstreams when EDNS is enabled.
+pub(crate) const EDNS_UDP_PAYLOAD: u16 = 1232;
+
+const OPT_TYPE: u16 = 41;
+
+/// Asynchronous client forwarding queries to a group of upstream servers.
+///
+/// Built from a `ForwarderConfig`:
+/// - `addresses` are tried one after the other, in the order given by
+///   `policy`, until one of them answers within `timeout_ms`
+/// - with `edns`, a query without OPT record gets one advertising
+///   `EDNS_UDP_PAYLOAD` bytes (removed again from the reply)
+/// - with `use_tcp_on_retry`, a truncated UDP reply is retried over TCP on
+///   the same server
+///
+/// Queries and replies are kept in wire format: the client ID is swapped
+/// for a random one upstream and restored in the reply.
+#[derive(Debug)]
+pub(crate) struct Forwarder {
+    pub(crate) name: String,
+    pub(crate) servers: Vec<SocketAddr>,
+    pub(crate) policy: ForwardPolicy,
+    pub(crate) timeout: Duration,
+    pub(crate) edns: bool,
+    pub(crate) use_tcp_on_retry: bool,
+    next: AtomicUsize, // round robin cursor
+}
+
+impl Forwarder {
+    /// Build a forwarder from its configuration.
+    ///
+    /// # Exemple :
+    /// ```
+    /// let cfg = ForwarderConfig {
+    ///     name: "cloudflare".to_string(),
+    ///     addresses: vec!["1.1.1.1:53".to_string(), "1.0.0.1:53".to_string()],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let forwarder = Forwarder::from_config(&cfg).unwrap();
+    ///
+    /// assert_eq!(forwarder.servers.len(), 2);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `SCLOUD_FORWARDER_NO_UPSTREAM` if none of the addresses is a
+    /// valid `ip:port`.
+    pub(crate) fn from_config(cfg: &ForwarderConfig) -> Result<Forwarder, SCloudException> {
+        let servers: Vec<SocketAddr> = cfg
+            .addresses
+            .iter()
+            .filter_map(|a| a.parse().ok())
+            .collect();
+        if servers.is_empty() {
+            return Err(SCloudException::SCLOUD_FORWARDER_NO_UPSTREAM);
+        }
+
+        Ok(Forwarder {
+            name: cfg.name.clone(),
+            servers,
+            policy: cfg.policy.clone(),
+            timeout: Duration::from_millis(cfg.timeout_ms),
+            edns: cfg.edns,
+            use_tcp_on_retry: cfg.use_tcp_on_retry.unwrap_or(true),
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// Servers to try for the next query, in order.
+    pub(crate) fn servers_in_order(&self) -> Vec<SocketAddr> {
+        let mut servers = self.servers.clone();
+        match self.policy {
+            ForwardPolicy::First => {}
+            ForwardPolicy::RoundRobin => {
+                let start = self.next.fetch_add(1, Ordering::Relaxed) % servers.len();
+                servers.rotate_left(start);
+            }
+            ForwardPolicy::Random => servers.shuffle(&mut rand::rng()),
+        }
+        servers
+    }
+
+    /// Forward a wire-format query and return the wire-format reply.
+    ///
+    /// # Errors
+    /// Returns `SCLOUD_HEADER_DESERIALIZATION_FAILED` if the query is shorter
+    /// than a header, and `SCLOUD_FORWARDER_NO_RESPONSE` if no server
+    /// answered.
+    pub(crate) async fn forward(&self, query: &[u8]) -> Result<Vec<u8>, SCloudException> {
+        if query.len() < Header::DNS_HEADER_LEN {
+            return Err(SCloudException::SCLOUD_HEADER_DESERIALIZATION_FAILED);
+        }
+        let client_id = [query[0], query[1]];
+
+        let mut upstream_query = query.to_vec();
+        let added_opt = self.edns && count(query, 10) == 0;
+        if added_opt {
+            append_opt(&mut upstream_query, EDNS_UDP_PAYLOAD);
+        }
+
+        for server in self.servers_in_order() {
+            let id = rand::random::<u16>();
+            upstream_query[..2].copy_from_slice(&id.to_be_bytes());
+
+            let mut reply = match self.query_udp(&upstream_query, server, id).await {
+                Ok(reply) => reply,
+                Err(e) => {
+                    log_debug!("forwarder {}: {} over udp: {}", self.name, server, e);
+                    continue;
+                }
+            };
+
+            if is_truncated(&reply) && self.use_tcp_on_retry {
+                match self.query_tcp(&upstream_query, server, id).await {
+                    Ok(full) => reply = full,
+                    Err(e) => {
+                        log_debug!("forwarder {}: {} over tcp: {}", self.name, server, e);
+                    }
+                }
+            }
+
+            if added_opt {
+                strip_opt(&mut reply);
+            }
+            reply[..2].copy_from_slice(&client_id);
+            return Ok(reply);
+        }
+
+        Err(SCloudException::SCLOUD_FORWARDER_NO_RESPONSE)
+    }
+
+    async fn query_udp(
+        &self,
+        query: &[u8],
+        server: SocketAddr,
+        id: u16,
+    ) -> std::io::Result<Vec<u8>> {
+        let bind: SocketAddr = if server.is_ipv4() {
+            "0.0.0.0:0".parse().unwrap()
+        } else {
+            "[::]:0".parse().unwrap()
+        };
+        let socket = UdpSocket::bind(bind).await?;
+        socket.connect(server).await?;
+        socket.send(query).await?;
+
+        let deadline = Instant::now() + self.timeout;
+        let mut buf = vec![0u8; u16::MAX as usize];
+        loop {
+            let len = timeout_at(deadline, socket.recv(&mut buf))
+                .await
+                .map_err(|_| std::io::ErrorKind::TimedOut)??;
+            // late replies to an earlier query, or spoofing attempts
+            if is_reply_to(&buf[..len], id) {
+                buf.truncate(len);
+                return Ok(buf);
+            }
+        }
+    }
+
+    async fn query_tcp(
+        &self,
+        query: &[u8],
+        server: SocketAddr,
+        id: u16,
+    ) -> std::io::Result<Vec<u8>> {
+        let exchange = async {
+            let mut stream = TcpStream::connect(server).await?;
+            let mut framed = Vec::with_capacity(query.len() + 2);
+            framed.extend_from_slice(&(query.len() as u16).to_be_bytes());
+            framed.extend_from_slice(query);
+            stream.write_all(&framed).await?;
+
+            let len = stream.read_u16().await? as usize;
+            let mut reply = vec![0u8; len];
+            stream.read_exact(&mut reply).await?;
+            Ok::<_, std::io::Error>(reply)
+        };
+
+        let reply = timeout(self.timeout, exchange)
+            .await
+            .map_err(|_| std::io::ErrorKind::TimedOut)??;
+        if !is_reply_to(&reply, id) {
+            return Err(std::io::ErrorKind::InvalidData.into());
+        }
+        Ok(reply)
+    }
+}
+
+/// Read the 16-bit header counter at `offset` (QDCOUNT is at 4, ARCOUNT at 10).
+fn count(packet: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([packet[offset], packet[offset + 1]])
+}
+
+fn set_count(packet: &mut [u8], offset: usize, value: u16) {
+    packet[offset..offset + 2].copy_from_slice(&value.to_be_bytes());
+}
+
+fn is_reply_to(packet: &[u8], id: u16) -> bool {
+    packet.len() >= Header::DNS_HEADER_LEN && count(packet, 0) == id && packet[2] & 0b1000_0000 != 0
+}
+
+fn is_truncated(packet: &[u8]) -> bool {
+    packet[2] & 0b0000_0010 != 0
+}
+
+/// Append an empty OPT record (RFC 6891) advertising `udp_payload` bytes.
+fn append_opt(packet: &mut Vec<u8>, udp_payload: u16) {
+    packet.push(0x00); // root owner
+    packet.extend_from_slice(&OPT_TYPE.to_be_bytes());
+    packet.extend_from_slice(&udp_payload.to_be_bytes());
+    packet.extend_from_slice(&[0, 0, 0, 0]); // extended RCODE, version, flags
+    packet.extend_from_slice(&[0, 0]); // RDLENGTH
+    let arcount = count(packet, 10) + 1;
+    set_count(packet, 10, arcount);
+}
+
+/// Remove the OPT record of the additional section, if any.
+fn strip_opt(packet: &mut Vec<u8>) {
+    if let Some((start, end)) = find_opt(packet) {
+        packet.drain(start..end);
+        let arcount = count(packet, 10) - 1;
+        set_count(packet, 10, arcount);
+    }
+}
+
+/// Byte range of the OPT record of the additional section.
+fn find_opt(packet: &[u8]) -> Option<(usize, usize)> {
+    if packet.len() < Header::DNS_HEADER_LEN {
+        return None;
+    }
+    let mut pos = Header::DNS_HEADER_LEN;
+    for _ in 0..count(packet, 4) {
+        pos = skip_name(packet, pos)? + 4;
+    }
+
+    let before_additional = count(packet, 6) as usize + count(packet, 8) as usize;
+    for i in 0..before_additional + count(packet, 10) as usize {
+        let start = pos;
+        let fixed = skip_name(packet, pos)?; // TYPE, CLASS, TTL, RDLENGTH
+        if fixed + 10 > packet.len() {
+            return None;
+        }
+        pos = fixed + 10 + count(packet, fixed + 8) as usize;
+        if i >= before_additional && count(packet, fixed) == OPT_TYPE {
+            return (pos <= packet.len()).then_some((start, pos));
+        }
+    }
+    None
+}
+
+/// Position right after the (possibly compressed) name at `pos`.
+fn skip_name(packet: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let len = *packet.get(pos)?;
+        match len & 0b1100_0000 {
+            0b1100_0000 => return Some(pos + 2),
+            0 if len == 0 => return Some(pos + 1),
+            0 => pos += 1 + len as usize,
+            _ => return None,
+        }
+    }
+}
@@ -1,3 +1,4 @@
+pub(crate) mod forwarder;
 pub(crate) mod stub;
 
 use crate::dns::packet::DNSPacket;
@@ -48,6 +49,7 @@ pub(crate) fn check_response_diff(
 /// use crate::dns::packet::answer::AnswerSection;
 /// use crate::dns::q_type::DNSRecordType;
 /// use crate::dns::q_class::DNSClass;
+/// use crate::dns::records::rdata::RData;
 ///
 /// let questions = vec![QuestionSection {
 ///     q_name: "example.com".to_string(),
@@ -60,8 +62,7 @@ pub(crate) fn check_response_diff(
 ///     r_type: DNSRecordType::A,
 ///     r_class: DNSClass::IN,
 ///     ttl: 300,
-///     rdlength: 4,
-///     rdata: vec![93, 184, 216, 34],
+///     rdata: RData::A("93.184.216.34".parse().unwrap()),
 /// }];
 ///
 /// assert!(check_answer_diff(&questions, &answers, &[], &[]).is_ok());
@@ -90,6 +91,7 @@ pub(crate) fn check_answer_diff(
 /// use crate::dns::packet::authority::AuthoritySection;
 /// use crate::dns::q_type::DNSRecordType;
 /// use crate::dns::q_class::DNSClass;
+/// use crate::dns::records::rdata::RData;
 ///
 /// let questions = vec![QuestionSection {
 ///     q_name: "example.com".to_string(),
@@ -102,7 +104,7 @@ pub(crate) fn check_answer_diff(
 ///     q_type: DNSRecordType::NS,
 ///     q_class: DNSClass::IN,
 ///     ttl: 3600,
-///     ns_name: "ns1.example.com".to_string(),
+///     rdata: RData::NS("ns1.example.com".to_string()),
 /// }];
 ///
 /// assert!(check_authority_diff(&questions, &authorities).is_ok());
@@ -131,6 +133,7 @@ pub(crate) fn check_authority_diff(
 /// use crate::dns::packet::additional::AdditionalSection;
 /// use crate::dns::q_type::DNSRecordType;
 /// use crate::dns::q_class::DNSClass;
+/// use crate::dns::records::rdata::RData;
 ///
 /// let questions = vec![QuestionSection {
 ///     q_name: "example.com".to_string(),
@@ -143,8 +146,7 @@ pub(crate) fn check_authority_diff(
 ///     q_type: DNSRecordType::A,
 ///     q_class: DNSClass::IN,
 ///     ttl: 300,
-///     rdlength: 4,
-///     rdata: vec![192, 0, 2, 1],
+///     rdata: RData::A("192.0.2.1".parse().unwrap()),
 /// }];
 ///
 /// assert!(check_additional_diff(&questions, &additionals).is_ok());
@@ -0,0 +1,315 @@
+#[cfg(test)]
+mod tests {
+    use crate::config::CacheConfig;
+    use crate::dns::cache::{DnsCache, EvictionPolicy};
+    use crate::dns::packet::DNSPacket;
+    use crate::dns::packet::additional::AdditionalSection;
+    use crate::dns::packet::answer::AnswerSection;
+    use crate::dns::packet::authority::AuthoritySection;
+    use crate::dns::packet::header::Header;
+    use crate::dns::q_class::DNSClass;
+    use crate::dns::q_type::DNSRecordType;
+    use crate::dns::records::rdata::RData;
+    use crate::dns::tests::fixtures::query;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    fn cache(max_entries: usize, policy: &str) -> DnsCache {
+        DnsCache::new(&CacheConfig {
+            max_entries,
+            max_ttl_seconds: 3600,
+            eviction_policy: policy.to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn answer(name: &str, ttl: u32) -> DNSPacket {
+        let mut response = DNSPacket::new_response(&query(name), Header::RCODE_NOERROR);
+        response.header.ra = true;
+        response.answers.push(AnswerSection {
+            q_name: name.to_string(),
+            r_type: DNSRecordType::A,
+            r_class: DNSClass::IN,
+            ttl,
+            rdata: RData::A(Ipv4Addr::new(192, 0, 2, 1)),
+        });
+        response.sync_counts();
+        response
+    }
+
+    /// NXDOMAIN or NODATA for `name` with the SOA of `example` in the
+    /// authority section.
+    fn negative(name: &str, rcode: u8, soa_ttl: u32, minimum: u32) -> DNSPacket {
+        let mut response = DNSPacket::new_response(&query(name), rcode);
+        response.authorities.push(AuthoritySection {
+            q_name: "example".to_string(),
+            q_type: DNSRecordType::SOA,
+            q_class: DNSClass::IN,
+            ttl: soa_ttl,
+            rdata: RData::SOA {
+                mname: String::new(),
+                rname: String::new(),
+                serial: 1,
+                refresh: 7200,
+                retry: 3600,
+                expire: 1_209_600,
+                minimum,
+            },
+        });
+        response.sync_counts();
+        response
+    }
+
+    #[test]
+    fn test_hit_returns_answer_with_query_id() {
+        let cache = cache(16, "lru");
+        assert!(cache.insert(&answer("example.com", 300)));
+
+        let q = query("example.com");
+        let hit = cache.get(&q).expect("cache miss");
+
+        assert_eq!(hit.header.id, q.header.id);
+        assert!(hit.header.qr);
+        assert!(hit.header.ra);
+        assert_eq!(hit.questions, q.questions);
+        assert_eq!(hit.header.ancount, 1);
+        assert_eq!(hit.answers[0].rdata, RData::A(Ipv4Addr::new(192, 0, 2, 1)));
+        assert_eq!(hit.answers[0].ttl, 300);
+    }
+
+    #[test]
+    fn test_key_is_case_insensitive() {
+        let cache = cache(16, "lru");
+        cache.insert(&answer("Example.COM", 300));
+
+        assert!(cache.get(&query("example.com.")).is_some());
+        assert!(cache.get(&query("www.example.com")).is_none());
+    }
+
+    #[test]
+    fn test_ttl_decremented_and_entry_expired() {
+        let cache = cache(16, "lru");
+        cache.insert(&answer("long.example", 300));
+        cache.insert(&answer("short.example", 1));
+
+        std::thread::sleep(Duration::from_millis(1100));
+
+        let hit = cache.get(&query("long.example")).expect("cache miss");
+        assert!(hit.answers[0].ttl < 300);
+        assert!(cache.get(&query("short.example")).is_none());
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_ttl_capped_by_max_ttl() {
+        let cache = cache(16, "lru");
+        cache.insert(&answer("example.com", 1_000_000));
+
+        let hit = cache.get(&query("example.com")).unwrap();
+        assert_eq!(hit.answers[0].ttl, 3600);
+    }
+
+    #[test]
+    fn test_uncacheable_responses() {
+        let cache = cache(16, "lru");
+
+        let mut truncated = answer("tc.example", 300);
+        truncated.header.tc = true;
+        let mut servfail = answer("servfail.example", 300);
+        servfail.header.rcode = Header::RCODE_SERVFAIL;
+        let empty = DNSPacket::new_response(&query("empty.example"), Header::RCODE_NOERROR);
+
+        assert!(!cache.insert(&truncated));
+        assert!(!cache.insert(&servfail));
+        assert!(!cache.insert(&empty));
+        assert!(!cache.insert(&answer("zero.example", 0)));
+        assert!(!cache.insert(&query("query.example")));
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[test]
+    fn test_opt_record_not_cached() {
+        let cache = cache(16, "lru");
+        let mut response = answer("example.com", 300);
+        response.additionals.push(AdditionalSection {
+            q_name: String::new(),
+            q_type: DNSRecordType::OPT,
+            q_class: DNSClass::IN,
+            ttl: 0,
+            rdata: RData::Unknown(vec![]),
+        });
+        response.sync_counts();
+
+        assert!(cache.insert(&response));
+        let hit = cache.get(&query("example.com")).unwrap();
+        assert!(hit.additionals.is_empty());
+        assert_eq!(hit.header.arcount, 0);
+    }
+
+    #[test]
+    fn test_disabled_cache() {
+        let cache = DnsCache::new(&CacheConfig {
+            enabled: false,
+            ..Default::default()
+        });
+
+        assert!(!cache.insert(&answer("example.com", 300)));
+        assert!(cache.get(&query("example.com")).is_none());
+    }
+
+    #[test]
+    fn test_lru_evicts_least_recently_used() {
+        let cache = cache(2, "lru");
+        cache.insert(&answer("a.example", 300));
+        cache.insert(&answer("b.example", 300));
+        assert!(cache.get(&query("a.example")).is_some());
+
+        cache.insert(&answer("c.example", 300));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&query("a.example")).is_some());
+        assert!(cache.get(&query("b.example")).is_none());
+        assert!(cache.get(&query("c.example")).is_some());
+    }
+
+    #[test]
+    fn test_lru_replacing_entry_does_not_evict() {
+        let cache = cache(2, "lru");
+        cache.insert(&answer("a.example", 300));
+        cache.insert(&answer("b.example", 300));
+        cache.insert(&answer("a.example", 600));
+
+        assert_eq!(cache.len(), 2);
+        let hit = cache.get(&query("a.example")).unwrap();
+        assert_eq!(hit.answers[0].ttl, 600);
+    }
+
+    #[test]
+    fn test_light_lru_evicts_oldest_batch() {
+        let cache = cache(16, "light-lru");
+        for i in 0..16 {
+            cache.insert(&answer(&format!("{i}.example"), 300));
+        }
+        for i in 0..2 {
+            assert!(cache.get(&query(&format!("{i}.example"))).is_some());
+        }
+
+        cache.insert(&answer("new.example", 300));
+
+        // 16 / 8 entries evicted, the least recently used ones
+        assert_eq!(cache.len(), 15);
+        assert!(cache.get(&query("0.example")).is_some());
+        assert!(cache.get(&query("1.example")).is_some());
+        assert!(cache.get(&query("2.example")).is_none());
+        assert!(cache.get(&query("3.example")).is_none());
+        assert!(cache.get(&query("new.example")).is_some());
+    }
+
+    #[test]
+    fn test_eviction_policy_parse() {
+        assert_eq!(EvictionPolicy::parse("lru"), Some(EvictionPolicy::Lru));
+        assert_eq!(
+            EvictionPolicy::parse("Light-LRU"),
+            Some(EvictionPolicy::LightLru)
+        );
+        assert_eq!(EvictionPolicy::parse("fifo"), None);
+        assert_eq!(cache(1, "fifo").policy(), EvictionPolicy::Lru);
+    }
+
+    #[test]
+    fn test_eviction_and_expiration_counters() {
+        let cache = cache(2, "lru");
+        cache.insert(&answer("a.example", 1));
+        cache.insert(&answer("b.example", 300));
+        cache.insert(&answer("c.example", 300));
+        assert_eq!(cache.evictions(), 1);
+
+        assert_eq!(cache.shrink_to(1), 1);
+        assert_eq!(cache.evictions(), 2);
+        assert!(cache.get(&query("c.example")).is_some());
+
+        cache.insert(&answer("d.example", 1));
+        std::thread::sleep(Duration::from_millis(1100));
+        assert_eq!(cache.remove_expired(), 1);
+        assert_eq!(cache.expirations(), 1);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_nxdomain_cached_with_soa() {
+        let cache = cache(16, "lru");
+        assert!(cache.insert(&negative(
+            "missing.example",
+            Header::RCODE_NXDOMAIN,
+            3600,
+            60
+        )));
+
+        let q = query("missing.example");
+        let hit = cache.get(&q).expect("negative answer not cached");
+
+        assert_eq!(hit.header.id, q.header.id);
+        assert_eq!(hit.header.rcode, Header::RCODE_NXDOMAIN);
+        assert!(hit.answers.is_empty());
+        assert_eq!(hit.header.nscount, 1);
+        assert_eq!(hit.authorities[0].q_type, DNSRecordType::SOA);
+        // min(SOA TTL, MINIMUM)
+        assert_eq!(hit.authorities[0].ttl, 60);
+    }
+
+    #[test]
+    fn test_nodata_cached_with_soa_ttl() {
+        let cache = cache(16, "lru");
+        cache.insert(&negative("www.example", Header::RCODE_NOERROR, 30, 600));
+
+        let hit = cache.get(&query("www.example")).expect("NODATA not cached");
+
+        assert_eq!(hit.header.rcode, Header::RCODE_NOERROR);
+        assert!(hit.answers.is_empty());
+        assert_eq!(hit.authorities[0].ttl, 30);
+    }
+
+    #[test]
+    fn test_negative_ttl_capped_by_config() {
+        let cache = DnsCache::new(&CacheConfig {
+            negative_ttl_seconds: 10,
+            ..Default::default()
+        });
+        cache.insert(&negative(
+            "missing.example",
+            Header::RCODE_NXDOMAIN,
+            3600,
+            3600,
+        ));
+
+        let hit = cache.get(&query("missing.example")).unwrap();
+        assert_eq!(hit.authorities[0].ttl, 10);
+    }
+
+    #[test]
+    fn test_negative_answer_without_soa_not_cached() {
+        let cache = cache(16, "lru");
+        let mut response = negative("missing.example", Header::RCODE_NXDOMAIN, 3600, 60);
+        response.authorities.clear();
+        response.sync_counts();
+
+        assert!(!cache.insert(&response));
+        assert!(cache.get(&query("missing.example")).is_none());
+    }
+
+    #[test]
+    fn test_negative_caching_disabled() {
+        let cache = DnsCache::new(&CacheConfig {
+            negative_ttl_seconds: 0,
+            ..Default::default()
+        });
+
+        assert!(!cache.insert(&negative(
+            "missing.example",
+            Header::RCODE_NXDOMAIN,
+            3600,
+            60
+        )));
+        assert!(cache.insert(&answer("example.com", 300)));
+    }
+}
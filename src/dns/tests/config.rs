@@ -63,6 +63,17 @@ mod tests {
         assert_eq!(server.bind_port, 53);
         assert!(server.enable_tcp);
         assert!(server.enable_edns);
+        assert_eq!(server.tcp_idle_timeout_secs, None);
+    }
+
+    #[test]
+    fn test_validate_tcp_idle_timeout() {
+        let mut cfg = Config::default();
+        cfg.server.tcp_idle_timeout_secs = Some(30);
+        assert!(cfg.validate().is_ok());
+
+        cfg.server.tcp_idle_timeout_secs = Some(0);
+        assert!(cfg.validate().is_err());
     }
 
     #[test]
@@ -83,6 +94,20 @@ mod tests {
         assert_eq!(zone.inline, Some(false));
     }
 
+    #[test]
+    fn test_zone_forward_policy() {
+        let zone: ZoneConfig = serde_json::from_str(
+            r#"{"name": "corp.", "type": "forward", "forward_policy": "round_robin"}"#,
+        )
+        .unwrap();
+        assert_eq!(zone.forward_policy, Some(ForwardPolicy::RoundRobin));
+
+        let zone: Result<ZoneConfig, _> = serde_json::from_str(
+            r#"{"name": "corp.", "type": "forward", "forward_policy": "roundrobin"}"#,
+        );
+        assert!(zone.is_err());
+    }
+
     #[test]
     fn test_forwarder_config_defaults() {
         let forwarder = ForwarderConfig::default();
@@ -109,6 +134,21 @@ mod tests {
         assert_eq!(recursion.max_recursive_queries, 50);
     }
 
+    #[test]
+    fn test_edns_udp_size() {
+        let mut cfg = Config::default();
+        cfg.server.enable_edns = true;
+        cfg.server.max_udp_payload = 1232;
+        cfg.amplification_mitigation.max_response_size_udp = 4096;
+        assert_eq!(cfg.edns_udp_size(), Some(1232));
+
+        cfg.amplification_mitigation.max_response_size_udp = 1000;
+        assert_eq!(cfg.edns_udp_size(), Some(1000));
+
+        cfg.server.enable_edns = false;
+        assert_eq!(cfg.edns_udp_size(), None);
+    }
+
     #[test]
     fn test_rate_limit_defaults() {
         let ratelimit = RateLimitConfig::default();
@@ -138,6 +178,103 @@ mod tests {
         assert!(cfg.validate().is_ok());
     }
 
+    #[test]
+    fn test_validate_doh_http2_limits() {
+        let mut cfg = Config::default();
+        cfg.doh.enabled = true;
+        cfg.doh.max_concurrent_streams = Some(32);
+        cfg.doh.idle_timeout_secs = Some(60);
+        assert!(cfg.validate().is_ok());
+
+        cfg.doh.max_concurrent_streams = Some(0);
+        assert!(cfg.validate().is_err());
+        cfg.doh.max_concurrent_streams = None;
+        cfg.doh.idle_timeout_secs = Some(0);
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_doq() {
+        let mut cfg = Config::default();
+        cfg.doq.enabled = true;
+        assert!(matches!(
+            cfg.validate(),
+            Err(crate::exceptions::SCloudException::SCLOUD_CONFIG_TLS_MISSING_CERT)
+        ));
+
+        cfg.doq.tls_cert_path = Some("/etc/scloud/certs/doq.crt".to_string());
+        cfg.doq.tls_key_path = Some("/etc/scloud/certs/doq.key".to_string());
+        assert!(cfg.validate().is_ok());
+
+        cfg.doq.bind = "853".to_string();
+        assert!(matches!(
+            cfg.validate(),
+            Err(crate::exceptions::SCloudException::SCLOUD_CONFIG_INVALID_DOQ)
+        ));
+    }
+
+    #[test]
+    fn test_listener_socket_addr() {
+        let mut listener = ListenerConfig {
+            address: "10.0.0.1".to_string(),
+            port: 5353,
+            ..Default::default()
+        };
+        assert_eq!(
+            listener.socket_addr().unwrap(),
+            "10.0.0.1:5353".parse::<SocketAddr>().unwrap()
+        );
+
+        listener.address = "[::1]".to_string();
+        assert_eq!(
+            listener.socket_addr().unwrap(),
+            "[::1]:5353".parse::<SocketAddr>().unwrap()
+        );
+        listener.address = "2001:db8::53".to_string();
+        assert!(listener.socket_addr().unwrap().is_ipv6());
+
+        listener.address = "localhost".to_string();
+        assert!(listener.socket_addr().is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_listener_address() {
+        let mut cfg = Config::default();
+        cfg.listener.push(ListenerConfig {
+            name: "bad".to_string(),
+            address: "not-an-address".to_string(),
+            ..Default::default()
+        });
+        assert!(matches!(
+            cfg.validate(),
+            Err(crate::exceptions::SCloudException::SCLOUD_CONFIG_INVALID_LISTENER_ADDRESS)
+        ));
+    }
+
+    #[test]
+    fn test_listeners_from_config() {
+        let cfg = Config::from_file(Path::new("./config/config.json")).unwrap();
+        let names: Vec<String> = cfg.listeners().into_iter().map(|l| l.name).collect();
+
+        assert_eq!(names, vec!["public-udp", "internal-resolver", "dot"]);
+    }
+
+    #[test]
+    fn test_listeners_default_to_bind_port() {
+        let mut cfg = Config::default();
+        cfg.server.bind_port = 5300;
+        let listeners = cfg.listeners();
+
+        assert_eq!(listeners.len(), 2);
+        for l in &listeners {
+            assert_eq!(l.port, 5300);
+            assert!(l.has_protocol(Protocol::UDP) && l.has_protocol(Protocol::TCP));
+            assert!(l.socket_addr().unwrap().ip().is_unspecified());
+        }
+        assert!(listeners[0].socket_addr().unwrap().is_ipv4());
+        assert!(listeners[1].socket_addr().unwrap().is_ipv6());
+    }
+
     #[test]
     fn test_get_forwarder_addr_by_index() {
         let cfg = Config::from_file(Path::new("./config/config.json").into()).unwrap();
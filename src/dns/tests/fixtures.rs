@@ -0,0 +1,20 @@
+//! Packets shared by the tests of the cache, the zones and the workers.
+
+use crate::dns::packet::DNSPacket;
+use crate::dns::packet::question::QuestionSection;
+use crate::dns::q_class::DNSClass;
+use crate::dns::q_type::DNSRecordType;
+
+/// A recursive IN A query for `name`.
+pub(crate) fn query(name: &str) -> DNSPacket {
+    query_type(name, DNSRecordType::A)
+}
+
+/// A recursive IN query for `name` and `q_type`.
+pub(crate) fn query_type(name: &str, q_type: DNSRecordType) -> DNSPacket {
+    DNSPacket::new_query(&[QuestionSection {
+        q_name: name.to_string(),
+        q_type,
+        q_class: DNSClass::IN,
+    }])
+}
@@ -1,7 +1,11 @@
+mod cache;
 mod config;
+#[cfg(test)]
+pub(crate) mod fixtures;
 mod packet;
 pub mod q_class;
 mod q_name;
 mod q_type;
+mod records;
 mod resolver;
 mod zones;
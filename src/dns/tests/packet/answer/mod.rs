@@ -3,6 +3,7 @@ mod tests {
     use crate::dns::packet::answer::AnswerSection;
     use crate::dns::q_class::DNSClass;
     use crate::dns::q_type::DNSRecordType;
+    use crate::dns::records::rdata::RData;
     use crate::exceptions::SCloudException;
 
     #[test]
@@ -14,8 +15,7 @@ mod tests {
             r_type: DNSRecordType::A,
             r_class: DNSClass::IN,
             ttl: 0,
-            rdlength: 0,
-            rdata: vec![],
+            rdata: RData::Unknown(vec![]),
         };
         let result = asec.to_bytes();
 
@@ -0,0 +1,188 @@
+#[cfg(test)]
+mod tests {
+    use crate::dns::packet::DNSPacket;
+    use crate::dns::packet::additional::AdditionalSection;
+    use crate::dns::packet::edns::{Edns, EdnsOption};
+    use crate::dns::packet::header::Header;
+    use crate::dns::packet::question::QuestionSection;
+    use crate::dns::packet::writer::MessageWriter;
+    use crate::dns::q_class::DNSClass;
+    use crate::dns::q_type::DNSRecordType;
+    use crate::dns::records::rdata::RData;
+    use crate::exceptions::SCloudException;
+
+    fn query() -> DNSPacket {
+        DNSPacket::new_query(&[QuestionSection {
+            q_name: "example.com".to_string(),
+            q_type: DNSRecordType::A,
+            q_class: DNSClass::IN,
+        }])
+    }
+
+    fn answer_record() -> AdditionalSection {
+        AdditionalSection {
+            q_name: "ns1.example.com".to_string(),
+            q_type: DNSRecordType::A,
+            q_class: DNSClass::IN,
+            ttl: 300,
+            rdata: RData::A("192.0.2.1".parse().unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_edns_from_bytes() {
+        let bytes: &[u8] = &[
+            0x00, // root owner
+            0x00, 0x29, // TYPE = OPT
+            0x04, 0xD0, // UDP payload size = 1232
+            0x01, 0x00, // extended RCODE 1, version 0
+            0x80, 0x00, // DO
+            0x00, 0x08, // RDLENGTH = 8
+            0x00, 0x0A, 0x00, 0x04, 0xDE, 0xAD, 0xBE, 0xEF, // COOKIE option
+            0xFF, // next record
+        ];
+
+        let (edns, consumed) = Edns::from_bytes(bytes, 0).unwrap();
+
+        assert_eq!(
+            edns,
+            Edns {
+                udp_size: 1232,
+                ext_rcode: 1,
+                version: 0,
+                dnssec_ok: true,
+                options: vec![EdnsOption {
+                    code: 10,
+                    data: vec![0xDE, 0xAD, 0xBE, 0xEF],
+                }],
+            }
+        );
+        assert_eq!(consumed, bytes.len() - 1);
+
+        let mut w = MessageWriter::uncompressed();
+        edns.write(&mut w).unwrap();
+        assert_eq!(w.finish(), &bytes[..consumed]);
+    }
+
+    #[test]
+    fn test_edns_from_bytes_rejects_malformed() {
+        // owner is not the root
+        let bytes: &[u8] = &[
+            0x01, b'a', 0x00, 0x00, 0x29, 0x04, 0xD0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(
+            Edns::from_bytes(bytes, 0).unwrap_err(),
+            SCloudException::SCLOUD_EDNS_MALFORMED
+        );
+
+        // option longer than the RDATA
+        let bytes: &[u8] = &[
+            0x00, 0x00, 0x29, 0x04, 0xD0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x0A, 0x00,
+            0x04, 0xDE,
+        ];
+        assert_eq!(
+            Edns::from_bytes(bytes, 0).unwrap_err(),
+            SCloudException::SCLOUD_EDNS_MALFORMED
+        );
+    }
+
+    #[test]
+    fn test_packet_round_trip_with_opt() {
+        let mut packet = query();
+        packet.additionals.push(answer_record());
+        packet.edns = Some(Edns {
+            dnssec_ok: true,
+            ..Edns::new(4096)
+        });
+        packet.sync_counts();
+        assert_eq!(packet.header.arcount, 2);
+
+        let bytes = packet.to_bytes().unwrap();
+        let parsed = DNSPacket::from_bytes(&bytes).unwrap();
+
+        assert_eq!(parsed, packet);
+        assert_eq!(parsed.additionals.len(), 1);
+    }
+
+    #[test]
+    fn test_packet_rejects_two_opt() {
+        let mut bytes = query().to_bytes().unwrap();
+        bytes[11] = 2; // ARCOUNT
+        for _ in 0..2 {
+            let mut w = MessageWriter::uncompressed();
+            Edns::new(1232).write(&mut w).unwrap();
+            bytes.extend(w.finish());
+        }
+
+        assert_eq!(
+            DNSPacket::from_bytes(&bytes).unwrap_err(),
+            SCloudException::SCLOUD_EDNS_MALFORMED
+        );
+    }
+
+    #[test]
+    fn test_max_udp_response_size() {
+        let mut packet = query();
+        assert_eq!(packet.max_udp_response_size(Some(4096), 4096), 512);
+
+        packet.edns = Some(Edns::new(1232));
+        assert_eq!(packet.max_udp_response_size(Some(4096), 4096), 1232);
+        assert_eq!(packet.max_udp_response_size(Some(4096), 1000), 1000);
+        assert_eq!(packet.max_udp_response_size(Some(4096), 100), 512);
+        // the server advertises less than the client
+        assert_eq!(packet.max_udp_response_size(Some(1024), 4096), 1024);
+        // EDNS disabled: the response has no OPT record
+        assert_eq!(packet.max_udp_response_size(None, 4096), 512);
+
+        packet.edns = Some(Edns::new(100));
+        assert_eq!(packet.max_udp_response_size(Some(4096), 4096), 512);
+    }
+
+    #[test]
+    fn test_negotiate_edns() {
+        let mut q = query();
+        let mut response = DNSPacket::new_response(&q, Header::RCODE_NOERROR);
+
+        // no OPT in the query: none in the response
+        response.negotiate_edns(&q, Some(1232));
+        assert_eq!(response.edns, None);
+        assert_eq!(response.header.arcount, 0);
+
+        // EDNS disabled
+        q.edns = Some(Edns::new(4096));
+        response.negotiate_edns(&q, None);
+        assert_eq!(response.edns, None);
+
+        q.edns = Some(Edns {
+            dnssec_ok: true,
+            ..Edns::new(4096)
+        });
+        response.negotiate_edns(&q, Some(1232));
+        assert_eq!(
+            response.edns,
+            Some(Edns {
+                dnssec_ok: true,
+                ..Edns::new(1232)
+            })
+        );
+        assert_eq!(response.header.arcount, 1);
+    }
+
+    #[test]
+    fn test_negotiate_edns_badvers() {
+        let mut q = query();
+        q.edns = Some(Edns {
+            version: 1,
+            ..Edns::new(4096)
+        });
+        let mut response = DNSPacket::new_response(&q, Header::RCODE_NOERROR);
+        response.additionals.push(answer_record());
+
+        response.negotiate_edns(&q, Some(1232));
+
+        assert!(response.additionals.is_empty());
+        assert_eq!(response.rcode(), Edns::RCODE_BADVERS);
+        assert_eq!(response.header.rcode, 0);
+        assert_eq!(response.edns.as_ref().unwrap().version, 0);
+    }
+}
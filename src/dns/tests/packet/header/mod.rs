@@ -31,6 +31,22 @@ mod tests {
         assert_eq!(expected, result.unwrap().as_slice());
     }
 
+    #[test]
+    fn test_header_rcode_round_trip() {
+        let header = Header {
+            id: 1,
+            qr: true,
+            aa: true,
+            rcode: Header::RCODE_NXDOMAIN,
+            ..Default::default()
+        };
+
+        let bytes = header.to_bytes().unwrap();
+
+        assert_eq!(bytes[3] & 0x0F, 3);
+        assert_eq!(Header::from_bytes(&bytes).unwrap(), header);
+    }
+
     #[test]
     fn test_header_deserialization_failure() {
         let result = Header::from_bytes(&[0xAA, 0xAA, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00]);
@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod tests {
+    use crate::dns::packet::DNSPacket;
+    use crate::dns::packet::additional::AdditionalSection;
+    use crate::dns::packet::answer::AnswerSection;
+    use crate::dns::packet::authority::AuthoritySection;
+    use crate::dns::packet::header::Header;
+    use crate::dns::packet::question::QuestionSection;
+    use crate::dns::q_class::DNSClass;
+    use crate::dns::q_type::DNSRecordType;
+    use crate::dns::records::rdata::RData;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    fn response() -> DNSPacket {
+        let query = DNSPacket::new_query(&[QuestionSection {
+            q_name: "example.com".to_string(),
+            q_type: DNSRecordType::A,
+            q_class: DNSClass::IN,
+        }]);
+        DNSPacket::new_response(&query, Header::RCODE_NOERROR)
+    }
+
+    #[test]
+    fn test_to_json_flags_and_question() {
+        let mut packet = response();
+        packet.header.tc = true;
+        packet.header.ra = true;
+        packet.header.z = Header::Z_AD;
+        packet.header.rcode = Header::RCODE_NXDOMAIN;
+
+        let json = packet.to_json();
+        assert_eq!(json["Status"], 3);
+        assert_eq!(json["TC"], true);
+        assert_eq!(json["RD"], true);
+        assert_eq!(json["RA"], true);
+        assert_eq!(json["AD"], true);
+        assert_eq!(json["CD"], false);
+        assert_eq!(json["Question"][0]["name"], "example.com.");
+        assert_eq!(json["Question"][0]["type"], 1);
+        assert!(json.get("Answer").is_none());
+        assert!(json.get("Authority").is_none());
+    }
+
+    #[test]
+    fn test_to_json_sections() {
+        let mut packet = response();
+        packet.answers.push(AnswerSection {
+            q_name: "example.com".to_string(),
+            r_type: DNSRecordType::A,
+            r_class: DNSClass::IN,
+            ttl: 300,
+            rdata: RData::A(Ipv4Addr::new(93, 184, 216, 34)),
+        });
+        packet.authorities.push(AuthoritySection {
+            q_name: "example.com".to_string(),
+            q_type: DNSRecordType::NS,
+            q_class: DNSClass::IN,
+            ttl: 3600,
+            rdata: RData::NS("ns1.example.com".to_string()),
+        });
+        packet.additionals.push(AdditionalSection {
+            q_name: "ns1.example.com".to_string(),
+            q_type: DNSRecordType::AAAA,
+            q_class: DNSClass::IN,
+            ttl: 3600,
+            rdata: RData::AAAA(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+        });
+
+        let json = packet.to_json();
+        assert_eq!(json["Answer"][0]["name"], "example.com.");
+        assert_eq!(json["Answer"][0]["type"], 1);
+        assert_eq!(json["Answer"][0]["TTL"], 300);
+        assert_eq!(json["Answer"][0]["data"], "93.184.216.34");
+        assert_eq!(json["Authority"][0]["data"], "ns1.example.com.");
+        assert_eq!(json["Additional"][0]["type"], 28);
+        assert_eq!(json["Additional"][0]["data"], "2001:db8::1");
+    }
+}
@@ -1,7 +1,11 @@
 mod answer;
 mod authority;
+mod edns;
 mod header;
+mod json;
 mod question;
+mod truncate;
+mod writer;
 
 #[cfg(test)]
 mod tests {
@@ -13,6 +17,8 @@ mod tests {
     use crate::dns::packet::question::QuestionSection;
     use crate::dns::q_class::DNSClass;
     use crate::dns::q_type::DNSRecordType;
+    use crate::dns::records::rdata::RData;
+    use std::net::Ipv4Addr;
 
     #[test]
     fn test_dns_packet_from_bytes() {
@@ -81,24 +87,23 @@ mod tests {
                 r_type: DNSRecordType::A,
                 r_class: DNSClass::IN,
                 ttl: 60,
-                rdlength: 4,
-                rdata: vec![127, 0, 0, 1],
+                rdata: RData::A(Ipv4Addr::new(127, 0, 0, 1)),
             }],
             authorities: vec![AuthoritySection {
                 q_name: "rust.trends.com".to_string(),
                 q_type: DNSRecordType::NS,
                 q_class: DNSClass::IN,
                 ttl: 60,
-                ns_name: "ns1.trends.com".to_string(),
+                rdata: RData::NS("ns1.trends.com".to_string()),
             }],
             additionals: vec![AdditionalSection {
                 q_name: "rust.trends.com".to_string(),
                 q_type: DNSRecordType::A,
                 q_class: DNSClass::IN,
                 ttl: 60,
-                rdlength: 4,
-                rdata: vec![127, 0, 0, 1],
+                rdata: RData::A(Ipv4Addr::new(127, 0, 0, 1)),
             }],
+            edns: None,
         };
 
         let result = DNSPacket::from_bytes(bytes).unwrap();
@@ -134,24 +139,23 @@ mod tests {
                 r_type: DNSRecordType::A,
                 r_class: DNSClass::IN,
                 ttl: 0,
-                rdlength: 0,
-                rdata: vec![],
+                rdata: RData::Unknown(vec![]),
             }],
             authorities: vec![AuthoritySection {
                 q_name: "rust.trends.com".to_string(),
                 q_type: DNSRecordType::A,
                 q_class: DNSClass::IN,
                 ttl: 0,
-                ns_name: "ns1.rust.trends.com".to_string(),
+                rdata: RData::NS("ns1.rust.trends.com".to_string()),
             }],
             additionals: vec![AdditionalSection {
                 q_name: "rust.trends.com".to_string(),
                 q_type: DNSRecordType::A,
                 q_class: DNSClass::IN,
                 ttl: 60,
-                rdlength: 4,
-                rdata: vec![127, 0, 0, 1],
+                rdata: RData::A(Ipv4Addr::new(127, 0, 0, 1)),
             }],
+            edns: None,
         };
 
         let expected_bytes: &[u8] = &[
@@ -170,10 +174,7 @@ mod tests {
             0x00, 0x01, // QTYPE = A
             0x00, 0x01, // QCLASS = IN
             // ===== ANSWER SECTION =====
-            0x04, b'r', b'u', b's', b't', // label "rust"
-            0x06, b't', b'r', b'e', b'n', b'd', b's', // label "trends"
-            0x03, b'c', b'o', b'm', // label "com"
-            0x00, // end of NAME
+            0xC0, 0x0C, // NAME = pointer to the QNAME at offset 12
             0x00, 0x01, // TYPE = A
             0x00, 0x01, // CLASS = IN
             0x00, 0x00, 0x00, 0x00, // TTL = 0
@@ -181,21 +182,16 @@ mod tests {
             // no RDATA since rdlength = 0
 
             // ===== AUTHORITY SECTION =====
-            // Authority NAME
-            0x04, b'r', b'u', b's', b't', 0x06, b't', b'r', b'e', b'n', b'd', b's', 0x03, b'c',
-            b'o', b'm', 0x00, // TYPE
-            0x00, 0x01, // A
-            // CLASS
-            0x00, 0x01, // IN
-            // TTL
-            0x00, 0x00, 0x00, 0x00, // RDLENGTH
-            0x00, 0x15, // 21 octets
-            // RDATA = ns1.rust.trends.com
-            0x03, b'n', b's', b'1', 0x04, b'r', b'u', b's', b't', 0x06, b't', b'r', b'e', b'n',
-            b'd', b's', 0x03, b'c', b'o', b'm', 0x00, // ===== ADDITIONAL SECTION =====
-            0x04, b'r', b'u', b's', b't', 0x06, b't', b'r', b'e', b'n', b'd', b's', 0x03, b'c',
-            b'o', b'm', 0x00, 0x00, 0x01, // TYPE
-            0x00, 0x01, // CLASS
+            0xC0, 0x0C, // NAME = pointer to the QNAME
+            0x00, 0x01, // TYPE = A
+            0x00, 0x01, // CLASS = IN
+            0x00, 0x00, 0x00, 0x00, // TTL = 0
+            0x00, 0x06, // RDLENGTH = 6
+            // RDATA = ns1.rust.trends.com: "ns1" then a pointer to the QNAME
+            0x03, b'n', b's', b'1', 0xC0, 0x0C, // ===== ADDITIONAL SECTION =====
+            0xC0, 0x0C, // NAME = pointer to the QNAME
+            0x00, 0x01, // TYPE = A
+            0x00, 0x01, // CLASS = IN
             0x00, 0x00, 0x00, 0x3C, // TTL = 60
             0x00, 0x04, // RDLENGTH = 4
             127, 0, 0, 1, // RDATA
@@ -239,6 +235,7 @@ mod tests {
             answers: vec![],
             authorities: vec![],
             additionals: vec![],
+            edns: None,
         };
 
         println!(
@@ -252,4 +249,68 @@ mod tests {
         );
         assert_eq!(expected_packet, result)
     }
+
+    /// Response with a record of every section, an unknown type and class,
+    /// compressed names and an OPT record.
+    fn rich_response() -> Vec<u8> {
+        let query = DNSPacket::new_query(&[QuestionSection {
+            q_name: "example.com".to_string(),
+            q_type: DNSRecordType::Unknown(65280),
+            q_class: DNSClass::Unknown(4096),
+        }]);
+        let mut response = DNSPacket::new_response(&query, Header::RCODE_NOERROR);
+        response.answers.push(AnswerSection {
+            q_name: "example.com".to_string(),
+            r_type: DNSRecordType::Unknown(65280),
+            r_class: DNSClass::Unknown(4096),
+            ttl: 60,
+            rdata: RData::Unknown(vec![0xDE, 0xAD]),
+        });
+        response.authorities.push(AuthoritySection {
+            q_name: "example.com".to_string(),
+            q_type: DNSRecordType::NS,
+            q_class: DNSClass::IN,
+            ttl: 3600,
+            rdata: RData::NS("ns1.example.com".to_string()),
+        });
+        response.additionals.push(AdditionalSection {
+            q_name: "ns1.example.com".to_string(),
+            q_type: DNSRecordType::A,
+            q_class: DNSClass::IN,
+            ttl: 3600,
+            rdata: RData::A(Ipv4Addr::new(192, 0, 2, 53)),
+        });
+        response.edns = Some(crate::dns::packet::edns::Edns::new(1232));
+        response.sync_counts();
+        response.to_bytes().unwrap()
+    }
+
+    #[test]
+    fn test_unknown_type_and_class_preserved() {
+        let bytes = rich_response();
+        let packet = DNSPacket::from_bytes(&bytes).unwrap();
+
+        assert_eq!(packet.questions[0].q_type, DNSRecordType::Unknown(65280));
+        assert_eq!(packet.questions[0].q_class, DNSClass::Unknown(4096));
+        assert_eq!(packet.answers[0].rdata, RData::Unknown(vec![0xDE, 0xAD]));
+        assert_eq!(packet.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_from_bytes_is_total() {
+        let bytes = rich_response();
+
+        // every truncation is an error, never a panic
+        for len in 0..bytes.len() {
+            assert!(DNSPacket::from_bytes(&bytes[..len]).is_err(), "len {}", len);
+        }
+        // any corrupted byte is parsed or rejected, never a panic
+        for i in 0..bytes.len() {
+            for value in [0x00, 0x01, 0x3F, 0x40, 0xC0, 0xFF] {
+                let mut corrupted = bytes.clone();
+                corrupted[i] = value;
+                let _ = DNSPacket::from_bytes(&corrupted);
+            }
+        }
+    }
 }
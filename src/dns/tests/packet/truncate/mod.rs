@@ -0,0 +1,122 @@
+#[cfg(test)]
+mod tests {
+    use crate::config::{ZoneConfig, ZoneType};
+    use crate::dns::packet::DNSPacket;
+    use crate::dns::packet::edns::Edns;
+    use crate::dns::q_type::DNSRecordType;
+    use crate::dns::tests::fixtures::query_type;
+    use crate::dns::zones::manager::ZoneManager;
+
+    fn query(name: &str, q_type: DNSRecordType, edns: Option<u16>) -> DNSPacket {
+        let mut query = query_type(name, q_type);
+        query.edns = edns.map(Edns::new);
+        query.sync_counts();
+        query
+    }
+
+    /// Answer `query` from zones/nihilist.moe.zone, as the encoder gets it.
+    fn answer(query: &DNSPacket) -> DNSPacket {
+        let manager = ZoneManager::from_config(&[ZoneConfig {
+            name: "nihilist.moe.".to_string(),
+            kind: ZoneType::Master,
+            file: Some("zones/nihilist.moe.zone".to_string()),
+            ..Default::default()
+        }]);
+        let mut response = manager.answer(query).unwrap();
+        response.negotiate_edns(query, Some(4096));
+        response
+    }
+
+    #[test]
+    fn test_fitting_response_is_untouched() {
+        let q = query("nihilist.moe", DNSRecordType::MX, None);
+        let mut response = answer(&q);
+        let full = response.to_bytes().unwrap();
+
+        let bytes = response
+            .truncate_to(q.max_udp_response_size(Some(4096), 4096))
+            .unwrap();
+
+        assert_eq!(bytes, full);
+        assert!(!response.header.tc);
+    }
+
+    #[test]
+    fn test_additionals_dropped_first() {
+        let q = query("nihilist.moe", DNSRecordType::MX, None);
+        let mut response = answer(&q);
+        assert!(!response.additionals.is_empty());
+        let full = response.to_bytes().unwrap();
+
+        let bytes = response.truncate_to(full.len() - 1).unwrap();
+        let sent = DNSPacket::from_bytes(&bytes).unwrap();
+
+        assert!(!sent.header.tc);
+        assert_eq!(sent.answers.len(), 2);
+        assert!(sent.additionals.is_empty());
+        assert_eq!(sent.header.arcount, 0);
+    }
+
+    #[test]
+    fn test_many_a_records_truncated_without_edns() {
+        let q = query("many.nihilist.moe", DNSRecordType::A, None);
+        let mut response = answer(&q);
+        assert_eq!(response.answers.len(), 40);
+
+        let bytes = response
+            .truncate_to(q.max_udp_response_size(Some(4096), 4096))
+            .unwrap();
+        let sent = DNSPacket::from_bytes(&bytes).unwrap();
+
+        assert!(bytes.len() <= 512);
+        assert!(sent.header.tc);
+        assert!(sent.answers.is_empty());
+        assert_eq!(sent.questions, q.questions);
+    }
+
+    #[test]
+    fn test_many_a_records_fit_with_edns() {
+        let q = query("many.nihilist.moe", DNSRecordType::A, Some(1232));
+        let mut response = answer(&q);
+
+        let bytes = response
+            .truncate_to(q.max_udp_response_size(Some(4096), 4096))
+            .unwrap();
+        let sent = DNSPacket::from_bytes(&bytes).unwrap();
+
+        assert!(bytes.len() > 512);
+        assert!(!sent.header.tc);
+        assert_eq!(sent.answers.len(), 40);
+    }
+
+    #[test]
+    fn test_large_txt_truncated_to_negotiated_size() {
+        let q = query("big-txt.nihilist.moe", DNSRecordType::TXT, Some(1232));
+        let mut response = answer(&q);
+        assert!(response.to_bytes().unwrap().len() > 1232);
+
+        let bytes = response
+            .truncate_to(q.max_udp_response_size(Some(4096), 4096))
+            .unwrap();
+        let sent = DNSPacket::from_bytes(&bytes).unwrap();
+
+        assert!(sent.header.tc);
+        assert!(sent.answers.is_empty());
+        // the OPT record is kept in a truncated response
+        assert_eq!(sent.edns, Some(Edns::new(4096)));
+    }
+
+    #[test]
+    fn test_large_txt_capped_by_server() {
+        let q = query("big-txt.nihilist.moe", DNSRecordType::TXT, Some(4096));
+        let mut response = answer(&q);
+
+        let fits = response.clone().truncate_to(4096).unwrap();
+        let capped = response
+            .truncate_to(q.max_udp_response_size(Some(4096), 1232))
+            .unwrap();
+
+        assert_eq!(DNSPacket::from_bytes(&fits).unwrap().answers.len(), 3);
+        assert!(DNSPacket::from_bytes(&capped).unwrap().header.tc);
+    }
+}
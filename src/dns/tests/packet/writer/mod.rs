@@ -0,0 +1,207 @@
+#[cfg(test)]
+mod tests {
+    use crate::dns::packet::DNSPacket;
+    use crate::dns::packet::additional::AdditionalSection;
+    use crate::dns::packet::answer::AnswerSection;
+    use crate::dns::packet::authority::AuthoritySection;
+    use crate::dns::packet::header::Header;
+    use crate::dns::packet::question::QuestionSection;
+    use crate::dns::packet::writer::MessageWriter;
+    use crate::dns::q_class::DNSClass;
+    use crate::dns::q_name::parse_qname;
+    use crate::dns::q_type::DNSRecordType;
+    use crate::dns::records::rdata::RData;
+    use crate::exceptions::SCloudException;
+
+    fn response(name: &str, q_type: DNSRecordType) -> DNSPacket {
+        let query = DNSPacket::new_query(&[QuestionSection {
+            q_name: name.to_string(),
+            q_type,
+            q_class: DNSClass::IN,
+        }]);
+        DNSPacket::new_response(&query, Header::RCODE_NOERROR)
+    }
+
+    fn answer(name: &str, r_type: DNSRecordType, rdata: RData) -> AnswerSection {
+        AnswerSection {
+            q_name: name.to_string(),
+            r_type,
+            r_class: DNSClass::IN,
+            ttl: 300,
+            rdata,
+        }
+    }
+
+    #[test]
+    fn test_write_name_points_at_suffixes() {
+        let mut w = MessageWriter::new();
+        w.write_name("www.example.com").unwrap();
+        w.write_name("mail.example.com.").unwrap();
+        w.write_name("example.com").unwrap();
+        w.write_name("org").unwrap();
+        let buf = w.finish();
+
+        assert_eq!(
+            buf,
+            b"\x03www\x07example\x03com\x00\x04mail\xc0\x04\xc0\x04\x03org\x00"
+        );
+        assert_eq!(
+            parse_qname(&buf, 0).unwrap(),
+            ("www.example.com".into(), 17)
+        );
+        assert_eq!(
+            parse_qname(&buf, 17).unwrap(),
+            ("mail.example.com".into(), 24)
+        );
+        assert_eq!(parse_qname(&buf, 24).unwrap(), ("example.com".into(), 26));
+        assert_eq!(parse_qname(&buf, 26).unwrap(), ("org".into(), 31));
+    }
+
+    #[test]
+    fn test_write_name_keeps_case() {
+        let mut w = MessageWriter::new();
+        w.write_name("Example.COM").unwrap();
+        w.write_name("example.COM").unwrap();
+        let buf = w.finish();
+
+        // only "COM" is shared: a pointer would change the case of "example"
+        assert_eq!(&buf[13..], b"\x07example\xc0\x08");
+        assert_eq!(parse_qname(&buf, 13).unwrap().0, "example.COM");
+    }
+
+    #[test]
+    fn test_write_name_root_and_errors() {
+        let mut w = MessageWriter::new();
+        w.write_name("").unwrap();
+        w.write_name(".").unwrap();
+        assert_eq!(
+            w.write_name(&format!("{}.example.com", "a".repeat(64)))
+                .unwrap_err(),
+            SCloudException::SCLOUD_QUESTION_SERIALIZATION_FAILED_QNAME_TOO_LONG
+        );
+        // nothing is written for a rejected name
+        assert_eq!(w.finish(), vec![0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_uncompressed_writer_never_points() {
+        let mut w = MessageWriter::uncompressed();
+        w.write_name("example.com").unwrap();
+        w.write_name("example.com").unwrap();
+
+        assert_eq!(w.finish(), b"\x07example\x03com\x00\x07example\x03com\x00");
+    }
+
+    #[test]
+    fn test_no_pointer_past_14_bits() {
+        let mut w = MessageWriter::new();
+        w.put(&vec![0; 0x4000]);
+        w.write_name("example.com").unwrap();
+        w.write_name("example.com").unwrap();
+        let buf = w.finish();
+
+        assert_eq!(
+            &buf[0x4000..],
+            b"\x07example\x03com\x00\x07example\x03com\x00"
+        );
+    }
+
+    #[test]
+    fn test_packet_compresses_rdata_names() {
+        let mut packet = response("example.com", DNSRecordType::MX);
+        packet.answers.push(answer(
+            "example.com",
+            DNSRecordType::MX,
+            RData::MX {
+                preference: 10,
+                exchange: "mail.example.com".to_string(),
+            },
+        ));
+        packet.authorities.push(AuthoritySection {
+            q_name: "example.com".to_string(),
+            q_type: DNSRecordType::SOA,
+            q_class: DNSClass::IN,
+            ttl: 3600,
+            rdata: RData::SOA {
+                mname: "ns1.example.com".to_string(),
+                rname: "hostmaster.example.com".to_string(),
+                serial: 1,
+                refresh: 7200,
+                retry: 3600,
+                expire: 1_209_600,
+                minimum: 300,
+            },
+        });
+        packet.additionals.push(AdditionalSection {
+            q_name: "mail.example.com".to_string(),
+            q_type: DNSRecordType::A,
+            q_class: DNSClass::IN,
+            ttl: 300,
+            rdata: RData::A("192.0.2.25".parse().unwrap()),
+        });
+        packet.sync_counts();
+
+        let bytes = packet.to_bytes().unwrap();
+        let uncompressed = 12
+            + packet.questions[0].to_bytes().unwrap().len()
+            + packet.answers[0].to_bytes().unwrap().len()
+            + packet.authorities[0].to_bytes().unwrap().len()
+            + packet.additionals[0].to_bytes().unwrap().len();
+
+        assert!(bytes.len() < uncompressed);
+        // answer: owner is a pointer to the QNAME, RDATA is 10, "mail" + pointer
+        assert_eq!(&bytes[29..31], b"\xc0\x0c");
+        assert_eq!(&bytes[39..50], b"\x00\x09\x00\x0a\x04mail\xc0\x0c");
+        // additional owner points at the exchange written in the MX RDATA
+        let additional = bytes.len() - 16;
+        assert_eq!(&bytes[additional..additional + 2], b"\xc0\x2b");
+        assert_eq!(DNSPacket::from_bytes(&bytes).unwrap(), packet);
+    }
+
+    #[test]
+    fn test_packet_keeps_name_case() {
+        // a question using 0x20 randomization, answered with the zone's case
+        let mut packet = response("wWw.ExAmPlE.cOm", DNSRecordType::CNAME);
+        packet.answers.push(answer(
+            "www.example.com",
+            DNSRecordType::CNAME,
+            RData::CNAME("web.EXAMPLE.COM".to_string()),
+        ));
+        packet.sync_counts();
+
+        let decoded = DNSPacket::from_bytes(&packet.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(decoded.questions[0].q_name, "wWw.ExAmPlE.cOm");
+        assert_eq!(decoded.answers[0].q_name, "www.example.com");
+        assert_eq!(decoded, packet);
+    }
+
+    #[test]
+    fn test_packet_keeps_other_rdata_names_whole() {
+        let mut packet = response("_sip._tcp.example.com", DNSRecordType::SRV);
+        packet.answers.push(answer(
+            "_sip._tcp.example.com",
+            DNSRecordType::SRV,
+            RData::SRV {
+                priority: 1,
+                weight: 5,
+                port: 5060,
+                target: "sip.example.com".to_string(),
+            },
+        ));
+        packet.answers.push(answer(
+            "sip.example.com",
+            DNSRecordType::A,
+            RData::A("192.0.2.5".parse().unwrap()),
+        ));
+        packet.sync_counts();
+
+        let bytes = packet.to_bytes().unwrap();
+        let target = b"\x03sip\x07example\x03com\x00";
+
+        assert!(bytes.windows(target.len()).any(|w| w == target));
+        // the A owner was not allowed to point into the SRV RDATA
+        assert_eq!(&bytes[bytes.len() - 20..bytes.len() - 15], b"\x03sip\xc0");
+        assert_eq!(DNSPacket::from_bytes(&bytes).unwrap(), packet);
+    }
+}
@@ -22,6 +22,13 @@ mod tests {
         assert_eq!(DNSClass::try_from(255).unwrap(), DNSClass::ANY);
     }
 
+    #[test]
+    fn unknown_qclass_preserved() {
+        assert_eq!(DNSClass::try_from(4096).unwrap(), DNSClass::Unknown(4096));
+        assert_eq!(u16::try_from(DNSClass::Unknown(4096)).unwrap(), 4096);
+        assert_eq!(DNSClass::from(&[0x10, 0x00]), DNSClass::Unknown(4096));
+    }
+
     #[test]
     fn test_dnsclass_from_bytes() {
         let bytes = [0x00, 0x01];
@@ -33,4 +40,17 @@ mod tests {
         let bytes = [0x00, 0xFF];
         assert_eq!(DNSClass::from(&bytes), DNSClass::ANY);
     }
+
+    #[test]
+    fn qclass_from_name() {
+        assert_eq!(DNSClass::from_name("IN"), Some(DNSClass::IN));
+        assert_eq!(DNSClass::from_name("ch"), Some(DNSClass::CH));
+        assert_eq!(DNSClass::from_name("CLASS1"), Some(DNSClass::IN));
+        assert_eq!(
+            DNSClass::from_name("CLASS4096"),
+            Some(DNSClass::Unknown(4096))
+        );
+        assert_eq!(DNSClass::from_name("CLASS"), None);
+        assert_eq!(DNSClass::from_name("MX"), None);
+    }
 }
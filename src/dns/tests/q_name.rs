@@ -2,6 +2,9 @@
 mod tests {
     use crate::dns::q_name::parse_qname;
     use crate::dns::q_name::parse_qname_at;
+    use crate::dns::q_name::write_qname;
+    use crate::dns::q_name::{NameLimits, parse_qname_with_limits};
+    use crate::dns::q_name::{is_subdomain, normalize_name, split_label, strip_root};
     use crate::exceptions::SCloudException;
 
     #[test]
@@ -81,4 +84,199 @@ mod tests {
             SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME
         );
     }
+
+    #[test]
+    fn test_compression_pointer_loop() {
+        // pointer to itself
+        let buf = [0xC0, 0x00];
+        assert_eq!(
+            parse_qname(&buf, 0).unwrap_err(),
+            SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME_COMPRESSION_FAILED
+        );
+
+        // "a" then a pointer back to "a": each jump lands on the same labels
+        let buf = [0x01, b'a', 0xC0, 0x00];
+        assert_eq!(
+            parse_qname(&buf, 0).unwrap_err(),
+            SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME_COMPRESSION_FAILED
+        );
+
+        // two pointers pointing at each other
+        let buf = [0x00, 0xC0, 0x03, 0xC0, 0x01];
+        assert_eq!(
+            parse_qname(&buf, 3).unwrap_err(),
+            SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME_COMPRESSION_FAILED
+        );
+    }
+
+    #[test]
+    fn test_compression_forward_pointer() {
+        let buf = [0xC0, 0x02, 0x03, b'c', b'o', b'm', 0x00];
+
+        assert_eq!(
+            parse_qname(&buf, 0).unwrap_err(),
+            SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME_COMPRESSION_FAILED
+        );
+    }
+
+    #[test]
+    fn test_compression_chain_end_position() {
+        // "com" at 0, "example" + pointer at 5, "www" + pointer at 15
+        let buf = [
+            0x03, b'c', b'o', b'm', 0x00, 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0xC0,
+            0x00, 0x03, b'w', b'w', b'w', 0xC0, 0x05,
+        ];
+
+        assert_eq!(
+            parse_qname(&buf, 15).unwrap(),
+            ("www.example.com".to_string(), buf.len())
+        );
+    }
+
+    #[test]
+    fn test_reserved_label_types() {
+        for first in [0x40, 0x80] {
+            assert_eq!(
+                parse_qname(&[first, 0x00], 0).unwrap_err(),
+                SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME
+            );
+        }
+    }
+
+    #[test]
+    fn test_name_length_limits() {
+        // 5 labels of 63 bytes: 319 characters
+        let mut buf = Vec::new();
+        for _ in 0..5 {
+            buf.push(63);
+            buf.extend_from_slice(&[b'a'; 63]);
+        }
+        buf.push(0x00);
+        assert_eq!(
+            parse_qname(&buf, 0).unwrap_err(),
+            SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME_TOO_LONG
+        );
+
+        let limits = NameLimits {
+            max_label_length: 3,
+            max_domain_length: 7,
+        };
+        assert_eq!(
+            parse_qname_with_limits(b"\x03www\x03com\x00", 0, &limits).unwrap(),
+            ("www.com".to_string(), 9)
+        );
+        assert_eq!(
+            parse_qname_with_limits(b"\x04mail\x03com\x00", 0, &limits).unwrap_err(),
+            SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME_TOO_LONG
+        );
+        assert_eq!(
+            parse_qname_with_limits(b"\x01a\x03www\x03com\x00", 0, &limits).unwrap_err(),
+            SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME_TOO_LONG
+        );
+    }
+
+    #[test]
+    fn test_binary_labels_are_escaped() {
+        // "a.b" (one label holding a dot), a space, a backslash, 0xff and UTF-8 bytes
+        let buf = b"\x03a.b\x04x y\\\x02\xff\xc3\x07example\x00";
+        let (name, consumed) = parse_qname(buf, 0).unwrap();
+
+        assert_eq!(name, "a\\.b.x\\032y\\\\.\\255\\195.example");
+        assert_eq!(consumed, buf.len());
+
+        let mut written = Vec::new();
+        write_qname(&mut written, &name).unwrap();
+        assert_eq!(written, buf);
+
+        // limits apply to the wire length, not to the escapes
+        let limits = NameLimits {
+            max_label_length: 2,
+            max_domain_length: 5,
+        };
+        assert_eq!(
+            parse_qname_with_limits(b"\x02\x00\x01\x02..\x00", 0, &limits).unwrap(),
+            ("\\000\\001.\\.\\.".to_string(), 7)
+        );
+    }
+
+    #[test]
+    fn test_presentation_helpers() {
+        assert_eq!(split_label("a\\.b.example"), ("a\\.b", "example"));
+        assert_eq!(split_label("a\\\\.b"), ("a\\\\", "b"));
+        assert_eq!(split_label("com"), ("com", ""));
+
+        assert_eq!(strip_root("example.com."), "example.com");
+        assert_eq!(strip_root("a\\."), "a\\.");
+        assert_eq!(strip_root("a\\\\."), "a\\\\");
+
+        assert!(is_subdomain("www.example.com", "example.com"));
+        assert!(is_subdomain("example.com", "example.com"));
+        assert!(is_subdomain("com", ""));
+        assert!(!is_subdomain("notexample.com", "example.com"));
+        // "www\.example" is a single label below com
+        assert!(!is_subdomain("www\\.example.com", "example.com"));
+        assert!(is_subdomain("www\\\\.example.com", "example.com"));
+    }
+
+    #[test]
+    fn test_normalize_name() {
+        assert_eq!(normalize_name("\\119ww.Example.").unwrap(), "www.Example.");
+        assert_eq!(normalize_name("a\\.b\\\\c").unwrap(), "a\\.b\\\\c");
+        assert_eq!(normalize_name("a\\046b").unwrap(), "a\\.b");
+        assert_eq!(normalize_name("\\200\\x").unwrap(), "\\200x");
+        assert_eq!(normalize_name("a\\.").unwrap(), "a\\.");
+        assert_eq!(normalize_name(".").unwrap(), ".");
+        assert_eq!(normalize_name("@").unwrap(), "@");
+        for name in ["a..b", ".a", "a\\", "a\\256"] {
+            assert!(normalize_name(name).is_none(), "{}", name);
+        }
+
+        // the same name off the wire
+        let (wire, _) = parse_qname(b"\x03a.b\x02\\\xc8\x00", 0).unwrap();
+        assert_eq!(normalize_name("a\\.b.\\092\\200").unwrap(), wire);
+    }
+
+    #[test]
+    fn test_write_qname_bad_escape() {
+        for name in ["a\\", "a\\256.com", "a..com"] {
+            assert_eq!(
+                write_qname(&mut Vec::new(), name).unwrap_err(),
+                SCloudException::SCLOUD_QUESTION_SERIALIZATION_FAILED_QNAME_TOO_LONG,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_write_qname_round_trip() {
+        let mut buf = Vec::new();
+        write_qname(&mut buf, "www.example.com.").unwrap();
+
+        assert_eq!(buf, b"\x03www\x07example\x03com\x00");
+        assert_eq!(
+            parse_qname(&buf, 0).unwrap(),
+            ("www.example.com".to_string(), buf.len())
+        );
+    }
+
+    #[test]
+    fn test_write_qname_root() {
+        let mut buf = Vec::new();
+        write_qname(&mut buf, ".").unwrap();
+        write_qname(&mut buf, "").unwrap();
+
+        assert_eq!(buf, vec![0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_write_qname_label_too_long() {
+        let mut buf = Vec::new();
+        let result = write_qname(&mut buf, &format!("{}.com", "a".repeat(64)));
+
+        assert_eq!(
+            result.unwrap_err(),
+            SCloudException::SCLOUD_QUESTION_SERIALIZATION_FAILED_QNAME_TOO_LONG
+        );
+    }
 }
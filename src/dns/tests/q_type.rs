@@ -19,12 +19,9 @@ mod tests {
 
     #[test]
     fn test_tryfrom_u16_for_dns_record_type_unknown() {
-        let err = DNSRecordType::try_from(24616).expect_err("expected Err for unknown QTYPE");
+        let result = DNSRecordType::try_from(24616).unwrap();
 
-        assert_eq!(
-            err,
-            crate::exceptions::SCloudException::SCLOUD_QTYPE_U16_FOR_DNSRECORDTYPE_UNKNOWN
-        );
+        assert_eq!(result, DNSRecordType::Unknown(24616));
     }
 
     #[test]
@@ -36,13 +33,9 @@ mod tests {
 
     #[test]
     fn test_tryfrom_dns_record_type_unknown_to_u16() {
-        let err = u16::try_from(DNSRecordType::Unknown(45616))
-            .expect_err("expected Err for Unknown DNSRecordType");
+        let result = u16::try_from(DNSRecordType::Unknown(45616)).unwrap();
 
-        assert_eq!(
-            err,
-            crate::exceptions::SCloudException::SCLOUD_QTYPE_DNSRECORDTYPE_FOR_U16_UNKNOWN
-        );
+        assert_eq!(result, 45616);
     }
 
     #[test]
@@ -57,11 +50,24 @@ mod tests {
     fn test_tryfrom_array_for_dns_record_type_unknown() {
         let bytes: &[u8; 2] = &[0x01, 0x80]; // 384
 
-        let err = DNSRecordType::try_from(bytes).expect_err("expected Err for unknown QTYPE bytes");
+        let result = DNSRecordType::try_from(bytes).unwrap();
+
+        assert_eq!(result, DNSRecordType::Unknown(384));
+    }
 
+    #[test]
+    fn test_from_name() {
+        assert_eq!(DNSRecordType::from_name("AAAA"), Some(DNSRecordType::AAAA));
+        assert_eq!(DNSRecordType::from_name("mx"), Some(DNSRecordType::MX));
+        assert_eq!(DNSRecordType::from_name("TYPE16"), Some(DNSRecordType::TXT));
+        assert_eq!(DNSRecordType::from_name("257"), Some(DNSRecordType::CAA));
+        assert_eq!(DNSRecordType::from_name("Unknown"), None);
         assert_eq!(
-            err,
-            crate::exceptions::SCloudException::SCLOUD_QTYPE_U16_FOR_DNSRECORDTYPE_UNKNOWN
+            DNSRecordType::from_name("TYPE384"),
+            Some(DNSRecordType::Unknown(384))
         );
+        assert_eq!(DNSRecordType::name_of(384), "TYPE384");
+        assert_eq!(DNSRecordType::name_of(41), "OPT");
+        assert_eq!(DNSRecordType::from_name(""), None);
     }
 }
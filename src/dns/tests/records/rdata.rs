@@ -0,0 +1,265 @@
+#[cfg(test)]
+mod tests {
+    use crate::dns::q_type::DNSRecordType;
+    use crate::dns::records::rdata::{RData, SvcbData};
+    use crate::exceptions::SCloudException;
+
+    /// Every record type with its presentation format.
+    const SAMPLES: &[(DNSRecordType, &str)] = &[
+        (DNSRecordType::A, "192.0.2.1"),
+        (DNSRecordType::AAAA, "2001:db8::1"),
+        (DNSRecordType::NS, "ns1.example.com."),
+        (DNSRecordType::CNAME, "www.example.com."),
+        (DNSRecordType::PTR, "host.example.com."),
+        (DNSRecordType::DNAME, "example.net."),
+        (DNSRecordType::MX, "10 mail.example.com."),
+        (DNSRecordType::TXT, "\"v=spf1 -all\" \"a \\\"b\\\\\""),
+        (
+            DNSRecordType::SOA,
+            "ns.example.com. host.example.com. 2024010101 7200 3600 1209600 300",
+        ),
+        (DNSRecordType::SRV, "1 5 443 sip.example.com."),
+        (DNSRecordType::CAA, "0 issue \"letsencrypt.org\""),
+        (
+            DNSRecordType::NAPTR,
+            "100 10 \"U\" \"E2U+sip\" \"!^.*$!sip:info@example.com!\" .",
+        ),
+        (
+            DNSRecordType::SVCB,
+            "1 svc.example.com. alpn=h2,h3 port=8443 ipv4hint=192.0.2.1",
+        ),
+        (DNSRecordType::HTTPS, "1 . alpn=h2 ipv6hint=2001:db8::1"),
+        (DNSRecordType::TLSA, "3 1 1 0123456789ABCDEF"),
+        (DNSRecordType::SSHFP, "4 2 ABCDEF0123"),
+        (
+            DNSRecordType::DS,
+            "60485 5 1 2BB183AF5F22588179A53B0A98631FAD1A292118",
+        ),
+        (DNSRecordType::DNSKEY, "257 3 8 AwEAAag="),
+        (
+            DNSRecordType::RRSIG,
+            "A 8 2 300 20240201000000 20240101000000 12345 example.com. AwEAAag=",
+        ),
+        (
+            DNSRecordType::NSEC,
+            "host.example.com. A MX RRSIG NSEC TYPE1234",
+        ),
+        (
+            DNSRecordType::NSEC3,
+            "1 0 10 AABBCCDD 2t7b4g4vsa5smi47k61mv5bv1a22bojr A RRSIG",
+        ),
+        (DNSRecordType::NSEC3PARAM, "1 0 0 -"),
+    ];
+
+    #[test]
+    fn test_presentation_round_trip() {
+        for (rtype, text) in SAMPLES {
+            let rdata =
+                RData::from_text(*rtype, text).unwrap_or_else(|e| panic!("{:?}: {:?}", rtype, e));
+            assert_eq!(rdata.to_string(), *text, "{:?}", rtype);
+        }
+    }
+
+    #[test]
+    fn test_wire_round_trip() {
+        for (rtype, text) in SAMPLES {
+            let rdata =
+                RData::from_text(*rtype, text).unwrap_or_else(|e| panic!("{:?}: {:?}", rtype, e));
+            let wire = rdata.to_bytes().unwrap();
+            let decoded = RData::decode(*rtype, &wire, 0, wire.len()).unwrap();
+
+            assert_eq!(decoded.to_string(), *text, "{:?}", rtype);
+            assert_eq!(decoded.to_bytes().unwrap(), wire, "{:?}", rtype);
+        }
+    }
+
+    #[test]
+    fn test_decode_follows_compression() {
+        // example.com at offset 0, then MX RDATA: preference 10, "mail" + pointer to 0
+        let buf = [
+            0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm', 0x00, 0x00,
+            0x0A, 0x04, b'm', b'a', b'i', b'l', 0xC0, 0x00, 0xFF, 0xFF,
+        ];
+        let rdata = RData::decode(DNSRecordType::MX, &buf, 13, 9).unwrap();
+
+        assert_eq!(
+            rdata,
+            RData::MX {
+                preference: 10,
+                exchange: "mail.example.com".to_string(),
+            }
+        );
+        assert_eq!(
+            rdata.to_bytes().unwrap(),
+            b"\x00\x0A\x04mail\x07example\x03com\x00"
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed() {
+        // A record of 3 bytes
+        assert_eq!(
+            RData::decode(DNSRecordType::A, &[10, 0, 0], 0, 3).unwrap_err(),
+            SCloudException::SCLOUD_RDATA_MALFORMED
+        );
+        // character-string longer than the RDATA
+        assert_eq!(
+            RData::decode(DNSRecordType::TXT, &[0x04, b'a'], 0, 2).unwrap_err(),
+            SCloudException::SCLOUD_RDATA_MALFORMED
+        );
+        // RDLENGTH past the end of the message
+        assert_eq!(
+            RData::decode(DNSRecordType::A, &[10, 0, 0, 1], 0, 8).unwrap_err(),
+            SCloudException::SCLOUD_RDATA_MALFORMED
+        );
+        // trailing bytes after the name
+        assert_eq!(
+            RData::decode(DNSRecordType::NS, &[0x00, 0x01], 0, 2).unwrap_err(),
+            SCloudException::SCLOUD_RDATA_MALFORMED
+        );
+        // name ending past the RDATA
+        assert!(RData::decode(DNSRecordType::MX, &[0x00, 0x0A, 0x04, b'm'], 0, 4).is_err());
+        // NSEC bitmap windows out of order, or repeated (RFC 4034 section 4.1.2)
+        for bitmap in [
+            [0x01, 0x01, 0x40, 0x00, 0x01, 0x40],
+            [0x00, 0x01, 0x40, 0x00, 0x01, 0x20],
+        ] {
+            let mut wire = vec![0x00];
+            wire.extend_from_slice(&bitmap);
+            assert_eq!(
+                RData::decode(DNSRecordType::NSEC, &wire, 0, wire.len()).unwrap_err(),
+                SCloudException::SCLOUD_RDATA_MALFORMED
+            );
+        }
+    }
+
+    #[test]
+    fn test_unknown_types_kept_opaque() {
+        let rdata = RData::decode(DNSRecordType::HINFO, &[0x01, 0x02, 0xab], 0, 3).unwrap();
+
+        assert_eq!(rdata, RData::Unknown(vec![0x01, 0x02, 0xab]));
+        assert_eq!(rdata.to_string(), "\\# 3 0102AB");
+        assert_eq!(
+            RData::from_text(DNSRecordType::HINFO, "\\# 3 0102ab").unwrap(),
+            rdata
+        );
+    }
+
+    #[test]
+    fn test_from_text_generic_form() {
+        assert_eq!(
+            RData::from_text(DNSRecordType::A, "\\# 4 C0000201").unwrap(),
+            RData::A("192.0.2.1".parse().unwrap())
+        );
+        assert_eq!(
+            RData::from_text(DNSRecordType::A, "\\# 4 C00002").unwrap_err(),
+            SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA
+        );
+    }
+
+    #[test]
+    fn test_from_text_rejects_invalid() {
+        for (rtype, text) in [
+            (DNSRecordType::A, "192.0.2"),
+            (DNSRecordType::A, "192.0.2.1 extra"),
+            (DNSRecordType::MX, "ten mail.example.com."),
+            (
+                DNSRecordType::SOA,
+                "ns.example.com. host.example.com. 1 2 3",
+            ),
+            (DNSRecordType::DS, "1 2 3 XYZ"),
+            (DNSRecordType::SVCB, "1 . unknown-key=1"),
+            (DNSRecordType::TXT, ""),
+        ] {
+            assert_eq!(
+                RData::from_text(rtype, text).unwrap_err(),
+                SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA,
+                "{:?} {:?}",
+                rtype,
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_text_escapes() {
+        assert_eq!(
+            RData::from_text(DNSRecordType::TXT, "\"a  b\\\"\" c\\032d \\255").unwrap(),
+            RData::TXT(vec![b"a  b\"".to_vec(), b"c d".to_vec(), vec![0xff]])
+        );
+        assert_eq!(
+            RData::from_text(DNSRecordType::CNAME, "\\119ww.example.com.").unwrap(),
+            RData::CNAME("www.example.com.".to_string())
+        );
+        // escapes are kept in names, as in the names read off the wire
+        assert_eq!(
+            RData::from_text(DNSRecordType::CNAME, "a\\.b\\200.example.com.").unwrap(),
+            RData::CNAME("a\\.b\\200.example.com.".to_string())
+        );
+        // an empty label, a byte above 255, a trailing backslash
+        for (rtype, text) in [
+            (DNSRecordType::CNAME, "a..example.com."),
+            (DNSRecordType::TXT, "\\256"),
+            (DNSRecordType::TXT, "abc\\"),
+        ] {
+            assert_eq!(
+                RData::from_text(rtype, text).unwrap_err(),
+                SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA,
+                "{:?}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_rrsig_times() {
+        let in_seconds = RData::from_text(
+            DNSRecordType::RRSIG,
+            "A 8 2 300 1706745600 1704067200 12345 example.com. AwEAAag=",
+        )
+        .unwrap();
+        let RData::RRSIG {
+            expiration,
+            inception,
+            ..
+        } = in_seconds
+        else {
+            panic!("not an RRSIG");
+        };
+
+        assert_eq!(expiration, 1_706_745_600);
+        assert_eq!(inception, 1_704_067_200);
+        assert_eq!(
+            in_seconds.to_string(),
+            "A 8 2 300 20240201000000 20240101000000 12345 example.com. AwEAAag="
+        );
+    }
+
+    #[test]
+    fn test_svcb_params() {
+        let rdata = RData::from_text(DNSRecordType::HTTPS, "1 . alpn=h2,h3 port=443").unwrap();
+
+        assert_eq!(
+            rdata,
+            RData::HTTPS(SvcbData {
+                priority: 1,
+                target: ".".to_string(),
+                params: vec![(1, b"\x02h2\x02h3".to_vec()), (3, vec![0x01, 0xbb])],
+            })
+        );
+    }
+
+    #[test]
+    fn test_target_and_map_names() {
+        let mut rdata = RData::from_text(DNSRecordType::SRV, "1 5 443 sip").unwrap();
+        rdata.map_names(|name| format!("{}.example.com", name));
+
+        assert_eq!(rdata.target(), Some("sip.example.com"));
+        assert_eq!(
+            RData::from_text(DNSRecordType::A, "192.0.2.1")
+                .unwrap()
+                .target(),
+            None
+        );
+    }
+}
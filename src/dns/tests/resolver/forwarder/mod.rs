@@ -0,0 +1,211 @@
+#[cfg(test)]
+mod tests {
+    use crate::config::{ForwardPolicy, ForwarderConfig};
+    use crate::dns::resolver::forwarder::{EDNS_UDP_PAYLOAD, Forwarder};
+    use crate::exceptions::SCloudException;
+    use std::net::SocketAddr;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, UdpSocket};
+
+    // example.com A, ID 0x1234, RD
+    const QUERY: [u8; 29] = [
+        0x12, 0x34, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, b'e', b'x',
+        b'a', b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm', 0x00, 0x00, 0x01, 0x00, 0x01,
+    ];
+
+    /// Reply to a query: same bytes with QR set, plus `extra_flags`.
+    fn echo_reply(query: &[u8], extra_flags: u8) -> Vec<u8> {
+        let mut reply = query.to_vec();
+        reply[2] |= 0x80 | extra_flags;
+        reply
+    }
+
+    /// UDP server answering every query with `handler`, in the background.
+    async fn udp_mock(handler: fn(&[u8]) -> Vec<u8>) -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 4096];
+            while let Ok((len, peer)) = socket.recv_from(&mut buf).await {
+                let _ = socket.send_to(&handler(&buf[..len]), peer).await;
+            }
+        });
+        addr
+    }
+
+    /// UDP server that never answers.
+    async fn silent_mock() -> (SocketAddr, UdpSocket) {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        (socket.local_addr().unwrap(), socket)
+    }
+
+    fn forwarder(servers: &[SocketAddr], policy: ForwardPolicy, tcp: bool) -> Forwarder {
+        Forwarder::from_config(&ForwarderConfig {
+            name: "test".to_string(),
+            addresses: servers.iter().map(|s| s.to_string()).collect(),
+            policy,
+            timeout_ms: 300,
+            edns: true,
+            use_tcp_on_retry: Some(tcp),
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_forward_restores_id_and_strips_opt() {
+        let server = udp_mock(|q| {
+            // the OPT record added upstream is echoed back
+            assert_eq!(u16::from_be_bytes([q[10], q[11]]), 1);
+            assert_eq!(
+                u16::from_be_bytes([q[q.len() - 8], q[q.len() - 7]]),
+                EDNS_UDP_PAYLOAD
+            );
+            echo_reply(q, 0)
+        })
+        .await;
+
+        let reply = forwarder(&[server], ForwardPolicy::First, true)
+            .forward(&QUERY)
+            .await
+            .unwrap();
+
+        assert_eq!(&reply[..2], &[0x12, 0x34]);
+        assert_eq!(reply.len(), QUERY.len());
+        assert_eq!(reply[11], 0); // ARCOUNT
+        assert_ne!(reply[2] & 0x80, 0);
+    }
+
+    #[tokio::test]
+    async fn test_forward_without_edns_keeps_query() {
+        let server = udp_mock(|q| {
+            assert_eq!(q.len(), QUERY.len());
+            echo_reply(q, 0)
+        })
+        .await;
+        let mut fwd = forwarder(&[server], ForwardPolicy::First, true);
+        fwd.edns = false;
+
+        let reply = fwd.forward(&QUERY).await.unwrap();
+
+        assert_eq!(reply.len(), QUERY.len());
+    }
+
+    #[tokio::test]
+    async fn test_forward_first_policy_falls_back() {
+        let (dead, _keep) = silent_mock().await;
+        let alive = udp_mock(|q| echo_reply(q, 0)).await;
+
+        let reply = forwarder(&[dead, alive], ForwardPolicy::First, true)
+            .forward(&QUERY)
+            .await
+            .unwrap();
+
+        assert_eq!(&reply[..2], &[0x12, 0x34]);
+    }
+
+    #[tokio::test]
+    async fn test_forward_no_response() {
+        let (dead, _keep) = silent_mock().await;
+
+        let result = forwarder(&[dead], ForwardPolicy::First, true)
+            .forward(&QUERY)
+            .await;
+
+        assert_eq!(
+            result.unwrap_err(),
+            SCloudException::SCLOUD_FORWARDER_NO_RESPONSE
+        );
+    }
+
+    #[tokio::test]
+    async fn test_forward_truncated_retries_over_tcp() {
+        let udp = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let server = udp.local_addr().unwrap();
+        let tcp = TcpListener::bind(server).await.unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 4096];
+            while let Ok((len, peer)) = udp.recv_from(&mut buf).await {
+                let _ = udp.send_to(&echo_reply(&buf[..len], 0x02), peer).await;
+            }
+        });
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = tcp.accept().await {
+                let len = stream.read_u16().await.unwrap() as usize;
+                let mut query = vec![0u8; len];
+                stream.read_exact(&mut query).await.unwrap();
+                let mut reply = echo_reply(&query, 0);
+                reply[7] = 1; // ANCOUNT marker, the payload is not parsed
+                stream.write_u16(reply.len() as u16).await.unwrap();
+                stream.write_all(&reply).await.unwrap();
+            }
+        });
+
+        let reply = forwarder(&[server], ForwardPolicy::First, true)
+            .forward(&QUERY)
+            .await
+            .unwrap();
+
+        assert_eq!(reply[2] & 0x02, 0); // TC
+        assert_eq!(reply[7], 1);
+        assert_eq!(&reply[..2], &[0x12, 0x34]);
+    }
+
+    #[tokio::test]
+    async fn test_forward_truncated_kept_without_tcp_retry() {
+        let server = udp_mock(|q| echo_reply(q, 0x02)).await;
+
+        let reply = forwarder(&[server], ForwardPolicy::First, false)
+            .forward(&QUERY)
+            .await
+            .unwrap();
+
+        assert_ne!(reply[2] & 0x02, 0);
+    }
+
+    #[test]
+    fn test_round_robin_rotates_servers() {
+        let servers: Vec<SocketAddr> = ["127.0.0.1:1", "127.0.0.1:2", "127.0.0.1:3"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let fwd = forwarder(&servers, ForwardPolicy::RoundRobin, true);
+
+        assert_eq!(fwd.servers_in_order()[0], servers[0]);
+        assert_eq!(fwd.servers_in_order()[0], servers[1]);
+        assert_eq!(
+            fwd.servers_in_order(),
+            vec![servers[2], servers[0], servers[1]]
+        );
+        assert_eq!(fwd.servers_in_order()[0], servers[0]);
+    }
+
+    #[test]
+    fn test_first_and_random_policies() {
+        let servers: Vec<SocketAddr> = ["127.0.0.1:1", "127.0.0.1:2", "127.0.0.1:3"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+
+        let first = forwarder(&servers, ForwardPolicy::First, true);
+        assert_eq!(first.servers_in_order(), servers);
+        assert_eq!(first.servers_in_order(), servers);
+
+        let random = forwarder(&servers, ForwardPolicy::Random, true);
+        let mut order = random.servers_in_order();
+        order.sort();
+        assert_eq!(order, servers);
+    }
+
+    #[test]
+    fn test_from_config_without_valid_address() {
+        let result = Forwarder::from_config(&ForwarderConfig {
+            addresses: vec!["not-an-address".to_string()],
+            ..Default::default()
+        });
+
+        assert_eq!(
+            result.unwrap_err(),
+            SCloudException::SCLOUD_FORWARDER_NO_UPSTREAM
+        );
+    }
+}
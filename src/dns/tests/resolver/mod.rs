@@ -1,3 +1,4 @@
+mod forwarder;
 mod stub;
 
 #[cfg(test)]
@@ -8,6 +9,7 @@ mod tests {
     use crate::dns::packet::authority::AuthoritySection;
     use crate::dns::packet::header::Header;
     use crate::dns::packet::question::QuestionSection;
+    use crate::dns::records::rdata::RData;
     use crate::{dns, exceptions};
 
     #[test]
@@ -29,11 +31,11 @@ mod tests {
                 r_type: dns::q_type::DNSRecordType::A,
                 r_class: dns::q_class::DNSClass::IN,
                 ttl: 0,
-                rdlength: 0,
-                rdata: vec![],
+                rdata: RData::Unknown(vec![]),
             }],
             authorities: vec![],
             additionals: vec![],
+            edns: None,
         };
         let result = dns::resolver::check_response_diff(response_packet, &[origin_q]).unwrap();
         assert_eq!(result, ());
@@ -58,11 +60,11 @@ mod tests {
                 r_type: dns::q_type::DNSRecordType::A,
                 r_class: dns::q_class::DNSClass::IN,
                 ttl: 0,
-                rdlength: 0,
-                rdata: vec![],
+                rdata: RData::Unknown(vec![]),
             }],
             authorities: vec![],
             additionals: vec![],
+            edns: None,
         };
         let result = dns::resolver::check_response_diff(response_packet, &[origin_q]).unwrap_err();
         assert_eq!(
@@ -83,8 +85,7 @@ mod tests {
             r_type: dns::q_type::DNSRecordType::A,
             r_class: dns::q_class::DNSClass::IN,
             ttl: 0,
-            rdlength: 0,
-            rdata: vec![],
+            rdata: RData::Unknown(vec![]),
         };
         let result = dns::resolver::check_answer_diff(&[q], &[a]).unwrap();
         assert_eq!(result, ());
@@ -102,8 +103,7 @@ mod tests {
             r_type: dns::q_type::DNSRecordType::A,
             r_class: dns::q_class::DNSClass::IN,
             ttl: 0,
-            rdlength: 0,
-            rdata: vec![],
+            rdata: RData::Unknown(vec![]),
         };
         let result = dns::resolver::check_answer_diff(&[q], &[a]).unwrap_err();
         assert_eq!(
@@ -124,7 +124,7 @@ mod tests {
             q_type: dns::q_type::DNSRecordType::A,
             q_class: dns::q_class::DNSClass::IN,
             ttl: 0,
-            ns_name: "".to_string(),
+            rdata: RData::Unknown(vec![]),
         };
         let result = dns::resolver::check_authority_diff(&[q], &[a]).unwrap();
         assert_eq!(result, ());
@@ -142,7 +142,7 @@ mod tests {
             q_type: dns::q_type::DNSRecordType::A,
             q_class: dns::q_class::DNSClass::IN,
             ttl: 0,
-            ns_name: "".to_string(),
+            rdata: RData::Unknown(vec![]),
         };
         let result = dns::resolver::check_authority_diff(&[q], &[a]).unwrap_err();
         assert_eq!(
@@ -163,8 +163,7 @@ mod tests {
             q_type: dns::q_type::DNSRecordType::A,
             q_class: dns::q_class::DNSClass::IN,
             ttl: 0,
-            rdlength: 0,
-            rdata: vec![],
+            rdata: RData::Unknown(vec![]),
         };
         let result = dns::resolver::check_additional_diff(&[q], &[a]).unwrap();
         assert_eq!(result, ());
@@ -182,8 +181,7 @@ mod tests {
             q_type: dns::q_type::DNSRecordType::A,
             q_class: dns::q_class::DNSClass::IN,
             ttl: 0,
-            rdlength: 0,
-            rdata: vec![],
+            rdata: RData::Unknown(vec![]),
         };
         let result = dns::resolver::check_additional_diff(&[q], &[a]).unwrap_err();
         assert_eq!(
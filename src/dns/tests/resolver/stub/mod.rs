@@ -7,9 +7,10 @@ mod tests {
     use crate::dns::packet::question::QuestionSection;
     use crate::dns::q_class::DNSClass;
     use crate::dns::q_type::DNSRecordType;
+    use crate::dns::records::rdata::RData;
     use crate::dns::resolver::stub::StubResolver;
     use crate::exceptions::SCloudException;
-    use std::net::SocketAddr;
+    use std::net::{Ipv4Addr, SocketAddr};
     use std::path::Path;
 
     pub fn resolve_with_fake(
@@ -147,11 +148,11 @@ mod tests {
                 r_type: DNSRecordType::A,
                 r_class: DNSClass::IN,
                 ttl: 300,
-                rdlength: 0,
-                rdata: vec![127, 0, 0, 1],
+                rdata: RData::A(Ipv4Addr::new(127, 0, 0, 1)),
             }],
             authorities: vec![],
             additionals: vec![],
+            edns: None,
         };
 
         let result = (|| {
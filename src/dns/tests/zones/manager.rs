@@ -0,0 +1,233 @@
+#[cfg(test)]
+mod tests {
+    use crate::config::{ZoneConfig, ZoneType};
+    use crate::dns::packet::DNSPacket;
+    use crate::dns::packet::header::Header;
+    use crate::dns::q_type::DNSRecordType;
+    use crate::dns::records::rdata::RData;
+    use crate::dns::tests::fixtures::query_type as query;
+    use crate::dns::zones::Zone;
+    use crate::dns::zones::manager::ZoneManager;
+    use std::io::Write;
+    use std::net::Ipv4Addr;
+    use tempfile::NamedTempFile;
+
+    fn manager() -> ZoneManager {
+        ZoneManager::from_config(&[
+            ZoneConfig {
+                name: "nihilist.moe.".to_string(),
+                kind: ZoneType::Master,
+                file: Some("zones/nihilist.moe.zone".to_string()),
+                ..Default::default()
+            },
+            ZoneConfig {
+                name: "broken.test.".to_string(),
+                kind: ZoneType::Master,
+                file: Some("zones/does-not-exist.zone".to_string()),
+                ..Default::default()
+            },
+        ])
+    }
+
+    #[test]
+    fn test_registry_by_origin() {
+        let mut zones = manager();
+        assert!(zones.get("broken.test.").is_none());
+        assert!(zones.find_zone("ns.sub.nihilist.moe").is_some());
+
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "@ SOA ns admin 1 2 3 4 5\n@ NS ns\nns A 10.0.0.1").unwrap();
+        let sub = Zone::load(file.path(), "sub.nihilist.moe").unwrap();
+        zones.insert(sub);
+
+        assert_eq!(zones.get("NIHILIST.moe.").unwrap().name, "nihilist.moe");
+        assert_eq!(
+            zones.find_zone("Host.Sub.Nihilist.Moe.").unwrap().name,
+            "sub.nihilist.moe"
+        );
+        assert_eq!(
+            zones.find_zone("host.nihilist.moe").unwrap().name,
+            "nihilist.moe"
+        );
+        assert!(zones.find_zone("nihilist.moe.example").is_none());
+    }
+
+    #[test]
+    fn test_slave_zone_served_from_its_file() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "@ SOA ns admin 1 2 3 4 5\n@ NS ns\nns A 10.0.0.1\nwww A 10.0.0.2"
+        )
+        .unwrap();
+        let zones = ZoneManager::from_config(&[
+            ZoneConfig {
+                name: "slave.test.".to_string(),
+                kind: ZoneType::Slave,
+                masters: vec!["192.0.2.53:53".to_string()],
+                file: Some(file.path().to_string_lossy().into_owned()),
+                ..Default::default()
+            },
+            ZoneConfig {
+                name: "forward.test.".to_string(),
+                kind: ZoneType::Forward,
+                forwarders: vec!["192.0.2.53:53".to_string()],
+                ..Default::default()
+            },
+        ]);
+
+        assert!(zones.get("forward.test").is_none());
+        let response = zones
+            .answer(&query("www.slave.test", DNSRecordType::A))
+            .unwrap();
+        assert!(response.header.aa);
+        assert_eq!(response.header.rcode, Header::RCODE_NOERROR);
+        assert_eq!(
+            response.answers[0].rdata,
+            RData::A(Ipv4Addr::new(10, 0, 0, 2))
+        );
+    }
+
+    #[test]
+    fn test_answer_is_authoritative() {
+        let q = query("www.nihilist.moe", DNSRecordType::A);
+        let response = manager().answer(&q).unwrap();
+
+        assert_eq!(response.header.id, q.header.id);
+        assert!(response.header.qr);
+        assert!(response.header.aa);
+        assert_eq!(response.header.rcode, Header::RCODE_NOERROR);
+        assert_eq!(response.header.ancount, 1);
+        assert_eq!(
+            response.answers[0].rdata,
+            RData::A(Ipv4Addr::new(192, 168, 1, 21))
+        );
+    }
+
+    #[test]
+    fn test_answer_follows_cname_chain() {
+        let response = manager()
+            .answer(&query("alias.nihilist.moe", DNSRecordType::A))
+            .unwrap();
+
+        assert!(response.header.aa);
+        assert_eq!(response.header.rcode, Header::RCODE_NOERROR);
+        let types: Vec<DNSRecordType> = response.answers.iter().map(|a| a.r_type).collect();
+        assert_eq!(
+            types,
+            [DNSRecordType::CNAME, DNSRecordType::CNAME, DNSRecordType::A]
+        );
+        assert_eq!(response.answers[2].q_name, "www.nihilist.moe");
+    }
+
+    #[test]
+    fn test_answer_wildcard_owned_by_qname() {
+        let response = manager()
+            .answer(&query("nope.nihilist.moe", DNSRecordType::A))
+            .unwrap();
+
+        assert!(response.header.aa);
+        assert_eq!(response.answers.len(), 1);
+        assert_eq!(response.answers[0].q_name, "nope.nihilist.moe");
+        assert_eq!(
+            response.answers[0].rdata,
+            RData::A(Ipv4Addr::new(192, 168, 1, 99))
+        );
+    }
+
+    #[test]
+    fn test_answer_mx_adds_target_addresses() {
+        let response = manager()
+            .answer(&query("nihilist.moe", DNSRecordType::MX))
+            .unwrap();
+
+        assert_eq!(response.answers.len(), 2);
+        assert_eq!(response.header.arcount, 2);
+        assert!(
+            response
+                .additionals
+                .iter()
+                .any(|a| a.q_name == "mail.nihilist.moe"
+                    && a.rdata == RData::A(Ipv4Addr::new(192, 168, 1, 30)))
+        );
+    }
+
+    #[test]
+    fn test_nxdomain_carries_soa() {
+        let response = manager()
+            .answer(&query("nope.mail.nihilist.moe", DNSRecordType::A))
+            .unwrap();
+
+        assert!(response.header.aa);
+        assert_eq!(response.header.rcode, Header::RCODE_NXDOMAIN);
+        assert!(response.answers.is_empty());
+        assert_eq!(response.header.nscount, 1);
+        let soa = &response.authorities[0];
+        assert_eq!(soa.q_type, DNSRecordType::SOA);
+        assert_eq!(soa.q_name, "nihilist.moe");
+        // min(SOA TTL 3600, MINIMUM 86400)
+        assert_eq!(soa.ttl, 3600);
+        let RData::SOA {
+            ref mname, minimum, ..
+        } = soa.rdata
+        else {
+            panic!("not a SOA: {:?}", soa.rdata);
+        };
+        assert_eq!(mname, "ns1.nihilist.moe");
+        assert_eq!(minimum, 86400);
+    }
+
+    #[test]
+    fn test_nodata_carries_soa() {
+        let response = manager()
+            .answer(&query("mail.nihilist.moe", DNSRecordType::AAAA))
+            .unwrap();
+
+        assert!(response.header.aa);
+        assert_eq!(response.header.rcode, Header::RCODE_NOERROR);
+        assert!(response.answers.is_empty());
+        assert_eq!(response.authorities[0].q_type, DNSRecordType::SOA);
+    }
+
+    #[test]
+    fn test_referral_is_not_authoritative() {
+        let response = manager()
+            .answer(&query("host.sub.nihilist.moe", DNSRecordType::A))
+            .unwrap();
+
+        assert!(!response.header.aa);
+        assert_eq!(response.header.rcode, Header::RCODE_NOERROR);
+        assert!(response.answers.is_empty());
+        assert_eq!(response.authorities[0].q_type, DNSRecordType::NS);
+        assert_eq!(
+            response.authorities[0].rdata,
+            RData::NS("ns.sub.nihilist.moe".to_string())
+        );
+        assert_eq!(
+            response.additionals[0].rdata,
+            RData::A(Ipv4Addr::new(192, 168, 2, 10))
+        );
+    }
+
+    #[test]
+    fn test_unknown_zone_is_refused() {
+        let response = manager()
+            .answer(&query("broken.test", DNSRecordType::A))
+            .unwrap();
+
+        assert_eq!(response.header.rcode, Header::RCODE_REFUSED);
+        assert!(!response.header.aa);
+    }
+
+    #[test]
+    fn test_response_round_trip() {
+        let response = manager()
+            .answer(&query("nope.mail.nihilist.moe", DNSRecordType::A))
+            .unwrap();
+
+        let bytes = response.to_bytes().unwrap();
+        let parsed = DNSPacket::from_bytes(&bytes).unwrap();
+
+        assert_eq!(parsed, response);
+    }
+}
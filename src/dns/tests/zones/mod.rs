@@ -1 +1,4 @@
+mod manager;
+mod tree;
+mod zone;
 mod zone_parser;
@@ -0,0 +1,189 @@
+#[cfg(test)]
+mod tests {
+    use crate::dns::q_class::DNSClass;
+    use crate::dns::q_type::DNSRecordType;
+    use crate::dns::records::DNSRecord;
+    use crate::dns::records::rdata::RData;
+    use crate::dns::zones::tree::ZoneTree;
+
+    fn record(name: &str) -> DNSRecord {
+        DNSRecord {
+            name: name.to_string(),
+            rtype: DNSRecordType::A,
+            rclass: DNSClass::IN,
+            ttl: 300,
+            rdata: RData::A("10.0.0.1".parse().unwrap()),
+        }
+    }
+
+    fn tree(names: &[&str]) -> ZoneTree {
+        let mut tree = ZoneTree::new();
+        for name in names {
+            tree.insert(record(name));
+        }
+        tree
+    }
+
+    #[test]
+    fn test_get_ignores_case() {
+        let mut tree = tree(&["WWW.example.com", "example.com"]);
+        tree.insert(record("www.EXAMPLE.com."));
+
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree.get("www.example.com").unwrap().len(), 2);
+        assert_eq!(
+            tree.get("Www.Example.Com.").unwrap()[0].name,
+            "WWW.example.com"
+        );
+        // the root and com only exist as empty non-terminals
+        assert!(tree.get("com").is_none());
+        assert!(tree.get("").is_none());
+        assert!(tree.get("ftp.example.com").is_none());
+        assert!(ZoneTree::new().is_empty());
+    }
+
+    #[test]
+    fn test_canonical_order() {
+        // RFC 4034 section 6.1
+        let tree = tree(&[
+            "z.example",
+            "\\200.z.example",
+            "zABC.a.EXAMPLE",
+            "*.z.example",
+            "example",
+            "\\001.z.example",
+            "Z.a.example",
+            "yljkjljk.a.example",
+            "a.example",
+        ]);
+
+        let owners: Vec<String> = tree.iter().map(|r| r[0].name.clone()).collect();
+        assert_eq!(
+            owners,
+            [
+                "example",
+                "a.example",
+                "yljkjljk.a.example",
+                "Z.a.example",
+                "zABC.a.EXAMPLE",
+                "z.example",
+                "\\001.z.example",
+                "*.z.example",
+                "\\200.z.example",
+            ]
+        );
+        let records: Vec<String> = tree.into_records().into_iter().map(|r| r.name).collect();
+        assert_eq!(records, owners);
+    }
+
+    #[test]
+    fn test_escaped_labels_order() {
+        let tree = tree(&[
+            "\\200.example",
+            "a\\\\.example",
+            "z.example",
+            "A\\.b.example",
+            "a.example",
+            "\\065\\.c.example",
+        ]);
+
+        // by label bytes: "a", "a.b", "a.c", "a\\", "z", then 0xc8
+        let owners: Vec<String> = tree.iter().map(|r| r[0].name.clone()).collect();
+        assert_eq!(
+            owners,
+            [
+                "a.example",
+                "A\\.b.example",
+                "\\065\\.c.example",
+                "a\\\\.example",
+                "z.example",
+                "\\200.example",
+            ]
+        );
+        assert_eq!(tree.next_name("a.example").unwrap(), "a\\.b.example");
+        assert_eq!(tree.next_name("a\\.c.example").unwrap(), "a\\\\.example");
+        assert_eq!(tree.next_name("z.example").unwrap(), "\\200.example");
+        assert_eq!(tree.next_name("\\200.example").unwrap(), "a.example");
+        // "a\.b" is a single label, not "a" below "b"
+        assert!(tree.get("a.b.example").is_none());
+        assert!(tree.get("a\\.B.example").is_some());
+        assert_eq!(tree.closest_encloser("x.\\200.example"), "\\200.example");
+    }
+
+    #[test]
+    fn test_closest_encloser_and_empty_non_terminals() {
+        let tree = tree(&["example.com", "a.b.c.example.com"]);
+
+        assert!(tree.is_empty_non_terminal("b.c.example.com"));
+        assert!(tree.is_empty_non_terminal("C.Example.com"));
+        assert!(!tree.is_empty_non_terminal("a.b.c.example.com"));
+        assert!(!tree.is_empty_non_terminal("x.example.com"));
+
+        assert_eq!(
+            tree.closest_encloser("x.b.c.example.com"),
+            "b.c.example.com"
+        );
+        assert_eq!(tree.closest_encloser("x.y.EXAMPLE.com"), "example.com");
+        assert_eq!(
+            tree.closest_encloser("a.b.c.example.com"),
+            "a.b.c.example.com"
+        );
+        assert_eq!(tree.closest_encloser("example.org"), "");
+    }
+
+    #[test]
+    fn test_ancestors() {
+        let tree = tree(&["example.com", "sub.example.com", "ns.sub.example.com"]);
+
+        let names: Vec<String> = tree
+            .ancestors("host.ns.Sub.example.com")
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(
+            names,
+            ["example.com", "sub.example.com", "ns.sub.example.com"]
+        );
+        assert_eq!(tree.ancestors("sub.example.com").len(), 2);
+        assert!(tree.ancestors("example.org").is_empty());
+    }
+
+    #[test]
+    fn test_next_name() {
+        let tree = tree(&[
+            "example",
+            "a.example",
+            "yljkjljk.a.example",
+            "z.a.example",
+            "c.b.example",
+            "z.example",
+        ]);
+
+        for (name, next) in [
+            // existing names, then their descendants
+            ("example", "a.example"),
+            ("a.example", "yljkjljk.a.example"),
+            ("z.a.example", "c.b.example"),
+            // an empty non-terminal
+            ("b.example", "c.b.example"),
+            // names that do not exist
+            ("m.a.example", "yljkjljk.a.example"),
+            ("yz.a.example", "z.a.example"),
+            ("x.z.a.example", "c.b.example"),
+            ("0.example", "a.example"),
+            ("aa.example", "c.b.example"),
+            // wrapping around to the first name
+            ("z.example", "example"),
+            ("zz.example", "example"),
+            ("example.org", "example"),
+        ] {
+            assert_eq!(tree.next_name(name).as_deref(), Some(next), "{}", name);
+        }
+
+        assert_eq!(ZoneTree::new().next_name("example"), None);
+        assert_eq!(
+            self::tree(&["example"]).next_name("EXAMPLE").as_deref(),
+            Some("example")
+        );
+    }
+}
@@ -0,0 +1,377 @@
+#[cfg(test)]
+mod tests {
+    use crate::config::{Config, ZoneConfig, ZoneType};
+    use crate::dns::q_type::DNSRecordType;
+    use crate::dns::records::rdata::RData;
+    use crate::dns::zones::{Zone, ZoneLookup};
+    use crate::exceptions::SCloudException;
+    use std::io::Write;
+    use std::path::Path;
+    use tempfile::NamedTempFile;
+
+    fn nihilist_zone() -> Zone {
+        Zone::from_config(&ZoneConfig {
+            name: "nihilist.moe.".to_string(),
+            kind: ZoneType::Master,
+            file: Some("zones/nihilist.moe.zone".to_string()),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_from_config_file_absolute_names() {
+        let zone = nihilist_zone();
+
+        assert_eq!(zone.name, "nihilist.moe");
+        assert_eq!(zone.origin.as_deref(), Some("nihilist.moe."));
+
+        let soa = zone.soa.as_ref().unwrap();
+        assert_eq!(soa.name, "nihilist.moe");
+        assert_eq!(
+            soa.rdata.to_string(),
+            "ns1.nihilist.moe. admin.nihilist.moe. 2025121601 3600 1800 604800 86400"
+        );
+
+        let blog = zone.get_records("blog.nihilist.moe").unwrap();
+        assert_eq!(blog[0].rdata, RData::CNAME("www.nihilist.moe".to_string()));
+        assert!(zone.get_records("ns.sub.nihilist.moe").is_some());
+        assert!(zone.get_records("www").is_none());
+    }
+
+    #[test]
+    fn test_from_config_inline() {
+        let cfg = Config::from_file(Path::new("./config/config.json")).unwrap();
+        let inline = cfg.zone.iter().find(|z| z.name == "inline.local.").unwrap();
+
+        let zone = Zone::from_config(inline).unwrap();
+
+        assert_eq!(zone.name, "inline.local");
+        let mx = zone.get_records("mail.inline.local").unwrap();
+        assert_eq!(mx[0].rtype, DNSRecordType::MX);
+        assert_eq!(
+            mx[0].rdata,
+            RData::MX {
+                preference: 10,
+                exchange: "mail.inline.local".to_string(),
+            }
+        );
+        let txt = zone.get_records("_acme-challenge.inline.local").unwrap();
+        assert_eq!(
+            txt[0].rdata,
+            RData::TXT(vec![b"acme-challenge-token-example".to_vec()])
+        );
+    }
+
+    #[test]
+    fn test_from_config_missing_soa() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "$ORIGIN test.\n@ IN NS ns1\nns1 IN A 10.0.0.1").unwrap();
+
+        let result = Zone::from_config(&ZoneConfig {
+            name: "test.".to_string(),
+            kind: ZoneType::Master,
+            file: Some(file.path().to_string_lossy().to_string()),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            result.unwrap_err(),
+            SCloudException::SCLOUD_ZONE_MISSING_SOA
+        );
+    }
+
+    #[test]
+    fn test_from_config_invalid_rdata() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "@ IN SOA ns1 admin 1 2 3 4 5\n@ IN NS ns1\nns1 IN A 10.0.0.300"
+        )
+        .unwrap();
+
+        let result = Zone::from_config(&ZoneConfig {
+            name: "test.".to_string(),
+            kind: ZoneType::Master,
+            file: Some(file.path().to_string_lossy().to_string()),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            result.unwrap_err(),
+            SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA
+        );
+    }
+
+    /// Load `text` as the zone `test.`.
+    fn load(text: &str) -> Result<Zone, SCloudException> {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "{}", text).unwrap();
+        Zone::load(file.path(), "test.")
+    }
+
+    #[test]
+    fn test_load_path_and_origin() {
+        let zone = Zone::load(Path::new("zones/nihilist.moe.zone"), "Nihilist.MOE.").unwrap();
+
+        assert_eq!(zone.name, "nihilist.moe");
+        assert_eq!(zone, nihilist_zone());
+
+        assert_eq!(
+            Zone::load(Path::new("zones/does-not-exist.zone"), "test.").unwrap_err(),
+            SCloudException::SCLOUD_ZONE_PARSER_FILE_NOT_FOUND
+        );
+    }
+
+    #[test]
+    fn test_load_sanity_checks() {
+        let valid = "@ SOA ns admin 1 2 3 4 5\n@ NS ns\nns A 10.0.0.1\n";
+        assert!(load(valid).is_ok());
+
+        for (text, error) in [
+            (
+                "@ NS ns\nns A 10.0.0.1\nsub SOA ns admin 1 2 3 4 5",
+                SCloudException::SCLOUD_ZONE_MISSING_SOA,
+            ),
+            (
+                "@ SOA ns admin 1 2 3 4 5\nns A 10.0.0.1",
+                SCloudException::SCLOUD_ZONE_MISSING_APEX_NS,
+            ),
+            (
+                "@ SOA ns admin 1 2 3 4 5\n@ NS ns\nns A 10.0.0.1\n@ CNAME www",
+                SCloudException::SCLOUD_ZONE_CNAME_AND_OTHER_DATA,
+            ),
+            (
+                "@ SOA ns admin 1 2 3 4 5\n@ NS ns\nns A 10.0.0.1\nwww CNAME a\nwww TXT x",
+                SCloudException::SCLOUD_ZONE_CNAME_AND_OTHER_DATA,
+            ),
+            (
+                "@ SOA ns admin 1 2 3 4 5\n@ NS ns\nns A 10.0.0.1\nwww CNAME a\nwww CNAME b",
+                SCloudException::SCLOUD_ZONE_CNAME_AND_OTHER_DATA,
+            ),
+            (
+                "@ SOA ns admin 1 2 3 4 5\n@ NS ns\n",
+                SCloudException::SCLOUD_ZONE_MISSING_GLUE,
+            ),
+            (
+                "@ SOA ns admin 1 2 3 4 5\n@ NS ns\nns A 10.0.0.1\nsub NS ns.sub\n",
+                SCloudException::SCLOUD_ZONE_MISSING_GLUE,
+            ),
+            (
+                "@ SOA ns admin 1 2 3 4 5\n@ SOA ns admin 2 2 3 4 5\n@ NS ns\nns A 10.0.0.1",
+                SCloudException::SCLOUD_ZONE_MULTIPLE_SOA,
+            ),
+        ] {
+            assert_eq!(load(text).unwrap_err(), error, "{:?}", text);
+        }
+
+        // out-of-zone name servers need no glue, CNAME may come with DNSSEC data
+        assert!(
+            load(&format!(
+                "{}sub NS ns.example.net.\nwww CNAME a\nwww NSEC x A",
+                valid
+            ))
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_lookup_answer_is_case_insensitive() {
+        let zone = nihilist_zone();
+
+        match zone.lookup("WWW.Nihilist.MOE.", DNSRecordType::A) {
+            ZoneLookup::Answer(records) => {
+                assert_eq!(records.len(), 1);
+                assert_eq!(records[0].rdata, RData::A("192.168.1.21".parse().unwrap()));
+            }
+            other => panic!("unexpected lookup result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_lookup_apex_soa() {
+        let zone = nihilist_zone();
+
+        match zone.lookup("nihilist.moe", DNSRecordType::SOA) {
+            ZoneLookup::Answer(records) => assert_eq!(records[0].rtype, DNSRecordType::SOA),
+            other => panic!("unexpected lookup result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_lookup_cname() {
+        let zone = nihilist_zone();
+
+        match zone.lookup("shop.nihilist.moe", DNSRecordType::AAAA) {
+            ZoneLookup::Alias { records, target } => {
+                assert_eq!(target, "www.nihilist.moe");
+                assert_eq!(records[0].rtype, DNSRecordType::CNAME);
+                assert_eq!(
+                    records[0].rdata,
+                    RData::CNAME("www.nihilist.moe".to_string())
+                );
+            }
+            other => panic!("unexpected lookup result: {:?}", other),
+        }
+        // the CNAME itself is an answer
+        assert!(matches!(
+            zone.lookup("shop.nihilist.moe", DNSRecordType::CNAME),
+            ZoneLookup::Answer(_)
+        ));
+    }
+
+    #[test]
+    fn test_resolve_cname_chain() {
+        let zone = nihilist_zone();
+
+        let answer = zone
+            .resolve("Alias.nihilist.moe", DNSRecordType::A)
+            .unwrap();
+        let owners: Vec<&str> = answer.aliases.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(owners, ["alias.nihilist.moe", "blog.nihilist.moe"]);
+        match answer.lookup {
+            ZoneLookup::Answer(records) => {
+                assert_eq!(records[0].name, "www.nihilist.moe");
+                assert_eq!(records[0].rdata, RData::A("192.168.1.21".parse().unwrap()));
+            }
+            other => panic!("unexpected lookup result: {:?}", other),
+        }
+
+        // a chain leaving the zone stops at the last alias
+        let zone = load(
+            "@ SOA ns admin 1 2 3 4 5\n@ NS ns\nns A 10.0.0.1\n\
+             out CNAME www.example.com.",
+        )
+        .unwrap();
+        let answer = zone.resolve("out.test", DNSRecordType::A).unwrap();
+        assert!(answer.aliases.is_empty());
+        assert!(matches!(
+            answer.lookup,
+            ZoneLookup::Alias { ref target, .. } if target == "www.example.com"
+        ));
+    }
+
+    #[test]
+    fn test_resolve_cname_loop() {
+        let zone = load(
+            "@ SOA ns admin 1 2 3 4 5\n@ NS ns\nns A 10.0.0.1\n\
+             a CNAME b\nb CNAME a",
+        )
+        .unwrap();
+
+        assert_eq!(
+            zone.resolve("a.test", DNSRecordType::A).unwrap_err(),
+            SCloudException::SCLOUD_ZONE_CNAME_LOOP
+        );
+    }
+
+    #[test]
+    fn test_lookup_wildcard() {
+        let zone = nihilist_zone();
+
+        match zone.lookup("nope.nihilist.moe", DNSRecordType::A) {
+            ZoneLookup::Answer(records) => {
+                assert_eq!(records.len(), 1);
+                assert_eq!(records[0].name, "nope.nihilist.moe");
+                assert_eq!(records[0].rdata, RData::A("192.168.1.99".parse().unwrap()));
+            }
+            other => panic!("unexpected lookup result: {:?}", other),
+        }
+        assert_eq!(
+            zone.lookup("a.b.nihilist.moe", DNSRecordType::AAAA),
+            ZoneLookup::NoData
+        );
+        // the closest encloser of nope.mail is mail, which has no wildcard
+        assert_eq!(
+            zone.lookup("nope.mail.nihilist.moe", DNSRecordType::A),
+            ZoneLookup::NxDomain
+        );
+    }
+
+    #[test]
+    fn test_lookup_dname() {
+        let zone = nihilist_zone();
+
+        match zone.lookup("WWW.old.nihilist.moe", DNSRecordType::A) {
+            ZoneLookup::Alias { records, target } => {
+                assert_eq!(target, "www.nihilist.moe");
+                assert_eq!(records.len(), 2);
+                assert_eq!(records[0].rdata, RData::DNAME("nihilist.moe".to_string()));
+                assert_eq!(records[1].name, "www.old.nihilist.moe");
+                assert_eq!(
+                    records[1].rdata,
+                    RData::CNAME("www.nihilist.moe".to_string())
+                );
+            }
+            other => panic!("unexpected lookup result: {:?}", other),
+        }
+
+        let answer = zone
+            .resolve("www.old.nihilist.moe", DNSRecordType::A)
+            .unwrap();
+        assert_eq!(answer.aliases.len(), 2);
+        assert!(matches!(answer.lookup, ZoneLookup::Answer(_)));
+    }
+
+    #[test]
+    fn test_lookup_ds_at_cut() {
+        let zone = nihilist_zone();
+
+        // the parent answers DS queries for the delegation itself
+        assert_eq!(
+            zone.lookup("sub.nihilist.moe", DNSRecordType::DS),
+            ZoneLookup::NoData
+        );
+        assert!(matches!(
+            zone.lookup("sub.nihilist.moe", DNSRecordType::A),
+            ZoneLookup::Referral { .. }
+        ));
+    }
+
+    #[test]
+    fn test_lookup_nodata_and_nxdomain() {
+        let zone = nihilist_zone();
+
+        assert_eq!(
+            zone.lookup("mail.nihilist.moe", DNSRecordType::AAAA),
+            ZoneLookup::NoData
+        );
+        // empty non-terminal above _xmpp-server._tcp
+        assert_eq!(
+            zone.lookup("_tcp.nihilist.moe", DNSRecordType::A),
+            ZoneLookup::NoData
+        );
+        assert_eq!(
+            zone.lookup("nope.mail.nihilist.moe", DNSRecordType::A),
+            ZoneLookup::NxDomain
+        );
+    }
+
+    #[test]
+    fn test_lookup_referral_with_glue() {
+        let zone = nihilist_zone();
+
+        match zone.lookup("host.sub.nihilist.moe", DNSRecordType::A) {
+            ZoneLookup::Referral { ns, glue } => {
+                assert_eq!(ns.len(), 1);
+                assert_eq!(ns[0].name, "sub.nihilist.moe");
+                assert_eq!(ns[0].rdata, RData::NS("ns.sub.nihilist.moe".to_string()));
+                assert_eq!(glue.len(), 1);
+                assert_eq!(glue[0].rdata, RData::A("192.168.2.10".parse().unwrap()));
+            }
+            other => panic!("unexpected lookup result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_contains() {
+        let zone = nihilist_zone();
+
+        assert!(zone.contains("nihilist.moe."));
+        assert!(zone.contains("a.b.NIHILIST.moe"));
+        assert!(!zone.contains("notnihilist.moe"));
+        // a single label "a.nihilist" below moe
+        assert!(!zone.contains("a\\.nihilist.moe"));
+        assert!(!zone.contains("moe"));
+    }
+}
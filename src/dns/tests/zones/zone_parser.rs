@@ -1,136 +1,534 @@
 #[cfg(test)]
 mod tests {
     use crate::dns::q_class::DNSClass;
+    use crate::dns::q_name::parse_qname;
     use crate::dns::q_type::DNSRecordType;
-    use crate::dns::zones::zone_parser::zone_parser;
+    use crate::dns::records::rdata::RData;
+    use crate::dns::zones::zone_parser::{
+        parse_ttl, parse_zone_file, parse_zone_text, substitute, tokenize,
+    };
+    use crate::exceptions::SCloudException;
+    use std::fs;
+    use std::path::Path;
 
     #[test]
     fn test_zone_parser() {
-        let result = zone_parser("nihilist.moe").unwrap();
-        let ns1_record = result.records.get("ns1").unwrap();
-        let ns2_record = result.records.get("ns2").unwrap();
-        let www_records = result.records.get("www").unwrap();
-        let api_records = result.records.get("api").unwrap();
-        let blog_records = result.records.get("blog").unwrap();
-        let shop_records = result.records.get("shop").unwrap();
-        let mail_records = result.records.get("mail").unwrap();
-        let backupmail_records = result.records.get("backup-mail").unwrap();
-        let xmpp_server_records = result.records.get("_xmpp-server._tcp").unwrap();
-        let xmpp_client_records = result.records.get("_xmpp-client._tcp").unwrap();
-        let xmpp_records = result.records.get("xmpp").unwrap();
-        let ptrtest_records = result.records.get("ptrtest").unwrap();
-        let sub_records = result.records.get("sub").unwrap();
-        let ns_sub_records = result.records.get("ns.sub").unwrap();
+        let result =
+            parse_zone_file(Path::new("zones/nihilist.moe.zone"), Some("nihilist.moe.")).unwrap();
+        let ns1_record = result.records.get("ns1.nihilist.moe").unwrap();
+        let ns2_record = result.records.get("ns2.nihilist.moe").unwrap();
+        let www_records = result.records.get("www.nihilist.moe").unwrap();
+        let api_records = result.records.get("api.nihilist.moe").unwrap();
+        let blog_records = result.records.get("blog.nihilist.moe").unwrap();
+        let shop_records = result.records.get("shop.nihilist.moe").unwrap();
+        let mail_records = result.records.get("mail.nihilist.moe").unwrap();
+        let backupmail_records = result.records.get("backup-mail.nihilist.moe").unwrap();
+        let xmpp_server_records = result
+            .records
+            .get("_xmpp-server._tcp.nihilist.moe")
+            .unwrap();
+        let xmpp_client_records = result
+            .records
+            .get("_xmpp-client._tcp.nihilist.moe")
+            .unwrap();
+        let xmpp_records = result.records.get("xmpp.nihilist.moe").unwrap();
+        let ptrtest_records = result.records.get("ptrtest.nihilist.moe").unwrap();
+        let sub_records = result.records.get("sub.nihilist.moe").unwrap();
+        let ns_sub_records = result.records.get("ns.sub.nihilist.moe").unwrap();
 
         // ns1 RECORD ASSERT
-        assert!(ns1_record.iter().any(|r| r.value == "192.168.1.10"
-            && r.name == "ns1"
-            && r.rtype == DNSRecordType::A
-            && r.rclass == DNSClass::IN));
-        assert!(ns1_record.iter().any(|r| r.value == "fd00::10"
-            && r.name == "ns1"
-            && r.rtype == DNSRecordType::AAAA
-            && r.rclass == DNSClass::IN));
+        assert!(
+            ns1_record
+                .iter()
+                .any(|r| r.rdata == RData::A("192.168.1.10".parse().unwrap())
+                    && r.name == "ns1.nihilist.moe"
+                    && r.rtype == DNSRecordType::A
+                    && r.rclass == DNSClass::IN)
+        );
+        assert!(
+            ns1_record
+                .iter()
+                .any(|r| r.rdata == RData::AAAA("fd00::10".parse().unwrap())
+                    && r.name == "ns1.nihilist.moe"
+                    && r.rtype == DNSRecordType::AAAA
+                    && r.rclass == DNSClass::IN)
+        );
 
         // ns2 RECORD ASSERT
-        assert!(ns2_record.iter().any(|r| r.value == "192.168.1.11"
-            && r.name == "ns2"
-            && r.rtype == DNSRecordType::A
-            && r.rclass == DNSClass::IN));
-        assert!(ns2_record.iter().any(|r| r.value == "fd00::11"
-            && r.name == "ns2"
-            && r.rtype == DNSRecordType::AAAA
-            && r.rclass == DNSClass::IN));
+        assert!(
+            ns2_record
+                .iter()
+                .any(|r| r.rdata == RData::A("192.168.1.11".parse().unwrap())
+                    && r.name == "ns2.nihilist.moe"
+                    && r.rtype == DNSRecordType::A
+                    && r.rclass == DNSClass::IN)
+        );
+        assert!(
+            ns2_record
+                .iter()
+                .any(|r| r.rdata == RData::AAAA("fd00::11".parse().unwrap())
+                    && r.name == "ns2.nihilist.moe"
+                    && r.rtype == DNSRecordType::AAAA
+                    && r.rclass == DNSClass::IN)
+        );
 
         // www RECORD ASSERT
-        assert!(www_records.iter().any(|r| r.value == "192.168.1.21"
-            && r.name == "www"
-            && r.rtype == DNSRecordType::A
-            && r.rclass == DNSClass::IN));
-        assert!(www_records.iter().any(|r| r.value == "fd00::21"
-            && r.name == "www"
-            && r.rtype == DNSRecordType::AAAA
-            && r.rclass == DNSClass::IN));
+        assert!(
+            www_records
+                .iter()
+                .any(|r| r.rdata == RData::A("192.168.1.21".parse().unwrap())
+                    && r.name == "www.nihilist.moe"
+                    && r.rtype == DNSRecordType::A
+                    && r.rclass == DNSClass::IN)
+        );
+        assert!(
+            www_records
+                .iter()
+                .any(|r| r.rdata == RData::AAAA("fd00::21".parse().unwrap())
+                    && r.name == "www.nihilist.moe"
+                    && r.rtype == DNSRecordType::AAAA
+                    && r.rclass == DNSClass::IN)
+        );
 
         // api RECORD ASSERT
-        assert!(api_records.iter().any(|r| r.value == "192.168.1.22"
-            && r.name == "api"
-            && r.rtype == DNSRecordType::A
-            && r.rclass == DNSClass::IN
-            && r.ttl == 600));
+        assert!(
+            api_records
+                .iter()
+                .any(|r| r.rdata == RData::A("192.168.1.22".parse().unwrap())
+                    && r.name == "api.nihilist.moe"
+                    && r.rtype == DNSRecordType::A
+                    && r.rclass == DNSClass::IN
+                    && r.ttl == 600)
+        );
 
         // blog RECORD ASSERT
-        assert!(blog_records.iter().any(|r| r.value == "www"
-            && r.name == "blog"
+        assert!(blog_records.iter().any(|r| r.rdata
+            == RData::CNAME("www.nihilist.moe".to_string())
+            && r.name == "blog.nihilist.moe"
             && r.rtype == DNSRecordType::CNAME
             && r.rclass == DNSClass::IN));
 
         // shop RECORD ASSERT
-        assert!(shop_records.iter().any(|r| r.value == "www.nihilist.moe."
-            && r.name == "shop"
+        assert!(shop_records.iter().any(|r| r.rdata
+            == RData::CNAME("www.nihilist.moe".to_string())
+            && r.name == "shop.nihilist.moe"
             && r.rtype == DNSRecordType::CNAME
             && r.rclass == DNSClass::IN));
 
         // mail RECORD ASSERT
-        assert!(mail_records.iter().any(|r| r.value == "192.168.1.30"
-            && r.name == "mail"
-            && r.rtype == DNSRecordType::A
-            && r.rclass == DNSClass::IN));
+        assert!(
+            mail_records
+                .iter()
+                .any(|r| r.rdata == RData::A("192.168.1.30".parse().unwrap())
+                    && r.name == "mail.nihilist.moe"
+                    && r.rtype == DNSRecordType::A
+                    && r.rclass == DNSClass::IN)
+        );
 
         // backup-mail RECORD ASSERT
-        assert!(backupmail_records.iter().any(|r| r.value == "192.168.1.31"
-            && r.name == "backup-mail"
+        assert!(backupmail_records.iter().any(|r| r.rdata
+            == RData::A("192.168.1.31".parse().unwrap())
+            && r.name == "backup-mail.nihilist.moe"
             && r.rtype == DNSRecordType::A
             && r.rclass == DNSClass::IN));
 
         // xmpp_server RECORD ASSERT
-        assert!(
-            xmpp_server_records
-                .iter()
-                .any(|r| r.value == "xmpp.nihilist.moe."
-                    && r.name == "_xmpp-server._tcp"
-                    && r.rtype == DNSRecordType::SRV
-                    && r.rclass == DNSClass::IN
-                    && r.priority == Some(10)
-                    && r.weight == Some(5)
-                    && r.port == Some(5269))
-        );
+        assert!(xmpp_server_records.iter().any(|r| r.rdata
+            == RData::SRV {
+                priority: 10,
+                weight: 5,
+                port: 5269,
+                target: "xmpp.nihilist.moe".to_string(),
+            }
+            && r.name == "_xmpp-server._tcp.nihilist.moe"
+            && r.rtype == DNSRecordType::SRV
+            && r.rclass == DNSClass::IN));
 
         // xmpp_client RECORD ASSERT
+        assert!(xmpp_client_records.iter().any(|r| r.rdata
+            == RData::SRV {
+                priority: 10,
+                weight: 5,
+                port: 5222,
+                target: "xmpp.nihilist.moe".to_string(),
+            }
+            && r.name == "_xmpp-client._tcp.nihilist.moe"
+            && r.rtype == DNSRecordType::SRV
+            && r.rclass == DNSClass::IN));
+
+        // xmpp RECORD ASSERT
         assert!(
-            xmpp_client_records
+            xmpp_records
                 .iter()
-                .any(|r| r.value == "xmpp.nihilist.moe."
-                    && r.name == "_xmpp-client._tcp"
-                    && r.rtype == DNSRecordType::SRV
-                    && r.rclass == DNSClass::IN
-                    && r.priority == Some(10)
-                    && r.weight == Some(5)
-                    && r.port == Some(5222))
+                .any(|r| r.rdata == RData::A("192.168.1.40".parse().unwrap())
+                    && r.name == "xmpp.nihilist.moe"
+                    && r.rtype == DNSRecordType::A
+                    && r.rclass == DNSClass::IN)
         );
 
-        // xmpp RECORD ASSERT
-        assert!(xmpp_records.iter().any(|r| r.value == "192.168.1.40"
-            && r.name == "xmpp"
-            && r.rtype == DNSRecordType::A
-            && r.rclass == DNSClass::IN));
-
         // ptrtest RECORD ASSERT
-        assert!(ptrtest_records.iter().any(|r| r.value == "example.com."
-            && r.name == "ptrtest"
-            && r.rtype == DNSRecordType::PTR
-            && r.rclass == DNSClass::IN));
+        assert!(
+            ptrtest_records
+                .iter()
+                .any(|r| r.rdata == RData::PTR("example.com".to_string())
+                    && r.name == "ptrtest.nihilist.moe"
+                    && r.rtype == DNSRecordType::PTR
+                    && r.rclass == DNSClass::IN)
+        );
 
         // sub RECORD ASSERT
-        assert!(sub_records.iter().any(|r| r.value == "ns.sub.nihilist.moe."
-            && r.name == "sub"
+        assert!(sub_records.iter().any(|r| r.rdata
+            == RData::NS("ns.sub.nihilist.moe".to_string())
+            && r.name == "sub.nihilist.moe"
             && r.rtype == DNSRecordType::NS
             && r.rclass == DNSClass::IN));
 
         // sub RECORD ASSERT
-        assert!(ns_sub_records.iter().any(|r| r.value == "192.168.2.10"
-            && r.name == "ns.sub"
+        assert!(ns_sub_records.iter().any(|r| r.rdata
+            == RData::A("192.168.2.10".parse().unwrap())
+            && r.name == "ns.sub.nihilist.moe"
             && r.rtype == DNSRecordType::A
             && r.rclass == DNSClass::IN));
     }
+
+    #[test]
+    fn test_multi_line_soa() {
+        let zone =
+            parse_zone_file(Path::new("zones/nihilist.moe.zone"), Some("nihilist.moe.")).unwrap();
+        let soa = zone.soa.unwrap();
+
+        assert_eq!(zone.name, "nihilist.moe");
+        assert_eq!(soa.name, "nihilist.moe");
+        assert_eq!(
+            soa.rdata.to_string(),
+            "ns1.nihilist.moe. admin.nihilist.moe. 2025121601 3600 1800 604800 86400"
+        );
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let entries =
+            tokenize("www  IN A 10.0.0.1 ; comment ( \n  TXT \"a ; (b)\" c\\ d\n( x\n y )\n")
+                .unwrap();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].fields, vec!["www", "IN", "A", "10.0.0.1"]);
+        assert!(!entries[0].blank_owner);
+        assert_eq!(entries[1].line, 2);
+        assert!(entries[1].blank_owner);
+        assert_eq!(entries[1].fields, vec!["TXT", "a ; (b)", "c\\ d"]);
+        assert_eq!(entries[2].line, 3);
+        assert_eq!(entries[2].fields, vec!["x", "y"]);
+    }
+
+    #[test]
+    fn test_owner_origin_and_class() {
+        let zone = parse_zone_text(
+            "@ SOA ns admin 1 2 3 4 5\n\
+             www 300 A 10.0.0.1\n\
+             \tAAAA fd00::1\n\
+             $ORIGIN sub\n\
+             host CH 60 A 10.0.0.2\n\
+             \tMX 10 @\n\
+             abs.example.net. A 10.0.0.3\n",
+            Some("Example.com."),
+        )
+        .unwrap();
+
+        assert_eq!(zone.name, "Example.com");
+        let www = zone.get_records("www.Example.com").unwrap();
+        assert_eq!(www.len(), 2);
+        assert_eq!(www[1].rtype, DNSRecordType::AAAA);
+        // TTL and class are those of the previous record
+        assert_eq!(www[1].ttl, 300);
+        assert_eq!(www[1].rclass, DNSClass::IN);
+
+        let host = zone.get_records("host.sub.Example.com").unwrap();
+        assert_eq!(host[0].rclass, DNSClass::CH);
+        assert_eq!(host[0].ttl, 60);
+        assert_eq!(
+            host[1].rdata,
+            RData::MX {
+                preference: 10,
+                exchange: "sub.Example.com".to_string(),
+            }
+        );
+        assert!(zone.get_records("abs.example.net").is_some());
+        assert_eq!(
+            zone.soa.unwrap().rdata.to_string(),
+            "ns.Example.com. admin.Example.com. 1 2 3 4 5"
+        );
+    }
+
+    #[test]
+    fn test_escaped_names_match_wire_names() {
+        let zone = parse_zone_text(
+            "@ SOA ns admin 1 2 3 4 5\n\
+             a\\\\ A 10.0.0.1\n\
+             b\\. A 10.0.0.2\n\
+             c\\046d CNAME a\\092\n",
+            Some("example."),
+        )
+        .unwrap();
+
+        // "a\" and "b." then "example", then the dot of "c.d"
+        for (wire, target) in [
+            (&b"\x02a\\\x07example\x00"[..], None),
+            (b"\x02b.\x07example\x00", None),
+            (b"\x03c.d\x07example\x00", Some("a\\\\.example")),
+        ] {
+            let (qname, _) = parse_qname(wire, 0).unwrap();
+            let records = zone.get_records(&qname).expect(&qname);
+            assert_eq!(records[0].name, qname);
+            if let Some(target) = target {
+                assert_eq!(records[0].rdata, RData::CNAME(target.to_string()));
+            }
+        }
+        // escaped, the dots are not label separators
+        assert!(zone.get_records("a.example").is_none());
+        assert!(zone.get_records("b.example").is_none());
+        assert!(zone.get_records("d.example").is_none());
+    }
+
+    #[test]
+    fn test_txt_keeps_whitespace() {
+        let zone = parse_zone_text(
+            "txt TXT \"v=spf1  ip4:10.0.0.1 \\\"-all\\\"\" ( \"two\"\n \"\\065\" )",
+            Some("example.com"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            zone.get_records("txt.example.com").unwrap()[0].rdata,
+            RData::TXT(vec![
+                b"v=spf1  ip4:10.0.0.1 \"-all\"".to_vec(),
+                b"two".to_vec(),
+                b"A".to_vec(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_ttl_units() {
+        assert_eq!(parse_ttl("3600"), Some(3600));
+        assert_eq!(parse_ttl("1h30m"), Some(5400));
+        assert_eq!(parse_ttl("1W2D"), Some(777_600));
+        assert_eq!(parse_ttl("2m30"), Some(150));
+        for ttl in ["", "h", "1x", "1hm", "4294967296", "7102w"] {
+            assert_eq!(parse_ttl(ttl), None, "{:?}", ttl);
+        }
+
+        let zone = parse_zone_text("$TTL 1d\na A 10.0.0.1\nb 1h30m A 10.0.0.2\n", None).unwrap();
+        assert_eq!(zone.ttl, 86400);
+        assert_eq!(zone.get_records("a").unwrap()[0].ttl, 86400);
+        assert_eq!(zone.get_records("b").unwrap()[0].ttl, 5400);
+    }
+
+    #[test]
+    fn test_generic_rdata() {
+        let zone = parse_zone_text(
+            "a TYPE1 \\# 4 0A000001\nb TYPE731 \\# 3 ( 0102\n 03 )\nc CLASS1 TYPE16 \\# 0\n",
+            Some("example.com"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            zone.get_records("a.example.com").unwrap()[0].rdata,
+            RData::A("10.0.0.1".parse().unwrap())
+        );
+        let b = &zone.get_records("b.example.com").unwrap()[0];
+        assert_eq!(b.rtype, DNSRecordType::Unknown(731));
+        assert_eq!(b.rdata, RData::Unknown(vec![1, 2, 3]));
+        // an empty TXT does not exist in presentation format, only generically
+        assert_eq!(
+            zone.get_records("c.example.com").unwrap()[0].rdata,
+            RData::TXT(Vec::new())
+        );
+    }
+
+    #[test]
+    fn test_generate() {
+        let zone = parse_zone_text(
+            "$GENERATE 1-4 host-$ A 10.0.0.$\n\
+             $GENERATE 10-20/5 pool 300 IN A 10.0.1.${100}\n\
+             $ORIGIN 0.0.10.in-addr.arpa.\n\
+             $GENERATE 1-2 $ PTR host-$.example.com.\n",
+            Some("example.com"),
+        )
+        .unwrap();
+
+        for i in 1..=4 {
+            assert_eq!(
+                zone.get_records(&format!("host-{}.example.com", i))
+                    .unwrap()[0]
+                    .rdata,
+                RData::A(format!("10.0.0.{}", i).parse().unwrap())
+            );
+        }
+        assert!(zone.get_records("host-5.example.com").is_none());
+
+        let pool = zone.get_records("pool.example.com").unwrap();
+        assert_eq!(pool.len(), 3);
+        assert_eq!(pool[2].ttl, 300);
+        assert_eq!(pool[2].rdata, RData::A("10.0.1.120".parse().unwrap()));
+
+        assert_eq!(
+            zone.get_records("2.0.0.10.in-addr.arpa").unwrap()[0].rdata,
+            RData::PTR("host-2.example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn test_generate_modifiers() {
+        assert_eq!(substitute("host-$", 7).unwrap(), "host-7");
+        assert_eq!(substitute("${10}", 7).unwrap(), "17");
+        assert_eq!(substitute("${-7,3}", 7).unwrap(), "000");
+        assert_eq!(substitute("${0,4,x}-${0,0,X}", 255).unwrap(), "00ff-FF");
+        assert_eq!(substitute("${0,3,o}", 8).unwrap(), "010");
+        assert_eq!(substitute("${0,0,n}", 0x1a).unwrap(), "a.1");
+        assert_eq!(substitute("${0,7,N}", 0xab).unwrap(), "B.A.0.0");
+        assert_eq!(substitute("${0,255}", 1).unwrap().len(), 255);
+        assert_eq!(substitute("$$-\\$-$", 3).unwrap(), "$-\\$-3");
+
+        for field in [
+            "${",
+            "${x}",
+            "${0,x}",
+            "${0,2,q}",
+            "${0,2,d,1}",
+            "${-8,0,x}",
+            // overflowing offset, width above the longest name
+            "${9223372036854775807}",
+            "${0,256}",
+            "${0,18446744073709551615,n}",
+        ] {
+            assert_eq!(substitute(field, 7), None, "{:?}", field);
+        }
+    }
+
+    #[test]
+    fn test_generate_errors() {
+        for text in [
+            "\n$GENERATE 5-1 h$ A 10.0.0.$",
+            "\n$GENERATE 1-x h$ A 10.0.0.$",
+            "\n$GENERATE 1-3/0 h$ A 10.0.0.$",
+            "\n$GENERATE 0-65536 h$ A 10.0.0.1",
+            "\n$GENERATE 1-3 h${1,x} A 10.0.0.$",
+            "\n$GENERATE 1-3 h${9223372036854775807} A 10.0.0.1",
+            "\n$GENERATE 1-3 h${0,256} A 10.0.0.1",
+            "\n$GENERATE 1-3 h$ A",
+        ] {
+            let err = parse_zone_text(text, Some("example.com")).unwrap_err();
+            assert_eq!(
+                (err.line, err.error),
+                (2, SCloudException::SCLOUD_ZONE_PARSER_SYNTAX_ERROR),
+                "{:?}",
+                text
+            );
+        }
+
+        // the RDATA of every generated record is checked
+        let err = parse_zone_text("$GENERATE 250-260 h$ A 10.0.0.$", None).unwrap_err();
+        assert_eq!(err.error, SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA);
+    }
+
+    #[test]
+    fn test_errors_carry_line() {
+        for (text, line, error) in [
+            (
+                "a A 10.0.0.1\nb BOGUS x\n",
+                2,
+                SCloudException::SCLOUD_ZONE_PARSER_UNKNOWN_TYPE,
+            ),
+            (
+                "a A 10.0.0.1\n\nb A 10.0.0.300\n",
+                3,
+                SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA,
+            ),
+            (
+                "a A 10.0.0.1\nb SOA ns admin (\n1 2 3 4 5\n",
+                2,
+                SCloudException::SCLOUD_ZONE_PARSER_SYNTAX_ERROR,
+            ),
+            (
+                "a TXT \"open\nb A 10.0.0.1\n",
+                1,
+                SCloudException::SCLOUD_ZONE_PARSER_SYNTAX_ERROR,
+            ),
+            (
+                "$TTL 1y\n",
+                1,
+                SCloudException::SCLOUD_ZONE_PARSER_FAILED_TO_READ_TTL_FIELD,
+            ),
+            (
+                "  A 10.0.0.1\n",
+                1,
+                SCloudException::SCLOUD_ZONE_PARSER_SYNTAX_ERROR,
+            ),
+            (
+                "a A 10.0.0.1\n$INCLUDE other.zone\n",
+                2,
+                SCloudException::SCLOUD_ZONE_PARSER_SYNTAX_ERROR,
+            ),
+            (
+                "@ SOA ns admin 1 2 3 4 5\n\n@ SOA ns admin 2 2 3 4 5\n",
+                3,
+                SCloudException::SCLOUD_ZONE_MULTIPLE_SOA,
+            ),
+            (
+                "a A 10.0.0.1\n$BOGUS\n",
+                2,
+                SCloudException::SCLOUD_ZONE_PARSER_SYNTAX_ERROR,
+            ),
+        ] {
+            let err = parse_zone_text(text, Some("example.com")).unwrap_err();
+            assert_eq!((err.line, err.error), (line, error), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_include() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("hosts.zone"),
+            "www A 10.0.0.1\n\tAAAA fd00::1\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("broken.zone"), "a A 10.0.0.1\nb A nope\n").unwrap();
+        fs::write(
+            dir.path().join("main.zone"),
+            "@ SOA ns admin 1 2 3 4 5\n\
+             $INCLUDE hosts.zone lan\n\
+             \tNS ns\n",
+        )
+        .unwrap();
+
+        let zone = parse_zone_file(&dir.path().join("main.zone"), Some("example.com")).unwrap();
+        assert_eq!(zone.get_records("www.lan.example.com").unwrap().len(), 2);
+        // the owner and the origin are back to those of the including file
+        assert_eq!(
+            zone.get_records("example.com").unwrap()[0].rdata,
+            RData::NS("ns.example.com".to_string())
+        );
+
+        fs::write(dir.path().join("main.zone"), "$INCLUDE broken.zone\n").unwrap();
+        let err = parse_zone_file(&dir.path().join("main.zone"), None).unwrap_err();
+        assert!(err.file.ends_with("broken.zone"));
+        assert_eq!(err.line, 2);
+
+        fs::write(dir.path().join("main.zone"), "\n$INCLUDE missing.zone\n").unwrap();
+        let err = parse_zone_file(&dir.path().join("main.zone"), None).unwrap_err();
+        assert!(err.file.ends_with("main.zone"));
+        assert_eq!(
+            (err.line, err.error),
+            (2, SCloudException::SCLOUD_ZONE_PARSER_FILE_NOT_FOUND)
+        );
+
+        fs::write(dir.path().join("main.zone"), "$INCLUDE main.zone\n").unwrap();
+        let err = parse_zone_file(&dir.path().join("main.zone"), None).unwrap_err();
+        assert_eq!(
+            err.error,
+            SCloudException::SCLOUD_ZONE_PARSER_FAILED_TO_READ_ZONE_FILE
+        );
+    }
 }
@@ -0,0 +1,221 @@
+use crate::config::{ZoneConfig, ZoneType};
+use crate::dns::packet::DNSPacket;
+use crate::dns::packet::additional::AdditionalSection;
+use crate::dns::packet::answer::AnswerSection;
+use crate::dns::packet::authority::AuthoritySection;
+use crate::dns::packet::header::Header;
+use crate::dns::q_name::split_label;
+use crate::dns::q_type::DNSRecordType;
+use crate::dns::records::DNSRecord;
+use crate::dns::records::rdata::RData;
+use crate::dns::zones::{Zone, ZoneLookup, canonical_name};
+use crate::exceptions::SCloudException;
+use crate::{log_error, log_info, log_warn};
+use std::collections::HashMap;
+
+/// Set of zones this server is authoritative for, indexed by origin.
+///
+/// Origins are canonical (lowercase, without the trailing dot), so that the
+/// zone of a query name is found by looking its suffixes up, most specific
+/// first.
+#[derive(Debug, Default)]
+pub(crate) struct ZoneManager {
+    zones: HashMap<String, Zone>,
+}
+
+impl ZoneManager {
+    /// Load every master and slave zone of the configuration. A slave zone
+    /// is served from its `file`, the local copy of the zone.
+    ///
+    /// A zone that fails to load is logged and skipped, so one broken zone
+    /// file does not take the other zones down.
+    pub(crate) fn from_config(zones: &[ZoneConfig]) -> ZoneManager {
+        let mut manager = ZoneManager::default();
+        for cfg in zones
+            .iter()
+            .filter(|z| matches!(z.kind, ZoneType::Master | ZoneType::Slave))
+        {
+            match Zone::from_config(cfg) {
+                Ok(zone) => {
+                    log_info!("zone {} loaded", cfg.name);
+                    manager.insert(zone);
+                }
+                Err(e) => {
+                    log_error!("zone {} not loaded: {}", cfg.name, e.to_str());
+                }
+            }
+        }
+        manager
+    }
+
+    /// Build a manager from already loaded zones.
+    #[allow(unused)]
+    pub(crate) fn with_zones(zones: Vec<Zone>) -> ZoneManager {
+        let mut manager = ZoneManager::default();
+        for zone in zones {
+            manager.insert(zone);
+        }
+        manager
+    }
+
+    /// Register `zone` under its origin, replacing the zone previously
+    /// loaded for that origin, if any.
+    pub(crate) fn insert(&mut self, zone: Zone) {
+        if let Some(old) = self.zones.insert(zone.name.clone(), zone) {
+            log_warn!("zone {} replaced", old.name);
+        }
+    }
+
+    /// The zone whose origin is `origin`.
+    #[allow(unused)]
+    pub(crate) fn get(&self, origin: &str) -> Option<&Zone> {
+        self.zones.get(&canonical_name(origin))
+    }
+
+    /// The most specific loaded zone containing `qname`.
+    pub(crate) fn find_zone(&self, qname: &str) -> Option<&Zone> {
+        let qname = canonical_name(qname);
+        let mut suffix = qname.as_str();
+        loop {
+            if let Some(zone) = self.zones.get(suffix) {
+                return Some(zone);
+            }
+            if suffix.is_empty() {
+                return None;
+            }
+            suffix = split_label(suffix).1;
+        }
+    }
+
+    /// Build the authoritative response to `query`.
+    ///
+    /// - a name outside of every loaded zone is REFUSED
+    /// - an answer has AA=1, plus in-zone addresses of NS/MX/SRV targets;
+    ///   wildcard answers are owned by the query name
+    /// - CNAME and DNAME records come first in the answer section, followed
+    ///   inside the zone; the RCODE is that of the last name (RFC 6604)
+    /// - NXDOMAIN and NODATA have AA=1 and the zone SOA in the authority
+    ///   section, with the negative TTL of RFC 2308 section 5
+    /// - a name below a delegation gets a referral: AA=0, NS of the cut in
+    ///   the authority section and glue in the additional section
+    ///
+    /// # Errors
+    /// Returns `SCLOUD_ZONE_MISSING_SOA` if a negative answer has no SOA to
+    /// put in the authority section, or `SCLOUD_ZONE_CNAME_LOOP` (see
+    /// `Zone::resolve`).
+    pub(crate) fn answer(&self, query: &DNSPacket) -> Result<DNSPacket, SCloudException> {
+        let Some(question) = query.questions.first() else {
+            return Ok(DNSPacket::new_response(query, Header::RCODE_FORMERR));
+        };
+        let Some(zone) = self.find_zone(&question.q_name) else {
+            return Ok(DNSPacket::new_response(query, Header::RCODE_REFUSED));
+        };
+
+        let mut response = DNSPacket::new_response(query, Header::RCODE_NOERROR);
+        response.header.aa = true;
+
+        let answer = zone.resolve(&question.q_name, question.q_type)?;
+        for record in &answer.aliases {
+            response.answers.push(to_answer(&question.q_name, record));
+        }
+
+        match answer.lookup {
+            ZoneLookup::Answer(records) => {
+                let targets: Vec<&str> = records
+                    .iter()
+                    .filter(|r| {
+                        matches!(
+                            r.rtype,
+                            DNSRecordType::NS | DNSRecordType::MX | DNSRecordType::SRV
+                        )
+                    })
+                    .filter_map(|r| r.rdata.target())
+                    .collect();
+                for record in zone.addresses_of(targets) {
+                    response.additionals.push(to_additional(&record));
+                }
+                for record in &records {
+                    response.answers.push(to_answer(&question.q_name, record));
+                }
+            }
+            ZoneLookup::Alias { records, .. } => {
+                for record in &records {
+                    response.answers.push(to_answer(&question.q_name, record));
+                }
+            }
+            ZoneLookup::Referral { ns, glue } => {
+                // the aliases leading to the cut are still authoritative
+                response.header.aa = !answer.aliases.is_empty();
+                for record in &ns {
+                    response.authorities.push(AuthoritySection {
+                        q_name: record.name.clone(),
+                        q_type: DNSRecordType::NS,
+                        q_class: record.rclass,
+                        ttl: record.ttl,
+                        rdata: record.rdata.clone(),
+                    });
+                }
+                for record in &glue {
+                    response.additionals.push(to_additional(record));
+                }
+            }
+            ZoneLookup::NoData => push_soa(&mut response, zone)?,
+            ZoneLookup::NxDomain => {
+                response.header.rcode = Header::RCODE_NXDOMAIN;
+                push_soa(&mut response, zone)?;
+            }
+        }
+
+        response.sync_counts();
+        Ok(response)
+    }
+}
+
+/// Answer records keep the case of the question name (RFC 4343).
+fn to_answer(q_name: &str, record: &DNSRecord) -> AnswerSection {
+    let owner = if q_name
+        .trim_end_matches('.')
+        .eq_ignore_ascii_case(&record.name)
+    {
+        q_name.trim_end_matches('.').to_string()
+    } else {
+        record.name.clone()
+    };
+    AnswerSection {
+        q_name: owner,
+        r_type: record.rtype,
+        r_class: record.rclass,
+        ttl: record.ttl,
+        rdata: record.rdata.clone(),
+    }
+}
+
+fn to_additional(record: &DNSRecord) -> AdditionalSection {
+    AdditionalSection {
+        q_name: record.name.clone(),
+        q_type: record.rtype,
+        q_class: record.rclass,
+        ttl: record.ttl,
+        rdata: record.rdata.clone(),
+    }
+}
+
+/// Add the SOA of `zone` to the authority section of a negative answer.
+fn push_soa(response: &mut DNSPacket, zone: &Zone) -> Result<(), SCloudException> {
+    let Some(soa) = zone.soa.as_ref() else {
+        return Err(SCloudException::SCLOUD_ZONE_MISSING_SOA);
+    };
+    let minimum = match soa.rdata {
+        RData::SOA { minimum, .. } => minimum,
+        _ => soa.ttl,
+    };
+
+    response.authorities.push(AuthoritySection {
+        q_name: soa.name.clone(),
+        q_type: DNSRecordType::SOA,
+        q_class: soa.rclass,
+        ttl: soa.ttl.min(minimum),
+        rdata: soa.rdata.clone(),
+    });
+    Ok(())
+}
@@ -1,7 +1,21 @@
+pub(crate) mod manager;
+pub(crate) mod tree;
 pub(crate) mod zone_parser;
 
+use crate::config::ZoneConfig;
+use crate::dns::q_name::{is_subdomain, strip_root};
+use crate::dns::q_type::DNSRecordType;
 use crate::dns::records::DNSRecord;
-use std::collections::HashMap;
+use crate::dns::records::rdata::RData;
+use crate::dns::zones::tree::ZoneTree;
+use crate::dns::zones::zone_parser::{ZoneParseError, parse_zone_file, parse_zone_text};
+use crate::exceptions::SCloudException;
+use crate::log_error;
+use std::collections::HashSet;
+use std::path::Path;
+
+// aliases followed inside a zone before giving up on a chain
+const MAX_ALIASES: usize = 16;
 
 /// Represents a DNS zone.
 ///
@@ -16,13 +30,14 @@ use std::collections::HashMap;
 /// - `name`: Zone name
 /// - `ttl`: Default TTL for records in the zone
 /// - `soa`: Start of Authority (SOA) record, if present
-/// - `records`: DNS records indexed by owner name
+/// - `records`: DNS records indexed by owner name (see `ZoneTree`)
 ///
 /// # Exemple :
 /// ```
-/// use std::collections::HashMap;
-/// use crate::dns::zone::Zone;
+/// use crate::dns::zones::Zone;
+/// use crate::dns::zones::tree::ZoneTree;
 /// use crate::dns::records::DNSRecord;
+/// use crate::dns::records::rdata::RData;
 /// use crate::dns::q_type::DNSRecordType;
 /// use crate::dns::q_class::DNSClass;
 ///
@@ -31,38 +46,21 @@ use std::collections::HashMap;
 ///     rtype: DNSRecordType::SOA,
 ///     rclass: DNSClass::IN,
 ///     ttl: 3600,
-///     value: "ns1.example.com hostmaster.example.com".to_string(),
-///     priority: None,
-///     weight: None,
-///     port: None,
-///     flags: None,
-///     tag: None,
-///     regex: None,
-///     replacement: None,
-///     order: None,
-///     preference: None,
+///     rdata: RData::from_text(
+///         DNSRecordType::SOA,
+///         "ns1.example.com hostmaster.example.com 1 7200 3600 1209600 300",
+///     )
+///     .unwrap(),
 /// };
 ///
-/// let mut records = HashMap::new();
-/// records.insert(
-///     "example.com".to_string(),
-///     vec![DNSRecord {
-///         name: "example.com".to_string(),
-///         rtype: DNSRecordType::A,
-///         rclass: DNSClass::IN,
-///         ttl: 300,
-///         value: "93.184.216.34".to_string(),
-///         priority: None,
-///         weight: None,
-///         port: None,
-///         flags: None,
-///         tag: None,
-///         regex: None,
-///         replacement: None,
-///         order: None,
-///         preference: None,
-///     }],
-/// );
+/// let mut records = ZoneTree::new();
+/// records.insert(DNSRecord {
+///     name: "example.com".to_string(),
+///     rtype: DNSRecordType::A,
+///     rclass: DNSClass::IN,
+///     ttl: 300,
+///     rdata: RData::A("93.184.216.34".parse().unwrap()),
+/// });
 ///
 /// let zone = Zone {
 ///     origin: Some("example.com.".to_string()),
@@ -80,14 +78,14 @@ pub struct Zone {
     pub name: String,
     pub ttl: u32,
     pub soa: Option<DNSRecord>,
-    pub records: HashMap<String, Vec<DNSRecord>>,
+    pub records: ZoneTree,
 }
 
 impl Zone {
     /// Get all DNS records for a given name.
     ///
-    /// The name must match the record owner name exactly as stored
-    /// in the zone (usually a fully-qualified domain name).
+    /// The name is absolute (usually a fully-qualified domain name) and
+    /// matched without regard to case.
     ///
     /// # Exemple :
     /// ```
@@ -105,3 +103,422 @@ impl Zone {
         self.records.get(name)
     }
 }
+
+/// Result of a name lookup inside an authoritative zone.
+#[derive(Debug, PartialEq)]
+pub(crate) enum ZoneLookup {
+    /// Records matching the query, owned by the query name even when
+    /// synthesized from a wildcard.
+    Answer(Vec<DNSRecord>),
+    /// The name is an alias of `target`: `records` holds its CNAME, or the
+    /// DNAME of an ancestor and the CNAME synthesized from it.
+    Alias {
+        records: Vec<DNSRecord>,
+        target: String,
+    },
+    /// The name is at or below a delegation: NS of the cut and in-zone glue.
+    Referral {
+        ns: Vec<DNSRecord>,
+        glue: Vec<DNSRecord>,
+    },
+    /// The name exists but has no record of the requested type.
+    NoData,
+    /// The name does not exist in the zone.
+    NxDomain,
+}
+
+/// Answer of a zone to a query, once the aliases inside the zone have been
+/// followed (RFC 1034 section 4.3.2 step 3).
+#[derive(Debug, PartialEq)]
+pub(crate) struct ZoneAnswer {
+    /// CNAME and DNAME records followed from the query name, in order
+    pub(crate) aliases: Vec<DNSRecord>,
+    /// lookup of the last name of the chain, an `Alias` when the chain
+    /// leaves the zone
+    pub(crate) lookup: ZoneLookup,
+}
+
+impl Zone {
+    /// Load an authoritative zone described by the configuration.
+    ///
+    /// Inline zones are built from `records`, other zones are read from
+    /// `file` (see `Zone::load`), with the zone name as origin.
+    ///
+    /// # Exemple :
+    /// ```
+    /// let cfg = Config::from_file(Path::new("./config/config.json")).unwrap();
+    /// let inline = cfg.zone.iter().find(|z| z.name == "inline.local.").unwrap();
+    ///
+    /// let zone = Zone::from_config(inline).unwrap();
+    ///
+    /// assert_eq!(zone.name, "inline.local");
+    /// assert!(zone.get_records("www.inline.local").is_some());
+    /// ```
+    ///
+    /// # Errors
+    /// Same as `Zone::load`.
+    pub(crate) fn from_config(cfg: &ZoneConfig) -> Result<Zone, SCloudException> {
+        let file = match cfg.file.as_deref() {
+            Some(file) if cfg.inline != Some(true) => file,
+            _ => {
+                let mut text = String::new();
+                for r in &cfg.records {
+                    let ttl = r.ttl.map(|ttl| ttl.to_string()).unwrap_or_default();
+                    let rdata = match (r.priority, r.r#type.to_uppercase().as_str()) {
+                        (Some(prio), "MX" | "SRV") => format!("{} {}", prio, r.rdata),
+                        _ => r.rdata.clone(),
+                    };
+                    text.push_str(&format!(
+                        "{} {} {} {} {}\n",
+                        r.name,
+                        ttl,
+                        r.class.as_deref().unwrap_or("IN"),
+                        r.r#type,
+                        rdata
+                    ));
+                }
+                let parsed = parse_zone_text(&text, Some(&cfg.name));
+                return Zone::from_parsed(parsed, &cfg.name);
+            }
+        };
+        Zone::load(Path::new(file), &cfg.name)
+    }
+
+    /// Load the zone file at `path`, whose origin is `origin` (e.g.
+    /// `example.com.`), and check it can be served.
+    ///
+    /// Every owner name and every domain name found in RDATA is made
+    /// absolute (lowercase, without the trailing dot) so the zone can be
+    /// queried with names taken from the wire. Parse errors are logged with
+    /// their file and line.
+    ///
+    /// # Exemple :
+    /// ```
+    /// let zone = Zone::load(Path::new("zones/nihilist.moe.zone"), "nihilist.moe.").unwrap();
+    ///
+    /// assert_eq!(zone.name, "nihilist.moe");
+    /// assert!(zone.get_records("www.nihilist.moe").is_some());
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `SCloudException` if:
+    /// - the zone file cannot be read or parsed (see `parse_zone_file`)
+    /// - the zone fails a sanity check (see `Zone::check`)
+    pub(crate) fn load(path: &Path, origin: &str) -> Result<Zone, SCloudException> {
+        Zone::from_parsed(parse_zone_file(path, Some(origin)), origin)
+    }
+
+    fn from_parsed(
+        parsed: Result<Zone, ZoneParseError>,
+        origin: &str,
+    ) -> Result<Zone, SCloudException> {
+        let mut zone = parsed.map_err(|e| {
+            log_error!("zone {}: {}", origin, e);
+            e.error
+        })?;
+
+        let mut soa = zone
+            .soa
+            .take()
+            .ok_or(SCloudException::SCLOUD_ZONE_MISSING_SOA)?;
+        canonicalize_record(&mut soa)?;
+
+        let mut records = ZoneTree::new();
+        for mut record in std::mem::take(&mut zone.records).into_records() {
+            canonicalize_record(&mut record)?;
+            records.insert(record);
+        }
+
+        let name = canonical_name(origin);
+        let zone = Zone {
+            origin: Some(format!("{}.", name)),
+            name,
+            ttl: zone.ttl,
+            soa: Some(soa),
+            records,
+        };
+        zone.check()?;
+        Ok(zone)
+    }
+
+    /// Sanity checks of a loaded zone (RFC 1034 sections 3.6.2 and 4.2.1):
+    /// - its SOA is at the apex (the parser refuses a second one)
+    /// - the apex has NS records
+    /// - a name owning a CNAME owns no other data, DNSSEC records aside
+    /// - every name server inside the zone has an address (glue below a
+    ///   delegation)
+    ///
+    /// # Errors
+    /// Returns `SCLOUD_ZONE_MISSING_SOA`, `SCLOUD_ZONE_MISSING_APEX_NS`,
+    /// `SCLOUD_ZONE_CNAME_AND_OTHER_DATA` or `SCLOUD_ZONE_MISSING_GLUE`,
+    /// after logging the name at fault.
+    pub(crate) fn check(&self) -> Result<(), SCloudException> {
+        let fail = |name: &str, error: SCloudException| {
+            log_error!("zone {}: {} ({})", self.name, error.to_str(), name);
+            Err(error)
+        };
+
+        if self.soa.as_ref().is_none_or(|soa| soa.name != self.name) {
+            return fail(&self.name, SCloudException::SCLOUD_ZONE_MISSING_SOA);
+        }
+        if !self
+            .records_of(&self.name)
+            .any(|r| r.rtype == DNSRecordType::NS)
+        {
+            return fail(&self.name, SCloudException::SCLOUD_ZONE_MISSING_APEX_NS);
+        }
+
+        for records in self.records.iter() {
+            let name = &records[0].name;
+            let cnames = records
+                .iter()
+                .filter(|r| r.rtype == DNSRecordType::CNAME)
+                .count();
+            let others = records.iter().any(|r| {
+                !matches!(
+                    r.rtype,
+                    DNSRecordType::CNAME | DNSRecordType::RRSIG | DNSRecordType::NSEC
+                )
+            });
+            if cnames > 1 || (cnames == 1 && (others || *name == self.name)) {
+                return fail(name, SCloudException::SCLOUD_ZONE_CNAME_AND_OTHER_DATA);
+            }
+
+            for target in records
+                .iter()
+                .filter(|r| r.rtype == DNSRecordType::NS)
+                .filter_map(|r| r.rdata.target())
+            {
+                if self.contains(target) && self.addresses_of([target]).is_empty() {
+                    return fail(target, SCloudException::SCLOUD_ZONE_MISSING_GLUE);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `qname` is the apex of this zone or a name below it.
+    pub(crate) fn contains(&self, qname: &str) -> bool {
+        is_subdomain(&canonical_name(qname), &self.name)
+    }
+
+    /// Look `qname`/`qtype` up in the zone, following the aliases that
+    /// stay inside it.
+    ///
+    /// # Exemple :
+    /// ```
+    /// // alias.nihilist.moe CNAME blog, blog CNAME www
+    /// let answer = zone.resolve("alias.nihilist.moe", DNSRecordType::A).unwrap();
+    ///
+    /// assert_eq!(answer.aliases.len(), 2);
+    /// assert!(matches!(answer.lookup, ZoneLookup::Answer(_)));
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `SCLOUD_ZONE_CNAME_LOOP` if the chain comes back to a name
+    /// already seen or is longer than 16 aliases.
+    pub(crate) fn resolve(
+        &self,
+        qname: &str,
+        qtype: DNSRecordType,
+    ) -> Result<ZoneAnswer, SCloudException> {
+        let mut aliases = Vec::new();
+        let mut seen = HashSet::new();
+        let mut name = canonical_name(qname);
+        loop {
+            if seen.len() > MAX_ALIASES || !seen.insert(name.clone()) {
+                return Err(SCloudException::SCLOUD_ZONE_CNAME_LOOP);
+            }
+            match self.lookup(&name, qtype) {
+                ZoneLookup::Alias { records, target } if self.contains(&target) => {
+                    aliases.extend(records);
+                    name = target;
+                }
+                lookup => return Ok(ZoneAnswer { aliases, lookup }),
+            }
+        }
+    }
+
+    /// Look `qname`/`qtype` up in the zone, without following aliases.
+    ///
+    /// Delegations and DNAME records are checked first, from the apex down
+    /// to `qname`, so that data below a zone cut is never answered
+    /// authoritatively (RFC 1034 section 4.3.2, RFC 6672 section 3.2). A
+    /// name that does not exist is then matched against the wildcard of
+    /// its closest encloser (RFC 4592 section 3.3). `qname` must belong to
+    /// the zone (see `contains`).
+    ///
+    /// # Exemple :
+    /// ```
+    /// match zone.lookup("www.inline.local", DNSRecordType::A) {
+    ///     ZoneLookup::Answer(records) => {
+    ///         assert_eq!(records[0].rdata, RData::A("10.0.0.11".parse().unwrap()))
+    ///     }
+    ///     other => panic!("unexpected lookup result: {:?}", other),
+    /// }
+    /// ```
+    pub(crate) fn lookup(&self, qname: &str, qtype: DNSRecordType) -> ZoneLookup {
+        let qname = canonical_name(qname);
+
+        for (name, records) in self.records.ancestors(&qname) {
+            if !self.contains(&name) {
+                continue;
+            }
+            let at_qname = name == qname;
+            // DS lives on the parent side of the cut (RFC 4035 section 3.1.4.1)
+            if name != self.name && !(at_qname && qtype == DNSRecordType::DS) {
+                let ns: Vec<DNSRecord> = records
+                    .iter()
+                    .filter(|r| r.rtype == DNSRecordType::NS)
+                    .cloned()
+                    .collect();
+                if !ns.is_empty() {
+                    let glue = self.glue_for(&ns);
+                    return ZoneLookup::Referral { ns, glue };
+                }
+            }
+            if !at_qname {
+                let dname = records.iter().find(|r| r.rtype == DNSRecordType::DNAME);
+                if let Some(dname) = dname {
+                    return synthesize_cname(&qname, &name, dname);
+                }
+            }
+        }
+
+        let mut rrset: Vec<DNSRecord> = self.records_of(&qname).cloned().collect();
+        if qname == self.name {
+            rrset.extend(self.soa.iter().cloned());
+        }
+        if !rrset.is_empty() {
+            return select(rrset, qtype);
+        }
+        if self.records.is_empty_non_terminal(&qname) {
+            return ZoneLookup::NoData;
+        }
+
+        let Some(encloser) = self.closest_encloser(&qname) else {
+            return ZoneLookup::NxDomain;
+        };
+        let source = if encloser.is_empty() {
+            "*".to_string()
+        } else {
+            format!("*.{}", encloser)
+        };
+        let wildcard: Vec<DNSRecord> = self
+            .records_of(&source)
+            .map(|r| DNSRecord {
+                name: qname.clone(),
+                ..r.clone()
+            })
+            .collect();
+        if wildcard.is_empty() {
+            return ZoneLookup::NxDomain;
+        }
+        select(wildcard, qtype)
+    }
+
+    /// In-zone A/AAAA records of the given names, used as additional data.
+    pub(crate) fn addresses_of<'a>(
+        &'a self,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Vec<DNSRecord> {
+        let mut addresses = Vec::new();
+        for name in names {
+            addresses.extend(
+                self.records_of(&canonical_name(name))
+                    .filter(|r| matches!(r.rtype, DNSRecordType::A | DNSRecordType::AAAA))
+                    .cloned(),
+            );
+        }
+        addresses
+    }
+
+    fn glue_for(&self, ns: &[DNSRecord]) -> Vec<DNSRecord> {
+        self.addresses_of(ns.iter().filter_map(|r| r.rdata.target()))
+    }
+
+    fn records_of(&self, name: &str) -> impl Iterator<Item = &DNSRecord> {
+        self.records.get(name).into_iter().flatten()
+    }
+
+    /// Longest ancestor of `qname` (which does not exist) that exists in
+    /// the zone, with data or as an empty non-terminal (RFC 4592 section
+    /// 3.3.1).
+    fn closest_encloser(&self, qname: &str) -> Option<String> {
+        let encloser = self.records.closest_encloser(qname);
+        if self.contains(&encloser) {
+            Some(encloser)
+        } else if self.contains(qname) {
+            // zone not checked, without records at its apex
+            Some(self.name.clone())
+        } else {
+            None
+        }
+    }
+}
+
+/// The records of `rrset` (all owned by the query name) answering `qtype`,
+/// or its CNAME.
+fn select(rrset: Vec<DNSRecord>, qtype: DNSRecordType) -> ZoneLookup {
+    let matching: Vec<DNSRecord> = rrset.iter().filter(|r| r.rtype == qtype).cloned().collect();
+    if !matching.is_empty() {
+        return ZoneLookup::Answer(matching);
+    }
+
+    let cname: Vec<DNSRecord> = rrset
+        .into_iter()
+        .filter(|r| r.rtype == DNSRecordType::CNAME)
+        .collect();
+    match cname.first().and_then(|r| r.rdata.target()) {
+        Some(target) => ZoneLookup::Alias {
+            target: canonical_name(target),
+            records: cname,
+        },
+        None => ZoneLookup::NoData,
+    }
+}
+
+/// The DNAME owned by `owner`, an ancestor of `qname`, followed by the CNAME
+/// it implies for `qname` (RFC 6672 section 3.2).
+fn synthesize_cname(qname: &str, owner: &str, dname: &DNSRecord) -> ZoneLookup {
+    let prefix = qname
+        .strip_suffix(owner)
+        .and_then(|prefix| prefix.strip_suffix('.'))
+        .unwrap_or(qname);
+    let target = match dname.rdata.target().map(canonical_name) {
+        Some(target) if target.is_empty() => prefix.to_string(),
+        Some(target) => format!("{}.{}", prefix, target),
+        None => prefix.to_string(),
+    };
+    let cname = DNSRecord {
+        name: qname.to_string(),
+        rtype: DNSRecordType::CNAME,
+        rclass: dname.rclass,
+        ttl: dname.ttl,
+        rdata: RData::CNAME(target.clone()),
+    };
+    ZoneLookup::Alias {
+        records: vec![dname.clone(), cname],
+        target,
+    }
+}
+
+/// Lowercase a domain name and drop its trailing dot.
+pub(crate) fn canonical_name(name: &str) -> String {
+    strip_root(name).to_ascii_lowercase()
+}
+
+/// Lowercase the owner and the RDATA names of `record`, already absolute.
+///
+/// # Errors
+/// Returns `SCLOUD_ZONE_INVALID_RECORD_RDATA` if a name of the RDATA
+/// cannot be encoded.
+fn canonicalize_record(record: &mut DNSRecord) -> Result<(), SCloudException> {
+    record.name = canonical_name(&record.name);
+    record.rdata.map_names(canonical_name);
+    record
+        .rdata
+        .to_bytes()
+        .map_err(|_| SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA)?;
+    Ok(())
+}
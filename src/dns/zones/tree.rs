@@ -0,0 +1,286 @@
+use crate::dns::q_name::{push_label, split_label, strip_root};
+use crate::dns::records::{DNSRecord, unescape};
+use std::collections::BTreeMap;
+use std::collections::btree_map;
+use std::ops::Bound;
+
+/// Records of a zone stored in a tree of labels, the root of the DNS at
+/// the top.
+///
+/// Nodes are keyed by the bytes of their label, escapes decoded and ASCII
+/// letters lowercased: siblings are thus kept in the canonical order of
+/// RFC 4034 section 6.1 and a depth-first walk of the tree visits names in
+/// canonical order. Names given back by the tree are lowercased, in
+/// presentation format. Lookups go down one label at
+/// a time, each step being a search among the children of a node, so
+/// finding a name, its closest encloser, the delegations above it or the
+/// name following it costs O(labels x log(names)).
+///
+/// A node exists as long as it or one of its descendants owns records: a
+/// node without records is an empty non-terminal.
+///
+/// # Exemple :
+/// ```
+/// let mut tree = ZoneTree::new();
+/// tree.insert(record("WWW.example.com", DNSRecordType::A));
+/// tree.insert(record("a.b.example.com", DNSRecordType::A));
+///
+/// assert!(tree.get("www.Example.com.").is_some());
+/// assert!(tree.is_empty_non_terminal("b.example.com"));
+/// assert_eq!(tree.closest_encloser("x.b.example.com"), "b.example.com");
+/// assert_eq!(tree.next_name("b.example.com").unwrap(), "a.b.example.com");
+/// ```
+#[derive(Debug, Default, PartialEq)]
+pub struct ZoneTree {
+    root: Node,
+    // names owning records
+    len: usize,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Node {
+    records: Vec<DNSRecord>,
+    children: BTreeMap<Vec<u8>, Node>,
+}
+
+impl ZoneTree {
+    pub(crate) fn new() -> ZoneTree {
+        ZoneTree::default()
+    }
+
+    /// Add `record` to the records owned by its name.
+    pub(crate) fn insert(&mut self, record: DNSRecord) {
+        let mut node = &mut self.root;
+        for label in labels(&record.name) {
+            node = node.children.entry(label).or_default();
+        }
+        if node.records.is_empty() {
+            self.len += 1;
+        }
+        node.records.push(record);
+    }
+
+    /// Records owned by `name`, whatever its case.
+    pub(crate) fn get(&self, name: &str) -> Option<&Vec<DNSRecord>> {
+        self.node(name)
+            .map(|node| &node.records)
+            .filter(|records| !records.is_empty())
+    }
+
+    /// Number of names owning records.
+    #[allow(unused)]
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    #[allow(unused)]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Records of every name, grouped by owner, in canonical order.
+    pub(crate) fn iter(&self) -> Iter<'_> {
+        Iter {
+            root: Some(&self.root),
+            stack: Vec::new(),
+        }
+    }
+
+    /// All the records, in canonical order of their owners.
+    pub(crate) fn into_records(self) -> Vec<DNSRecord> {
+        fn collect(node: Node, out: &mut Vec<DNSRecord>) {
+            out.extend(node.records);
+            for (_, child) in node.children {
+                collect(child, out);
+            }
+        }
+        let mut out = Vec::new();
+        collect(self.root, &mut out);
+        out
+    }
+
+    /// Whether `name` owns no record but has descendants owning some.
+    pub(crate) fn is_empty_non_terminal(&self, name: &str) -> bool {
+        self.node(name)
+            .is_some_and(|node| node.records.is_empty() && !node.children.is_empty())
+    }
+
+    /// Longest of `name` and its ancestors that exists in the tree, with
+    /// records or as an empty non-terminal (RFC 4592 section 3.3.1). The
+    /// root when nothing else matches.
+    pub(crate) fn closest_encloser(&self, name: &str) -> String {
+        let labels = labels(name);
+        let mut node = &self.root;
+        let mut depth = 0;
+        for label in &labels {
+            match node.children.get(label) {
+                Some(child) => {
+                    node = child;
+                    depth += 1;
+                }
+                None => break,
+            }
+        }
+        join(&labels[..depth])
+    }
+
+    /// Names owning records from the root down to `name` (included when it
+    /// owns some), with their records. Delegation cuts and DNAME records
+    /// above a name are found this way.
+    pub(crate) fn ancestors(&self, name: &str) -> Vec<(String, &Vec<DNSRecord>)> {
+        let labels = labels(name);
+        let mut node = &self.root;
+        let mut found = Vec::new();
+        if !node.records.is_empty() {
+            found.push((String::new(), &node.records));
+        }
+        for (depth, label) in labels.iter().enumerate() {
+            let Some(child) = node.children.get(label) else {
+                break;
+            };
+            node = child;
+            if !node.records.is_empty() {
+                found.push((join(&labels[..=depth]), &node.records));
+            }
+        }
+        found
+    }
+
+    /// First name owning records after `name` in canonical order, wrapping
+    /// around to the first name of the tree: the "next owner name" of an
+    /// NSEC record (RFC 4034 section 4.1.1). `name` does not have to
+    /// exist.
+    #[allow(unused)]
+    pub(crate) fn next_name(&self, name: &str) -> Option<String> {
+        let labels = labels(name);
+        let mut path = vec![&self.root];
+        for label in &labels {
+            match path[path.len() - 1].children.get(label) {
+                Some(child) => path.push(child),
+                None => break,
+            }
+        }
+
+        let depth = path.len() - 1;
+        let mut prefix: Vec<&[u8]> = labels[..depth].iter().map(Vec::as_slice).collect();
+        if depth == labels.len() {
+            // descendants of an existing name come right after it
+            if let Some(next) = first_below(path[depth], &mut prefix) {
+                return Some(next);
+            }
+        } else if let Some(next) = first_after(path[depth], &labels[depth], &mut prefix) {
+            return Some(next);
+        }
+
+        // then the next siblings of each ancestor, deepest first
+        for i in (0..depth).rev() {
+            prefix.truncate(i);
+            if let Some(next) = first_after(path[i], &labels[i], &mut prefix) {
+                return Some(next);
+            }
+        }
+
+        prefix.clear();
+        if !self.root.records.is_empty() {
+            return Some(String::new());
+        }
+        first_below(&self.root, &mut prefix)
+    }
+
+    fn node(&self, name: &str) -> Option<&Node> {
+        let mut node = &self.root;
+        for label in labels(name) {
+            node = node.children.get(&label)?;
+        }
+        Some(node)
+    }
+}
+
+/// Iterator over the records of a `ZoneTree`, see `ZoneTree::iter`.
+pub(crate) struct Iter<'a> {
+    root: Option<&'a Node>,
+    stack: Vec<btree_map::Values<'a, Vec<u8>, Node>>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a Vec<DNSRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(root) = self.root.take() {
+            self.stack.push(root.children.values());
+            if !root.records.is_empty() {
+                return Some(&root.records);
+            }
+        }
+        loop {
+            let node = match self.stack.last_mut()?.next() {
+                Some(node) => node,
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
+            };
+            self.stack.push(node.children.values());
+            if !node.records.is_empty() {
+                return Some(&node.records);
+            }
+        }
+    }
+}
+
+/// Labels of `name` from the root down, as lowercased bytes. A badly
+/// escaped label is taken as it is written.
+fn labels(name: &str) -> Vec<Vec<u8>> {
+    let mut labels = Vec::new();
+    let mut rest = strip_root(name);
+    while !rest.is_empty() {
+        let (label, tail) = split_label(rest);
+        let mut bytes = unescape(label).unwrap_or_else(|| label.as_bytes().to_vec());
+        bytes.make_ascii_lowercase();
+        labels.push(bytes);
+        rest = tail;
+    }
+    labels.reverse();
+    labels
+}
+
+/// Name made of `labels`, given from the root down, in presentation
+/// format.
+fn join<L: AsRef<[u8]>>(labels: &[L]) -> String {
+    let mut name = String::new();
+    for label in labels.iter().rev() {
+        if !name.is_empty() {
+            name.push('.');
+        }
+        push_label(&mut name, label.as_ref());
+    }
+    name
+}
+
+/// First name owning records strictly below `node`, whose labels are
+/// `prefix`. Every node has such a descendant or records of its own, so
+/// following the first children always ends on one.
+fn first_below<'a>(mut node: &'a Node, prefix: &mut Vec<&'a [u8]>) -> Option<String> {
+    loop {
+        let (label, child) = node.children.iter().next()?;
+        prefix.push(label);
+        if !child.records.is_empty() {
+            return Some(join(prefix));
+        }
+        node = child;
+    }
+}
+
+/// First name owning records among the children of `node` following
+/// `label` and their descendants.
+fn first_after<'a>(node: &'a Node, label: &[u8], prefix: &mut Vec<&'a [u8]>) -> Option<String> {
+    let (label, child) = node
+        .children
+        .range::<[u8], _>((Bound::Excluded(label), Bound::Unbounded))
+        .next()?;
+    prefix.push(label);
+    if !child.records.is_empty() {
+        return Some(join(prefix));
+    }
+    first_below(child, prefix)
+}
@@ -1,206 +1,618 @@
 use crate::dns::q_class::DNSClass;
+use crate::dns::q_name::{normalize_name, strip_root};
 use crate::dns::q_type::DNSRecordType;
 use crate::dns::records::DNSRecord;
+use crate::dns::records::rdata::RData;
+use crate::dns::records::unescape;
 use crate::dns::zones::Zone;
+use crate::dns::zones::tree::ZoneTree;
 use crate::exceptions::SCloudException;
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 
-/// Parse a DNS zone file and build an in-memory `Zone` structure.
+// nesting allowed for $INCLUDE, so that a file including itself fails
+const MAX_INCLUDE_DEPTH: usize = 16;
+const DEFAULT_TTL: u32 = 3600;
+// records a single $GENERATE may produce, a /16 reverse zone
+const MAX_GENERATED: u32 = 65536;
+// widest $GENERATE substitution, the longest name
+const MAX_GENERATE_WIDTH: usize = 255;
+
+/// Error of the zone file parser, with the place it was found at.
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct ZoneParseError {
+    /// file holding the faulty entry, empty for a zone parsed from text
+    pub(crate) file: String,
+    /// line the faulty entry starts on, 0 when the file cannot be read
+    pub(crate) line: usize,
+    pub(crate) error: SCloudException,
+}
+
+impl fmt::Display for ZoneParseError {
+    fn fmt(&self, out: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.file.is_empty() {
+            write!(out, "line {}: {}", self.line, self.error.to_str())
+        } else {
+            write!(out, "{}:{}: {}", self.file, self.line, self.error.to_str())
+        }
+    }
+}
+
+impl From<ZoneParseError> for SCloudException {
+    fn from(e: ZoneParseError) -> SCloudException {
+        e.error
+    }
+}
+
+/// Parse the DNS zone file located at `path` and build an in-memory `Zone`
+/// structure. `origin` is the origin of the file until its first `$ORIGIN`
+/// directive (the root when `None`), typically the name of its
+/// `ZoneConfig`.
 ///
-/// The zone file must be located in the `zones/` directory and named
-/// `<qname>.zone`.
+/// This parser supports the master file format of RFC 1035 section 5:
+/// - `$TTL` (RFC 2308), `$ORIGIN` and `$INCLUDE <file> [origin]`, the
+///   included file being looked up relative to the including one
+/// - `$GENERATE` ranges of records, as in BIND
+/// - records spread over several lines with parentheses
+/// - owners inherited from the previous record (line starting with a blank)
+/// - `@` and relative names, expanded against the current origin
+/// - quoted strings and `\X` / `\DDD` escapes
+/// - TTLs with units (`1h30m`, `2d`, `1w`)
+/// - the record types with a presentation format in `RData`, and any type
+///   in the generic `\# <length> <hex>` form of RFC 3597
 ///
-/// This parser supports:
-/// - `$TTL` directive (default TTL)
-/// - `$ORIGIN` directive
-/// - SOA record (unique per zone)
-/// - Common DNS record types: A, AAAA, NS, MX, TXT, SOA, CNAME, PTR, SRV, CAA, NAPTR
+/// Records are stored by absolute owner name (without the trailing dot, as
+/// written) in a `ZoneTree`. The SOA record is stored separately in
+/// `zone.soa`. See `Zone::load` for a zone ready to be served.
+///
+/// # Example
+/// ```
+/// use crate::dns::zones::zone_parser::parse_zone_file;
 ///
-/// Records are stored by owner name in a `HashMap<String, Vec<DNSRecord>>`.
-/// The SOA record is stored separately in `zone.soa`.
+/// let zone = parse_zone_file(Path::new("zones/nihilist.moe.zone"), Some("nihilist.moe."))
+///     .expect("Failed to parse zone");
 ///
-/// # Arguments
-/// * `qname` - The zone name (used to locate the zone file)
+/// assert!(zone.soa.is_some());
+/// assert!(zone.get_records("www.nihilist.moe").is_some());
+/// ```
 ///
 /// # Errors
-/// Returns `SCloudException` if:
+/// Returns a `ZoneParseError`, with the file and line of the faulty entry,
+/// if:
 /// - the zone file cannot be found
 /// - the file is empty or unreadable
 /// - TTL parsing fails
+/// - a record has an unsupported type, or RDATA that does not match it
+/// - the file holds a second SOA record
+/// - the file is not well formed (quotes, parentheses, directives)
+pub(crate) fn parse_zone_file(path: &Path, origin: Option<&str>) -> Result<Zone, ZoneParseError> {
+    let text = read_zone_file(path).map_err(|error| ZoneParseError {
+        file: path.display().to_string(),
+        line: 0,
+        error,
+    })?;
+    let mut reader = ZoneReader::new(origin);
+    reader.read(&text, Some(path), 0)?;
+    Ok(reader.finish())
+}
+
+/// Parse the content of a zone file, with `origin` as initial origin (the
+/// root when `None`). `$INCLUDE` is refused, there being no file to look
+/// the included one up from.
 ///
-/// # Example
-/// ```
-/// use crate::dns::zones::zone_parser;
+/// # Errors
+/// Same as `parse_zone_file`, except for the file errors.
+pub(crate) fn parse_zone_text(text: &str, origin: Option<&str>) -> Result<Zone, ZoneParseError> {
+    let mut reader = ZoneReader::new(origin);
+    reader.read(text, None, 0)?;
+    Ok(reader.finish())
+}
+
+fn read_zone_file(path: &Path) -> Result<String, SCloudException> {
+    fs::read_to_string(path).map_err(|e| match e.kind() {
+        io::ErrorKind::InvalidData => SCloudException::SCLOUD_ZONE_PARSER_FILE_EMPTY,
+        _ => SCloudException::SCLOUD_ZONE_PARSER_FILE_NOT_FOUND,
+    })
+}
+
+/// One record or directive of a zone file.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Entry {
+    /// line the entry starts on
+    pub(crate) line: usize,
+    /// the line starts with a blank: there is no owner field, the owner of
+    /// the previous record is reused
+    pub(crate) blank_owner: bool,
+    /// fields without their quotes, escapes kept (see `unescape`)
+    pub(crate) fields: Vec<String>,
+}
+
+/// Split a zone file into entries (RFC 1035 section 5.1).
 ///
-/// let zone = zone_parser("example.com").expect("Failed to parse zone");
+/// Fields are separated by blanks. A double-quoted field may hold blanks,
+/// `;` and parentheses; an escaped character never separates nor quotes.
+/// `;` starts a comment up to the end of the line. An entry ends with its
+/// line, unless a parenthesis is still open.
 ///
-/// assert!(zone.soa.is_some());
-/// assert!(!zone.records.is_empty());
+/// # Exemple :
 /// ```
-#[allow(unused)]
-pub fn zone_parser(qname: &str) -> Result<Zone, SCloudException> {
-    let filename = format!("zones/{}.zone", qname);
-    let file =
-        File::open(&filename).map_err(|_| SCloudException::SCLOUD_ZONE_PARSER_FILE_NOT_FOUND)?;
-
-    let mut zone = Zone {
-        origin: None,
-        name: String::new(),
-        ttl: 3600,
-        soa: None,
-        records: HashMap::new(),
+/// let entries = tokenize("@ IN SOA ns admin (\n 1 2 3 4 5 ) ; soa\n  TXT \"a b\"\n").unwrap();
+///
+/// assert_eq!(entries.len(), 2);
+/// assert_eq!(entries[0].fields.len(), 9);
+/// assert!(entries[1].blank_owner);
+/// assert_eq!(entries[1].fields, vec!["TXT", "a b"]);
+/// ```
+///
+/// # Errors
+/// Returns `SCLOUD_ZONE_PARSER_SYNTAX_ERROR`, with its line, for an
+/// unterminated quoted string, an unbalanced parenthesis or a trailing `\`.
+pub(crate) fn tokenize(text: &str) -> Result<Vec<Entry>, ZoneParseError> {
+    let syntax = |line| ZoneParseError {
+        file: String::new(),
+        line,
+        error: SCloudException::SCLOUD_ZONE_PARSER_SYNTAX_ERROR,
     };
 
-    let mut default_ttl = 3600u32;
+    let mut entries = Vec::new();
+    let mut current: Option<Entry> = None;
+    let mut line = 1;
+    let mut blank_line_start = text.starts_with([' ', '\t']);
+    // depth of parentheses, and line of the outermost one
+    let mut depth = 0usize;
+    let mut open_line = 0;
+    let mut chars = text.chars().peekable();
 
-    for line in io::BufReader::new(file).lines() {
-        let line = line.map_err(|_| SCloudException::SCLOUD_ZONE_PARSER_FILE_EMPTY)?;
-        let line = line.trim();
-
-        if line.is_empty() || line.starts_with(';') {
-            continue;
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => {
+                line += 1;
+                blank_line_start = matches!(chars.peek(), Some(' ' | '\t'));
+                if depth == 0 {
+                    entries.extend(current.take());
+                }
+            }
+            ';' => while chars.next_if(|&c| c != '\n').is_some() {},
+            '(' => {
+                if depth == 0 {
+                    open_line = line;
+                }
+                depth += 1;
+            }
+            ')' => depth = depth.checked_sub(1).ok_or_else(|| syntax(line))?,
+            c if c.is_whitespace() => {}
+            c => {
+                let start = line;
+                let mut field = String::new();
+                let quoted = c == '"';
+                if !quoted {
+                    field.push(c);
+                }
+                let mut escaped = c == '\\';
+                loop {
+                    let next = if escaped {
+                        chars.next()
+                    } else if quoted {
+                        chars.next_if(|&c| c != '"' && c != '\n')
+                    } else {
+                        chars.next_if(|&c| !c.is_whitespace() && !"();".contains(c))
+                    };
+                    let Some(next) = next else {
+                        if escaped {
+                            return Err(syntax(line));
+                        }
+                        break;
+                    };
+                    if next == '\n' {
+                        line += 1;
+                    }
+                    field.push(next);
+                    escaped = !escaped && next == '\\';
+                }
+                if quoted && chars.next() != Some('"') {
+                    return Err(syntax(start));
+                }
+                current
+                    .get_or_insert_with(|| Entry {
+                        line: start,
+                        blank_owner: blank_line_start,
+                        fields: Vec::new(),
+                    })
+                    .fields
+                    .push(field);
+            }
         }
+    }
 
-        let line = if let Some(idx) = line.find(';') {
-            &line[..idx]
-        } else {
-            line
-        }
-        .trim();
-
-        if line.starts_with("$TTL") {
-            if let Some(ttl_str) = line.split_whitespace().nth(1) {
-                default_ttl = ttl_str
-                    .parse::<u32>()
-                    .map_err(|_| SCloudException::SCLOUD_ZONE_PARSER_FAILED_TO_READ_TTL_FIELD)?;
-                zone.ttl = default_ttl;
-            }
-            continue;
+    if depth > 0 {
+        return Err(syntax(open_line));
+    }
+    entries.extend(current);
+    Ok(entries)
+}
+
+/// State of a zone being read, shared with the files it includes.
+struct ZoneReader {
+    zone: Zone,
+    /// initial origin, name of the zone when it has no SOA
+    apex: String,
+    /// current origin, absolute without the trailing dot ("" for the root)
+    origin: String,
+    /// `$TTL`, if any
+    default_ttl: Option<u32>,
+    last_ttl: u32,
+    last_class: DNSClass,
+    last_owner: Option<String>,
+}
+
+impl ZoneReader {
+    fn new(origin: Option<&str>) -> ZoneReader {
+        let origin = origin.map(|o| qualify(o, "")).unwrap_or_default();
+        ZoneReader {
+            zone: Zone {
+                origin: None,
+                name: String::new(),
+                ttl: DEFAULT_TTL,
+                soa: None,
+                records: ZoneTree::new(),
+            },
+            apex: origin.clone(),
+            origin,
+            default_ttl: None,
+            last_ttl: DEFAULT_TTL,
+            last_class: DNSClass::IN,
+            last_owner: None,
         }
+    }
+
+    fn read(
+        &mut self,
+        text: &str,
+        file: Option<&Path>,
+        depth: usize,
+    ) -> Result<(), ZoneParseError> {
+        let file_name = file.map(|p| p.display().to_string()).unwrap_or_default();
+        let at = |line, error| ZoneParseError {
+            file: file_name.clone(),
+            line,
+            error,
+        };
 
-        if line.starts_with("$ORIGIN") {
-            if let Some(origin_str) = line.split_whitespace().nth(1) {
-                zone.origin = Some(origin_str.to_string());
+        let entries = tokenize(text).map_err(|e| at(e.line, e.error))?;
+        for entry in entries {
+            let directive = match entry.fields.first() {
+                Some(first) if !entry.blank_owner && first.starts_with('$') => {
+                    first.to_ascii_uppercase()
+                }
+                _ => {
+                    self.record(&entry).map_err(|e| at(entry.line, e))?;
+                    continue;
+                }
+            };
+            let args = &entry.fields[1..];
+            match (directive.as_str(), args) {
+                ("$ORIGIN", [origin]) => {
+                    self.origin = self.name(origin).map_err(|e| at(entry.line, e))?;
+                }
+                ("$TTL", [ttl]) => {
+                    let ttl = parse_ttl(ttl).ok_or_else(|| {
+                        at(
+                            entry.line,
+                            SCloudException::SCLOUD_ZONE_PARSER_FAILED_TO_READ_TTL_FIELD,
+                        )
+                    })?;
+                    self.default_ttl = Some(ttl);
+                    self.zone.ttl = ttl;
+                }
+                ("$GENERATE", [_, _, _, _, ..]) => {
+                    self.generate(entry.line, args)
+                        .map_err(|e| at(entry.line, e))?;
+                }
+                ("$INCLUDE", [_] | [_, _]) if file.is_some() => {
+                    self.include(&file_name, entry.line, args, depth)?;
+                }
+                _ => {
+                    return Err(at(
+                        entry.line,
+                        SCloudException::SCLOUD_ZONE_PARSER_SYNTAX_ERROR,
+                    ));
+                }
             }
-            continue;
         }
+        Ok(())
+    }
 
-        let mut parts = line.split_whitespace();
-        let name = match parts.next() {
-            Some(n) => n.to_string(),
-            None => continue,
+    /// Read the file named by `$INCLUDE <file> [origin]`, found at `line`
+    /// of `parent`. The origin and the owner of the parent file are left as
+    /// they were (RFC 1035 section 5.1).
+    fn include(
+        &mut self,
+        parent: &str,
+        line: usize,
+        args: &[String],
+        depth: usize,
+    ) -> Result<(), ZoneParseError> {
+        let at = |error| ZoneParseError {
+            file: parent.to_string(),
+            line,
+            error,
         };
+        if depth >= MAX_INCLUDE_DEPTH {
+            return Err(at(
+                SCloudException::SCLOUD_ZONE_PARSER_FAILED_TO_READ_ZONE_FILE,
+            ));
+        }
+        let path = unescape(&args[0])
+            .and_then(|p| String::from_utf8(p).ok())
+            .ok_or_else(|| at(SCloudException::SCLOUD_ZONE_PARSER_SYNTAX_ERROR))?;
+        let path = match Path::new(parent).parent() {
+            Some(dir) => dir.join(path),
+            None => PathBuf::from(path),
+        };
+        let text = read_zone_file(&path).map_err(at)?;
+
+        let saved_origin = self.origin.clone();
+        let saved_owner = self.last_owner.take();
+        if let Some(origin) = args.get(1) {
+            self.origin = self.name(origin).map_err(at)?;
+        }
+        let result = self.read(&text, Some(&path), depth + 1);
+        self.origin = saved_origin;
+        self.last_owner = saved_owner;
+        result
+    }
 
-        let next = match parts.next() {
-            Some(n) => n,
-            None => continue,
+    /// Expand `$GENERATE <start>-<stop>[/<step>] <lhs> [ttl] [class] <type>
+    /// <rhs...>` (BIND syntax): one record per value of the range, `$` in
+    /// the owner and the RDATA being replaced by the value (see
+    /// `substitute`).
+    fn generate(&mut self, line: usize, args: &[String]) -> Result<(), SCloudException> {
+        let syntax = || SCloudException::SCLOUD_ZONE_PARSER_SYNTAX_ERROR;
+        let (range, step) = match args[0].split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| syntax())?),
+            None => (args[0].as_str(), 1),
         };
+        let (start, stop) = range.split_once('-').ok_or_else(syntax)?;
+        let start = start.parse::<u32>().map_err(|_| syntax())?;
+        let stop = stop.parse::<u32>().map_err(|_| syntax())?;
+        if step == 0 || start > stop || (stop - start) / step >= MAX_GENERATED {
+            return Err(syntax());
+        }
 
-        let (ttl, class, type_str) = if let Ok(parsed_ttl) = next.parse::<u32>() {
-            let class = parts.next().unwrap_or("IN");
-            let type_str = parts.next().unwrap_or_default();
-            (parsed_ttl, class.to_string(), type_str)
-        } else if next.eq_ignore_ascii_case("IN") || next.eq_ignore_ascii_case("CH") {
-            let class = next;
-            let type_str = parts.next().unwrap_or_default();
-            (default_ttl, class.to_string(), type_str)
+        for value in (start..=stop).step_by(step as usize) {
+            let fields = args[1..]
+                .iter()
+                .map(|field| substitute(field, value))
+                .collect::<Option<Vec<String>>>()
+                .ok_or_else(syntax)?;
+            self.record(&Entry {
+                line,
+                blank_owner: false,
+                fields,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Parse `[owner] [ttl] [class] <type> <rdata...>`; TTL and class may
+    /// come in any order and default to those of the previous record.
+    fn record(&mut self, entry: &Entry) -> Result<(), SCloudException> {
+        let syntax = || SCloudException::SCLOUD_ZONE_PARSER_SYNTAX_ERROR;
+        let fields = &entry.fields;
+        let (owner, mut i) = if entry.blank_owner {
+            (self.last_owner.clone().ok_or_else(syntax)?, 0)
         } else {
-            (default_ttl, "IN".to_string(), next)
+            (self.name(&fields[0])?, 1)
         };
 
-        let rclass = match class.to_uppercase().as_str() {
-            "IN" => DNSClass::IN,
-            "CS" => DNSClass::CS,
-            "CH" => DNSClass::CH,
-            "HS" => DNSClass::HS,
-            "NONE" => DNSClass::NONE,
-            "ANY" => DNSClass::ANY,
-            _ => continue,
+        let mut ttl = None;
+        let mut class = None;
+        let rtype = loop {
+            let field = fields.get(i).ok_or_else(syntax)?;
+            i += 1;
+            if field.starts_with(|c: char| c.is_ascii_digit()) && ttl.is_none() {
+                ttl = Some(
+                    parse_ttl(field)
+                        .ok_or(SCloudException::SCLOUD_ZONE_PARSER_FAILED_TO_READ_TTL_FIELD)?,
+                );
+            } else if let (None, Some(c)) = (class, DNSClass::from_name(field)) {
+                class = Some(c);
+            } else if field.starts_with(|c: char| c.is_ascii_digit()) {
+                return Err(syntax());
+            } else {
+                break DNSRecordType::from_name(field)
+                    .filter(|t| *t != DNSRecordType::OPT)
+                    .ok_or(SCloudException::SCLOUD_ZONE_PARSER_UNKNOWN_TYPE)?;
+            }
         };
 
-        let rtype = match type_str.to_uppercase().as_str() {
-            "A" => DNSRecordType::A,
-            "AAAA" => DNSRecordType::AAAA,
-            "NS" => DNSRecordType::NS,
-            "MX" => DNSRecordType::MX,
-            "TXT" => DNSRecordType::TXT,
-            "SOA" => DNSRecordType::SOA,
-            "CNAME" => DNSRecordType::CNAME,
-            "PTR" => DNSRecordType::PTR,
-            "SRV" => DNSRecordType::SRV,
-            "CAA" => DNSRecordType::CAA,
-            "NAPTR" => DNSRecordType::NAPTR,
-            _ => continue,
-        };
+        if rtype == DNSRecordType::SOA && self.zone.soa.is_some() {
+            return Err(SCloudException::SCLOUD_ZONE_MULTIPLE_SOA);
+        }
+        let mut rdata = RData::from_fields(rtype, &fields[i..])?;
+        rdata.map_names(|name| qualify(name, &self.origin));
 
-        let value_parts: Vec<&str> = parts.collect();
+        let ttl = ttl.or(self.default_ttl).unwrap_or(self.last_ttl);
+        let class = class.unwrap_or(self.last_class);
+        self.last_ttl = ttl;
+        self.last_class = class;
+        self.last_owner = Some(owner.clone());
 
-        let value_str = if rtype == DNSRecordType::TXT {
-            value_parts.join(" ")
-        } else {
-            value_parts.join(" ")
+        let record = DNSRecord {
+            name: owner,
+            rtype,
+            rclass: class,
+            ttl,
+            rdata,
         };
+        match record.rtype {
+            DNSRecordType::SOA => self.zone.soa = Some(record),
+            _ => self.zone.records.insert(record),
+        }
+        Ok(())
+    }
 
-        let mut record = DNSRecord {
-            name: name.clone(),
-            rtype: rtype.clone(),
-            rclass: rclass.clone(),
-            ttl,
-            value: value_str,
-            priority: None,
-            weight: None,
-            port: None,
-            flags: None,
-            tag: None,
-            regex: None,
-            replacement: None,
-            order: None,
-            preference: None,
+    /// Absolute form of a name field.
+    fn name(&self, field: &str) -> Result<String, SCloudException> {
+        let name = normalize_name(field)
+            .filter(|n| !n.is_empty())
+            .ok_or(SCloudException::SCLOUD_ZONE_PARSER_SYNTAX_ERROR)?;
+        Ok(qualify(&name, &self.origin))
+    }
+
+    fn finish(mut self) -> Zone {
+        self.zone.name = match &self.zone.soa {
+            Some(soa) => soa.name.clone(),
+            None => self.apex,
         };
+        self.zone.origin = Some(format!("{}.", self.zone.name));
+        self.zone
+    }
+}
 
-        match rtype {
-            DNSRecordType::MX => {
-                if value_parts.len() >= 2 {
-                    if let Ok(prio) = value_parts[0].parse::<u16>() {
-                        record.priority = Some(prio);
-                        record.value = value_parts[1..].join(" ");
+/// Expand `@` and a relative name against `origin`, and drop the trailing
+/// dot of an absolute one. The case is kept, and an escaped trailing dot
+/// belongs to the last label of a relative name.
+fn qualify(name: &str, origin: &str) -> String {
+    let absolute = strip_root(name);
+    if name == "@" {
+        origin.to_string()
+    } else if absolute.len() < name.len() {
+        absolute.to_string()
+    } else if origin.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", name, origin)
+    }
+}
+
+/// Replace the `$` of a `$GENERATE` field by `value`, as BIND does:
+/// - `${offset[,width[,base]]}` adds `offset` to the value and writes it
+///   on at least `width` characters, zero-padded, in base `d` (decimal,
+///   the default), `o` (octal), `x` / `X` (hexadecimal) or `n` / `N`
+///   (reversed nibbles separated by dots, for `ip6.arpa`)
+/// - `$$` and `\$` stand for a `$` itself
+///
+/// Returns `None` for a malformed modifier, an offset overflowing the
+/// value, a width above 255, or a negative value written in another base
+/// than decimal.
+///
+/// # Exemple :
+/// ```
+/// assert_eq!(substitute("host-$", 7).unwrap(), "host-7");
+/// assert_eq!(substitute("${10,3}.$$", 7).unwrap(), "017.$");
+/// assert_eq!(substitute("${0,0,n}", 0x1a).unwrap(), "a.1");
+/// ```
+pub(crate) fn substitute(field: &str, value: u32) -> Option<String> {
+    let mut out = String::with_capacity(field.len());
+    let mut chars = field.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // escapes are decoded with the rest of the field
+            '\\' => {
+                out.push(c);
+                out.extend(chars.next());
+            }
+            '$' if chars.next_if_eq(&'$').is_some() => out.push('$'),
+            '$' if chars.next_if_eq(&'{').is_some() => {
+                let mut modifier = String::new();
+                loop {
+                    match chars.next()? {
+                        '}' => break,
+                        c => modifier.push(c),
                     }
                 }
-            }
-            DNSRecordType::SRV => {
-                if value_parts.len() >= 4 {
-                    record.priority = value_parts[0].parse().ok();
-                    record.weight = value_parts[1].parse().ok();
-                    record.port = value_parts[2].parse().ok();
-                    record.value = value_parts[3..].join(" ");
+                let mut parts = modifier.split(',');
+                let offset: i64 = parts.next()?.trim().parse().ok()?;
+                let width: usize = match parts.next() {
+                    Some(width) => width.trim().parse().ok()?,
+                    None => 0,
+                };
+                if width > MAX_GENERATE_WIDTH {
+                    return None;
                 }
-            }
-            DNSRecordType::CAA => {
-                if value_parts.len() >= 3 {
-                    record.flags = value_parts[0].parse().ok();
-                    record.tag = Some(value_parts[1].to_string());
-                    record.value = value_parts[2..].join(" ");
+                let base = parts.next().map_or("d", str::trim);
+                if parts.next().is_some() {
+                    return None;
                 }
+                let value = i64::from(value).checked_add(offset)?;
+                out.push_str(&format_value(value, width, base)?);
             }
-            DNSRecordType::NAPTR => {
-                if value_parts.len() >= 5 {
-                    record.order = value_parts[0].parse().ok();
-                    record.preference = value_parts[1].parse().ok();
-                    record.flags = Some(value_parts[2].chars().next().unwrap_or_default() as u8);
-                    record.regex = Some(value_parts[3].to_string());
-                    record.replacement = Some(value_parts[4].to_string());
+            '$' => out.push_str(&value.to_string()),
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+fn format_value(value: i64, width: usize, base: &str) -> Option<String> {
+    if base == "d" {
+        return Some(format!("{:0width$}", value));
+    }
+    let value = u64::try_from(value).ok()?;
+    match base {
+        "o" => Some(format!("{:0width$o}", value)),
+        "x" => Some(format!("{:0width$x}", value)),
+        "X" => Some(format!("{:0width$X}", value)),
+        "n" | "N" => {
+            // width counts the dots, as in BIND
+            let mut out = String::new();
+            let mut value = value;
+            let mut width = width;
+            loop {
+                let nibble = char::from_digit((value & 0x0f) as u32, 16)?;
+                out.push(if base == "N" {
+                    nibble.to_ascii_uppercase()
+                } else {
+                    nibble
+                });
+                width = width.saturating_sub(1);
+                value >>= 4;
+                if value == 0 && width == 0 {
+                    break;
                 }
+                out.push('.');
+                width = width.saturating_sub(1);
             }
-            _ => {}
+            Some(out)
         }
+        _ => None,
+    }
+}
 
-        match rtype {
-            DNSRecordType::SOA => zone.soa = Some(record),
-            _ => zone.records.entry(name).or_default().push(record),
+/// TTL in seconds, or with units: `1h30m` (s, m, h, d, w; a trailing
+/// number is in seconds).
+///
+/// # Exemple :
+/// ```
+/// assert_eq!(parse_ttl("3600"), Some(3600));
+/// assert_eq!(parse_ttl("1h30m"), Some(5400));
+/// assert_eq!(parse_ttl("1W2d"), Some(777600));
+/// assert_eq!(parse_ttl("h"), None);
+/// ```
+pub(crate) fn parse_ttl(field: &str) -> Option<u32> {
+    let mut total: u32 = 0;
+    let mut value: Option<u32> = None;
+    for c in field.chars() {
+        if let Some(digit) = c.to_digit(10) {
+            value = Some(value.unwrap_or(0).checked_mul(10)?.checked_add(digit)?);
+            continue;
         }
+        let unit = match c.to_ascii_lowercase() {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            'w' => 604800,
+            _ => return None,
+        };
+        total = total.checked_add(value.take()?.checked_mul(unit)?)?;
+    }
+    match value {
+        Some(seconds) => total.checked_add(seconds),
+        None if field.is_empty() => None,
+        None => Some(total),
     }
-
-    Ok(zone)
 }
@@ -36,6 +36,7 @@ pub enum SCloudException {
     SCLOUD_IMPOSSIBLE_PARSE_QNAME_POS_GREATER_THAN_BUF = 15,
     SCLOUD_IMPOSSIBLE_PARSE_QNAME_POS_AND_LEN_GREATER_THAN_BUF = 16,
     SCLOUD_IMPOSSIBLE_PARSE_QNAME_COMPRESSION_FAILED = 17,
+    SCLOUD_IMPOSSIBLE_PARSE_QNAME_TOO_LONG = 97,
 
     // QTYPE
     SCLOUD_QTYPE_U16_FOR_DNSRECORDTYPE_UNKNOWN = 18,
@@ -65,6 +66,15 @@ pub enum SCloudException {
     SCLOUD_ZONE_PARSER_FILE_EMPTY = 33,
     SCLOUD_ZONE_PARSER_FAILED_TO_READ_ZONE_FILE = 34,
     SCLOUD_ZONE_PARSER_FAILED_TO_READ_TTL_FIELD = 35,
+    SCLOUD_ZONE_INVALID_RECORD_RDATA = 83,
+    SCLOUD_ZONE_MISSING_SOA = 84,
+    SCLOUD_ZONE_PARSER_SYNTAX_ERROR = 98,
+    SCLOUD_ZONE_PARSER_UNKNOWN_TYPE = 99,
+    SCLOUD_ZONE_MULTIPLE_SOA = 100,
+    SCLOUD_ZONE_MISSING_APEX_NS = 101,
+    SCLOUD_ZONE_CNAME_AND_OTHER_DATA = 102,
+    SCLOUD_ZONE_MISSING_GLUE = 103,
+    SCLOUD_ZONE_CNAME_LOOP = 104,
 
     // CONFIG
     SCLOUD_CONFIG_FILE_NOT_FOUND = 36,
@@ -99,6 +109,8 @@ pub enum SCloudException {
     SCLOUD_CONFIG_DUPLICATE_VIEW_NAME = 65,
     SCLOUD_CONFIG_INVALID_DYNUPDATE = 66,
     SCLOUD_CONFIG_DYNUPDATE_UNKNOWN_ZONE = 67,
+    SCLOUD_CONFIG_INVALID_LISTENER_ADDRESS = 88,
+    SCLOUD_CONFIG_INVALID_DOQ = 93,
 
     // LOGGING
     SCLOUD_LOGGING_PATH_CREATION_FAILED = 68,
@@ -117,11 +129,32 @@ pub enum SCloudException {
     SCLOUD_WORKER_TCPA_SOCKET_CREATION_FAILED = 78,
     SCLOUD_WORKER_TCPA_RECV_FAILED = 79,
     SCLOUD_WORKER_TCPA_SOCKET_BIND_FAILED = 80,
+    SCLOUD_WORKER_TCPA_NO_LISTENER = 89,
 
     // LISTENER
     SCLOUD_WORKER_LISTENER_RECV_FAILED = 76,
     SCLOUD_WORKER_LISTENER_NO_SOCKET = 82,
+
     // DECODER
+    SCLOUD_WORKER_DECODER_NOT_A_QUERY = 85,
+
+    // FORWARDER
+    SCLOUD_FORWARDER_NO_UPSTREAM = 86,
+    SCLOUD_FORWARDER_NO_RESPONSE = 87,
+
+    // TLS
+    SCLOUD_TLS_CERT_LOAD_FAILED = 90,
+    SCLOUD_TLS_KEY_LOAD_FAILED = 91,
+    SCLOUD_TLS_CONFIG_FAILED = 92,
+
+    // DOQ
+    SCLOUD_WORKER_DOQ_ENDPOINT_FAILED = 94,
+
+    // RDATA
+    SCLOUD_RDATA_MALFORMED = 95,
+
+    // EDNS
+    SCLOUD_EDNS_MALFORMED = 96,
 }
 
 impl SCloudException {
@@ -363,6 +396,50 @@ impl SCloudException {
             SCloudException::SCLOUD_WORKER_LISTENER_NO_SOCKET => {
                 "LISTENER worker spawned directly — use TCP_ACCEPTOR instead."
             }
+            SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA => "Invalid record data in zone.",
+            SCloudException::SCLOUD_ZONE_MISSING_SOA => "Zone has no SOA record.",
+            SCloudException::SCLOUD_WORKER_DECODER_NOT_A_QUERY => {
+                "Packet is not a query with a single question."
+            }
+            SCloudException::SCLOUD_FORWARDER_NO_UPSTREAM => {
+                "No usable upstream address for this forwarder."
+            }
+            SCloudException::SCLOUD_FORWARDER_NO_RESPONSE => "No upstream server answered in time.",
+            SCloudException::SCLOUD_CONFIG_INVALID_LISTENER_ADDRESS => "Invalid listener address.",
+            SCloudException::SCLOUD_WORKER_TCPA_NO_LISTENER => {
+                "TCP_ACCEPTOR has no listener assigned."
+            }
+            SCloudException::SCLOUD_TLS_CERT_LOAD_FAILED => {
+                "Unable to load the TLS certificate chain."
+            }
+            SCloudException::SCLOUD_TLS_KEY_LOAD_FAILED => "Unable to load the TLS private key.",
+            SCloudException::SCLOUD_TLS_CONFIG_FAILED => "Invalid TLS configuration.",
+            SCloudException::SCLOUD_CONFIG_INVALID_DOQ => "Invalid DoQ configuration.",
+            SCloudException::SCLOUD_WORKER_DOQ_ENDPOINT_FAILED => {
+                "DOQ_ACCEPTOR failed to open its QUIC endpoint."
+            }
+            SCloudException::SCLOUD_RDATA_MALFORMED => "Malformed record data.",
+            SCloudException::SCLOUD_EDNS_MALFORMED => {
+                "Malformed or duplicate OPT record in the additional section."
+            }
+            SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME_TOO_LONG => {
+                "Domain name or label longer than the configured limit."
+            }
+            SCloudException::SCLOUD_ZONE_PARSER_SYNTAX_ERROR => "Syntax error in zone file.",
+            SCloudException::SCLOUD_ZONE_PARSER_UNKNOWN_TYPE => {
+                "Unsupported record type in zone file."
+            }
+            SCloudException::SCLOUD_ZONE_MULTIPLE_SOA => "Zone has more than one SOA record.",
+            SCloudException::SCLOUD_ZONE_MISSING_APEX_NS => "Zone has no NS record at its apex.",
+            SCloudException::SCLOUD_ZONE_CNAME_AND_OTHER_DATA => {
+                "CNAME record next to other data of the same name."
+            }
+            SCloudException::SCLOUD_ZONE_MISSING_GLUE => {
+                "In-zone name server has no address record."
+            }
+            SCloudException::SCLOUD_ZONE_CNAME_LOOP => {
+                "CNAME or DNAME chain loops inside the zone."
+            }
             _ => "Unknown error.",
         }
     }
@@ -472,6 +549,28 @@ impl TryFrom<u16> for SCloudException {
             81 => Ok(SCloudException::SCLOUD_WORKER_SEM_CLOSED),
             82 => Ok(SCloudException::SCLOUD_WORKER_LISTENER_NO_SOCKET),
 
+            83 => Ok(SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA),
+            84 => Ok(SCloudException::SCLOUD_ZONE_MISSING_SOA),
+            85 => Ok(SCloudException::SCLOUD_WORKER_DECODER_NOT_A_QUERY),
+            86 => Ok(SCloudException::SCLOUD_FORWARDER_NO_UPSTREAM),
+            87 => Ok(SCloudException::SCLOUD_FORWARDER_NO_RESPONSE),
+            88 => Ok(SCloudException::SCLOUD_CONFIG_INVALID_LISTENER_ADDRESS),
+            89 => Ok(SCloudException::SCLOUD_WORKER_TCPA_NO_LISTENER),
+            90 => Ok(SCloudException::SCLOUD_TLS_CERT_LOAD_FAILED),
+            91 => Ok(SCloudException::SCLOUD_TLS_KEY_LOAD_FAILED),
+            92 => Ok(SCloudException::SCLOUD_TLS_CONFIG_FAILED),
+            93 => Ok(SCloudException::SCLOUD_CONFIG_INVALID_DOQ),
+            94 => Ok(SCloudException::SCLOUD_WORKER_DOQ_ENDPOINT_FAILED),
+            95 => Ok(SCloudException::SCLOUD_RDATA_MALFORMED),
+            96 => Ok(SCloudException::SCLOUD_EDNS_MALFORMED),
+            97 => Ok(SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME_TOO_LONG),
+            98 => Ok(SCloudException::SCLOUD_ZONE_PARSER_SYNTAX_ERROR),
+            99 => Ok(SCloudException::SCLOUD_ZONE_PARSER_UNKNOWN_TYPE),
+            100 => Ok(SCloudException::SCLOUD_ZONE_MULTIPLE_SOA),
+            101 => Ok(SCloudException::SCLOUD_ZONE_MISSING_APEX_NS),
+            102 => Ok(SCloudException::SCLOUD_ZONE_CNAME_AND_OTHER_DATA),
+            103 => Ok(SCloudException::SCLOUD_ZONE_MISSING_GLUE),
+            104 => Ok(SCloudException::SCLOUD_ZONE_CNAME_LOOP),
             _ => Err(SCloudException::SCLOUD_WORKER_UNKNOWN_TYPE),
         }
     }
@@ -566,6 +665,28 @@ impl TryFrom<SCloudException> for u16 {
             SCloudException::SCLOUD_WORKER_TCPA_SOCKET_BIND_FAILED => Ok(80),
             SCloudException::SCLOUD_WORKER_SEM_CLOSED => Ok(81),
             SCloudException::SCLOUD_WORKER_LISTENER_NO_SOCKET => Ok(82),
+            SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA => Ok(83),
+            SCloudException::SCLOUD_ZONE_MISSING_SOA => Ok(84),
+            SCloudException::SCLOUD_WORKER_DECODER_NOT_A_QUERY => Ok(85),
+            SCloudException::SCLOUD_FORWARDER_NO_UPSTREAM => Ok(86),
+            SCloudException::SCLOUD_FORWARDER_NO_RESPONSE => Ok(87),
+            SCloudException::SCLOUD_CONFIG_INVALID_LISTENER_ADDRESS => Ok(88),
+            SCloudException::SCLOUD_WORKER_TCPA_NO_LISTENER => Ok(89),
+            SCloudException::SCLOUD_TLS_CERT_LOAD_FAILED => Ok(90),
+            SCloudException::SCLOUD_TLS_KEY_LOAD_FAILED => Ok(91),
+            SCloudException::SCLOUD_TLS_CONFIG_FAILED => Ok(92),
+            SCloudException::SCLOUD_CONFIG_INVALID_DOQ => Ok(93),
+            SCloudException::SCLOUD_WORKER_DOQ_ENDPOINT_FAILED => Ok(94),
+            SCloudException::SCLOUD_RDATA_MALFORMED => Ok(95),
+            SCloudException::SCLOUD_EDNS_MALFORMED => Ok(96),
+            SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME_TOO_LONG => Ok(97),
+            SCloudException::SCLOUD_ZONE_PARSER_SYNTAX_ERROR => Ok(98),
+            SCloudException::SCLOUD_ZONE_PARSER_UNKNOWN_TYPE => Ok(99),
+            SCloudException::SCLOUD_ZONE_MULTIPLE_SOA => Ok(100),
+            SCloudException::SCLOUD_ZONE_MISSING_APEX_NS => Ok(101),
+            SCloudException::SCLOUD_ZONE_CNAME_AND_OTHER_DATA => Ok(102),
+            SCloudException::SCLOUD_ZONE_MISSING_GLUE => Ok(103),
+            SCloudException::SCLOUD_ZONE_CNAME_LOOP => Ok(104),
             _ => Err(SCloudException::SCLOUD_QCLASS_DNSCLASS_FOR_U16_UNKNOWN),
         }
     }
@@ -164,12 +164,34 @@ mod tests {
                 77,
                 SCloudException::SCLOUD_QTYPE_DNSRECORDTYPE_FOR_U16_UNKNOWN,
             ),
+            (83, SCloudException::SCLOUD_ZONE_INVALID_RECORD_RDATA),
+            (84, SCloudException::SCLOUD_ZONE_MISSING_SOA),
+            (85, SCloudException::SCLOUD_WORKER_DECODER_NOT_A_QUERY),
+            (86, SCloudException::SCLOUD_FORWARDER_NO_UPSTREAM),
+            (87, SCloudException::SCLOUD_FORWARDER_NO_RESPONSE),
+            (88, SCloudException::SCLOUD_CONFIG_INVALID_LISTENER_ADDRESS),
+            (89, SCloudException::SCLOUD_WORKER_TCPA_NO_LISTENER),
+            (90, SCloudException::SCLOUD_TLS_CERT_LOAD_FAILED),
+            (91, SCloudException::SCLOUD_TLS_KEY_LOAD_FAILED),
+            (92, SCloudException::SCLOUD_TLS_CONFIG_FAILED),
+            (93, SCloudException::SCLOUD_CONFIG_INVALID_DOQ),
+            (94, SCloudException::SCLOUD_WORKER_DOQ_ENDPOINT_FAILED),
+            (95, SCloudException::SCLOUD_RDATA_MALFORMED),
+            (96, SCloudException::SCLOUD_EDNS_MALFORMED),
+            (97, SCloudException::SCLOUD_IMPOSSIBLE_PARSE_QNAME_TOO_LONG),
+            (98, SCloudException::SCLOUD_ZONE_PARSER_SYNTAX_ERROR),
+            (99, SCloudException::SCLOUD_ZONE_PARSER_UNKNOWN_TYPE),
+            (100, SCloudException::SCLOUD_ZONE_MULTIPLE_SOA),
+            (101, SCloudException::SCLOUD_ZONE_MISSING_APEX_NS),
+            (102, SCloudException::SCLOUD_ZONE_CNAME_AND_OTHER_DATA),
+            (103, SCloudException::SCLOUD_ZONE_MISSING_GLUE),
+            (104, SCloudException::SCLOUD_ZONE_CNAME_LOOP),
         ]
     }
 
     #[test]
     fn test_exceptions_to_str() {
-        let ex_msg_array: [&'static str; 83] = [
+        let ex_msg_array: [&'static str; 105] = [
             // HEADER SECTION
             "Buffer length is less than header length.",
             "The header is empty.",
@@ -194,6 +216,7 @@ mod tests {
             "Impossible to parse the `q_name`, pos is greater than buffer length.",
             "Impossible to parse the `q_name`, pos and len are greater than buffer length.",
             "Impossible to parse the `q_name`, compression 0xC0xx failed.",
+            "Domain name or label longer than the configured limit.",
             // QTYPE
             "Unknown `q_type`, failed to find a DNSRecordType for a u16.",
             "Unknown `q_type`, failed to find a u16 for a DNSRecordType.",
@@ -218,6 +241,15 @@ mod tests {
             "Zone file is empty.",
             "`zone_parser()` failed to read the zone file.",
             "`zone_parser()` detect TTL field but failed to read this field.",
+            "Invalid record data in zone.",
+            "Zone has no SOA record.",
+            "Syntax error in zone file.",
+            "Unsupported record type in zone file.",
+            "Zone has more than one SOA record.",
+            "Zone has no NS record at its apex.",
+            "CNAME record next to other data of the same name.",
+            "In-zone name server has no address record.",
+            "CNAME or DNAME chain loops inside the zone.",
             // CONFIG
             "Configuration file not found.",
             "Error while parsing the JSON file.",
@@ -251,6 +283,8 @@ mod tests {
             "Duplicate view name detected.",
             "Invalid dynamic update configuration.",
             "Dynamic update references an unknown zone.",
+            "Invalid listener address.",
+            "Invalid DoQ configuration.",
             // LOGGING
             "Logging path creation failed.",
             "Log file creation/opening failed.",
@@ -266,9 +300,25 @@ mod tests {
             "Impossible to create a TCP_ACCEPTOR worker, socket creation failed.",
             "TCP_ACCEPTOR recv() failed.",
             "Impossible to bind TCP_ACCEPTOR socket, most probable cause: another worker is already using this port.",
+            "TCP_ACCEPTOR has no listener assigned.",
             // LISTENER
             "Listener recv() failed.",
             "LISTENER worker spawned directly — use TCP_ACCEPTOR instead.",
+            // DECODER
+            "Packet is not a query with a single question.",
+            // FORWARDER
+            "No usable upstream address for this forwarder.",
+            "No upstream server answered in time.",
+            // TLS
+            "Unable to load the TLS certificate chain.",
+            "Unable to load the TLS private key.",
+            "Invalid TLS configuration.",
+            // DOQ
+            "DOQ_ACCEPTOR failed to open its QUIC endpoint.",
+            // RDATA
+            "Malformed record data.",
+            // EDNS
+            "Malformed or duplicate OPT record in the additional section.",
         ];
 
         let mut i = 0;
@@ -300,7 +350,7 @@ mod tests {
     #[test]
     fn test_exceptions_iter_count() {
         let count = SCloudException::iter().count();
-        let expected_count = 83;
+        let expected_count = 105;
         assert_eq!(count, expected_count);
     }
 
@@ -328,7 +378,7 @@ mod tests {
 
     #[test]
     fn tryfrom_u16_to_exception_out_of_range_is_err() {
-        for &code in &[83u16, 100, 1000, u16::MAX] {
+        for &code in &[105u16, 200, 1000, u16::MAX] {
             let err = SCloudException::try_from(code)
                 .expect_err(&format!("code {code}: expected Err, got Ok"));
             assert_eq!(
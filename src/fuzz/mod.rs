@@ -0,0 +1,49 @@
+//! Entry points of the fuzz targets in `fuzz/`.
+//!
+//! Each function takes the raw input of libFuzzer and panics only when it
+//! finds a bug: a malformed input must be rejected with a `SCloudException`.
+//! They are also run on the seed corpus by the unit tests, so the properties
+//! hold offline, without `cargo fuzz`.
+
+use crate::dns::packet::DNSPacket;
+use crate::dns::q_name;
+use crate::dns::zones::zone_parser::parse_zone_text;
+
+#[cfg(test)]
+mod tests;
+
+/// `DNSPacket::from_bytes` on any input.
+pub fn packet_from_bytes(data: &[u8]) {
+    let _ = DNSPacket::from_bytes(data);
+}
+
+/// `parse_qname` on any input; the first byte is the offset of the name.
+pub fn parse_qname(data: &[u8]) {
+    let Some((&offset, buf)) = data.split_first() else {
+        return;
+    };
+    if let Ok((_, end)) = q_name::parse_qname(buf, usize::from(offset)) {
+        assert!(end <= buf.len(), "name ends past the buffer");
+    }
+}
+
+/// The zone parser on any UTF-8 input.
+pub fn zone_parser(data: &[u8]) {
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = parse_zone_text(text, None);
+    }
+}
+
+/// A packet that decodes encodes again, to a message decoding to the same
+/// packet.
+pub fn packet_round_trip(data: &[u8]) {
+    let Ok(packet) = DNSPacket::from_bytes(data) else {
+        return;
+    };
+    let bytes = packet
+        .to_bytes()
+        .unwrap_or_else(|e| panic!("decoded packet not encoded: {:?}", e));
+    let decoded = DNSPacket::from_bytes(&bytes)
+        .unwrap_or_else(|e| panic!("encoded packet not decoded: {:?}", e));
+    assert_eq!(decoded, packet);
+}
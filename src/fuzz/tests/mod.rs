@@ -0,0 +1,54 @@
+use crate::fuzz;
+use std::fs;
+
+/// Seed corpus of the fuzz target `target`.
+fn corpus(target: &str) -> Vec<Vec<u8>> {
+    let dir = format!("fuzz/corpus/{}", target);
+    let mut seeds: Vec<Vec<u8>> = fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("{}: {}", dir, e))
+        .map(|entry| fs::read(entry.unwrap().path()).unwrap())
+        .collect();
+    assert!(!seeds.is_empty(), "{} is empty", dir);
+    seeds.sort();
+    seeds
+}
+
+#[test]
+fn test_seed_corpus() {
+    for seed in corpus("packet_from_bytes") {
+        fuzz::packet_from_bytes(&seed);
+    }
+    for seed in corpus("parse_qname") {
+        fuzz::parse_qname(&seed);
+    }
+    for seed in corpus("zone_parser") {
+        fuzz::zone_parser(&seed);
+    }
+}
+
+#[test]
+fn test_packet_round_trip() {
+    for seed in corpus("packet_round_trip") {
+        fuzz::packet_round_trip(&seed);
+
+        // and every single byte change of it
+        for i in 0..seed.len() {
+            for value in 0..=u8::MAX {
+                let mut mutated = seed.clone();
+                mutated[i] = value;
+                fuzz::packet_round_trip(&mutated);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_parse_qname_offsets() {
+    for seed in corpus("parse_qname") {
+        for offset in 0..seed.len() {
+            let mut input = seed.clone();
+            input[0] = offset as u8;
+            fuzz::parse_qname(&input);
+        }
+    }
+}
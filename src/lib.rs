@@ -0,0 +1,15 @@
+//! Library build of the server, for the fuzz targets in `fuzz/` only.
+//!
+//! `cargo fuzz` builds with `--cfg fuzzing`. Any other build of this target
+//! is empty, so the modules are compiled once, by the binary.
+#![cfg(fuzzing)]
+#![allow(dead_code, unused)]
+
+mod config;
+mod dns;
+mod exceptions;
+mod ui;
+mod utils;
+mod workers;
+
+pub mod fuzz;
@@ -9,6 +9,8 @@ use tokio::sync::mpsc;
 mod config;
 mod dns;
 mod exceptions;
+#[cfg(test)]
+mod fuzz;
 mod ui;
 mod utils;
 mod workers;
@@ -17,6 +19,7 @@ mod workers;
 async fn main() -> Result<(), SCloudException> {
     let config = Config::from_file(Path::new("./config/config.json"))?;
     utils::logging::init(config.logging.clone())?;
+    dns::q_name::init_name_limits(&config.tuning);
 
     if config.logging.dyn_ui == false {
         println!(
@@ -46,9 +49,10 @@ async fn main() -> Result<(), SCloudException> {
 
     let gate = Arc::new(StartGate::new(1));
 
-    let worker_specs: [(WorkerType, u16); 11] = [
-        (WorkerType::TCP_ACCEPTOR, config.workers.tcp_acceptor),
+    let worker_specs: [(WorkerType, u16); 12] = [
         (WorkerType::DOH_ACCEPTOR, config.workers.doh_acceptor),
+        (WorkerType::DOQ_ACCEPTOR, config.workers.doq_acceptor),
+        (WorkerType::DECODER, config.workers.decoder),
         (
             WorkerType::QUERY_DISPATCHER,
             config.workers.query_dispatcher,
@@ -72,6 +76,19 @@ async fn main() -> Result<(), SCloudException> {
         }
     }
 
+    // one set of acceptors per listener, `workers.tcp_acceptor` each by default
+    for listener in config.listeners() {
+        let count = listener
+            .workers
+            .unwrap_or(config.workers.tcp_acceptor as usize);
+        let listener = Arc::new(listener);
+        for _ in 0..count {
+            let worker = SCloudWorker::new(WorkerType::TCP_ACCEPTOR)?;
+            worker.set_listener(listener.clone());
+            workers.push(Arc::new(worker));
+        }
+    }
+
     workers::manager::channels_generation::generate_channels(workers.clone()).await;
     workers.sort_by_key(|w| w.get_worker_id());
 
@@ -0,0 +1,41 @@
+use crate::config::AclEntry;
+use ipnet::IpNet;
+use std::net::IpAddr;
+
+/// Whether `ip` is allowed by `acl`.
+///
+/// `acl` is either the name of an entry of `acls`, or a raw list of
+/// networks (CIDRs or single addresses, separated by commas or spaces),
+/// like the `acl` field of a listener. Unparsable networks never match.
+///
+/// # Exemple :
+/// ```
+/// let acls = vec![AclEntry {
+///     name: "internal".to_string(),
+///     networks: vec!["10.0.0.0/8".to_string()],
+/// }];
+///
+/// assert!(acl_allows(&acls, "internal", "10.1.2.3".parse().unwrap()));
+/// assert!(acl_allows(&acls, "0.0.0.0/0", "192.0.2.1".parse().unwrap()));
+/// assert!(!acl_allows(&acls, "internal", "192.0.2.1".parse().unwrap()));
+/// ```
+pub(crate) fn acl_allows(acls: &[AclEntry], acl: &str, ip: IpAddr) -> bool {
+    // IPv4 clients of a dual-stack socket show up as ::ffff:a.b.c.d
+    let ip = ip.to_canonical();
+
+    match acls.iter().find(|entry| entry.name == acl) {
+        Some(entry) => entry.networks.iter().any(|n| network_contains(n, ip)),
+        None => acl
+            .split([',', ' '])
+            .filter(|n| !n.is_empty())
+            .any(|n| network_contains(n, ip)),
+    }
+}
+
+fn network_contains(network: &str, ip: IpAddr) -> bool {
+    let network = network.trim();
+    if let Ok(net) = network.parse::<IpNet>() {
+        return net.contains(&ip);
+    }
+    network.parse::<IpAddr>().is_ok_and(|addr| addr == ip)
+}
@@ -1,4 +1,6 @@
+pub(crate) mod acl;
 pub(crate) mod logging;
 mod tests;
 pub(crate) mod time;
+pub(crate) mod tls;
 pub(crate) mod uuid;
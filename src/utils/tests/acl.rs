@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod tests {
+    use crate::config::AclEntry;
+    use crate::utils::acl::acl_allows;
+
+    fn acls() -> Vec<AclEntry> {
+        vec![
+            AclEntry {
+                name: "localhost".to_string(),
+                networks: vec!["127.0.0.0/8".to_string(), "::1/128".to_string()],
+            },
+            AclEntry {
+                name: "internal".to_string(),
+                networks: vec!["10.0.0.0/8".to_string(), "192.168.0.0/16".to_string()],
+            },
+        ]
+    }
+
+    #[test]
+    fn test_acl_by_name() {
+        assert!(acl_allows(
+            &acls(),
+            "internal",
+            "10.20.30.40".parse().unwrap()
+        ));
+        assert!(acl_allows(
+            &acls(),
+            "internal",
+            "192.168.1.1".parse().unwrap()
+        ));
+        assert!(!acl_allows(
+            &acls(),
+            "internal",
+            "172.16.0.1".parse().unwrap()
+        ));
+        assert!(acl_allows(&acls(), "localhost", "::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_acl_ipv4_mapped_client() {
+        assert!(acl_allows(
+            &acls(),
+            "internal",
+            "::ffff:10.0.0.1".parse().unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_acl_raw_networks() {
+        assert!(acl_allows(
+            &acls(),
+            "0.0.0.0/0",
+            "203.0.113.9".parse().unwrap()
+        ));
+        assert!(acl_allows(
+            &acls(),
+            "198.51.100.7, 2001:db8::/32",
+            "2001:db8::1".parse().unwrap()
+        ));
+        assert!(acl_allows(
+            &acls(),
+            "198.51.100.7, 2001:db8::/32",
+            "198.51.100.7".parse().unwrap()
+        ));
+        assert!(!acl_allows(
+            &acls(),
+            "198.51.100.7",
+            "198.51.100.8".parse().unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_acl_unknown_or_invalid() {
+        assert!(!acl_allows(&acls(), "", "10.0.0.1".parse().unwrap()));
+        assert!(!acl_allows(&acls(), "nope", "10.0.0.1".parse().unwrap()));
+        assert!(!acl_allows(&[], "internal", "10.0.0.1".parse().unwrap()));
+    }
+}
@@ -1,3 +1,5 @@
+mod acl;
 mod logging;
 mod time;
+mod tls;
 mod uuid;
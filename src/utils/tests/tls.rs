@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod tests {
+    use crate::exceptions::SCloudException;
+    use crate::utils::tls::TlsCertStore;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    /// Write a fresh self-signed certificate for `localhost` and its key
+    /// to `dir`, and return their paths.
+    fn write_cert(dir: &Path) -> (PathBuf, PathBuf) {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert_path = dir.join("cert.pem");
+        let key_path = dir.join("key.pem");
+        std::fs::write(&cert_path, cert.cert.pem()).unwrap();
+        std::fs::write(&key_path, cert.key_pair.serialize_pem()).unwrap();
+        (cert_path, key_path)
+    }
+
+    #[test]
+    fn test_load_pem_files() {
+        let dir = TempDir::new().unwrap();
+        let (cert, key) = write_cert(dir.path());
+
+        let store = TlsCertStore::load(&cert, &key).unwrap();
+        assert_eq!(store.current().cert.len(), 1);
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let dir = TempDir::new().unwrap();
+        let (cert, key) = write_cert(dir.path());
+        let missing = dir.path().join("missing.pem");
+
+        assert!(matches!(
+            TlsCertStore::load(&missing, &key),
+            Err(SCloudException::SCLOUD_TLS_CERT_LOAD_FAILED)
+        ));
+        assert!(matches!(
+            TlsCertStore::load(&cert, &missing),
+            Err(SCloudException::SCLOUD_TLS_KEY_LOAD_FAILED)
+        ));
+        // a key file holding a certificate
+        assert!(matches!(
+            TlsCertStore::load(&cert, &cert),
+            Err(SCloudException::SCLOUD_TLS_KEY_LOAD_FAILED)
+        ));
+    }
+
+    #[test]
+    fn test_key_must_match_certificate() {
+        let first = TempDir::new().unwrap();
+        let second = TempDir::new().unwrap();
+        let (cert, _) = write_cert(first.path());
+        let (_, other_key) = write_cert(second.path());
+
+        assert!(matches!(
+            TlsCertStore::load(&cert, &other_key),
+            Err(SCloudException::SCLOUD_TLS_CONFIG_FAILED)
+        ));
+    }
+
+    #[test]
+    fn test_reload_if_changed() {
+        let dir = TempDir::new().unwrap();
+        let (cert, key) = write_cert(dir.path());
+        let store = TlsCertStore::load(&cert, &key).unwrap();
+        let before = store.current().cert[0].clone();
+
+        assert!(!store.reload_if_changed().unwrap());
+
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        write_cert(dir.path());
+        assert!(store.reload_if_changed().unwrap());
+        assert_ne!(store.current().cert[0], before);
+    }
+
+    #[test]
+    fn test_failed_reload_keeps_certificate() {
+        let dir = TempDir::new().unwrap();
+        let (cert, key) = write_cert(dir.path());
+        let store = TlsCertStore::load(&cert, &key).unwrap();
+        let before = store.current().cert[0].clone();
+
+        std::fs::write(&cert, "not a certificate").unwrap();
+        assert!(store.reload().is_err());
+        assert_eq!(store.current().cert[0], before);
+    }
+}
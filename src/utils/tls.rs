@@ -0,0 +1,149 @@
+use crate::exceptions::SCloudException;
+use crate::{log_error, log_info};
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::{CertificateDer, PrivateKeyDer};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::server::{ClientHello, ResolvesServerCert};
+use tokio_rustls::rustls::sign::CertifiedKey;
+use tokio_rustls::rustls::{Error, InconsistentKeys, ServerConfig};
+
+/// Certificate chain and private key of a TLS listener.
+///
+/// The pair is read from PEM files and can be reloaded while the server
+/// runs: handshakes started after a reload use the new certificate, open
+/// connections keep the one they were set up with.
+#[derive(Debug)]
+pub(crate) struct TlsCertStore {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    current: RwLock<Arc<CertifiedKey>>,
+    modified: Mutex<(Option<SystemTime>, Option<SystemTime>)>, // cert, key
+}
+
+impl TlsCertStore {
+    /// Load the PEM certificate chain at `cert_path` and the PEM private
+    /// key at `key_path`.
+    ///
+    /// # Errors
+    /// - `SCLOUD_TLS_CERT_LOAD_FAILED` if the chain is missing or empty
+    /// - `SCLOUD_TLS_KEY_LOAD_FAILED` if the key is missing or unsupported
+    /// - `SCLOUD_TLS_CONFIG_FAILED` if the key does not match the certificate
+    pub(crate) fn load(
+        cert_path: impl AsRef<Path>,
+        key_path: impl AsRef<Path>,
+    ) -> Result<TlsCertStore, SCloudException> {
+        let cert_path = cert_path.as_ref().to_path_buf();
+        let key_path = key_path.as_ref().to_path_buf();
+        let modified = modified_times(&cert_path, &key_path);
+        let key = read_certified_key(&cert_path, &key_path)?;
+
+        Ok(TlsCertStore {
+            cert_path,
+            key_path,
+            current: RwLock::new(Arc::new(key)),
+            modified: Mutex::new(modified),
+        })
+    }
+
+    /// Read the files again and use them for the next handshakes. On error
+    /// the current certificate is kept.
+    pub(crate) fn reload(&self) -> Result<(), SCloudException> {
+        let modified = modified_times(&self.cert_path, &self.key_path);
+        let key = read_certified_key(&self.cert_path, &self.key_path)?;
+
+        *self.current.write().unwrap() = Arc::new(key);
+        *self.modified.lock().unwrap() = modified;
+        Ok(())
+    }
+
+    /// Reload when the certificate or the key file changed on disk.
+    ///
+    /// Returns whether a new certificate is in use.
+    pub(crate) fn reload_if_changed(&self) -> Result<bool, SCloudException> {
+        let modified = modified_times(&self.cert_path, &self.key_path);
+        if *self.modified.lock().unwrap() == modified {
+            return Ok(false);
+        }
+        self.reload()?;
+        Ok(true)
+    }
+
+    /// Check the files every `every` and reload them when they change.
+    /// Never returns.
+    pub(crate) async fn watch(self: Arc<Self>, every: Duration) -> Result<(), SCloudException> {
+        let mut ticker = tokio::time::interval(every);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            match self.reload_if_changed() {
+                Ok(true) => {
+                    log_info!("TLS certificate {} reloaded", self.cert_path.display());
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    log_error!(
+                        "TLS certificate {} not reloaded: {}",
+                        self.cert_path.display(),
+                        e.to_str()
+                    );
+                }
+            }
+        }
+    }
+
+    /// The certificate currently served.
+    pub(crate) fn current(&self) -> Arc<CertifiedKey> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Server configuration taking its certificate from this store, and
+    /// offering the `alpn` protocols in order of preference.
+    pub(crate) fn server_config(
+        self: &Arc<Self>,
+        alpn: &[&[u8]],
+    ) -> Result<Arc<ServerConfig>, SCloudException> {
+        let mut config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|_| SCloudException::SCLOUD_TLS_CONFIG_FAILED)?
+            .with_no_client_auth()
+            .with_cert_resolver(self.clone());
+        config.alpn_protocols = alpn.iter().map(|p| p.to_vec()).collect();
+        Ok(Arc::new(config))
+    }
+}
+
+impl ResolvesServerCert for TlsCertStore {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current())
+    }
+}
+
+fn read_certified_key(cert_path: &Path, key_path: &Path) -> Result<CertifiedKey, SCloudException> {
+    let chain = CertificateDer::pem_file_iter(cert_path)
+        .map_err(|_| SCloudException::SCLOUD_TLS_CERT_LOAD_FAILED)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| SCloudException::SCLOUD_TLS_CERT_LOAD_FAILED)?;
+    if chain.is_empty() {
+        return Err(SCloudException::SCLOUD_TLS_CERT_LOAD_FAILED);
+    }
+
+    let key = PrivateKeyDer::from_pem_file(key_path)
+        .map_err(|_| SCloudException::SCLOUD_TLS_KEY_LOAD_FAILED)?;
+    let signing_key = ring::sign::any_supported_type(&key)
+        .map_err(|_| SCloudException::SCLOUD_TLS_KEY_LOAD_FAILED)?;
+
+    let certified = CertifiedKey::new(chain, signing_key);
+    // keys whose public half is unknown to the provider cannot be checked
+    if let Err(Error::InconsistentKeys(InconsistentKeys::KeyMismatch)) = certified.keys_match() {
+        return Err(SCloudException::SCLOUD_TLS_CONFIG_FAILED);
+    }
+    Ok(certified)
+}
+
+fn modified_times(cert_path: &Path, key_path: &Path) -> (Option<SystemTime>, Option<SystemTime>) {
+    let modified = |p: &Path| std::fs::metadata(p).and_then(|m| m.modified()).ok();
+    (modified(cert_path), modified(key_path))
+}
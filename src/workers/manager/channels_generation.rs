@@ -23,6 +23,7 @@ pub(crate) async fn generate_channels(
             WorkerType::METRICS => "metrics",
             WorkerType::TCP_ACCEPTOR => "tcp-acceptor",
             WorkerType::DOH_ACCEPTOR => "doh-acceptor",
+            WorkerType::DOQ_ACCEPTOR => "doq-acceptor",
             WorkerType::NONE => "none",
         };
         wl.entry(key).or_insert_with(Vec::new).push(Arc::clone(&w));
@@ -31,6 +32,7 @@ pub(crate) async fn generate_channels(
     let default_worker = vec![Arc::new(SCloudWorker::new(WorkerType::NONE)?)];
     let tcp_acceptor = wl.get("tcp-acceptor").unwrap_or(&default_worker);
     let doh_acceptor = wl.get("doh-acceptor").cloned();
+    let doq_acceptor = wl.get("doq-acceptor").cloned();
     let decoder = wl.get("decoder").unwrap_or(&default_worker);
     let query_dispatcher = wl.get("query-dispatcher").unwrap_or(&default_worker);
     let cache_lookup = wl.get("cache-lookup").unwrap_or(&default_worker);
@@ -47,6 +49,9 @@ pub(crate) async fn generate_channels(
         consumers: &[Arc<SCloudWorker>],
         capacity: usize,
     ) {
+        let Some(consumer_type) = consumers.first().map(|c| c.get_worker_type()) else {
+            return;
+        };
         for p in producers {
             let mut txs = Vec::new();
             for c in consumers {
@@ -54,7 +59,7 @@ pub(crate) async fn generate_channels(
                 c.push_dns_rx(rx).await;
                 txs.push(tx);
             }
-            p.push_dns_tx_many(txs).await;
+            p.push_dns_route(consumer_type, txs).await;
         }
     }
 
@@ -62,11 +67,16 @@ pub(crate) async fn generate_channels(
     if let Some(doh) = doh_acceptor.as_deref() {
         wire(doh, decoder, 1024).await;
     }
+    if let Some(doq) = doq_acceptor.as_deref() {
+        wire(doq, decoder, 1024).await;
+    }
     wire(decoder, cache_lookup, 1024).await;
-    wire(cache_lookup, cache_writers, 1024).await; // tx[0] = miss path
-    wire(cache_lookup, query_dispatcher, 1024).await; // tx[1] = hit path
+    wire(decoder, encoders, 1024).await; // malformed queries (FORMERR)
+    wire(cache_lookup, query_dispatcher, 1024).await; // cache miss
+    wire(cache_lookup, encoders, 1024).await; // cache hit
     wire(query_dispatcher, zone_manager, 1024).await;
     wire(query_dispatcher, resolvers, 1024).await;
+    wire(query_dispatcher, encoders, 1024).await; // REFUSED
     wire(zone_manager, cache_writers, 1024).await;
     wire(resolvers, cache_writers, 1024).await;
     wire(cache_writers, encoders, 1024).await;
@@ -1,11 +1,13 @@
+use crate::config::ListenerConfig;
 use crate::exceptions::SCloudException;
 use crate::workers::manager::StartGate;
+use crate::workers::queue::Routes;
 use crate::workers::task::InFlightTask;
 use crate::{log_error, log_info, log_sdebug, log_strace};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
 use tokio::sync::{Mutex, MutexGuard, Semaphore, mpsc};
 
 pub(crate) mod manager;
@@ -25,6 +27,10 @@ pub(crate) struct SCloudWorker {
     // CHANNEL
     pub(crate) dns_tx: Mutex<Vec<mpsc::Sender<InFlightTask>>>,
     pub(crate) dns_rx: Mutex<Vec<mpsc::Receiver<InFlightTask>>>,
+    pub(crate) dns_routes: Mutex<Routes>, // same senders as `dns_tx`, by consumer type
+
+    // LISTENER
+    pub(crate) listener: OnceLock<Arc<ListenerConfig>>, // acceptors only
 
     // RESOURCES/LIMITS
     pub(crate) stack_size_bytes: AtomicUsize,
@@ -46,6 +52,9 @@ pub(crate) struct SCloudWorker {
     pub(crate) jobs_done: AtomicU64,
     pub(crate) jobs_failed: AtomicU64,
     pub(crate) jobs_retried: AtomicU64,
+    pub(crate) cache_entries: AtomicU64, // cache janitor only
+    pub(crate) cache_evictions: AtomicU64,
+    pub(crate) cache_expirations: AtomicU64,
 
     pub(crate) last_job_started_ms: AtomicU64,
     pub(crate) last_job_finished_ms: AtomicU64,
@@ -67,6 +76,8 @@ impl SCloudWorker {
             worker_type: AtomicU8::new(worker_type as u8),
             dns_tx: Mutex::new(Vec::new()),
             dns_rx: Mutex::new(Vec::new()),
+            dns_routes: Mutex::new(Routes::new()),
+            listener: OnceLock::new(),
             stack_size_bytes: AtomicUsize::new(2 * 1024 * 1024),
             buffer_budget_bytes: AtomicUsize::new(4 * 1024 * 1024),
             max_stack_size_bytes: AtomicUsize::new(32 * 1024 * 1024),
@@ -80,6 +91,9 @@ impl SCloudWorker {
             jobs_done: AtomicU64::new(0),
             jobs_failed: AtomicU64::new(0),
             jobs_retried: AtomicU64::new(0),
+            cache_entries: AtomicU64::new(0),
+            cache_evictions: AtomicU64::new(0),
+            cache_expirations: AtomicU64::new(0),
             last_job_started_ms: AtomicU64::new(0),
             last_job_finished_ms: AtomicU64::new(0),
             last_error_code: AtomicU64::new(0),
@@ -105,18 +119,21 @@ impl SCloudWorker {
             }
             WorkerType::DECODER => {
                 self.clone().set_state(WorkerState::IDLE);
-                let (rx, tx) = self.get_dns_rx_tx().await?;
-                types::decoder::run_dns_decoder(self.clone(), rx, tx).await?;
+                let rx = self.get_dns_rx().await?;
+                let routes = self.get_dns_routes().await?;
+                types::decoder::run_dns_decoder(self.clone(), rx, routes).await?;
             }
             WorkerType::QUERY_DISPATCHER => {
                 self.clone().set_state(WorkerState::IDLE);
-                let (rx, tx) = self.get_dns_rx_tx().await?;
-                types::query_dispatcher::run_dns_query_dispatcher(self.clone(), rx, tx).await?;
+                let rx = self.get_dns_rx().await?;
+                let routes = self.get_dns_routes().await?;
+                types::query_dispatcher::run_dns_query_dispatcher(self.clone(), rx, routes).await?;
             }
             WorkerType::CACHE_LOOKUP => {
                 self.clone().set_state(WorkerState::IDLE);
-                let (rx, tx) = self.get_dns_rx_tx().await?;
-                types::cache_lookup::run_dns_cache_lookup(self.clone(), rx, tx).await?;
+                let rx = self.get_dns_rx().await?;
+                let routes = self.get_dns_routes().await?;
+                types::cache_lookup::run_dns_cache_lookup(self.clone(), rx, routes).await?;
             }
             WorkerType::ZONE_MANAGER => {
                 self.clone().set_state(WorkerState::IDLE);
@@ -161,6 +178,11 @@ impl SCloudWorker {
                 let tx = self.get_dns_tx().await?;
                 types::doh_acceptor::run_dns_doh_acceptor(self.clone(), tx).await?;
             }
+            WorkerType::DOQ_ACCEPTOR => {
+                self.clone().set_state(WorkerState::IDLE);
+                let tx = self.get_dns_tx().await?;
+                types::doq_acceptor::run_dns_doq_acceptor(self.clone(), tx).await?;
+            }
             _ => {}
         }
         Ok(())
@@ -176,6 +198,18 @@ impl SCloudWorker {
         WorkerType::try_from(self.worker_type.load(Ordering::Relaxed)).unwrap()
     }
 
+    /// Assign the listener this acceptor serves. Only the first call has
+    /// an effect.
+    #[inline]
+    pub fn set_listener(&self, listener: Arc<ListenerConfig>) {
+        let _ = self.listener.set(listener);
+    }
+
+    #[inline]
+    pub fn get_listener(&self) -> Option<Arc<ListenerConfig>> {
+        self.listener.get().cloned()
+    }
+
     #[inline]
     pub async fn push_dns_rx(&self, rx: mpsc::Receiver<InFlightTask>) {
         self.dns_rx.lock().await.push(rx);
@@ -186,6 +220,31 @@ impl SCloudWorker {
         self.dns_tx.lock().await.extend(txs);
     }
 
+    #[inline]
+    pub async fn push_dns_route(&self, to: WorkerType, txs: Vec<mpsc::Sender<InFlightTask>>) {
+        self.dns_routes
+            .lock()
+            .await
+            .entry(to)
+            .or_default()
+            .extend(txs.iter().cloned());
+        self.push_dns_tx_many(txs).await;
+    }
+
+    /// Take the channels of this worker grouped by consumer type, for the
+    /// stages that pick the next stage per task instead of always
+    /// forwarding to the same one.
+    #[inline]
+    pub async fn get_dns_routes(&self) -> Result<Routes, SCloudException> {
+        let mut guard = self.dns_routes.lock().await;
+        if guard.is_empty() {
+            return Err(SCloudException::SCLOUD_WORKER_TX_NOT_SET);
+        }
+        // the flat list holds the same senders, drop it so channels close
+        self.dns_tx.lock().await.clear();
+        Ok(std::mem::take(&mut *guard))
+    }
+
     #[inline]
     pub async fn get_dns_rx_tx(
         &self,
@@ -282,6 +341,21 @@ impl SCloudWorker {
         self.jobs_retried.load(Ordering::Relaxed)
     }
 
+    #[inline]
+    pub fn get_cache_entries(&self) -> u64 {
+        self.cache_entries.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub fn get_cache_evictions(&self) -> u64 {
+        self.cache_evictions.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub fn get_cache_expirations(&self) -> u64 {
+        self.cache_expirations.load(Ordering::Relaxed)
+    }
+
     #[inline]
     pub fn get_last_job_started_ms(&self) -> u64 {
         self.last_job_started_ms.load(Ordering::Relaxed)
@@ -398,6 +472,23 @@ impl SCloudWorker {
         self.jobs_retried.store(jobs_retried, Ordering::Relaxed);
     }
 
+    #[inline]
+    pub fn set_cache_entries(&self, cache_entries: u64) {
+        self.cache_entries.store(cache_entries, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn set_cache_evictions(&self, cache_evictions: u64) {
+        self.cache_evictions
+            .store(cache_evictions, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn set_cache_expirations(&self, cache_expirations: u64) {
+        self.cache_expirations
+            .store(cache_expirations, Ordering::Relaxed);
+    }
+
     #[inline]
     pub fn set_last_job_started_ms(&self, last_job_started_ms: u64) {
         self.last_job_started_ms
@@ -438,7 +529,7 @@ impl SCloudWorker {
 #[repr(u8)]
 #[allow(unused)]
 #[allow(non_camel_case_types)]
-#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize, Eq, Hash)]
 pub enum WorkerType {
     NONE = 99,
     LISTENER = 0,
@@ -456,6 +547,7 @@ pub enum WorkerType {
     METRICS = 10,
     TCP_ACCEPTOR = 11,
     DOH_ACCEPTOR = 12,
+    DOQ_ACCEPTOR = 13,
 }
 
 impl TryFrom<u8> for WorkerType {
@@ -476,6 +568,7 @@ impl TryFrom<u8> for WorkerType {
             10 => WorkerType::METRICS,
             11 => WorkerType::TCP_ACCEPTOR,
             12 => WorkerType::DOH_ACCEPTOR,
+            13 => WorkerType::DOQ_ACCEPTOR,
             99 => WorkerType::NONE,
             // TODO: return an SCloudException
             _ => return Err(()),
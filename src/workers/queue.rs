@@ -1,3 +1,79 @@
-use crate::workers::task::SCloudWorkerTask;
-use anyhow::Result;
-use futures_util::StreamExt;
+use crate::log_debug;
+use crate::workers::WorkerType;
+use crate::workers::task::InFlightTask;
+use std::collections::HashMap;
+use std::future::poll_fn;
+use std::task::Poll;
+use tokio::sync::mpsc;
+
+/// Outgoing channels of a worker, grouped by the type of the consumer.
+pub(crate) type Routes = HashMap<WorkerType, Vec<mpsc::Sender<InFlightTask>>>;
+
+/// Receive the next task from any of the given channels.
+///
+/// Every receiver is polled, so a worker fed by several producers never
+/// starves the channels that are not first in the list. Returns `None`
+/// once every channel is closed.
+pub(crate) async fn recv_any(rx: &mut [mpsc::Receiver<InFlightTask>]) -> Option<InFlightTask> {
+    if rx.is_empty() {
+        return None;
+    }
+    // random starting point so a busy channel cannot starve the others
+    let start = rand::random::<u32>() as usize;
+    poll_fn(|cx| {
+        let len = rx.len();
+        let mut closed = 0;
+        for i in 0..len {
+            let idx = (start + i) % len;
+            match rx[idx].poll_recv(cx) {
+                Poll::Ready(Some(msg)) => return Poll::Ready(Some(msg)),
+                Poll::Ready(None) => closed += 1,
+                Poll::Pending => {}
+            }
+        }
+        if closed == len {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+/// Hand a task to the first channel with free capacity, falling back to
+/// an awaited send on the first channel when all of them are full.
+///
+/// Returns `false` when the downstream side is closed.
+pub(crate) async fn forward_task(task: InFlightTask, tx: &[mpsc::Sender<InFlightTask>]) -> bool {
+    let mut current = Some(task);
+    for tx_channel in tx.iter() {
+        match tx_channel.try_send(current.take().unwrap()) {
+            Ok(_) => return true,
+            Err(mpsc::error::TrySendError::Full(returned)) => {
+                current = Some(returned);
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => return false,
+        }
+    }
+    if let Some(unsent) = current {
+        if let Some(first) = tx.first() {
+            return first.send(unsent).await.is_ok();
+        }
+        return false;
+    }
+    true
+}
+
+/// Hand a task to a worker of type `to` (see `forward_task`).
+///
+/// A task with no route to `to` is dropped and logged. Returns `false` when
+/// the downstream side is closed.
+pub(crate) async fn route_task(task: InFlightTask, routes: &Routes, to: WorkerType) -> bool {
+    match routes.get(&to) {
+        Some(tx) => forward_task(task, tx).await,
+        None => {
+            log_debug!("no route to {:?}, task {} dropped", to, task.task.task_id);
+            true
+        }
+    }
+}
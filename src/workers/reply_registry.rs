@@ -5,6 +5,9 @@ use tokio::sync::oneshot;
 use uuid::Uuid;
 
 pub const REPLY_TAG_DOH: &str = "doh";
+pub const REPLY_TAG_UDP: &str = "udp";
+pub const REPLY_TAG_TCP: &str = "tcp";
+pub const REPLY_TAG_DOQ: &str = "doq";
 
 static REGISTRY: Lazy<DashMap<Uuid, oneshot::Sender<Bytes>>> = Lazy::new(DashMap::new);
 
@@ -1,3 +1,5 @@
+use crate::config::{ForwarderConfig, ListenerConfig};
+use crate::dns::packet::DNSPacket;
 use crate::exceptions::SCloudException;
 use crate::workers::WorkerType;
 use bytes::Bytes;
@@ -5,6 +7,7 @@ use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::SystemTime;
+use tokio::net::UdpSocket;
 use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use uuid::Uuid;
 
@@ -23,6 +26,16 @@ pub(crate) struct SCloudWorkerTask {
     pub priority: u8,                   // if supported by the broker
     pub reply_to: Option<String>,       // response endpoint
     pub correlation_id: Option<String>, // id request/response
+    #[serde(skip)]
+    pub udp_socket: Option<Arc<UdpSocket>>, // socket the query came in on
+    #[serde(skip)]
+    pub query: Option<DNSPacket>, // `payload` decoded once by the decoder
+    #[serde(skip)]
+    pub response: Option<DNSPacket>, // answer to encode into `payload`
+    #[serde(skip)]
+    pub forwarder: Option<Arc<ForwarderConfig>>, // upstreams of a forward zone
+    #[serde(skip)]
+    pub listener: Option<Arc<ListenerConfig>>, // listener the query came in on
 }
 
 pub struct InFlightTask {
@@ -55,6 +68,11 @@ impl InFlightTask {
                 priority: 0,
                 reply_to: None,
                 correlation_id: None,
+                udp_socket: None,
+                query: None,
+                response: None,
+                forwarder: None,
+                listener: None,
             },
             _permit: permit,
         })
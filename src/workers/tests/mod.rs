@@ -351,6 +351,35 @@ mod tests {
         assert!(w.dns_tx.lock().await.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_get_dns_routes() {
+        let w = Arc::new(workers::SCloudWorker::new(workers::WorkerType::DECODER).unwrap());
+
+        let (tx0, _rx0) = mpsc::channel::<InFlightTask>(8);
+        let (tx1, _rx1) = mpsc::channel::<InFlightTask>(8);
+        w.push_dns_route(workers::WorkerType::CACHE_LOOKUP, vec![tx0])
+            .await;
+        w.push_dns_route(workers::WorkerType::ENCODER, vec![tx1])
+            .await;
+        assert_eq!(w.dns_tx.lock().await.len(), 2);
+
+        let routes = w.get_dns_routes().await.expect("should return routes");
+
+        assert_eq!(routes[&workers::WorkerType::CACHE_LOOKUP].len(), 1);
+        assert_eq!(routes[&workers::WorkerType::ENCODER].len(), 1);
+        assert!(w.dns_routes.lock().await.is_empty());
+        assert!(w.dns_tx.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_dns_routes_not_set() {
+        let w = Arc::new(workers::SCloudWorker::new(workers::WorkerType::DECODER).unwrap());
+
+        let err = w.get_dns_routes().await.unwrap_err();
+
+        assert_eq!(err, exceptions::SCloudException::SCLOUD_WORKER_TX_NOT_SET);
+    }
+
     #[tokio::test]
     async fn test_get_dns_rx() {
         let w = Arc::new(workers::SCloudWorker::new(workers::WorkerType::TCP_ACCEPTOR).unwrap());
@@ -663,6 +692,27 @@ mod tests {
         assert_eq!(w.get_jobs_retried(), 367);
     }
 
+    #[test]
+    pub fn test_set_cache_entries() {
+        let w = Arc::new(workers::SCloudWorker::new(workers::WorkerType::CACHE_JANITOR).unwrap());
+        w.set_cache_entries(1500);
+        assert_eq!(w.get_cache_entries(), 1500);
+    }
+
+    #[test]
+    pub fn test_set_cache_evictions() {
+        let w = Arc::new(workers::SCloudWorker::new(workers::WorkerType::CACHE_JANITOR).unwrap());
+        w.set_cache_evictions(42);
+        assert_eq!(w.get_cache_evictions(), 42);
+    }
+
+    #[test]
+    pub fn test_set_cache_expirations() {
+        let w = Arc::new(workers::SCloudWorker::new(workers::WorkerType::CACHE_JANITOR).unwrap());
+        w.set_cache_expirations(77);
+        assert_eq!(w.get_cache_expirations(), 77);
+    }
+
     #[test]
     pub fn test_set_last_job_started_ms() {
         let w = Arc::new(workers::SCloudWorker::new(workers::WorkerType::TCP_ACCEPTOR).unwrap());
@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::config::CacheConfig;
+    use crate::dns::cache::DnsCache;
+    use crate::dns::packet::DNSPacket;
+    use crate::dns::packet::answer::AnswerSection;
+    use crate::dns::packet::header::Header;
+    use crate::dns::packet::question::QuestionSection;
+    use crate::dns::q_class::DNSClass;
+    use crate::dns::q_type::DNSRecordType;
+    use crate::dns::records::rdata::RData;
+    use crate::workers::types::cache_janitor::{run_dns_cache_janitor_with_cache, sweep_cache};
+    use crate::workers::{SCloudWorker, WorkerType};
+
+    fn cache(max_entries: usize) -> Arc<DnsCache> {
+        Arc::new(DnsCache::new(&CacheConfig {
+            max_entries,
+            ..Default::default()
+        }))
+    }
+
+    fn answer(name: &str, ttl: u32) -> DNSPacket {
+        let query = DNSPacket::new_query(&[QuestionSection {
+            q_name: name.to_string(),
+            q_type: DNSRecordType::A,
+            q_class: DNSClass::IN,
+        }]);
+        let mut response = DNSPacket::new_response(&query, Header::RCODE_NOERROR);
+        response.answers.push(AnswerSection {
+            q_name: name.to_string(),
+            r_type: DNSRecordType::A,
+            r_class: DNSClass::IN,
+            ttl,
+            rdata: RData::A(Ipv4Addr::new(192, 0, 2, 1)),
+        });
+        response.sync_counts();
+        response
+    }
+
+    #[test]
+    fn janitor_removes_expired_entries() {
+        let worker = SCloudWorker::new(WorkerType::CACHE_JANITOR).unwrap();
+        let cache = cache(100);
+        cache.insert(&answer("short.example", 1));
+        cache.insert(&answer("long.example", 300));
+
+        std::thread::sleep(Duration::from_millis(1100));
+        sweep_cache(&worker, &cache);
+
+        assert_eq!(cache.len(), 1);
+        assert_eq!(worker.get_cache_entries(), 1);
+        assert_eq!(worker.get_cache_expirations(), 1);
+        assert_eq!(worker.get_cache_evictions(), 0);
+        assert_eq!(worker.get_jobs_done(), 1);
+    }
+
+    #[test]
+    fn janitor_evicts_under_pressure() {
+        let worker = SCloudWorker::new(WorkerType::CACHE_JANITOR).unwrap();
+        let cache = cache(20);
+        for i in 0..20 {
+            cache.insert(&answer(&format!("{i}.example"), 300));
+        }
+
+        sweep_cache(&worker, &cache);
+
+        // 95% of 20 reached, back down to 90%
+        assert_eq!(cache.len(), 18);
+        assert_eq!(worker.get_cache_entries(), 18);
+        assert_eq!(worker.get_cache_evictions(), 2);
+    }
+
+    #[test]
+    fn janitor_leaves_cache_below_watermark() {
+        let worker = SCloudWorker::new(WorkerType::CACHE_JANITOR).unwrap();
+        let cache = cache(20);
+        for i in 0..10 {
+            cache.insert(&answer(&format!("{i}.example"), 300));
+        }
+
+        sweep_cache(&worker, &cache);
+
+        assert_eq!(cache.len(), 10);
+        assert_eq!(worker.get_cache_evictions(), 0);
+    }
+
+    #[tokio::test]
+    async fn janitor_stops_on_shutdown() {
+        let worker = Arc::new(SCloudWorker::new(WorkerType::CACHE_JANITOR).unwrap());
+        let handle = tokio::spawn(run_dns_cache_janitor_with_cache(
+            worker.clone(),
+            cache(20),
+            Duration::from_millis(10),
+        ));
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        worker.set_shutdown_requested(true);
+
+        tokio::time::timeout(Duration::from_secs(1), handle)
+            .await
+            .expect("janitor did not stop")
+            .unwrap()
+            .unwrap();
+        assert!(worker.get_jobs_done() > 0);
+    }
+}
@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use tokio::sync::{Semaphore, mpsc};
+
+    use crate::config::{CacheConfig, Config};
+    use crate::dns::cache::DnsCache;
+    use crate::dns::packet::DNSPacket;
+    use crate::dns::packet::answer::AnswerSection;
+    use crate::dns::packet::header::Header;
+    use crate::dns::q_class::DNSClass;
+    use crate::dns::q_type::DNSRecordType;
+    use crate::dns::records::rdata::RData;
+    use crate::dns::tests::fixtures::query;
+    use crate::workers::queue::Routes;
+    use crate::workers::task::InFlightTask;
+    use crate::workers::types::query_dispatcher::QueryRouter;
+    use crate::workers::{self, SCloudWorker, WorkerType};
+
+    fn cached(cache: &DnsCache, name: &str) -> DNSPacket {
+        let mut cached = DNSPacket::new_response(&query(name), Header::RCODE_NOERROR);
+        cached.answers.push(AnswerSection {
+            q_name: name.to_string(),
+            r_type: DNSRecordType::A,
+            r_class: DNSClass::IN,
+            ttl: 300,
+            rdata: RData::A(Ipv4Addr::new(192, 0, 2, 1)),
+        });
+        cached.sync_counts();
+        cache.insert(&cached);
+        cached
+    }
+
+    /// Run the cache lookup on one query of `client` and return what
+    /// reached the dispatcher and the encoder.
+    async fn lookup(
+        cache: Arc<DnsCache>,
+        query: DNSPacket,
+        client: &str,
+    ) -> (Option<InFlightTask>, Option<InFlightTask>) {
+        let cfg = Config::from_file(Path::new("./config/config.json")).unwrap();
+        let router = Arc::new(QueryRouter::from_config(&cfg));
+        let worker = Arc::new(SCloudWorker::new(WorkerType::CACHE_LOOKUP).unwrap());
+        let (in_tx, in_rx) = mpsc::channel::<InFlightTask>(1);
+        let (dispatcher_tx, mut dispatcher_rx) = mpsc::channel::<InFlightTask>(1);
+        let (encoder_tx, mut encoder_rx) = mpsc::channel::<InFlightTask>(1);
+        let mut routes = Routes::new();
+        routes.insert(WorkerType::QUERY_DISPATCHER, vec![dispatcher_tx]);
+        routes.insert(WorkerType::ENCODER, vec![encoder_tx]);
+
+        let mut task = InFlightTask::new(
+            &query.to_bytes().unwrap(),
+            client.parse::<SocketAddr>().unwrap(),
+            WorkerType::LISTENER,
+            Arc::new(Semaphore::new(1)),
+        )
+        .await
+        .unwrap();
+        task.task.query = Some(query);
+        in_tx.send(task).await.unwrap();
+        drop(in_tx);
+
+        workers::types::cache_lookup::run_dns_cache_lookup_with_cache(
+            worker,
+            cache,
+            router,
+            vec![in_rx],
+            routes,
+        )
+        .await
+        .unwrap();
+
+        (dispatcher_rx.recv().await, encoder_rx.recv().await)
+    }
+
+    #[tokio::test]
+    async fn cache_lookup_sends_miss_to_dispatcher() {
+        let cache = Arc::new(DnsCache::new(&CacheConfig::default()));
+
+        let (dispatcher, encoder) = lookup(cache, query("example.com"), "10.1.2.3:5300").await;
+
+        let task = dispatcher.expect("miss not sent to the dispatcher");
+        assert!(task.task.response.is_none());
+        assert!(encoder.is_none());
+    }
+
+    #[tokio::test]
+    async fn cache_lookup_sends_hit_to_encoder() {
+        let cache = Arc::new(DnsCache::new(&CacheConfig::default()));
+        let cached = cached(&cache, "example.com");
+
+        let q = query("example.com");
+        let (dispatcher, encoder) = lookup(cache, q.clone(), "10.1.2.3:5300").await;
+
+        assert!(dispatcher.is_none());
+        let task = encoder.expect("hit not sent to the encoder");
+        let response = task.task.response.expect("no cached response");
+        assert_eq!(response.header.id, q.header.id);
+        assert_eq!(response.answers, cached.answers);
+    }
+
+    #[tokio::test]
+    async fn cache_lookup_does_not_serve_refused_client() {
+        let cache = Arc::new(DnsCache::new(&CacheConfig::default()));
+        cached(&cache, "github.com");
+
+        // outside of the recursion ACL: the dispatcher answers REFUSED
+        let (dispatcher, encoder) = lookup(cache, query("github.com"), "203.0.113.1:5300").await;
+
+        assert!(encoder.is_none());
+        let task = dispatcher.expect("refused client not sent to the dispatcher");
+        assert!(task.task.response.is_none());
+    }
+}
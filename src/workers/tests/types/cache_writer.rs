@@ -0,0 +1,123 @@
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::sync::Arc;
+
+    use tokio::sync::{Semaphore, mpsc};
+
+    use crate::config::CacheConfig;
+    use crate::dns::cache::DnsCache;
+    use crate::dns::packet::DNSPacket;
+    use crate::dns::packet::answer::AnswerSection;
+    use crate::dns::packet::authority::AuthoritySection;
+    use crate::dns::packet::header::Header;
+    use crate::dns::q_class::DNSClass;
+    use crate::dns::q_type::DNSRecordType;
+    use crate::dns::records::rdata::RData;
+    use crate::dns::tests::fixtures::query;
+    use crate::workers::task::InFlightTask;
+    use crate::workers::{self, SCloudWorker, WorkerType};
+
+    fn answer(query: &DNSPacket) -> DNSPacket {
+        let mut response = DNSPacket::new_response(query, Header::RCODE_NOERROR);
+        response.answers.push(AnswerSection {
+            q_name: query.questions[0].q_name.clone(),
+            r_type: DNSRecordType::A,
+            r_class: DNSClass::IN,
+            ttl: 300,
+            rdata: RData::A(Ipv4Addr::new(192, 0, 2, 1)),
+        });
+        response.sync_counts();
+        response
+    }
+
+    /// Run the cache writer on one task and return it once forwarded.
+    async fn write(
+        cache: Arc<DnsCache>,
+        query: DNSPacket,
+        payload: &[u8],
+        response: Option<DNSPacket>,
+    ) -> (Arc<SCloudWorker>, InFlightTask) {
+        let worker = Arc::new(SCloudWorker::new(WorkerType::CACHE_WRITER).unwrap());
+        let (in_tx, in_rx) = mpsc::channel::<InFlightTask>(1);
+        let (out_tx, mut out_rx) = mpsc::channel::<InFlightTask>(1);
+
+        let mut task = InFlightTask::new(
+            payload,
+            "127.0.0.1:5300".parse().unwrap(),
+            WorkerType::LISTENER,
+            Arc::new(Semaphore::new(1)),
+        )
+        .await
+        .unwrap();
+        task.task.query = Some(query);
+        task.task.response = response;
+        in_tx.send(task).await.unwrap();
+        drop(in_tx);
+
+        workers::types::cache_writer::run_dns_cache_writer_with_cache(
+            worker.clone(),
+            cache,
+            vec![in_rx],
+            vec![out_tx],
+        )
+        .await
+        .unwrap();
+
+        (worker, out_rx.recv().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn cache_writer_stores_zone_response() {
+        let cache = Arc::new(DnsCache::new(&CacheConfig::default()));
+        let q = query("example.com");
+
+        let (_, out) = write(cache.clone(), q.clone(), &[], Some(answer(&q))).await;
+
+        assert!(out.task.response.is_some());
+        assert!(cache.get(&q).is_some());
+    }
+
+    #[tokio::test]
+    async fn cache_writer_ignores_raw_payload() {
+        let cache = Arc::new(DnsCache::new(&CacheConfig::default()));
+        let q = query("example.com");
+        let reply = answer(&q).to_bytes().unwrap();
+
+        // only decoded responses are stored, never the bytes of the task
+        let (worker, out) = write(cache.clone(), q, &reply, None).await;
+
+        assert_eq!(out.task.payload.as_ref(), reply.as_slice());
+        assert_eq!(worker.get_jobs_done(), 0);
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn cache_writer_stores_upstream_nxdomain() {
+        let cache = Arc::new(DnsCache::new(&CacheConfig::default()));
+        let q = query("missing.example.com");
+        let mut reply = DNSPacket::new_response(&q, Header::RCODE_NXDOMAIN);
+        reply.authorities.push(AuthoritySection {
+            q_name: "example.com".to_string(),
+            q_type: DNSRecordType::SOA,
+            q_class: DNSClass::IN,
+            ttl: 3600,
+            rdata: RData::SOA {
+                mname: "ns.example.com".to_string(),
+                rname: "admin.example.com".to_string(),
+                serial: 1,
+                refresh: 7200,
+                retry: 3600,
+                expire: 1_209_600,
+                minimum: 60,
+            },
+        });
+        reply.sync_counts();
+
+        write(cache.clone(), q.clone(), &[], Some(reply)).await;
+
+        let hit = cache.get(&q).expect("NXDOMAIN not cached");
+        assert_eq!(hit.header.rcode, Header::RCODE_NXDOMAIN);
+        assert_eq!(hit.authorities[0].ttl, 60);
+    }
+}
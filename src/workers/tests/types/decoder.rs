@@ -0,0 +1,103 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::{Semaphore, mpsc};
+
+    use crate::dns::packet::header::Header;
+    use crate::workers::queue::Routes;
+    use crate::workers::task::InFlightTask;
+    use crate::workers::{self, SCloudWorker, WorkerType};
+
+    const QUERY: [u8; 29] = [
+        0x12, 0x34, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, b'e', b'x',
+        b'a', b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm', 0x00, 0x00, 0x01, 0x00, 0x01,
+    ];
+
+    /// Run the decoder on one payload and return what reached the cache
+    /// lookup and the encoder.
+    async fn decode(
+        payload: &[u8],
+    ) -> (
+        Arc<SCloudWorker>,
+        Option<InFlightTask>,
+        Option<InFlightTask>,
+    ) {
+        let worker = Arc::new(SCloudWorker::new(WorkerType::DECODER).unwrap());
+        let (in_tx, in_rx) = mpsc::channel::<InFlightTask>(1);
+        let (lookup_tx, mut lookup_rx) = mpsc::channel::<InFlightTask>(1);
+        let (encoder_tx, mut encoder_rx) = mpsc::channel::<InFlightTask>(1);
+        let mut routes = Routes::new();
+        routes.insert(WorkerType::CACHE_LOOKUP, vec![lookup_tx]);
+        routes.insert(WorkerType::ENCODER, vec![encoder_tx]);
+
+        let task = InFlightTask::new(
+            payload,
+            "127.0.0.1:5300".parse().unwrap(),
+            WorkerType::LISTENER,
+            Arc::new(Semaphore::new(1)),
+        )
+        .await
+        .unwrap();
+        in_tx.send(task).await.unwrap();
+        drop(in_tx);
+
+        workers::types::decoder::run_dns_decoder(worker.clone(), vec![in_rx], routes)
+            .await
+            .unwrap();
+
+        (worker, lookup_rx.recv().await, encoder_rx.recv().await)
+    }
+
+    #[tokio::test]
+    async fn decoder_attaches_query() {
+        let (worker, lookup, encoder) = decode(&QUERY).await;
+
+        let task = lookup.expect("query not forwarded to the cache lookup");
+        let query = task.task.query.expect("no decoded query");
+        assert_eq!(query.header.id, 0x1234);
+        assert_eq!(query.questions[0].q_name, "example.com");
+        assert!(task.task.response.is_none());
+        assert!(encoder.is_none());
+        assert_eq!(worker.get_jobs_done(), 1);
+    }
+
+    #[tokio::test]
+    async fn decoder_answers_formerr_to_truncated_question() {
+        let (worker, lookup, encoder) = decode(&QUERY[..20]).await;
+
+        assert!(lookup.is_none());
+        let task = encoder.expect("FORMERR not sent to the encoder");
+        let response = task.task.response.expect("no response");
+        assert_eq!(response.header.id, 0x1234);
+        assert!(response.header.qr);
+        assert_eq!(response.header.rcode, Header::RCODE_FORMERR);
+        assert!(response.questions.is_empty());
+        assert_eq!(worker.get_jobs_failed(), 1);
+    }
+
+    #[tokio::test]
+    async fn decoder_answers_formerr_to_several_questions() {
+        let mut payload = QUERY.to_vec();
+        payload[5] = 2; // QDCOUNT = 2, same question twice
+        payload.extend_from_slice(&QUERY[12..]);
+
+        let (_, lookup, encoder) = decode(&payload).await;
+
+        assert!(lookup.is_none());
+        let response = encoder.unwrap().task.response.unwrap();
+        assert_eq!(response.header.rcode, Header::RCODE_FORMERR);
+    }
+
+    #[tokio::test]
+    async fn decoder_drops_garbage_and_responses() {
+        let (worker, lookup, encoder) = decode(b"abc").await;
+        assert!(lookup.is_none() && encoder.is_none());
+        assert_eq!(worker.get_jobs_failed(), 1);
+
+        let mut response = QUERY.to_vec();
+        response[2] |= 0x80; // QR = 1
+        let (_, lookup, encoder) = decode(&response).await;
+        assert!(lookup.is_none() && encoder.is_none());
+    }
+}
@@ -0,0 +1,275 @@
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+
+    use base64::Engine;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use bytes::Bytes;
+    use http_body_util::{BodyExt, Empty, Full};
+    use hyper::{Request, StatusCode, Version};
+    use hyper_util::rt::{TokioExecutor, TokioIo};
+    use rustls_pki_types::{CertificateDer, ServerName};
+    use tempfile::TempDir;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::sync::mpsc;
+    use tokio::time::{Duration, timeout};
+    use tokio_rustls::rustls::crypto::ring;
+    use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+    use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+    use crate::config::DohConfig;
+    use crate::dns::packet::DNSPacket;
+    use crate::dns::packet::answer::AnswerSection;
+    use crate::dns::packet::header::Header;
+    use crate::dns::q_class::DNSClass;
+    use crate::dns::q_type::DNSRecordType;
+    use crate::dns::records::rdata::RData;
+    use crate::utils::tls::TlsCertStore;
+    use crate::workers::reply_registry;
+    use crate::workers::task::InFlightTask;
+    use crate::workers::types::doh_acceptor::run_dns_doh_acceptor_with_listener;
+    use crate::workers::{SCloudWorker, WorkerType};
+
+    fn query(id: u16) -> Vec<u8> {
+        let mut q = id.to_be_bytes().to_vec();
+        q.extend_from_slice(&[
+            0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, b'e', b'x', b'a',
+            b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm', 0x00, 0x00, 0x01, 0x00, 0x01,
+        ]);
+        q
+    }
+
+    /// Answer a task the way the sender does: through the reply registry.
+    fn reply(task: &InFlightTask) {
+        let mut answer = task.task.payload.to_vec();
+        answer[2] |= 0x80;
+        reply_registry::take(&task.task.task_id)
+            .expect("no reply channel registered")
+            .send(answer.into())
+            .unwrap();
+    }
+
+    /// Start a DoH acceptor whose tasks land in the returned channel.
+    async fn start(
+        doh: DohConfig,
+        tls: Option<TlsAcceptor>,
+    ) -> (SocketAddr, mpsc::Receiver<InFlightTask>) {
+        let worker = Arc::new(SCloudWorker::new(WorkerType::DOH_ACCEPTOR).unwrap());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = mpsc::channel::<InFlightTask>(16);
+        tokio::spawn(async move {
+            run_dns_doh_acceptor_with_listener(worker, listener, &doh, tls, vec![tx]).await
+        });
+        (addr, rx)
+    }
+
+    /// Answer every task of `rx` right away.
+    fn answer_all(mut rx: mpsc::Receiver<InFlightTask>) {
+        tokio::spawn(async move {
+            while let Some(task) = rx.recv().await {
+                reply(&task);
+            }
+        });
+    }
+
+    /// A TLS acceptor for a fresh self-signed `localhost` certificate, and
+    /// that certificate for the client to trust.
+    fn tls_acceptor(dir: &std::path::Path) -> (TlsAcceptor, CertificateDer<'static>) {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        std::fs::write(dir.join("cert.pem"), cert.cert.pem()).unwrap();
+        std::fs::write(dir.join("key.pem"), cert.key_pair.serialize_pem()).unwrap();
+        let certs =
+            Arc::new(TlsCertStore::load(dir.join("cert.pem"), dir.join("key.pem")).unwrap());
+        let acceptor = TlsAcceptor::from(certs.server_config(&[b"h2", b"http/1.1"]).unwrap());
+        (acceptor, cert.cert.der().clone())
+    }
+
+    async fn connect_tls(
+        addr: SocketAddr,
+        trusted: CertificateDer<'static>,
+        alpn: &[u8],
+    ) -> tokio_rustls::client::TlsStream<TcpStream> {
+        let mut roots = RootCertStore::empty();
+        roots.add(trusted).unwrap();
+        let mut config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        config.alpn_protocols = vec![alpn.to_vec()];
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await
+            .unwrap()
+    }
+
+    fn get(id: u16) -> Request<Empty<Bytes>> {
+        let dns = URL_SAFE_NO_PAD.encode(query(id));
+        Request::get(format!("https://localhost/dns-query?dns={}", dns))
+            .body(Empty::new())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn doh_https_negotiates_h2() {
+        let dir = TempDir::new().unwrap();
+        let (acceptor, trusted) = tls_acceptor(dir.path());
+        let (addr, rx) = start(DohConfig::default(), Some(acceptor)).await;
+        answer_all(rx);
+
+        let stream = connect_tls(addr, trusted, b"h2").await;
+        assert_eq!(stream.get_ref().1.alpn_protocol(), Some(&b"h2"[..]));
+        let (mut sender, conn) =
+            hyper::client::conn::http2::handshake(TokioExecutor::new(), TokioIo::new(stream))
+                .await
+                .unwrap();
+        tokio::spawn(conn);
+
+        let resp = sender.send_request(get(0x0443)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.version(), Version::HTTP_2);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..2], &[0x04, 0x43]);
+        assert_eq!(body[2] & 0x80, 0x80);
+    }
+
+    #[tokio::test]
+    async fn doh_https_serves_http1() {
+        let dir = TempDir::new().unwrap();
+        let (acceptor, trusted) = tls_acceptor(dir.path());
+        let (addr, rx) = start(DohConfig::default(), Some(acceptor)).await;
+        answer_all(rx);
+
+        let stream = connect_tls(addr, trusted, b"http/1.1").await;
+        assert_eq!(stream.get_ref().1.alpn_protocol(), Some(&b"http/1.1"[..]));
+        let (mut sender, conn) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+            .await
+            .unwrap();
+        tokio::spawn(conn);
+
+        let req = Request::post("/dns-query")
+            .header("Host", "localhost")
+            .header("Content-Type", "application/dns-message")
+            .body(Full::new(Bytes::from(query(0x0011))))
+            .unwrap();
+        let resp = sender.send_request(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.version(), Version::HTTP_11);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..2], &[0x00, 0x11]);
+    }
+
+    #[tokio::test]
+    async fn doh_json_resolve() {
+        let (addr, mut rx) = start(DohConfig::default(), None).await;
+        tokio::spawn(async move {
+            while let Some(task) = rx.recv().await {
+                let query = DNSPacket::from_bytes(&task.task.payload).unwrap();
+                let mut answer = DNSPacket::new_response(&query, Header::RCODE_NOERROR);
+                answer.header.ra = true;
+                answer.answers.push(AnswerSection {
+                    q_name: query.questions[0].q_name.clone(),
+                    r_type: DNSRecordType::MX,
+                    r_class: DNSClass::IN,
+                    ttl: 300,
+                    rdata: RData::MX {
+                        preference: 10,
+                        exchange: "mail.ex".to_string(),
+                    },
+                });
+                answer.sync_counts();
+                reply_registry::take(&task.task.task_id)
+                    .unwrap()
+                    .send(answer.to_bytes().unwrap().into())
+                    .unwrap();
+            }
+        });
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let (mut sender, conn) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+            .await
+            .unwrap();
+        tokio::spawn(conn);
+
+        let req = Request::get("/resolve?name=example.com&type=mx&cd=1")
+            .header("Host", "localhost")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+        let resp = sender.send_request(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["content-type"], "application/dns-json");
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["Status"], 0);
+        assert_eq!(json["RD"], true);
+        assert_eq!(json["RA"], true);
+        assert_eq!(json["CD"], true);
+        assert_eq!(json["Question"][0]["name"], "example.com.");
+        assert_eq!(json["Question"][0]["type"], 15);
+        assert_eq!(json["Answer"][0]["TTL"], 300);
+        assert_eq!(json["Answer"][0]["data"], "10 mail.ex.");
+
+        let req = Request::get("/resolve?type=A")
+            .header("Host", "localhost")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+        let resp = sender.send_request(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn doh_h2_concurrent_streams_limited() {
+        let doh = DohConfig {
+            max_concurrent_streams: Some(1),
+            ..Default::default()
+        };
+        let (addr, mut rx) = start(doh, None).await;
+
+        // plain HTTP/2 with prior knowledge
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let (sender, conn) =
+            hyper::client::conn::http2::handshake(TokioExecutor::new(), TokioIo::new(stream))
+                .await
+                .unwrap();
+        tokio::spawn(conn);
+
+        let mut first_sender = sender.clone();
+        let mut second_sender = sender;
+        let first = tokio::spawn(async move { first_sender.send_request(get(1)).await });
+        let first_task = rx.recv().await.unwrap();
+        let second = tokio::spawn(async move { second_sender.send_request(get(2)).await });
+
+        // the second stream waits for the first one to close
+        assert!(
+            timeout(Duration::from_millis(200), rx.recv())
+                .await
+                .is_err()
+        );
+        reply(&first_task);
+        assert_eq!(first.await.unwrap().unwrap().status(), StatusCode::OK);
+
+        reply(&rx.recv().await.unwrap());
+        assert_eq!(second.await.unwrap().unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn doh_idle_connection_closed() {
+        let doh = DohConfig {
+            idle_timeout_secs: Some(1),
+            ..Default::default()
+        };
+        let (addr, _rx) = start(doh, None).await;
+        let mut client = TcpStream::connect(addr).await.unwrap();
+
+        let mut buf = [0u8; 1];
+        let read = timeout(Duration::from_secs(3), client.read(&mut buf))
+            .await
+            .expect("idle connection not closed");
+        assert!(matches!(read, Ok(0) | Err(_)));
+    }
+}
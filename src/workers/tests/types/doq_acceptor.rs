@@ -0,0 +1,165 @@
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+
+    use quinn::crypto::rustls::QuicClientConfig;
+    use quinn::{ConnectionError, Endpoint, VarInt};
+    use rustls_pki_types::CertificateDer;
+    use tempfile::TempDir;
+    use tokio::sync::mpsc;
+    use tokio_rustls::rustls::crypto::ring;
+    use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+
+    use crate::config::DoqConfig;
+    use crate::utils::tls::TlsCertStore;
+    use crate::workers::reply_registry;
+    use crate::workers::task::InFlightTask;
+    use crate::workers::types::doq_acceptor::{doq_endpoint, run_dns_doq_acceptor_with_endpoint};
+    use crate::workers::{SCloudWorker, WorkerType};
+
+    fn query(id: u16) -> Vec<u8> {
+        let mut q = id.to_be_bytes().to_vec();
+        q.extend_from_slice(&[
+            0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, b'e', b'x', b'a',
+            b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm', 0x00, 0x00, 0x01, 0x00, 0x01,
+        ]);
+        q
+    }
+
+    fn frame(message: &[u8]) -> Vec<u8> {
+        let mut f = (message.len() as u16).to_be_bytes().to_vec();
+        f.extend_from_slice(message);
+        f
+    }
+
+    /// Answer a task the way the sender does, tagging the answer with
+    /// `marker` in its last byte.
+    fn reply(task: &InFlightTask, marker: u8) {
+        let mut answer = task.task.payload.to_vec();
+        answer[2] |= 0x80;
+        *answer.last_mut().unwrap() = marker;
+        reply_registry::take(&task.task.task_id)
+            .expect("no reply channel registered")
+            .send(answer.into())
+            .unwrap();
+    }
+
+    /// Start a DoQ acceptor with a fresh self-signed certificate. Returns
+    /// its address, the certificate to trust and the channel its tasks
+    /// land in.
+    async fn start() -> (
+        SocketAddr,
+        CertificateDer<'static>,
+        mpsc::Receiver<InFlightTask>,
+        TempDir,
+    ) {
+        let dir = TempDir::new().unwrap();
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        std::fs::write(dir.path().join("cert.pem"), cert.cert.pem()).unwrap();
+        std::fs::write(dir.path().join("key.pem"), cert.key_pair.serialize_pem()).unwrap();
+        let certs = Arc::new(
+            TlsCertStore::load(dir.path().join("cert.pem"), dir.path().join("key.pem")).unwrap(),
+        );
+
+        let endpoint = doq_endpoint(
+            "127.0.0.1:0".parse().unwrap(),
+            &certs,
+            &DoqConfig::default(),
+        )
+        .unwrap();
+        let addr = endpoint.local_addr().unwrap();
+        let worker = Arc::new(SCloudWorker::new(WorkerType::DOQ_ACCEPTOR).unwrap());
+        let (tx, rx) = mpsc::channel::<InFlightTask>(16);
+        tokio::spawn(run_dns_doq_acceptor_with_endpoint(
+            worker,
+            endpoint,
+            vec![tx],
+        ));
+        (addr, cert.cert.der().clone(), rx, dir)
+    }
+
+    async fn connect(addr: SocketAddr, trusted: CertificateDer<'static>) -> quinn::Connection {
+        let mut roots = RootCertStore::empty();
+        roots.add(trusted).unwrap();
+        let mut tls = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        tls.alpn_protocols = vec![b"doq".to_vec()];
+
+        let mut client = Endpoint::client("127.0.0.1:0".parse().unwrap()).unwrap();
+        client.set_default_client_config(quinn::ClientConfig::new(Arc::new(
+            QuicClientConfig::try_from(tls).unwrap(),
+        )));
+        client.connect(addr, "localhost").unwrap().await.unwrap()
+    }
+
+    /// Send `message` on a new stream and close the sending side.
+    async fn send_query(conn: &quinn::Connection, message: &[u8]) -> quinn::RecvStream {
+        let (mut send, recv) = conn.open_bi().await.unwrap();
+        send.write_all(&frame(message)).await.unwrap();
+        send.finish().unwrap();
+        recv
+    }
+
+    #[tokio::test]
+    async fn doq_query_answered_on_its_stream() {
+        let (addr, trusted, mut rx, _dir) = start().await;
+        let conn = connect(addr, trusted).await;
+
+        let mut recv = send_query(&conn, &query(0)).await;
+        let task = rx.recv().await.unwrap();
+        assert_eq!(task.task.for_type, WorkerType::DOQ_ACCEPTOR);
+        assert_eq!(
+            task.task.reply_to.as_deref(),
+            Some(reply_registry::REPLY_TAG_DOQ)
+        );
+        assert_eq!(task.task.payload.to_vec(), query(0));
+        reply(&task, 1);
+
+        let answer = recv.read_to_end(65_537).await.unwrap();
+        assert_eq!(
+            u16::from_be_bytes([answer[0], answer[1]]) as usize,
+            answer.len() - 2
+        );
+        assert_eq!(&answer[2..4], &[0x00, 0x00]);
+        assert_eq!(answer[4] & 0x80, 0x80);
+    }
+
+    #[tokio::test]
+    async fn doq_streams_answered_independently() {
+        let (addr, trusted, mut rx, _dir) = start().await;
+        let conn = connect(addr, trusted).await;
+
+        let mut first = send_query(&conn, &query(0)).await;
+        let first_task = rx.recv().await.unwrap();
+        let mut second = send_query(&conn, &query(0)).await;
+        let second_task = rx.recv().await.unwrap();
+
+        // the second query is answered before the first one
+        reply(&second_task, 2);
+        assert_eq!(
+            *second.read_to_end(65_537).await.unwrap().last().unwrap(),
+            2
+        );
+        reply(&first_task, 1);
+        assert_eq!(*first.read_to_end(65_537).await.unwrap().last().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn doq_nonzero_message_id_closes_connection() {
+        let (addr, trusted, _rx, _dir) = start().await;
+        let conn = connect(addr, trusted).await;
+
+        let _recv = send_query(&conn, &query(0x1234)).await;
+
+        match conn.closed().await {
+            ConnectionError::ApplicationClosed(close) => {
+                assert_eq!(close.error_code, VarInt::from_u32(0x2));
+            }
+            other => panic!("unexpected close: {:?}", other),
+        }
+    }
+}
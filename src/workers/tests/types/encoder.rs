@@ -0,0 +1,189 @@
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::sync::Arc;
+
+    use tokio::net::UdpSocket;
+    use tokio::sync::{Semaphore, mpsc};
+
+    use crate::dns::packet::DNSPacket;
+    use crate::dns::packet::answer::AnswerSection;
+    use crate::dns::packet::edns::Edns;
+    use crate::dns::packet::header::Header;
+    use crate::dns::packet::question::QuestionSection;
+    use crate::dns::q_class::DNSClass;
+    use crate::dns::q_type::DNSRecordType;
+    use crate::dns::records::rdata::RData;
+    use crate::workers::task::InFlightTask;
+    use crate::workers::types::encoder::{ResponseLimits, run_dns_encoder_with_limits};
+    use crate::workers::{SCloudWorker, WorkerType};
+
+    const LIMITS: ResponseLimits = ResponseLimits {
+        edns_udp_size: Some(1232),
+        max_udp_response: 4096,
+    };
+
+    /// Query for many.example.com with its 40 A records answer.
+    fn exchange(edns: Option<u16>) -> (DNSPacket, DNSPacket) {
+        let mut query = DNSPacket::new_query(&[QuestionSection {
+            q_name: "many.example.com".to_string(),
+            q_type: DNSRecordType::A,
+            q_class: DNSClass::IN,
+        }]);
+        query.edns = edns.map(Edns::new);
+        query.sync_counts();
+
+        let mut response = DNSPacket::new_response(&query, Header::RCODE_NOERROR);
+        response.answers = (1..=40)
+            .map(|i| AnswerSection {
+                q_name: "many.example.com".to_string(),
+                r_type: DNSRecordType::A,
+                r_class: DNSClass::IN,
+                ttl: 300,
+                rdata: RData::A(Ipv4Addr::new(192, 0, 2, i)),
+            })
+            .collect();
+        response.sync_counts();
+        (query, response)
+    }
+
+    /// Run the encoder on one task and return the encoded response.
+    async fn encode(query: DNSPacket, response: DNSPacket, udp: bool) -> DNSPacket {
+        encode_with(LIMITS, query, response, udp).await
+    }
+
+    async fn encode_with(
+        limits: ResponseLimits,
+        query: DNSPacket,
+        response: DNSPacket,
+        udp: bool,
+    ) -> DNSPacket {
+        let worker = Arc::new(SCloudWorker::new(WorkerType::ENCODER).unwrap());
+        let (in_tx, in_rx) = mpsc::channel::<InFlightTask>(1);
+        let (out_tx, mut out_rx) = mpsc::channel::<InFlightTask>(1);
+
+        let mut task = InFlightTask::new(
+            &[],
+            "127.0.0.1:5300".parse().unwrap(),
+            WorkerType::LISTENER,
+            Arc::new(Semaphore::new(1)),
+        )
+        .await
+        .unwrap();
+        task.task.query = Some(query);
+        task.task.response = Some(response);
+        if udp {
+            let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            task.task.udp_socket = Some(Arc::new(socket));
+        }
+        in_tx.send(task).await.unwrap();
+        drop(in_tx);
+
+        run_dns_encoder_with_limits(worker.clone(), limits, vec![in_rx], vec![out_tx])
+            .await
+            .unwrap();
+
+        let task = out_rx.recv().await.expect("task not forwarded");
+        assert_eq!(worker.get_jobs_done(), 1);
+        DNSPacket::from_bytes(&task.task.payload).unwrap()
+    }
+
+    #[tokio::test]
+    async fn encoder_answers_servfail_when_response_not_encodable() {
+        let (query, mut response) = exchange(Some(4096));
+        // a label longer than 63 bytes cannot be written
+        response.answers[0].q_name = format!("{}.example.com", "a".repeat(64));
+
+        let worker = Arc::new(SCloudWorker::new(WorkerType::ENCODER).unwrap());
+        let (in_tx, in_rx) = mpsc::channel::<InFlightTask>(2);
+        let (out_tx, mut out_rx) = mpsc::channel::<InFlightTask>(2);
+        for query in [Some(query.clone()), None] {
+            let mut task = InFlightTask::new(
+                &query
+                    .as_ref()
+                    .map(|q| q.to_bytes().unwrap())
+                    .unwrap_or_default(),
+                "127.0.0.1:5300".parse().unwrap(),
+                WorkerType::LISTENER,
+                Arc::new(Semaphore::new(1)),
+            )
+            .await
+            .unwrap();
+            task.task.query = query;
+            task.task.response = Some(response.clone());
+            in_tx.send(task).await.unwrap();
+        }
+        drop(in_tx);
+
+        run_dns_encoder_with_limits(worker.clone(), LIMITS, vec![in_rx], vec![out_tx])
+            .await
+            .unwrap();
+
+        let sent = DNSPacket::from_bytes(&out_rx.recv().await.unwrap().task.payload).unwrap();
+        assert_eq!(sent.header.id, query.header.id);
+        assert!(sent.header.qr);
+        assert_eq!(sent.header.rcode, Header::RCODE_SERVFAIL);
+        assert!(sent.answers.is_empty());
+        // without a query, the task is dropped instead of echoing its payload
+        assert!(out_rx.recv().await.is_none());
+        assert_eq!(worker.get_jobs_failed(), 2);
+    }
+
+    #[tokio::test]
+    async fn encoder_truncates_udp_response() {
+        let (query, response) = exchange(None);
+        let sent = encode(query, response, true).await;
+
+        assert!(sent.header.tc);
+        assert!(sent.answers.is_empty());
+        assert_eq!(sent.edns, None);
+    }
+
+    #[tokio::test]
+    async fn encoder_negotiates_udp_size() {
+        let (query, response) = exchange(Some(4096));
+        let sent = encode(query, response, true).await;
+
+        assert!(!sent.header.tc);
+        assert_eq!(sent.answers.len(), 40);
+        assert_eq!(sent.edns, Some(Edns::new(1232)));
+    }
+
+    #[tokio::test]
+    async fn encoder_uses_smaller_server_udp_size() {
+        let (query, response) = exchange(Some(4096));
+        let limits = ResponseLimits {
+            edns_udp_size: Some(512),
+            ..LIMITS
+        };
+        let sent = encode_with(limits, query, response, true).await;
+
+        assert!(sent.header.tc);
+        assert!(sent.answers.is_empty());
+        assert_eq!(sent.edns, Some(Edns::new(512)));
+    }
+
+    #[tokio::test]
+    async fn encoder_truncates_to_512_without_edns() {
+        // the client advertises 4096 bytes, but EDNS is disabled
+        let (query, response) = exchange(Some(4096));
+        let limits = ResponseLimits {
+            edns_udp_size: None,
+            ..LIMITS
+        };
+        let sent = encode_with(limits, query, response, true).await;
+
+        assert!(sent.header.tc);
+        assert!(sent.answers.is_empty());
+        assert_eq!(sent.edns, None);
+    }
+
+    #[tokio::test]
+    async fn encoder_sends_full_response_over_tcp() {
+        let (query, response) = exchange(None);
+        let sent = encode(query, response, false).await;
+
+        assert!(!sent.header.tc);
+        assert_eq!(sent.answers.len(), 40);
+    }
+}
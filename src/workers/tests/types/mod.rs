@@ -1,2 +1,14 @@
+mod cache_janitor;
+mod cache_lookup;
+mod cache_writer;
+mod decoder;
+mod doh_acceptor;
+mod doq_acceptor;
+mod encoder;
 mod listener;
 mod metrics;
+mod query_dispatcher;
+mod resolver;
+mod sender;
+mod tcp_acceptor;
+mod zone_manager;
@@ -0,0 +1,304 @@
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use tokio::sync::{Semaphore, mpsc};
+
+    use crate::config::{
+        Config, ForwardPolicy, ForwarderConfig, ListenerConfig, ZoneConfig, ZoneType,
+    };
+    use crate::dns::packet::DNSPacket;
+    use crate::dns::packet::header::Header;
+    use crate::dns::packet::question::QuestionSection;
+    use crate::dns::q_class::DNSClass;
+    use crate::dns::q_type::DNSRecordType;
+    use crate::workers::queue::Routes;
+    use crate::workers::task::InFlightTask;
+    use crate::workers::types::query_dispatcher::{QueryRouter, Route};
+    use crate::workers::{self, SCloudWorker, WorkerType};
+
+    fn config() -> Config {
+        Config::from_file(Path::new("./config/config.json")).unwrap()
+    }
+
+    #[test]
+    fn test_route_authoritative_zones() {
+        let router = QueryRouter::from_config(&config());
+        let client = "203.0.113.1".parse().unwrap();
+
+        assert!(matches!(
+            router.route("www.inline.local.", client),
+            Route::Zone
+        ));
+        assert!(matches!(router.route("INLINE.local", client), Route::Zone));
+        assert!(matches!(
+            router.route("ftp.example.org", client),
+            Route::Zone
+        ));
+    }
+
+    #[test]
+    fn test_route_forward_zone() {
+        let router = QueryRouter::from_config(&config());
+
+        match router.route("host.private.example", "203.0.113.1".parse().unwrap()) {
+            Route::Forward(forwarder) => {
+                assert_eq!(forwarder.addresses, vec!["10.10.10.2:5353".to_string()]);
+                assert_eq!(forwarder.policy, ForwardPolicy::First);
+            }
+            other => panic!("unexpected route: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_route_forward_zone_inherits_forwarder_settings() {
+        let cfg = Config {
+            forwarder: vec![ForwarderConfig {
+                name: "upstream".to_string(),
+                addresses: vec!["192.0.2.53:53".to_string()],
+                policy: ForwardPolicy::Random,
+                timeout_ms: 4000,
+                edns: false,
+                use_tcp_on_retry: Some(false),
+            }],
+            zone: vec![
+                ZoneConfig {
+                    name: "corp.example.".to_string(),
+                    kind: ZoneType::Forward,
+                    forwarders: vec!["10.0.0.53:53".to_string()],
+                    ..Default::default()
+                },
+                ZoneConfig {
+                    name: "lab.example.".to_string(),
+                    kind: ZoneType::Forward,
+                    forwarders: vec!["10.0.1.53:53".to_string()],
+                    forward_policy: Some(ForwardPolicy::RoundRobin),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let router = QueryRouter::from_config(&cfg);
+        let client = "203.0.113.1".parse().unwrap();
+
+        let Route::Forward(corp) = router.route("www.corp.example", client) else {
+            panic!("corp.example should be forwarded");
+        };
+        assert_eq!(corp.name, "corp.example.");
+        assert_eq!(corp.addresses, vec!["10.0.0.53:53".to_string()]);
+        assert_eq!(corp.policy, ForwardPolicy::Random);
+        assert_eq!(corp.timeout_ms, 4000);
+        assert!(!corp.edns);
+        assert_eq!(corp.use_tcp_on_retry, Some(false));
+
+        let Route::Forward(lab) = router.route("www.lab.example", client) else {
+            panic!("lab.example should be forwarded");
+        };
+        assert_eq!(lab.policy, ForwardPolicy::RoundRobin);
+        assert_eq!(lab.timeout_ms, 4000);
+    }
+
+    #[test]
+    fn test_route_recursion_acl() {
+        let router = QueryRouter::from_config(&config());
+
+        assert!(matches!(
+            router.route("github.com", "10.1.2.3".parse().unwrap()),
+            Route::Recursion
+        ));
+        assert!(matches!(
+            router.route("github.com", "203.0.113.1".parse().unwrap()),
+            Route::Refused
+        ));
+    }
+
+    #[test]
+    fn test_route_recursion_disabled() {
+        let mut cfg = config();
+        cfg.recursion.enabled = false;
+        let router = QueryRouter::from_config(&cfg);
+
+        assert!(matches!(
+            router.route("github.com", "10.1.2.3".parse().unwrap()),
+            Route::Refused
+        ));
+    }
+
+    fn listener(recursion_allowed: bool, acl: &str) -> ListenerConfig {
+        ListenerConfig {
+            name: "test".to_string(),
+            recursion_allowed,
+            acl: acl.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_route_on_listener_without_recursion() {
+        let router = QueryRouter::from_config(&config());
+        let public = listener(false, "");
+        let client = "10.1.2.3".parse().unwrap();
+
+        assert!(matches!(
+            router.route_on(Some(&public), "www.inline.local", client),
+            Route::Zone
+        ));
+        assert!(matches!(
+            router.route_on(Some(&public), "github.com", client),
+            Route::Refused
+        ));
+        assert!(matches!(
+            router.route_on(Some(&public), "host.private.example", client),
+            Route::Refused
+        ));
+        assert!(matches!(
+            router.route_on(Some(&listener(true, "")), "github.com", client),
+            Route::Recursion
+        ));
+        assert!(matches!(
+            router.route_on(None, "github.com", client),
+            Route::Recursion
+        ));
+    }
+
+    #[test]
+    fn test_route_on_listener_acl() {
+        let router = QueryRouter::from_config(&config());
+        let internal = listener(true, "internal");
+
+        assert!(matches!(
+            router.route_on(
+                Some(&internal),
+                "www.inline.local",
+                "10.1.2.3".parse().unwrap()
+            ),
+            Route::Zone
+        ));
+        assert!(matches!(
+            router.route_on(
+                Some(&internal),
+                "www.inline.local",
+                "203.0.113.1".parse().unwrap()
+            ),
+            Route::Refused
+        ));
+        assert!(matches!(
+            router.route_on(
+                Some(&listener(true, "2001:db8::/32")),
+                "www.inline.local",
+                "2001:db8::1".parse().unwrap()
+            ),
+            Route::Zone
+        ));
+    }
+
+    /// Dispatch one query from `client` and return the worker type it was
+    /// routed to with the task.
+    async fn dispatch(name: &str, client: &str) -> (WorkerType, InFlightTask) {
+        dispatch_on(None, name, client).await
+    }
+
+    /// Same as `dispatch`, for a query that came in on `listener`.
+    async fn dispatch_on(
+        listener: Option<ListenerConfig>,
+        name: &str,
+        client: &str,
+    ) -> (WorkerType, InFlightTask) {
+        let router = QueryRouter::from_config(&config());
+        let worker = Arc::new(SCloudWorker::new(WorkerType::QUERY_DISPATCHER).unwrap());
+        let (in_tx, in_rx) = mpsc::channel::<InFlightTask>(1);
+        let mut routes = Routes::new();
+        let mut outputs = Vec::new();
+        for t in [
+            WorkerType::ZONE_MANAGER,
+            WorkerType::RESOLVER,
+            WorkerType::ENCODER,
+        ] {
+            let (tx, rx) = mpsc::channel::<InFlightTask>(1);
+            routes.insert(t, vec![tx]);
+            outputs.push((t, rx));
+        }
+
+        let mut task = InFlightTask::new(
+            b"",
+            client.parse().unwrap(),
+            WorkerType::LISTENER,
+            Arc::new(Semaphore::new(1)),
+        )
+        .await
+        .unwrap();
+        task.task.listener = listener.map(Arc::new);
+        task.task.query = Some(DNSPacket::new_query(&[QuestionSection {
+            q_name: name.to_string(),
+            q_type: DNSRecordType::A,
+            q_class: DNSClass::IN,
+        }]));
+        in_tx.send(task).await.unwrap();
+        drop(in_tx);
+
+        workers::types::query_dispatcher::run_dns_query_dispatcher_with_router(
+            worker,
+            Arc::new(router),
+            vec![in_rx],
+            routes,
+        )
+        .await
+        .unwrap();
+
+        let mut routed = Vec::new();
+        for (t, mut rx) in outputs {
+            if let Ok(task) = rx.try_recv() {
+                routed.push((t, task));
+            }
+        }
+        assert_eq!(routed.len(), 1);
+        routed.pop().unwrap()
+    }
+
+    #[tokio::test]
+    async fn dispatcher_sends_zone_query_to_zone_manager() {
+        let (to, task) = dispatch("www.inline.local", "203.0.113.1:5300").await;
+
+        assert_eq!(to, WorkerType::ZONE_MANAGER);
+        assert!(task.task.response.is_none());
+    }
+
+    #[tokio::test]
+    async fn dispatcher_attaches_forwarders() {
+        let (to, task) = dispatch("host.private.example", "203.0.113.1:5300").await;
+
+        assert_eq!(to, WorkerType::RESOLVER);
+        assert_eq!(task.task.forwarder.unwrap().name, "private.example.");
+    }
+
+    #[tokio::test]
+    async fn dispatcher_sends_recursion_to_resolver() {
+        let (to, task) = dispatch("github.com", "10.0.0.7:5300").await;
+
+        assert_eq!(to, WorkerType::RESOLVER);
+        assert!(task.task.forwarder.is_none());
+    }
+
+    #[tokio::test]
+    async fn dispatcher_refuses_other_clients() {
+        let (to, task) = dispatch("github.com", "203.0.113.1:5300").await;
+
+        assert_eq!(to, WorkerType::ENCODER);
+        let response = task.task.response.unwrap();
+        assert!(response.header.qr);
+        assert_eq!(response.header.rcode, Header::RCODE_REFUSED);
+    }
+
+    #[tokio::test]
+    async fn dispatcher_applies_listener_recursion_policy() {
+        let (to, task) =
+            dispatch_on(Some(listener(false, "")), "github.com", "10.0.0.7:5300").await;
+
+        assert_eq!(to, WorkerType::ENCODER);
+        assert_eq!(
+            task.task.response.unwrap().header.rcode,
+            Header::RCODE_REFUSED
+        );
+    }
+}
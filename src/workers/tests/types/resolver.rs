@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::net::UdpSocket;
+    use tokio::sync::{Semaphore, mpsc};
+
+    use crate::config::ForwarderConfig;
+    use crate::dns::packet::DNSPacket;
+    use crate::dns::packet::header::Header;
+    use crate::dns::packet::question::QuestionSection;
+    use crate::dns::q_class::DNSClass;
+    use crate::dns::q_type::DNSRecordType;
+    use crate::workers::task::InFlightTask;
+    use crate::workers::{self, SCloudWorker, WorkerType};
+
+    async fn run_one(forwarder: Option<ForwarderConfig>) -> (Arc<SCloudWorker>, InFlightTask) {
+        let query = DNSPacket::new_query(&[QuestionSection {
+            q_name: "www.corp.example".to_string(),
+            q_type: DNSRecordType::A,
+            q_class: DNSClass::IN,
+        }]);
+        let worker = Arc::new(SCloudWorker::new(WorkerType::RESOLVER).unwrap());
+        let (in_tx, in_rx) = mpsc::channel::<InFlightTask>(1);
+        let (out_tx, mut out_rx) = mpsc::channel::<InFlightTask>(1);
+
+        let mut task = InFlightTask::new(
+            &query.to_bytes().unwrap(),
+            "127.0.0.1:5300".parse().unwrap(),
+            WorkerType::LISTENER,
+            Arc::new(Semaphore::new(1)),
+        )
+        .await
+        .unwrap();
+        task.task.query = Some(query);
+        task.task.forwarder = forwarder.map(Arc::new);
+        in_tx.send(task).await.unwrap();
+        drop(in_tx);
+
+        workers::types::resolver::run_dns_resolver_with_forwarder(
+            worker.clone(),
+            None,
+            vec![in_rx],
+            vec![out_tx],
+        )
+        .await
+        .unwrap();
+
+        (worker.clone(), out_rx.recv().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn resolver_uses_forward_zone_upstream() {
+        let upstream = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = upstream.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 4096];
+            let (len, peer) = upstream.recv_from(&mut buf).await.unwrap();
+            buf[2] |= 0x80;
+            upstream.send_to(&buf[..len], peer).await.unwrap();
+        });
+
+        let (worker, out) = run_one(Some(ForwarderConfig {
+            name: "corp.example".to_string(),
+            addresses: vec![addr.to_string()],
+            timeout_ms: 500,
+            ..Default::default()
+        }))
+        .await;
+
+        // decoded, so that the encoder negotiates EDNS and truncates it
+        let reply = out.task.response.expect("upstream reply not decoded");
+        assert!(reply.header.qr);
+        assert_eq!(reply.header.id, out.task.query.as_ref().unwrap().header.id);
+        assert_eq!(reply.questions[0].q_name, "www.corp.example");
+        assert_eq!(worker.get_jobs_done(), 1);
+    }
+
+    #[tokio::test]
+    async fn resolver_answers_servfail_to_reply_for_other_question() {
+        let upstream = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = upstream.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 4096];
+            let (len, peer) = upstream.recv_from(&mut buf).await.unwrap();
+            let query = DNSPacket::from_bytes(&buf[..len]).unwrap();
+            let mut other = query.clone();
+            other.questions[0].q_name = "other.example".to_string();
+            let mut reply = DNSPacket::new_response(&other, Header::RCODE_NOERROR);
+            reply.header.id = query.header.id;
+            upstream
+                .send_to(&reply.to_bytes().unwrap(), peer)
+                .await
+                .unwrap();
+        });
+
+        let (worker, out) = run_one(Some(ForwarderConfig {
+            name: "corp.example".to_string(),
+            addresses: vec![addr.to_string()],
+            timeout_ms: 500,
+            ..Default::default()
+        }))
+        .await;
+
+        let response = out.task.response.expect("SERVFAIL response expected");
+        assert_eq!(response.header.rcode, Header::RCODE_SERVFAIL);
+        assert_eq!(response.questions[0].q_name, "www.corp.example");
+        assert_eq!(worker.get_jobs_failed(), 1);
+    }
+
+    #[tokio::test]
+    async fn resolver_answers_servfail_without_upstream() {
+        let (worker, out) = run_one(None).await;
+
+        let response = out.task.response.expect("SERVFAIL response expected");
+        assert_eq!(response.header.rcode, Header::RCODE_SERVFAIL);
+        assert_eq!(worker.get_jobs_failed(), 1);
+    }
+}
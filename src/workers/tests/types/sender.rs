@@ -0,0 +1,156 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::net::UdpSocket;
+    use tokio::time::{Duration, timeout};
+
+    use crate::dns::packet::DNSPacket;
+    use crate::dns::packet::header::Header;
+    use crate::workers::manager::channels_generation::generate_channels;
+    use crate::workers::{self, SCloudWorker, WorkerType};
+
+    /// Build one worker of each pipeline stage, wire them, and run the UDP
+    /// listener of the acceptor on an ephemeral loopback socket.
+    async fn start_pipeline() -> (std::net::SocketAddr, Vec<tokio::task::JoinHandle<()>>) {
+        let types = [
+            WorkerType::TCP_ACCEPTOR,
+            WorkerType::DECODER,
+            WorkerType::QUERY_DISPATCHER,
+            WorkerType::CACHE_LOOKUP,
+            WorkerType::ZONE_MANAGER,
+            WorkerType::RESOLVER,
+            WorkerType::CACHE_WRITER,
+            WorkerType::ENCODER,
+            WorkerType::SENDER,
+        ];
+        let workers: Vec<Arc<SCloudWorker>> = types
+            .iter()
+            .map(|t| Arc::new(SCloudWorker::new(*t).unwrap()))
+            .collect();
+        generate_channels(workers.clone()).await.unwrap();
+
+        let acceptor = workers[0].clone();
+        let acceptor_tx = acceptor.get_dns_tx().await.unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = socket.local_addr().unwrap();
+
+        let mut handles = Vec::new();
+        handles.push(tokio::spawn(async move {
+            let _ = workers::types::listener::run_dns_listener_with_socket(
+                acceptor,
+                socket,
+                vec![],
+                acceptor_tx,
+            )
+            .await;
+        }));
+        for w in workers.into_iter().skip(1) {
+            handles.push(tokio::spawn(async move {
+                let _ = w.run(None).await;
+            }));
+        }
+        (server_addr, handles)
+    }
+
+    #[tokio::test]
+    async fn pipeline_replies_to_udp_client() {
+        let (server_addr, handles) = start_pipeline().await;
+
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        // www.inline.local A, from the inline zone of the configuration
+        let query: Vec<u8> = [
+            &[
+                0x12, 0x34, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            ][..],
+            b"\x03www\x06inline\x05local\x00",
+            &[0x00, 0x01, 0x00, 0x01],
+        ]
+        .concat();
+        client.send_to(&query, server_addr).await.unwrap();
+
+        let mut buf = [0u8; 4096];
+        let (len, from) = timeout(Duration::from_secs(2), client.recv_from(&mut buf))
+            .await
+            .expect("timeout while waiting for the reply")
+            .unwrap();
+
+        assert_eq!(from, server_addr);
+        assert!(len >= 12);
+        assert_eq!(&buf[..2], &[0x12, 0x34]);
+        // a response, not the query sent back
+        assert_ne!(buf[2] & 0x80, 0);
+        let reply = DNSPacket::from_bytes(&buf[..len]).unwrap();
+        assert_eq!(reply.header.rcode, Header::RCODE_NOERROR);
+        assert_eq!(
+            reply.questions,
+            DNSPacket::from_bytes(&query).unwrap().questions
+        );
+        assert_eq!(reply.answers.len(), 1);
+
+        for h in handles {
+            h.abort();
+        }
+    }
+
+    #[tokio::test]
+    async fn sender_drops_udp_task_without_socket() {
+        use crate::workers::reply_registry::REPLY_TAG_UDP;
+        use crate::workers::task::InFlightTask;
+        use tokio::sync::{Semaphore, mpsc};
+
+        let worker = Arc::new(SCloudWorker::new(WorkerType::SENDER).unwrap());
+        let (tx, rx) = mpsc::channel::<InFlightTask>(1);
+        let sem = Arc::new(Semaphore::new(1));
+        let mut task = InFlightTask::new(
+            b"abc",
+            "127.0.0.1:9".parse().unwrap(),
+            WorkerType::LISTENER,
+            sem,
+        )
+        .await
+        .unwrap();
+        task.task.reply_to = Some(REPLY_TAG_UDP.to_string());
+        tx.send(task).await.unwrap();
+        drop(tx);
+
+        workers::types::sender::run_dns_sender(worker.clone(), vec![rx])
+            .await
+            .unwrap();
+        assert_eq!(worker.get_jobs_failed(), 1);
+        assert_eq!(worker.get_jobs_done(), 0);
+    }
+
+    #[tokio::test]
+    async fn sender_replies_to_stream_tasks_through_registry() {
+        use crate::workers::reply_registry::{self, REPLY_TAG_DOQ, REPLY_TAG_TCP};
+        use crate::workers::task::InFlightTask;
+        use tokio::sync::{Semaphore, mpsc};
+
+        for (tag, from) in [
+            (REPLY_TAG_TCP, WorkerType::TCP_ACCEPTOR),
+            (REPLY_TAG_DOQ, WorkerType::DOQ_ACCEPTOR),
+        ] {
+            let worker = Arc::new(SCloudWorker::new(WorkerType::SENDER).unwrap());
+            let (tx, rx) = mpsc::channel::<InFlightTask>(1);
+            let mut task = InFlightTask::new(
+                b"reply",
+                "127.0.0.1:9".parse().unwrap(),
+                from,
+                Arc::new(Semaphore::new(1)),
+            )
+            .await
+            .unwrap();
+            task.task.reply_to = Some(tag.to_string());
+            let reply = reply_registry::register(task.task.task_id);
+            tx.send(task).await.unwrap();
+            drop(tx);
+
+            workers::types::sender::run_dns_sender(worker.clone(), vec![rx])
+                .await
+                .unwrap();
+            assert_eq!(reply.await.unwrap().as_ref(), b"reply");
+            assert_eq!(worker.get_jobs_done(), 1);
+        }
+    }
+}
@@ -0,0 +1,372 @@
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+
+    use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream, UdpSocket};
+    use tokio::sync::mpsc;
+    use tokio::time::{Duration, timeout};
+
+    use crate::config::{Config, ListenerConfig};
+    use crate::utils::tls::TlsCertStore;
+    use crate::workers::reply_registry;
+    use crate::workers::task::InFlightTask;
+    use crate::workers::types::listener::run_dns_listener_with_socket;
+    use crate::workers::types::tcp_acceptor::{
+        TcpLimits, TcpSessions, bind_tcp, bind_udp, run_dns_dot_listener, run_dns_tcp_listener,
+        serve_connection,
+    };
+    use crate::workers::{SCloudWorker, WorkerType};
+    use rustls_pki_types::{CertificateDer, ServerName};
+    use tempfile::TempDir;
+    use tokio_rustls::rustls::crypto::ring;
+    use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+    use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+    fn query(id: u16) -> Vec<u8> {
+        let mut q = id.to_be_bytes().to_vec();
+        q.extend_from_slice(&[
+            0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, b'e', b'x', b'a',
+            b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm', 0x00, 0x00, 0x01, 0x00, 0x01,
+        ]);
+        q
+    }
+
+    fn frame(message: &[u8]) -> Vec<u8> {
+        let mut f = (message.len() as u16).to_be_bytes().to_vec();
+        f.extend_from_slice(message);
+        f
+    }
+
+    async fn read_frame<S: AsyncRead + Unpin>(stream: &mut S) -> Vec<u8> {
+        let len = stream.read_u16().await.unwrap() as usize;
+        let mut buf = vec![0u8; len];
+        stream.read_exact(&mut buf).await.unwrap();
+        buf
+    }
+
+    /// Start a TCP listener whose tasks land in the returned channel.
+    async fn start(
+        limits: TcpLimits,
+    ) -> (SocketAddr, Arc<TcpSessions>, mpsc::Receiver<InFlightTask>) {
+        let worker = Arc::new(SCloudWorker::new(WorkerType::TCP_ACCEPTOR).unwrap());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let sessions = Arc::new(TcpSessions::default());
+        let (tx, rx) = mpsc::channel::<InFlightTask>(16);
+        tokio::spawn(run_dns_tcp_listener(
+            worker,
+            listener,
+            sessions.clone(),
+            limits,
+            vec![tx],
+        ));
+        (addr, sessions, rx)
+    }
+
+    fn limits(max_sessions_per_ip: usize) -> TcpLimits {
+        TcpLimits {
+            max_sessions_per_ip,
+            idle_timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Answer a task the way the sender does: through the reply registry.
+    fn reply(task: &InFlightTask) {
+        let mut answer = task.task.payload.to_vec();
+        answer[2] |= 0x80;
+        reply_registry::take(&task.task.task_id)
+            .expect("no reply channel registered")
+            .send(answer.into())
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn tcp_pipelined_queries_answered_out_of_order() {
+        let (addr, _, mut rx) = start(limits(4)).await;
+        let mut client = TcpStream::connect(addr).await.unwrap();
+
+        let mut both = frame(&query(0x0001));
+        both.extend_from_slice(&frame(&query(0x0002)));
+        client.write_all(&both).await.unwrap();
+
+        let first = rx.recv().await.unwrap();
+        let second = rx.recv().await.unwrap();
+        assert_eq!(
+            first.task.reply_to.as_deref(),
+            Some(reply_registry::REPLY_TAG_TCP)
+        );
+        assert_eq!(first.task.for_type, WorkerType::TCP_ACCEPTOR);
+        assert_eq!(first.task.payload.as_ref(), query(0x0001).as_slice());
+        reply(&second);
+        reply(&first);
+
+        let a = timeout(Duration::from_secs(1), read_frame(&mut client))
+            .await
+            .unwrap();
+        let b = timeout(Duration::from_secs(1), read_frame(&mut client))
+            .await
+            .unwrap();
+        assert_eq!(&a[..2], &[0x00, 0x02]);
+        assert_eq!(&b[..2], &[0x00, 0x01]);
+        assert_ne!(a[2] & 0x80, 0);
+    }
+
+    #[tokio::test]
+    async fn tcp_sessions_limited_per_ip() {
+        let (addr, sessions, _rx) = start(limits(1)).await;
+
+        let _first = TcpStream::connect(addr).await.unwrap();
+        let mut second = TcpStream::connect(addr).await.unwrap();
+
+        // the second connection is closed by the server
+        let mut buf = [0u8; 1];
+        let read = timeout(Duration::from_secs(1), second.read(&mut buf))
+            .await
+            .unwrap();
+        assert!(matches!(read, Ok(0) | Err(_)));
+        assert_eq!(sessions.count(&"127.0.0.1".parse().unwrap()), 1);
+    }
+
+    #[test]
+    fn tcp_sessions_zero_is_unlimited() {
+        let sessions = Arc::new(TcpSessions::default());
+        let ip = "192.0.2.1".parse().unwrap();
+
+        let open: Vec<_> = (0..100).map(|_| sessions.open(ip, 0).unwrap()).collect();
+        assert_eq!(sessions.count(&ip), 100);
+        drop(open);
+        assert_eq!(sessions.count(&ip), 0);
+    }
+
+    #[tokio::test]
+    async fn tcp_session_released_on_close() {
+        let (addr, sessions, mut rx) = start(limits(1)).await;
+
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        client.write_all(&frame(&query(7))).await.unwrap();
+        client.shutdown().await.unwrap();
+        // a half-closed connection still gets its pending replies
+        reply(&rx.recv().await.unwrap());
+        assert_eq!(&read_frame(&mut client).await[..2], &[0x00, 0x07]);
+        drop(client);
+
+        let ip = "127.0.0.1".parse().unwrap();
+        timeout(Duration::from_secs(1), async {
+            while sessions.count(&ip) != 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("session still counted");
+    }
+
+    #[tokio::test]
+    async fn tcp_idle_connection_closed() {
+        let (addr, _, _rx) = start(TcpLimits {
+            max_sessions_per_ip: 4,
+            idle_timeout: Duration::from_millis(100),
+        })
+        .await;
+        let mut client = TcpStream::connect(addr).await.unwrap();
+
+        let mut buf = [0u8; 1];
+        let read = timeout(Duration::from_secs(1), client.read(&mut buf))
+            .await
+            .expect("idle connection not closed");
+        assert!(matches!(read, Ok(0) | Err(_)));
+    }
+
+    #[test]
+    fn tcp_idle_timeout_from_config() {
+        let mut cfg = Config::default();
+        assert_eq!(
+            TcpLimits::from_config(&cfg).idle_timeout,
+            Duration::from_secs(10)
+        );
+        cfg.server.tcp_idle_timeout_secs = Some(42);
+        assert_eq!(
+            TcpLimits::from_config(&cfg).idle_timeout,
+            Duration::from_secs(42)
+        );
+    }
+
+    #[tokio::test]
+    async fn tcp_pending_replies_unregistered_on_close() {
+        let worker = Arc::new(SCloudWorker::new(WorkerType::TCP_ACCEPTOR).unwrap());
+        let (tx, mut rx) = mpsc::channel::<InFlightTask>(16);
+        let (mut client, server) = tokio::io::duplex(4096);
+        let serve = tokio::spawn(serve_connection(
+            worker,
+            server,
+            "127.0.0.1:5300".parse().unwrap(),
+            Duration::from_secs(5),
+            vec![tx],
+        ));
+
+        let mut both = frame(&query(0x0001));
+        both.extend_from_slice(&frame(&query(0x0002)));
+        client.write_all(&both).await.unwrap();
+        let first = rx.recv().await.unwrap();
+        let second = rx.recv().await.unwrap();
+
+        // the reply to the first query cannot be written back
+        drop(client);
+        reply(&first);
+        timeout(Duration::from_secs(1), serve)
+            .await
+            .expect("connection not closed")
+            .unwrap();
+
+        // the pipeline dropped the second task: nothing is left behind
+        assert!(reply_registry::take(&second.task.task_id).is_none());
+    }
+
+    fn tagged_worker(name: &str) -> Arc<SCloudWorker> {
+        let worker = SCloudWorker::new(WorkerType::TCP_ACCEPTOR).unwrap();
+        worker.set_listener(Arc::new(ListenerConfig {
+            name: name.to_string(),
+            address: "::1".to_string(),
+            ..Default::default()
+        }));
+        Arc::new(worker)
+    }
+
+    #[tokio::test]
+    async fn tcp_ipv6_listener_tags_tasks() {
+        let listener = bind_tcp("[::1]:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, mut rx) = mpsc::channel::<InFlightTask>(1);
+        tokio::spawn(run_dns_tcp_listener(
+            tagged_worker("v6"),
+            listener,
+            Arc::new(TcpSessions::default()),
+            limits(4),
+            vec![tx],
+        ));
+
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        client.write_all(&frame(&query(9))).await.unwrap();
+        let task = rx.recv().await.unwrap();
+
+        assert!(task.task.for_who.is_ipv6());
+        assert_eq!(task.task.listener.as_ref().unwrap().name, "v6");
+        reply(&task);
+        assert_eq!(&read_frame(&mut client).await[..2], &[0x00, 0x09]);
+    }
+
+    #[tokio::test]
+    async fn udp_ipv6_listener_tags_tasks() {
+        let socket = bind_udp("[::1]:0".parse().unwrap()).unwrap();
+        let addr = socket.local_addr().unwrap();
+        let (tx, mut rx) = mpsc::channel::<InFlightTask>(1);
+        tokio::spawn(run_dns_listener_with_socket(
+            tagged_worker("v6"),
+            socket,
+            vec![],
+            vec![tx],
+        ));
+
+        let client = UdpSocket::bind("[::1]:0").await.unwrap();
+        client.send_to(&query(10), addr).await.unwrap();
+        let task = timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(task.task.for_who, client.local_addr().unwrap());
+        assert_eq!(task.task.listener.unwrap().name, "v6");
+    }
+
+    #[tokio::test]
+    async fn ipv4_and_ipv6_listeners_share_a_port() {
+        let v6 = bind_udp("[::]:0".parse().unwrap()).unwrap();
+        let port = v6.local_addr().unwrap().port();
+
+        assert!(bind_udp(SocketAddr::from(([0, 0, 0, 0], port))).is_ok());
+    }
+
+    /// Write a self-signed certificate for `localhost` to `dir` and return
+    /// it in DER form, to be trusted by the client.
+    fn write_cert(dir: &std::path::Path) -> CertificateDer<'static> {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        std::fs::write(dir.join("cert.pem"), cert.cert.pem()).unwrap();
+        std::fs::write(dir.join("key.pem"), cert.key_pair.serialize_pem()).unwrap();
+        cert.cert.der().clone()
+    }
+
+    /// Start a DoT listener serving the certificate of `dir`.
+    async fn start_dot(
+        dir: &std::path::Path,
+    ) -> (SocketAddr, Arc<TlsCertStore>, mpsc::Receiver<InFlightTask>) {
+        let certs =
+            Arc::new(TlsCertStore::load(dir.join("cert.pem"), dir.join("key.pem")).unwrap());
+        let tls = TlsAcceptor::from(certs.server_config(&[b"dot"]).unwrap());
+        let worker = Arc::new(SCloudWorker::new(WorkerType::TCP_ACCEPTOR).unwrap());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = mpsc::channel::<InFlightTask>(16);
+        tokio::spawn(run_dns_dot_listener(
+            worker,
+            listener,
+            tls,
+            Arc::new(TcpSessions::default()),
+            limits(4),
+            vec![tx],
+        ));
+        (addr, certs, rx)
+    }
+
+    async fn connect_dot(
+        addr: SocketAddr,
+        trusted: CertificateDer<'static>,
+    ) -> std::io::Result<tokio_rustls::client::TlsStream<TcpStream>> {
+        let mut roots = RootCertStore::empty();
+        roots.add(trusted).unwrap();
+        let mut config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        config.alpn_protocols = vec![b"dot".to_vec()];
+
+        let stream = TcpStream::connect(addr).await?;
+        TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await
+    }
+
+    #[tokio::test]
+    async fn dot_query_answered_over_tls() {
+        let dir = TempDir::new().unwrap();
+        let trusted = write_cert(dir.path());
+        let (addr, _, mut rx) = start_dot(dir.path()).await;
+
+        let mut client = connect_dot(addr, trusted).await.unwrap();
+        assert_eq!(client.get_ref().1.alpn_protocol(), Some(&b"dot"[..]));
+
+        client.write_all(&frame(&query(0x0853))).await.unwrap();
+        let task = rx.recv().await.unwrap();
+        assert_eq!(task.task.payload.to_vec(), query(0x0853));
+        reply(&task);
+
+        let answer = read_frame(&mut client).await;
+        assert_eq!(&answer[..2], &[0x08, 0x53]);
+        assert_eq!(answer[2] & 0x80, 0x80);
+    }
+
+    #[tokio::test]
+    async fn dot_serves_reloaded_certificate() {
+        let dir = TempDir::new().unwrap();
+        let old = write_cert(dir.path());
+        let (addr, certs, _rx) = start_dot(dir.path()).await;
+        assert!(connect_dot(addr, old.clone()).await.is_ok());
+
+        let new = write_cert(dir.path());
+        certs.reload().unwrap();
+
+        assert!(connect_dot(addr, old).await.is_err());
+        assert!(connect_dot(addr, new).await.is_ok());
+    }
+}
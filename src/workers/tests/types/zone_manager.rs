@@ -0,0 +1,82 @@
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::sync::Arc;
+
+    use tokio::sync::{Semaphore, mpsc};
+
+    use crate::config::{ZoneConfig, ZoneType};
+    use crate::dns::packet::DNSPacket;
+    use crate::dns::packet::header::Header;
+    use crate::dns::packet::question::QuestionSection;
+    use crate::dns::q_class::DNSClass;
+    use crate::dns::q_type::DNSRecordType;
+    use crate::dns::records::rdata::RData;
+    use crate::dns::zones::manager::ZoneManager;
+    use crate::workers::task::InFlightTask;
+    use crate::workers::{self, SCloudWorker, WorkerType};
+
+    async fn run_one(query: Option<DNSPacket>) -> (Arc<SCloudWorker>, Option<InFlightTask>) {
+        let zones = ZoneManager::from_config(&[ZoneConfig {
+            name: "nihilist.moe.".to_string(),
+            kind: ZoneType::Master,
+            file: Some("zones/nihilist.moe.zone".to_string()),
+            ..Default::default()
+        }]);
+        let worker = Arc::new(SCloudWorker::new(WorkerType::ZONE_MANAGER).unwrap());
+        let (in_tx, in_rx) = mpsc::channel::<InFlightTask>(1);
+        let (out_tx, mut out_rx) = mpsc::channel::<InFlightTask>(1);
+
+        let mut task = InFlightTask::new(
+            b"",
+            "127.0.0.1:5300".parse().unwrap(),
+            WorkerType::LISTENER,
+            Arc::new(Semaphore::new(1)),
+        )
+        .await
+        .unwrap();
+        task.task.query = query;
+        in_tx.send(task).await.unwrap();
+        drop(in_tx);
+
+        workers::types::zone_manager::run_dns_zone_manager_with_zones(
+            worker.clone(),
+            Arc::new(zones),
+            vec![in_rx],
+            vec![out_tx],
+        )
+        .await
+        .unwrap();
+
+        (worker, out_rx.recv().await)
+    }
+
+    #[tokio::test]
+    async fn zone_manager_answers_query() {
+        let query = DNSPacket::new_query(&[QuestionSection {
+            q_name: "www.nihilist.moe".to_string(),
+            q_type: DNSRecordType::A,
+            q_class: DNSClass::IN,
+        }]);
+
+        let (worker, out) = run_one(Some(query.clone())).await;
+
+        let response = out.unwrap().task.response.expect("no response attached");
+        assert!(response.header.aa);
+        assert_eq!(response.header.id, query.header.id);
+        assert_eq!(response.header.rcode, Header::RCODE_NOERROR);
+        assert_eq!(
+            response.answers[0].rdata,
+            RData::A(Ipv4Addr::new(192, 168, 1, 21))
+        );
+        assert_eq!(worker.get_jobs_done(), 1);
+    }
+
+    #[tokio::test]
+    async fn zone_manager_drops_task_without_query() {
+        let (worker, out) = run_one(None).await;
+
+        assert!(out.is_none());
+        assert_eq!(worker.get_jobs_failed(), 1);
+    }
+}
@@ -1,7 +1,61 @@
+use crate::config::Config;
+use crate::dns::cache::DnsCache;
 use crate::exceptions::SCloudException;
+use crate::log_debug;
 use crate::workers::SCloudWorker;
+use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use tokio::time::{Duration, MissedTickBehavior, interval};
+
+const SWEEP_EVERY: Duration = Duration::from_secs(5);
+// pressure starts at 95% of `max_entries`, the sweep brings it back to 90%
+const HIGH_WATERMARK_PERCENT: usize = 95;
+const LOW_WATERMARK_PERCENT: usize = 90;
 
 pub async fn run_dns_cache_janitor(worker: Arc<SCloudWorker>) -> Result<(), SCloudException> {
+    let cfg = Config::from_file(Path::new("./config/config.json"))?;
+    run_dns_cache_janitor_with_cache(worker, DnsCache::shared(&cfg.cache), SWEEP_EVERY).await
+}
+
+/// Sweep `cache` every `every` until the worker is asked to shut down.
+pub async fn run_dns_cache_janitor_with_cache(
+    worker: Arc<SCloudWorker>,
+    cache: Arc<DnsCache>,
+    every: Duration,
+) -> Result<(), SCloudException> {
+    let mut ticker = interval(every);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    while !worker.get_shutdown_requested() {
+        ticker.tick().await;
+        sweep_cache(&worker, &cache);
+    }
     Ok(())
 }
+
+/// Drop the expired entries of `cache`, evict the least recently used ones
+/// if it is close to `max_entries`, and publish the cache counters on the
+/// worker.
+pub(crate) fn sweep_cache(worker: &SCloudWorker, cache: &DnsCache) {
+    let expired = cache.remove_expired();
+
+    let max_entries = cache.max_entries();
+    let evicted = if cache.len() * 100 >= max_entries * HIGH_WATERMARK_PERCENT {
+        cache.shrink_to(max_entries * LOW_WATERMARK_PERCENT / 100)
+    } else {
+        0
+    };
+    if expired > 0 || evicted > 0 {
+        log_debug!(
+            "cache janitor: {} expired, {} evicted, {} entries left",
+            expired,
+            evicted,
+            cache.len()
+        );
+    }
+
+    worker.set_cache_entries(cache.len() as u64);
+    worker.set_cache_evictions(cache.evictions());
+    worker.set_cache_expirations(cache.expirations());
+    worker.jobs_done.fetch_add(1, Ordering::Relaxed);
+}
@@ -1,39 +1,62 @@
+use crate::config::Config;
+use crate::dns::cache::DnsCache;
 use crate::exceptions::SCloudException;
-use crate::workers::SCloudWorker;
+use crate::workers::queue::{Routes, recv_any, route_task};
 use crate::workers::task::InFlightTask;
+use crate::workers::types::query_dispatcher::{QueryRouter, Route};
+use crate::workers::{SCloudWorker, WorkerType};
+use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::Ordering;
 use tokio::sync::mpsc;
 
 pub async fn run_dns_cache_lookup(
     worker: Arc<SCloudWorker>,
-    mut rx: Vec<mpsc::Receiver<InFlightTask>>,
-    tx: Vec<mpsc::Sender<InFlightTask>>,
+    rx: Vec<mpsc::Receiver<InFlightTask>>,
+    routes: Routes,
 ) -> Result<(), SCloudException> {
-    loop {
-        for rx_channel in rx.iter_mut() {
-            while let Some(msg) = rx_channel.recv().await {
-                let mut current = Some(msg);
-
-                for tx_channel in tx.iter() {
-                    match tx_channel.try_send(current.take().unwrap()) {
-                        Ok(_) => {
-                            break;
-                        }
-                        Err(mpsc::error::TrySendError::Full(returned)) => {
-                            current = Some(returned);
-                        }
-                        Err(mpsc::error::TrySendError::Closed(_)) => {
-                            return Ok(());
-                        }
-                    }
-                }
+    let cfg = Config::from_file(Path::new("./config/config.json"))?;
+    let router = Arc::new(QueryRouter::from_config(&cfg));
+    run_dns_cache_lookup_with_cache(worker, DnsCache::shared(&cfg.cache), router, rx, routes).await
+}
 
-                if let Some(unsent) = current {
-                    if tx[0].send(unsent).await.is_err() {
-                        return Ok(());
-                    }
-                }
+/// Answer the queries of `rx` found in `cache` straight through the
+/// encoder, and hand the other ones to the query dispatcher.
+///
+/// The cache is only looked up for clients `router` would not refuse, so a
+/// client denied recursion gets REFUSED from the dispatcher instead of the
+/// answers cached for the other ones.
+pub async fn run_dns_cache_lookup_with_cache(
+    worker: Arc<SCloudWorker>,
+    cache: Arc<DnsCache>,
+    router: Arc<QueryRouter>,
+    mut rx: Vec<mpsc::Receiver<InFlightTask>>,
+    routes: Routes,
+) -> Result<(), SCloudException> {
+    while let Some(mut msg) = recv_any(&mut rx).await {
+        let hit = msg.task.query.as_ref().and_then(|q| {
+            let question = q.questions.first()?;
+            let route = router.route_on(
+                msg.task.listener.as_deref(),
+                &question.q_name,
+                msg.task.for_who.ip(),
+            );
+            if matches!(route, Route::Refused) {
+                return None;
+            }
+            cache.get(q)
+        });
+        let next = match hit {
+            Some(response) => {
+                msg.task.response = Some(response);
+                WorkerType::ENCODER
             }
+            None => WorkerType::QUERY_DISPATCHER,
+        };
+        worker.jobs_done.fetch_add(1, Ordering::Relaxed);
+        if !route_task(msg, &routes, next).await {
+            return Ok(());
         }
     }
+    Ok(())
 }
@@ -1,37 +1,46 @@
+use crate::config::Config;
+use crate::dns::cache::DnsCache;
 use crate::exceptions::SCloudException;
 use crate::workers::SCloudWorker;
+use crate::workers::queue::{forward_task, recv_any};
 use crate::workers::task::InFlightTask;
+use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::Ordering;
 use tokio::sync::mpsc;
 
 pub async fn run_dns_cache_writer(
     worker: Arc<SCloudWorker>,
-    mut rx: Vec<mpsc::Receiver<InFlightTask>>,
+    rx: Vec<mpsc::Receiver<InFlightTask>>,
     tx: Vec<mpsc::Sender<InFlightTask>>,
 ) -> Result<(), SCloudException> {
-    loop {
-        for rx_channel in rx.iter_mut() {
-            while let Some(msg) = rx_channel.recv().await {
-                let mut current = Some(msg);
-
-                for tx_channel in tx.iter() {
-                    match tx_channel.try_send(current.take().unwrap()) {
-                        Ok(_) => break,
-                        Err(mpsc::error::TrySendError::Full(returned)) => {
-                            current = Some(returned);
-                        }
-                        Err(mpsc::error::TrySendError::Closed(_)) => {
-                            return Ok(());
-                        }
-                    }
-                }
+    let cfg = Config::from_file(Path::new("./config/config.json"))?;
+    run_dns_cache_writer_with_cache(worker, DnsCache::shared(&cfg.cache), rx, tx).await
+}
 
-                if let Some(unsent) = current {
-                    if tx[0].send(unsent).await.is_err() {
-                        return Ok(());
-                    }
-                }
-            }
+/// Store the answers going through `rx` into `cache`, then hand every task
+/// over to `tx`.
+///
+/// Zone and resolver answers both come as `task.response`; the resolver
+/// only attaches upstream replies answering the question of the client.
+pub async fn run_dns_cache_writer_with_cache(
+    worker: Arc<SCloudWorker>,
+    cache: Arc<DnsCache>,
+    mut rx: Vec<mpsc::Receiver<InFlightTask>>,
+    tx: Vec<mpsc::Sender<InFlightTask>>,
+) -> Result<(), SCloudException> {
+    while let Some(msg) = recv_any(&mut rx).await {
+        let stored = msg
+            .task
+            .response
+            .as_ref()
+            .is_some_and(|response| cache.insert(response));
+        if stored {
+            worker.jobs_done.fetch_add(1, Ordering::Relaxed);
+        }
+        if !forward_task(msg, &tx).await {
+            return Ok(());
         }
     }
+    Ok(())
 }
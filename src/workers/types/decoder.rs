@@ -1,46 +1,89 @@
+use crate::dns::packet::DNSPacket;
+use crate::dns::packet::header::Header;
 use crate::exceptions::SCloudException;
-use crate::workers::SCloudWorker;
+use crate::workers::queue::{Routes, recv_any, route_task};
 use crate::workers::task::InFlightTask;
+use crate::workers::{SCloudWorker, WorkerType};
 use crate::{log_debug, log_trace};
 use bytes::Buf;
 use std::sync::Arc;
+use std::sync::atomic::Ordering;
 use tokio::sync::mpsc;
 
+/// Decode every incoming payload into a `DNSPacket` attached to the task.
+///
+/// Valid queries go on to the cache lookup. A malformed query whose header
+/// can still be read is answered with FORMERR straight through the encoder;
+/// anything else (no header, or a response rather than a query) is dropped.
 pub async fn run_dns_decoder(
     worker: Arc<SCloudWorker>,
     mut rx: Vec<mpsc::Receiver<InFlightTask>>,
-    tx: Vec<mpsc::Sender<InFlightTask>>,
+    routes: Routes,
 ) -> Result<(), SCloudException> {
-    loop {
-        for rx_channel in rx.iter_mut() {
-            while let Some(msg) = rx_channel.recv().await {
+    while let Some(mut msg) = recv_any(&mut rx).await {
+        log_debug!(
+            "decoder got {} bytes from {}",
+            msg.task.payload.len(),
+            msg.task.for_who
+        );
+        log_trace!("bytes: {:?}", msg.task.payload.chunk());
+
+        let next = match decode_query(&msg.task.payload) {
+            Ok(query) => {
+                msg.task.query = Some(query);
+                worker.jobs_done.fetch_add(1, Ordering::Relaxed);
+                WorkerType::CACHE_LOOKUP
+            }
+            Err(e) => {
                 log_debug!(
-                    "decoder got {} bytes from {}",
-                    msg.task.payload.len(),
-                    msg.task.for_who
+                    "decoder: malformed query from {}: {}",
+                    msg.task.for_who,
+                    e.to_str()
                 );
-                log_trace!("bytes: {:?}", msg.task.payload.chunk());
-
-                let mut current = Some(msg);
-
-                for tx_channel in tx.iter() {
-                    match tx_channel.try_send(current.take().unwrap()) {
-                        Ok(_) => break,
-                        Err(mpsc::error::TrySendError::Full(returned)) => {
-                            current = Some(returned);
-                        }
-                        Err(mpsc::error::TrySendError::Closed(_)) => {
-                            return Ok(());
-                        }
-                    }
-                }
-
-                if let Some(unsent) = current {
-                    if tx[0].send(unsent).await.is_err() {
-                        return Ok(());
+                worker.jobs_failed.fetch_add(1, Ordering::Relaxed);
+                match format_error(&msg.task.payload) {
+                    Some(response) => {
+                        msg.task.response = Some(response);
+                        WorkerType::ENCODER
                     }
+                    None => continue,
                 }
             }
+        };
+
+        if !route_task(msg, &routes, next).await {
+            return Ok(());
         }
     }
+    Ok(())
+}
+
+/// Parse a query, which must hold exactly one question.
+pub(crate) fn decode_query(buf: &[u8]) -> Result<DNSPacket, SCloudException> {
+    let packet = DNSPacket::from_bytes(buf)?;
+    if packet.header.qr || packet.questions.len() != 1 {
+        return Err(SCloudException::SCLOUD_WORKER_DECODER_NOT_A_QUERY);
+    }
+    Ok(packet)
+}
+
+/// FORMERR response to a query that could not be decoded.
+///
+/// Only the header is echoed back. Returns `None` when the header itself
+/// cannot be read, or when the packet is a response: answering those could
+/// start a loop between two servers.
+pub(crate) fn format_error(buf: &[u8]) -> Option<DNSPacket> {
+    let header = Header::from_bytes(buf).ok()?;
+    if header.qr {
+        return None;
+    }
+    let query = DNSPacket {
+        header,
+        questions: vec![],
+        answers: vec![],
+        authorities: vec![],
+        additionals: vec![],
+        edns: None,
+    };
+    Some(DNSPacket::new_response(&query, Header::RCODE_FORMERR))
 }
@@ -10,15 +10,27 @@ use hyper_util::server::conn::auto::Builder;
 use std::collections::HashSet;
 use std::net::SocketAddr;
 use std::path::Path;
+use std::pin::Pin;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 use tokio::net::TcpListener;
 use tokio::sync::mpsc;
 use tokio::time::timeout;
+use tokio_rustls::TlsAcceptor;
 
-use crate::config::Config;
+use crate::config::{Config, DohConfig};
+use crate::dns::packet::DNSPacket;
+use crate::dns::packet::header::Header;
+use crate::dns::packet::question::QuestionSection;
+use crate::dns::q_class::DNSClass;
+use crate::dns::q_type::DNSRecordType;
 use crate::exceptions::SCloudException;
 use crate::utils;
+use crate::utils::tls::TlsCertStore;
+use crate::workers::queue::forward_task;
 use crate::workers::task::{InFlightTask, SCloudWorkerTask};
 use crate::workers::{SCloudWorker, WorkerType, reply_registry};
 use crate::{log_debug, log_error, log_info};
@@ -26,12 +38,21 @@ use crate::{log_debug, log_error, log_info};
 const MAX_DNS_MESSAGE_BYTES: usize = 65_535;
 const REPLY_TIMEOUT_SECS: u64 = 10;
 const DNS_MESSAGE_MIME: &str = "application/dns-message";
+const DNS_JSON_MIME: &str = "application/dns-json";
+const DEFAULT_MAX_CONCURRENT_STREAMS: u32 = 100;
+const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 30;
+const TLS_HANDSHAKE_TIMEOUT_SECS: u64 = 5;
+// how often the certificate files are checked for changes
+const TLS_RELOAD_EVERY_SECS: u64 = 60;
+// RFC 8484 section 5.2: HTTP/2 is the minimum recommended version
+const DOH_ALPN: [&[u8]; 2] = [b"h2", b"http/1.1"];
 
 #[derive(Clone)]
 struct DohHandlerCtx {
     worker: Arc<SCloudWorker>,
     tx: Vec<mpsc::Sender<InFlightTask>>,
     paths: Arc<HashSet<String>>,
+    json_paths: Arc<HashSet<String>>,
     allowed_origins: Arc<HashSet<String>>,
 }
 
@@ -60,14 +81,53 @@ pub async fn run_dns_doh_acceptor(
         .await
         .map_err(|_| SCloudException::SCLOUD_WORKER_LISTENER_BIND_FAILED)?;
 
-    log_info!("DoH acceptor listening on http://{}", bind_addr);
+    if !cfg.doh.terminate_tls {
+        log_info!("DoH acceptor listening on http://{}", bind_addr);
+        return run_dns_doh_acceptor_with_listener(worker, listener, &cfg.doh, None, tx).await;
+    }
+
+    let certs = Arc::new(TlsCertStore::load(
+        cfg.doh.tls_cert_path.as_deref().unwrap_or_default(),
+        cfg.doh.tls_key_path.as_deref().unwrap_or_default(),
+    )?);
+    let tls = TlsAcceptor::from(certs.server_config(&DOH_ALPN)?);
+    log_info!("DoH acceptor listening on https://{}", bind_addr);
+
+    tokio::select! {
+        res = run_dns_doh_acceptor_with_listener(worker, listener, &cfg.doh, Some(tls), tx) => res,
+        res = certs.watch(Duration::from_secs(TLS_RELOAD_EVERY_SECS)) => res,
+    }
+}
+
+/// Serve DoH on `listener`, over TLS when `tls` is set.
+///
+/// HTTP/1.1 and HTTP/2 are both served: picked by ALPN over TLS, by the
+/// connection preface otherwise.
+pub(crate) async fn run_dns_doh_acceptor_with_listener(
+    worker: Arc<SCloudWorker>,
+    listener: TcpListener,
+    doh: &DohConfig,
+    tls: Option<TlsAcceptor>,
+    tx: Vec<mpsc::Sender<InFlightTask>>,
+) -> Result<(), SCloudException> {
+    if tx.is_empty() {
+        return Err(SCloudException::SCLOUD_WORKER_TX_NOT_SET);
+    }
 
     let ctx = DohHandlerCtx {
         worker: worker.clone(),
         tx,
-        paths: Arc::new(cfg.doh.paths.into_iter().collect()),
-        allowed_origins: Arc::new(cfg.doh.allowed_origins.into_iter().collect()),
+        paths: Arc::new(doh.paths.iter().cloned().collect()),
+        json_paths: Arc::new(doh.json_paths.iter().cloned().collect()),
+        allowed_origins: Arc::new(doh.allowed_origins.iter().cloned().collect()),
     };
+    let mut builder = Builder::new(TokioExecutor::new());
+    builder.http2().max_concurrent_streams(
+        doh.max_concurrent_streams
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_STREAMS),
+    );
+    let idle_timeout =
+        Duration::from_secs(doh.idle_timeout_secs.unwrap_or(DEFAULT_IDLE_TIMEOUT_SECS));
 
     loop {
         let (stream, peer) = match listener.accept().await {
@@ -77,21 +137,147 @@ pub async fn run_dns_doh_acceptor(
                 continue;
             }
         };
-        let io = TokioIo::new(stream);
+        let builder = builder.clone();
         let ctx = ctx.clone();
+        let tls = tls.clone();
 
         tokio::spawn(async move {
-            let svc = service_fn(move |req| handle_request(ctx.clone(), peer, req));
-            if let Err(e) = Builder::new(TokioExecutor::new())
-                .serve_connection(io, svc)
-                .await
+            let Some(tls) = tls else {
+                serve_connection(builder, ctx, stream, peer, idle_timeout).await;
+                return;
+            };
+            match timeout(
+                Duration::from_secs(TLS_HANDSHAKE_TIMEOUT_SECS),
+                tls.accept(stream),
+            )
+            .await
             {
-                log_debug!("doh connection from {} ended: {}", peer, e);
+                Ok(Ok(stream)) => serve_connection(builder, ctx, stream, peer, idle_timeout).await,
+                Ok(Err(e)) => {
+                    log_debug!("doh: handshake with {} failed: {}", peer, e);
+                }
+                Err(_) => {
+                    log_debug!("doh: handshake with {} timed out", peer);
+                }
             }
         });
     }
 }
 
+/// Serve the requests of one connection until the client closes it, or no
+/// byte went through for `idle_timeout`. An idle connection is shut down
+/// gracefully, so requests still waiting for their answer complete.
+async fn serve_connection<S>(
+    builder: Builder<TokioExecutor>,
+    ctx: DohHandlerCtx,
+    stream: S,
+    peer: SocketAddr,
+    idle_timeout: Duration,
+) where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let activity = Arc::new(Activity::new());
+    let io = TokioIo::new(ActivityIo {
+        inner: stream,
+        activity: activity.clone(),
+    });
+    let svc = service_fn(move |req| handle_request(ctx.clone(), peer, req));
+    let conn = builder.serve_connection(io, svc);
+    tokio::pin!(conn);
+
+    loop {
+        let idle = activity.idle_for();
+        if idle >= idle_timeout {
+            log_debug!("doh: idle connection from {} closed", peer);
+            conn.as_mut().graceful_shutdown();
+            break;
+        }
+        tokio::select! {
+            res = conn.as_mut() => {
+                if let Err(e) = res {
+                    log_debug!("doh connection from {} ended: {}", peer, e);
+                }
+                return;
+            }
+            _ = tokio::time::sleep(idle_timeout - idle) => {}
+        }
+    }
+    if let Err(e) = conn.await {
+        log_debug!("doh connection from {} ended: {}", peer, e);
+    }
+}
+
+/// Time of the last byte read or written on a connection.
+#[derive(Debug)]
+struct Activity {
+    since: Instant,
+    last_ms: AtomicU64, // milliseconds after `since`
+}
+
+impl Activity {
+    fn new() -> Activity {
+        Activity {
+            since: Instant::now(),
+            last_ms: AtomicU64::new(0),
+        }
+    }
+
+    fn touch(&self) {
+        let now = self.since.elapsed().as_millis() as u64;
+        self.last_ms.store(now, Ordering::Relaxed);
+    }
+
+    fn idle_for(&self) -> Duration {
+        let last = Duration::from_millis(self.last_ms.load(Ordering::Relaxed));
+        self.since.elapsed().saturating_sub(last)
+    }
+}
+
+/// Stream recording its traffic in an `Activity`.
+struct ActivityIo<S> {
+    inner: S,
+    activity: Arc<Activity>,
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for ActivityIo<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let before = buf.filled().len();
+        let res = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if buf.filled().len() > before {
+            self.activity.touch();
+        }
+        res
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for ActivityIo<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let res = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = res
+            && n > 0
+        {
+            self.activity.touch();
+        }
+        res
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
 async fn handle_request(
     ctx: DohHandlerCtx,
     peer: SocketAddr,
@@ -109,6 +295,15 @@ async fn handle_request(
         return Ok(cors_preflight(&ctx, origin.as_deref()));
     }
 
+    if ctx.json_paths.contains(&uri_path) {
+        let mut resp = match method {
+            Method::GET => handle_json(&ctx, peer, req.uri().query().unwrap_or("")).await,
+            _ => json_error(StatusCode::METHOD_NOT_ALLOWED, "method not allowed"),
+        };
+        apply_cors(&mut resp, &ctx, origin.as_deref());
+        return Ok(resp);
+    }
+
     if !ctx.paths.contains(&uri_path) {
         return Ok(simple(StatusCode::NOT_FOUND, "unknown doh path"));
     }
@@ -144,6 +339,77 @@ async fn handle_request(
     Ok(resp)
 }
 
+/// `GET ?name=<name>&type=<type>&cd=<bool>` answered in the JSON format of
+/// the Google and Cloudflare DoH APIs.
+///
+/// `type` is a mnemonic or a number and defaults to `A`.
+async fn handle_json(ctx: &DohHandlerCtx, peer: SocketAddr, query: &str) -> Response<Full<Bytes>> {
+    let mut name = None;
+    let mut rtype = DNSRecordType::A;
+    let mut cd = false;
+    for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+        let Some(value) = percent_decode(value) else {
+            return json_error(StatusCode::BAD_REQUEST, "invalid percent-encoding");
+        };
+        match key {
+            "name" => name = Some(value),
+            "type" => match DNSRecordType::from_name(&value) {
+                Some(t) => rtype = t,
+                None => return json_error(StatusCode::BAD_REQUEST, "unknown type parameter"),
+            },
+            "cd" => cd = matches!(value.as_str(), "1" | "true"),
+            _ => {}
+        }
+    }
+    let Some(name) = name.filter(|n| !n.is_empty()) else {
+        return json_error(StatusCode::BAD_REQUEST, "missing name parameter");
+    };
+
+    let mut packet = DNSPacket::new_query(&[QuestionSection {
+        q_name: name.trim_end_matches('.').to_string(),
+        q_type: rtype,
+        q_class: DNSClass::IN,
+    }]);
+    if cd {
+        packet.header.z |= Header::Z_CD;
+    }
+    let Ok(wire) = packet.to_bytes() else {
+        return json_error(StatusCode::BAD_REQUEST, "invalid name parameter");
+    };
+
+    let reply = match dispatch_and_wait(ctx, peer, Bytes::from(wire)).await {
+        Ok(b) => b,
+        Err(status) => return json_error(status, "dispatch failed"),
+    };
+    let Ok(reply) = DNSPacket::from_bytes(&reply) else {
+        return json_error(StatusCode::BAD_GATEWAY, "unreadable dns reply");
+    };
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", DNS_JSON_MIME)
+        .header("Cache-Control", "no-store")
+        .body(Full::new(Bytes::from(reply.to_json().to_string())))
+        .unwrap()
+}
+
+/// Decode a query string value: `%XX` escapes and `+` for a space.
+fn percent_decode(value: &str) -> Option<String> {
+    let mut out = Vec::with_capacity(value.len());
+    let mut bytes = value.bytes();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'%' => {
+                let hex = [bytes.next()?, bytes.next()?];
+                out.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            b'+' => out.push(b' '),
+            _ => out.push(b),
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
 fn extract_get_dns(req: &Request<Incoming>) -> Result<Bytes, Response<Full<Bytes>>> {
     let q = req.uri().query().unwrap_or("");
     let mut dns_param: Option<&str> = None;
@@ -210,6 +476,11 @@ async fn dispatch_and_wait(
         priority: 0,
         reply_to: Some(reply_registry::REPLY_TAG_DOH.to_string()),
         correlation_id: None,
+        udp_socket: None,
+        query: None,
+        response: None,
+        forwarder: None,
+        listener: None,
     };
     let in_flight = InFlightTask { task, _permit: permit };
 
@@ -229,25 +500,6 @@ async fn dispatch_and_wait(
     }
 }
 
-async fn forward_task(task: InFlightTask, tx: &[mpsc::Sender<InFlightTask>]) -> bool {
-    let mut current = Some(task);
-    for tx_channel in tx.iter() {
-        match tx_channel.try_send(current.take().unwrap()) {
-            Ok(_) => return true,
-            Err(mpsc::error::TrySendError::Full(returned)) => {
-                current = Some(returned);
-            }
-            Err(mpsc::error::TrySendError::Closed(_)) => return false,
-        }
-    }
-    if let Some(unsent) = current {
-        if let Some(first) = tx.first() {
-            return first.send(unsent).await.is_ok();
-        }
-    }
-    true
-}
-
 fn simple(status: StatusCode, msg: &str) -> Response<Full<Bytes>> {
     Response::builder()
         .status(status)
@@ -256,6 +508,16 @@ fn simple(status: StatusCode, msg: &str) -> Response<Full<Bytes>> {
         .unwrap()
 }
 
+fn json_error(status: StatusCode, msg: &str) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", DNS_JSON_MIME)
+        .body(Full::new(Bytes::from(
+            serde_json::json!({ "error": msg }).to_string(),
+        )))
+        .unwrap()
+}
+
 fn cors_preflight(ctx: &DohHandlerCtx, origin: Option<&str>) -> Response<Full<Bytes>> {
     let mut resp = Response::builder()
         .status(StatusCode::NO_CONTENT)
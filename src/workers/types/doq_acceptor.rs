@@ -0,0 +1,255 @@
+use bytes::Bytes;
+use quinn::crypto::rustls::QuicServerConfig;
+use quinn::{Connection, Endpoint, IdleTimeout, RecvStream, SendStream, TransportConfig, VarInt};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+use crate::config::{Config, DoqConfig};
+use crate::exceptions::SCloudException;
+use crate::utils;
+use crate::utils::tls::TlsCertStore;
+use crate::workers::queue::forward_task;
+use crate::workers::task::{InFlightTask, SCloudWorkerTask};
+use crate::workers::{SCloudWorker, WorkerType, reply_registry};
+use crate::{log_debug, log_info};
+
+const MAX_DNS_MESSAGE_BYTES: usize = 65_535;
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_MAX_CONCURRENT_STREAMS: u32 = 100;
+const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 30;
+// how often the certificate files are checked for changes
+const TLS_RELOAD_EVERY: Duration = Duration::from_secs(60);
+// RFC 9250 section 4.1.1 ALPN token
+const DOQ_ALPN: &[u8] = b"doq";
+// RFC 9250 section 4.3 error codes
+const DOQ_INTERNAL_ERROR: u32 = 0x1;
+const DOQ_PROTOCOL_ERROR: u32 = 0x2;
+
+pub async fn run_dns_doq_acceptor(
+    worker: Arc<SCloudWorker>,
+    tx: Vec<mpsc::Sender<InFlightTask>>,
+) -> Result<(), SCloudException> {
+    if tx.is_empty() {
+        return Err(SCloudException::SCLOUD_WORKER_TX_NOT_SET);
+    }
+
+    let cfg = Config::from_file(Path::new("./config/config.json"))?;
+    if !cfg.doq.enabled {
+        log_info!("DoQ disabled in config, acceptor idle");
+        futures_util::future::pending::<()>().await;
+        return Ok(());
+    }
+
+    let bind_addr: SocketAddr = cfg
+        .doq
+        .bind
+        .parse()
+        .map_err(|_| SCloudException::SCLOUD_CONFIG_IMPOSSIBLE_TO_PARSE_ADDR)?;
+    let certs = Arc::new(TlsCertStore::load(
+        cfg.doq.tls_cert_path.as_deref().unwrap_or_default(),
+        cfg.doq.tls_key_path.as_deref().unwrap_or_default(),
+    )?);
+    let endpoint = doq_endpoint(bind_addr, &certs, &cfg.doq)?;
+    log_info!("DoQ acceptor listening on quic://{}", bind_addr);
+
+    tokio::select! {
+        res = run_dns_doq_acceptor_with_endpoint(worker, endpoint, tx) => res,
+        res = certs.watch(TLS_RELOAD_EVERY) => res,
+    }
+}
+
+/// QUIC endpoint bound to `addr`, serving the certificate of `certs` with
+/// the stream limit and idle timeout of `doq`.
+pub(crate) fn doq_endpoint(
+    addr: SocketAddr,
+    certs: &Arc<TlsCertStore>,
+    doq: &DoqConfig,
+) -> Result<Endpoint, SCloudException> {
+    let crypto = QuicServerConfig::try_from(certs.server_config(&[DOQ_ALPN])?)
+        .map_err(|_| SCloudException::SCLOUD_TLS_CONFIG_FAILED)?;
+
+    let idle_timeout =
+        Duration::from_secs(doq.idle_timeout_secs.unwrap_or(DEFAULT_IDLE_TIMEOUT_SECS));
+    let mut transport = TransportConfig::default();
+    transport
+        .max_concurrent_bidi_streams(VarInt::from_u32(
+            doq.max_concurrent_streams
+                .unwrap_or(DEFAULT_MAX_CONCURRENT_STREAMS),
+        ))
+        // DoQ only uses client-initiated bidirectional streams
+        .max_concurrent_uni_streams(VarInt::from_u32(0))
+        .max_idle_timeout(Some(
+            IdleTimeout::try_from(idle_timeout)
+                .map_err(|_| SCloudException::SCLOUD_CONFIG_INVALID_DOQ)?,
+        ));
+
+    let mut server = quinn::ServerConfig::with_crypto(Arc::new(crypto));
+    server.transport_config(Arc::new(transport));
+    Endpoint::server(server, addr).map_err(|_| SCloudException::SCLOUD_WORKER_DOQ_ENDPOINT_FAILED)
+}
+
+/// Serve DNS over QUIC (RFC 9250) on `endpoint` until it is closed.
+///
+/// Every connection gets its own task, and every query stream of a
+/// connection its own task as well, so a slow answer never holds back the
+/// other queries of the connection.
+pub(crate) async fn run_dns_doq_acceptor_with_endpoint(
+    worker: Arc<SCloudWorker>,
+    endpoint: Endpoint,
+    tx: Vec<mpsc::Sender<InFlightTask>>,
+) -> Result<(), SCloudException> {
+    if tx.is_empty() {
+        return Err(SCloudException::SCLOUD_WORKER_TX_NOT_SET);
+    }
+
+    while let Some(incoming) = endpoint.accept().await {
+        let worker = worker.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            match incoming.await {
+                Ok(conn) => serve_connection(worker, conn, tx).await,
+                Err(e) => {
+                    log_debug!("doq: handshake failed: {}", e);
+                }
+            }
+        });
+    }
+    Ok(())
+}
+
+async fn serve_connection(
+    worker: Arc<SCloudWorker>,
+    conn: Connection,
+    tx: Vec<mpsc::Sender<InFlightTask>>,
+) {
+    let peer = conn.remote_address();
+    loop {
+        let (send, recv) = match conn.accept_bi().await {
+            Ok(streams) => streams,
+            Err(e) => {
+                log_debug!("doq: connection from {} ended: {}", peer, e);
+                return;
+            }
+        };
+        tokio::spawn(serve_stream(
+            worker.clone(),
+            conn.clone(),
+            peer,
+            send,
+            recv,
+            tx.clone(),
+        ));
+    }
+}
+
+/// Answer the single query of one stream.
+///
+/// RFC 9250 section 4.2: the client sends one length-prefixed message with
+/// a message ID of 0 and closes its side of the stream; the server answers
+/// the same way. Anything else is a protocol error that closes the whole
+/// connection.
+async fn serve_stream(
+    worker: Arc<SCloudWorker>,
+    conn: Connection,
+    peer: SocketAddr,
+    mut send: SendStream,
+    mut recv: RecvStream,
+    tx: Vec<mpsc::Sender<InFlightTask>>,
+) {
+    let message = match recv.read_to_end(2 + MAX_DNS_MESSAGE_BYTES).await {
+        Ok(message) => message,
+        Err(e) => {
+            log_debug!("doq: read from {} failed: {}", peer, e);
+            return;
+        }
+    };
+    let Some(query) = unframe(&message) else {
+        log_debug!("doq: malformed query from {}, connection closed", peer);
+        conn.close(VarInt::from_u32(DOQ_PROTOCOL_ERROR), b"malformed query");
+        return;
+    };
+
+    let Some(reply) = dispatch(&worker, peer, Bytes::copy_from_slice(query), &tx).await else {
+        let _ = send.reset(VarInt::from_u32(DOQ_INTERNAL_ERROR));
+        return;
+    };
+
+    let mut frame = Vec::with_capacity(reply.len() + 2);
+    frame.extend_from_slice(&(reply.len() as u16).to_be_bytes());
+    frame.extend_from_slice(&reply);
+    if let Err(e) = send.write_all(&frame).await {
+        log_debug!("doq: write to {} failed: {}", peer, e);
+        return;
+    }
+    let _ = send.finish();
+}
+
+/// The DNS message of a stream: exactly as long as its 2-byte prefix says,
+/// with a message ID of 0.
+fn unframe(message: &[u8]) -> Option<&[u8]> {
+    let (len, query) = message.split_first_chunk::<2>()?;
+    if usize::from(u16::from_be_bytes(*len)) != query.len() || query.len() < 12 {
+        return None;
+    }
+    if query[..2] != [0, 0] {
+        return None;
+    }
+    Some(query)
+}
+
+/// Hand one query to the pipeline and wait for its reply.
+///
+/// Returns `None` when the query is dropped: worker at capacity, pipeline
+/// closed, or no reply in time.
+async fn dispatch(
+    worker: &Arc<SCloudWorker>,
+    peer: SocketAddr,
+    query: Bytes,
+    tx: &[mpsc::Sender<InFlightTask>],
+) -> Option<Bytes> {
+    let Ok(permit) = worker.in_flight_sem.clone().try_acquire_owned() else {
+        log_debug!("doq: worker at capacity, query from {} dropped", peer);
+        return None;
+    };
+
+    let task_id = utils::uuid::generate_uuid();
+    let task = SCloudWorkerTask {
+        task_id,
+        for_type: WorkerType::DOQ_ACCEPTOR,
+        for_who: peer,
+        payload: query,
+        attempts: 0,
+        max_attempts: 0,
+        created_at: SystemTime::now(),
+        deadline_timeout: None,
+        priority: 0,
+        reply_to: Some(reply_registry::REPLY_TAG_DOQ.to_string()),
+        correlation_id: None,
+        udp_socket: None,
+        query: None,
+        response: None,
+        forwarder: None,
+        listener: None,
+    };
+    let in_flight = InFlightTask {
+        task,
+        _permit: permit,
+    };
+
+    let rx = reply_registry::register(task_id);
+    if !forward_task(in_flight, tx).await {
+        reply_registry::drop_entry(&task_id);
+        return None;
+    }
+    match timeout(REPLY_TIMEOUT, rx).await {
+        Ok(Ok(reply)) => Some(reply),
+        _ => {
+            reply_registry::drop_entry(&task_id);
+            None
+        }
+    }
+}
@@ -1,37 +1,115 @@
+use crate::config::Config;
+use crate::dns::packet::DNSPacket;
+use crate::dns::packet::edns::Edns;
+use crate::dns::packet::header::Header;
 use crate::exceptions::SCloudException;
+use crate::log_error;
 use crate::workers::SCloudWorker;
+use crate::workers::queue::{forward_task, recv_any};
 use crate::workers::task::InFlightTask;
+use bytes::Bytes;
+use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::Ordering;
 use tokio::sync::mpsc;
 
+// stream transports (TCP, DoT, DoQ, DoH) frame messages with a 16-bit length
+const MAX_STREAM_RESPONSE: usize = u16::MAX as usize;
+
+/// Size limits applied by the encoder to every response.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ResponseLimits {
+    /// UDP payload size advertised in the OPT record, `None` when EDNS is
+    /// disabled (see `Config::edns_udp_size`).
+    pub(crate) edns_udp_size: Option<u16>,
+    /// Largest response sent over UDP, whatever the client advertises.
+    pub(crate) max_udp_response: usize,
+}
+
+impl ResponseLimits {
+    pub(crate) fn from_config(cfg: &Config) -> ResponseLimits {
+        ResponseLimits {
+            edns_udp_size: cfg.edns_udp_size(),
+            max_udp_response: cfg.amplification_mitigation.max_response_size_udp,
+        }
+    }
+}
+
 pub async fn run_dns_encoder(
     worker: Arc<SCloudWorker>,
-    mut rx: Vec<mpsc::Receiver<InFlightTask>>,
+    rx: Vec<mpsc::Receiver<InFlightTask>>,
     tx: Vec<mpsc::Sender<InFlightTask>>,
 ) -> Result<(), SCloudException> {
-    loop {
-        for rx_channel in rx.iter_mut() {
-            while let Some(msg) = rx_channel.recv().await {
-                let mut current = Some(msg);
-
-                for tx_channel in tx.iter() {
-                    match tx_channel.try_send(current.take().unwrap()) {
-                        Ok(_) => break,
-                        Err(mpsc::error::TrySendError::Full(returned)) => {
-                            current = Some(returned);
-                        }
-                        Err(mpsc::error::TrySendError::Closed(_)) => {
-                            return Ok(());
-                        }
-                    }
-                }
+    let cfg = Config::from_file(Path::new("./config/config.json"))?;
+    run_dns_encoder_with_limits(worker, ResponseLimits::from_config(&cfg), rx, tx).await
+}
 
-                if let Some(unsent) = current {
-                    if tx[0].send(unsent).await.is_err() {
-                        return Ok(());
-                    }
+/// Encode the responses of `rx` into their task payload.
+///
+/// The EDNS part of every response is negotiated against its query first:
+/// with `limits.edns_udp_size` set, a query carrying an OPT record gets one
+/// back advertising that size (see `DNSPacket::negotiate_edns`). Responses
+/// to UDP queries are then truncated to the smaller of the sizes the client
+/// and the server advertise, capped by `limits.max_udp_response`, or to 512
+/// bytes without EDNS; other transports get the full response.
+///
+/// A response that cannot be encoded is replaced by a SERVFAIL; without a
+/// query to build it from, the task is dropped rather than sending its
+/// payload (the query itself) back. So is a task without a response.
+pub async fn run_dns_encoder_with_limits(
+    worker: Arc<SCloudWorker>,
+    limits: ResponseLimits,
+    mut rx: Vec<mpsc::Receiver<InFlightTask>>,
+    tx: Vec<mpsc::Sender<InFlightTask>>,
+) -> Result<(), SCloudException> {
+    while let Some(mut msg) = recv_any(&mut rx).await {
+        let Some(mut response) = msg.task.response.take() else {
+            log_error!(
+                "encoder: task {} has no response, dropped",
+                msg.task.task_id
+            );
+            worker.jobs_failed.fetch_add(1, Ordering::Relaxed);
+            continue;
+        };
+        let query = msg.task.query.as_ref();
+        if let Some(query) = query {
+            response.negotiate_edns(query, limits.edns_udp_size);
+        }
+        let max = if msg.task.udp_socket.is_some() {
+            query.map_or(usize::from(Edns::MIN_UDP_SIZE), |q| {
+                q.max_udp_response_size(limits.edns_udp_size, limits.max_udp_response)
+            })
+        } else {
+            MAX_STREAM_RESPONSE
+        };
+        match response.truncate_to(max) {
+            Ok(bytes) => {
+                msg.task.payload = Bytes::from(bytes);
+                worker.jobs_done.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) => {
+                log_error!(
+                    "encoder: task {} response not encoded: {}",
+                    msg.task.task_id,
+                    e.to_str()
+                );
+                worker.jobs_failed.fetch_add(1, Ordering::Relaxed);
+                match query.and_then(|q| servfail(q, &limits)) {
+                    Some(bytes) => msg.task.payload = Bytes::from(bytes),
+                    None => continue,
                 }
             }
         }
+        if !forward_task(msg, &tx).await {
+            return Ok(());
+        }
     }
+    Ok(())
+}
+
+/// Encoded SERVFAIL answering `query`, small enough for any transport.
+fn servfail(query: &DNSPacket, limits: &ResponseLimits) -> Option<Vec<u8>> {
+    let mut response = DNSPacket::new_response(query, Header::RCODE_SERVFAIL);
+    response.negotiate_edns(query, limits.edns_udp_size);
+    response.to_bytes().ok()
 }
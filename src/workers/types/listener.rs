@@ -1,5 +1,7 @@
 use crate::exceptions::SCloudException;
 use crate::utils;
+use crate::workers::queue::forward_task;
+use crate::workers::reply_registry;
 use crate::workers::task::{InFlightTask, SCloudWorkerTask};
 use crate::workers::{SCloudWorker, WorkerState, WorkerType};
 use bytes::Bytes;
@@ -14,6 +16,7 @@ pub async fn run_dns_listener_with_socket(
     rx: Vec<mpsc::Receiver<InFlightTask>>,
     tx: Vec<mpsc::Sender<InFlightTask>>,
 ) -> Result<(), SCloudException> {
+    let socket = Arc::new(socket);
     let mut buf = [0u8; 65_535];
     worker.set_state(WorkerState::IDLE);
     if tx.is_empty() {
@@ -44,8 +47,13 @@ pub async fn run_dns_listener_with_socket(
             created_at: SystemTime::now(),
             deadline_timeout: None,
             priority: 0,
-            reply_to: None,
+            reply_to: Some(reply_registry::REPLY_TAG_UDP.to_string()),
             correlation_id: None,
+            udp_socket: Some(socket.clone()),
+            query: None,
+            response: None,
+            forwarder: None,
+            listener: worker.get_listener(),
         };
 
         let in_flight = InFlightTask {
@@ -92,8 +100,13 @@ pub async fn run_dns_listener_with_shared_socket(
             created_at: SystemTime::now(),
             deadline_timeout: None,
             priority: 0,
-            reply_to: None,
+            reply_to: Some(reply_registry::REPLY_TAG_UDP.to_string()),
             correlation_id: None,
+            udp_socket: Some(udp.clone()),
+            query: None,
+            response: None,
+            forwarder: None,
+            listener: worker.get_listener(),
         };
 
         let in_flight = InFlightTask {
@@ -104,24 +117,5 @@ pub async fn run_dns_listener_with_shared_socket(
     }
 }
 
-async fn forward_task(task: InFlightTask, tx: &[mpsc::Sender<InFlightTask>]) -> bool {
-    let mut current = Some(task);
-    for tx_channel in tx.iter() {
-        match tx_channel.try_send(current.take().unwrap()) {
-            Ok(_) => return true,
-            Err(mpsc::error::TrySendError::Full(returned)) => {
-                current = Some(returned);
-            }
-            Err(mpsc::error::TrySendError::Closed(_)) => return false,
-        }
-    }
-    if let Some(unsent) = current {
-        if let Some(tx_channel) = tx.first() {
-            return tx_channel.send(unsent).await.is_ok();
-        }
-    }
-    true
-}
-
 #[cfg(target_os = "windows")]
 pub static SHARED_UDP_SOCKET: std::sync::OnceLock<Arc<UdpSocket>> = std::sync::OnceLock::new();
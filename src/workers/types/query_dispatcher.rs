@@ -1,39 +1,174 @@
+use crate::config::{AclEntry, Config, ForwarderConfig, ListenerConfig, ZoneType};
+use crate::dns::packet::DNSPacket;
+use crate::dns::packet::header::Header;
+use crate::dns::zones::canonical_name;
 use crate::exceptions::SCloudException;
-use crate::workers::SCloudWorker;
+use crate::log_debug;
+use crate::utils::acl::acl_allows;
+use crate::workers::queue::{Routes, recv_any, route_task};
 use crate::workers::task::InFlightTask;
-use crate::{log_debug, log_trace};
-use bytes::Buf;
+use crate::workers::{SCloudWorker, WorkerType};
+use std::net::IpAddr;
+use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::Ordering;
 use tokio::sync::mpsc;
 
+/// Where a query has to be answered from.
+#[derive(Debug, Clone)]
+pub(crate) enum Route {
+    /// Name under a master or slave zone: answered by the zone manager.
+    Zone,
+    /// Name under a forward zone: sent to that zone's forwarders.
+    Forward(Arc<ForwarderConfig>),
+    /// Any other name, for a client allowed to recurse.
+    Recursion,
+    /// Any other name, for everybody else.
+    Refused,
+}
+
+/// Routing table of the query dispatcher, built from the configuration.
+#[derive(Debug, Default)]
+pub(crate) struct QueryRouter {
+    zones: Vec<(String, Route)>, // canonical zone name -> route
+    recursion_enabled: bool,
+    recursion_acl: String,
+    acls: Vec<AclEntry>,
+}
+
+impl QueryRouter {
+    pub(crate) fn from_config(cfg: &Config) -> QueryRouter {
+        let zones = cfg
+            .zone
+            .iter()
+            .filter_map(|z| {
+                let route = match z.kind {
+                    ZoneType::Master | ZoneType::Slave => Route::Zone,
+                    ZoneType::Forward => {
+                        // timeout, EDNS and TCP retry come from the first
+                        // forwarder group, like the resolver's default route
+                        let base = cfg.forwarder.first().cloned().unwrap_or_default();
+                        Route::Forward(Arc::new(ForwarderConfig {
+                            name: z.name.clone(),
+                            addresses: z.forwarders.clone(),
+                            policy: z.forward_policy.clone().unwrap_or(base.policy.clone()),
+                            ..base
+                        }))
+                    }
+                    ZoneType::Stub => return None,
+                };
+                Some((canonical_name(&z.name), route))
+            })
+            .collect();
+
+        QueryRouter {
+            zones,
+            recursion_enabled: cfg.recursion.enabled,
+            recursion_acl: cfg.recursion.allowed_acl.clone(),
+            acls: cfg.acl.clone(),
+        }
+    }
+
+    /// Route a question for `qname` asked by `client`.
+    ///
+    /// The most specific configured zone wins, so a forward zone nested in
+    /// a master zone (or the other way around) takes its own names.
+    pub(crate) fn route(&self, qname: &str, client: IpAddr) -> Route {
+        let qname = canonical_name(qname);
+        let zone = self
+            .zones
+            .iter()
+            .filter(|(name, _)| {
+                name.is_empty() || qname == *name || qname.ends_with(&format!(".{}", name))
+            })
+            .max_by_key(|(name, _)| name.len());
+
+        match zone {
+            Some((_, route)) => route.clone(),
+            None if self.recursion_enabled
+                && acl_allows(&self.acls, &self.recursion_acl, client) =>
+            {
+                Route::Recursion
+            }
+            None => Route::Refused,
+        }
+    }
+
+    /// Route a question asked by `client` on `listener`.
+    ///
+    /// A client outside of the listener ACL is refused, and a listener
+    /// without `recursion_allowed` only answers from the local zones.
+    pub(crate) fn route_on(
+        &self,
+        listener: Option<&ListenerConfig>,
+        qname: &str,
+        client: IpAddr,
+    ) -> Route {
+        let Some(listener) = listener else {
+            return self.route(qname, client);
+        };
+        if !listener.acl.trim().is_empty() && !acl_allows(&self.acls, &listener.acl, client) {
+            return Route::Refused;
+        }
+        match self.route(qname, client) {
+            Route::Forward(_) | Route::Recursion if !listener.recursion_allowed => Route::Refused,
+            route => route,
+        }
+    }
+}
+
 pub async fn run_dns_query_dispatcher(
     worker: Arc<SCloudWorker>,
+    rx: Vec<mpsc::Receiver<InFlightTask>>,
+    routes: Routes,
+) -> Result<(), SCloudException> {
+    let cfg = Config::from_file(Path::new("./config/config.json"))?;
+    let router = QueryRouter::from_config(&cfg);
+    run_dns_query_dispatcher_with_router(worker, Arc::new(router), rx, routes).await
+}
+
+/// Send every query of `rx` to the zone manager or the resolver, or answer
+/// REFUSED through the encoder, according to `router`.
+pub async fn run_dns_query_dispatcher_with_router(
+    worker: Arc<SCloudWorker>,
+    router: Arc<QueryRouter>,
     mut rx: Vec<mpsc::Receiver<InFlightTask>>,
-    tx: Vec<mpsc::Sender<InFlightTask>>,
+    routes: Routes,
 ) -> Result<(), SCloudException> {
-    loop {
-        for rx_channel in rx.iter_mut() {
-            while let Some(msg) = rx_channel.recv().await {
-                let mut current = Some(msg);
-
-                for tx_channel in tx.iter() {
-                    match tx_channel.try_send(current.take().unwrap()) {
-                        Ok(_) => break,
-                        Err(mpsc::error::TrySendError::Full(returned)) => {
-                            current = Some(returned);
-                        }
-                        Err(mpsc::error::TrySendError::Closed(_)) => {
-                            return Ok(());
-                        }
-                    }
-                }
+    while let Some(mut msg) = recv_any(&mut rx).await {
+        let Some(query) = msg.task.query.as_ref() else {
+            log_debug!("dispatcher: task {} has no decoded query", msg.task.task_id);
+            worker.jobs_failed.fetch_add(1, Ordering::Relaxed);
+            continue;
+        };
+        let Some(question) = query.questions.first() else {
+            log_debug!("dispatcher: task {} has no question", msg.task.task_id);
+            worker.jobs_failed.fetch_add(1, Ordering::Relaxed);
+            continue;
+        };
 
-                if let Some(unsent) = current {
-                    if tx[0].send(unsent).await.is_err() {
-                        return Ok(());
-                    }
-                }
+        let route = router.route_on(
+            msg.task.listener.as_deref(),
+            &question.q_name,
+            msg.task.for_who.ip(),
+        );
+        let next = match route {
+            Route::Zone => WorkerType::ZONE_MANAGER,
+            Route::Forward(forwarder) => {
+                msg.task.forwarder = Some(forwarder);
+                WorkerType::RESOLVER
+            }
+            Route::Recursion => WorkerType::RESOLVER,
+            Route::Refused => {
+                msg.task.response = Some(DNSPacket::new_response(query, Header::RCODE_REFUSED));
+                WorkerType::ENCODER
             }
+        };
+        worker.jobs_done.fetch_add(1, Ordering::Relaxed);
+
+        if !route_task(msg, &routes, next).await {
+            return Ok(());
         }
     }
+    Ok(())
 }
@@ -1,39 +1,123 @@
+use crate::config::Config;
+use crate::dns::packet::DNSPacket;
+use crate::dns::packet::header::Header;
+use crate::dns::resolver::forwarder::Forwarder;
 use crate::exceptions::SCloudException;
 use crate::workers::SCloudWorker;
+use crate::workers::queue::{forward_task, recv_any};
 use crate::workers::task::InFlightTask;
-use crate::{log_debug, log_trace};
-use bytes::Buf;
+use crate::{log_debug, log_error};
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::Ordering;
 use tokio::sync::mpsc;
 
 pub async fn run_dns_resolver(
     worker: Arc<SCloudWorker>,
+    rx: Vec<mpsc::Receiver<InFlightTask>>,
+    tx: Vec<mpsc::Sender<InFlightTask>>,
+) -> Result<(), SCloudException> {
+    let cfg = Config::from_file(Path::new("./config/config.json"))?;
+    // recursion goes through the first configured forwarder group
+    let default = match cfg.forwarder.first().map(Forwarder::from_config) {
+        Some(Ok(forwarder)) => Some(Arc::new(forwarder)),
+        Some(Err(e)) => {
+            log_error!("resolver: default forwarder unusable: {}", e.to_str());
+            None
+        }
+        None => None,
+    };
+    run_dns_resolver_with_forwarder(worker, default, rx, tx).await
+}
+
+/// Forward every query of `rx` upstream and hand the reply over to `tx`.
+///
+/// Queries of a forward zone use the forwarders attached to the task by the
+/// dispatcher, the others use `default`. Each query runs in its own task so
+/// a slow upstream does not hold the following ones back. The reply is
+/// decoded into `task.response`, so the encoder negotiates EDNS and
+/// truncates it like any other answer. A query that gets no reply, or a
+/// reply that does not answer its question, is answered with SERVFAIL.
+pub async fn run_dns_resolver_with_forwarder(
+    worker: Arc<SCloudWorker>,
+    default: Option<Arc<Forwarder>>,
     mut rx: Vec<mpsc::Receiver<InFlightTask>>,
     tx: Vec<mpsc::Sender<InFlightTask>>,
 ) -> Result<(), SCloudException> {
-    loop {
-        for rx_channel in rx.iter_mut() {
-            while let Some(msg) = rx_channel.recv().await {
-                let mut current = Some(msg);
+    // one forwarder per forward zone, so the round robin state is shared
+    let mut zone_forwarders: HashMap<String, Arc<Forwarder>> = HashMap::new();
 
-                for tx_channel in tx.iter() {
-                    match tx_channel.try_send(current.take().unwrap()) {
-                        Ok(_) => break,
-                        Err(mpsc::error::TrySendError::Full(returned)) => {
-                            current = Some(returned);
-                        }
-                        Err(mpsc::error::TrySendError::Closed(_)) => {
-                            return Ok(());
-                        }
-                    }
-                }
+    while let Some(mut msg) = recv_any(&mut rx).await {
+        let forwarder = match msg.task.forwarder.as_ref() {
+            Some(cfg) => match zone_forwarders.get(&cfg.name) {
+                Some(forwarder) => Ok(forwarder.clone()),
+                None => Forwarder::from_config(cfg).map(|forwarder| {
+                    let forwarder = Arc::new(forwarder);
+                    zone_forwarders.insert(cfg.name.clone(), forwarder.clone());
+                    forwarder
+                }),
+            },
+            None => default
+                .clone()
+                .ok_or(SCloudException::SCLOUD_CONFIG_MISSING_FORWARDER),
+        };
 
-                if let Some(unsent) = current {
-                    if tx[0].send(unsent).await.is_err() {
-                        return Ok(());
-                    }
+        let worker = worker.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let reply = match forwarder {
+                Ok(forwarder) => forwarder.forward(&msg.task.payload).await,
+                Err(e) => Err(e),
+            };
+            match reply.and_then(|reply| decode_reply(&reply, msg.task.query.as_ref())) {
+                Ok(response) => {
+                    msg.task.response = Some(response);
+                    worker.jobs_done.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(e) => {
+                    log_debug!(
+                        "resolver: task {} not resolved: {}",
+                        msg.task.task_id,
+                        e.to_str()
+                    );
+                    msg.task.response = msg
+                        .task
+                        .query
+                        .as_ref()
+                        .map(|query| DNSPacket::new_response(query, Header::RCODE_SERVFAIL));
+                    worker.jobs_failed.fetch_add(1, Ordering::Relaxed);
                 }
             }
+            forward_task(msg, &tx).await;
+        });
+    }
+    Ok(())
+}
+
+/// Decode an upstream `reply` to `query`.
+///
+/// # Errors
+/// Returns the error of `DNSPacket::from_bytes`, or
+/// `SCLOUD_RESOLVER_RESPONSE_MISMATCH` if the reply is not a response to
+/// the question of `query`.
+fn decode_reply(reply: &[u8], query: Option<&DNSPacket>) -> Result<DNSPacket, SCloudException> {
+    let reply = DNSPacket::from_bytes(reply)?;
+    let Some(query) = query else {
+        return Err(SCloudException::SCLOUD_RESOLVER_RESPONSE_MISMATCH);
+    };
+    let answers = match (reply.questions.first(), query.questions.first()) {
+        (Some(r), Some(q)) => {
+            reply.header.qr
+                && reply.header.id == query.header.id
+                && r.q_type == q.q_type
+                && r.q_class == q.q_class
+                && r.q_name.eq_ignore_ascii_case(&q.q_name)
         }
+        _ => false,
+    };
+    if !answers {
+        return Err(SCloudException::SCLOUD_RESOLVER_RESPONSE_MISMATCH);
     }
+    Ok(reply)
 }
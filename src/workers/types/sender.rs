@@ -1,36 +1,55 @@
 use crate::exceptions::SCloudException;
 use crate::workers::SCloudWorker;
+use crate::workers::queue::recv_any;
 use crate::workers::reply_registry;
 use crate::workers::task::InFlightTask;
-use crate::log_debug;
+use crate::{log_debug, log_error};
 use std::sync::Arc;
+use std::sync::atomic::Ordering;
 use tokio::sync::mpsc;
 
 pub async fn run_dns_sender(
     worker: Arc<SCloudWorker>,
     mut rx: Vec<mpsc::Receiver<InFlightTask>>,
 ) -> Result<(), SCloudException> {
-    let _ = worker;
-    loop {
-        for rx_channel in rx.iter_mut() {
-            while let Some(msg) = rx_channel.recv().await {
-                let tag = msg.task.reply_to.as_deref().unwrap_or("");
-                match tag {
-                    reply_registry::REPLY_TAG_DOH => {
-                        if let Some(sender) = reply_registry::take(&msg.task.task_id) {
-                            let _ = sender.send(msg.task.payload.clone());
-                        } else {
-                            log_debug!(
-                                "sender: no registered reply channel for task {}",
-                                msg.task.task_id
-                            );
-                        }
-                    }
-                    _ => {
-                        // TODO: UDP / TCP reply path — not implemented yet.
-                    }
+    while let Some(msg) = recv_any(&mut rx).await {
+        let tag = msg.task.reply_to.as_deref().unwrap_or("");
+        match tag {
+            reply_registry::REPLY_TAG_DOH
+            | reply_registry::REPLY_TAG_TCP
+            | reply_registry::REPLY_TAG_DOQ => {
+                if let Some(sender) = reply_registry::take(&msg.task.task_id) {
+                    let _ = sender.send(msg.task.payload.clone());
+                } else {
+                    log_debug!(
+                        "sender: no registered reply channel for task {}",
+                        msg.task.task_id
+                    );
                 }
             }
+            reply_registry::REPLY_TAG_UDP => {
+                let Some(socket) = msg.task.udp_socket.as_ref() else {
+                    log_debug!("sender: udp task {} has no socket", msg.task.task_id);
+                    worker.jobs_failed.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                };
+                if let Err(e) = socket.send_to(&msg.task.payload, msg.task.for_who).await {
+                    log_error!("sender: udp send to {} failed: {}", msg.task.for_who, e);
+                    worker.jobs_failed.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
+            }
+            _ => {
+                log_debug!(
+                    "sender: unknown reply tag {:?} for task {}",
+                    tag,
+                    msg.task.task_id
+                );
+                worker.jobs_failed.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
         }
+        worker.jobs_done.fetch_add(1, Ordering::Relaxed);
     }
+    Ok(())
 }
@@ -1,55 +1,519 @@
 use super::listener::run_dns_listener_with_socket;
+use crate::config::{Config, Protocol as ListenerProtocol};
 use crate::exceptions::SCloudException;
-use crate::workers::SCloudWorker;
-use crate::workers::task::InFlightTask;
+use crate::utils;
+use crate::utils::tls::TlsCertStore;
+use crate::workers::queue::forward_task;
+use crate::workers::task::{InFlightTask, SCloudWorkerTask};
+use crate::workers::{SCloudWorker, WorkerType, reply_registry};
+use crate::{log_debug, log_error, log_info};
+use bytes::Bytes;
+use dashmap::DashMap;
+use futures_util::StreamExt;
+use futures_util::stream::FuturesUnordered;
+use once_cell::sync::Lazy;
 use socket2::{Domain, Protocol, Socket, Type};
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
 use std::sync::Arc;
-use tokio::net::UdpSocket;
-use tokio::sync::mpsc;
+use std::time::SystemTime;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, UdpSocket};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{Duration, Instant, sleep, sleep_until, timeout};
+use tokio_rustls::TlsAcceptor;
+use uuid::Uuid;
 
-#[cfg(target_os = "windows")]
-use super::listener::run_dns_listener_with_shared_socket;
+// RFC 7766 section 6.2.3: idle connections are closed after a few seconds,
+// unless `server.tcp_idle_timeout_secs` says otherwise
+const DEFAULT_TCP_IDLE_TIMEOUT_SECS: u64 = 10;
+// pause after a failed accept, so that running out of file descriptors
+// does not spin the acceptor
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+// once the length prefix is in, the rest of the message must follow
+const TCP_READ_TIMEOUT: Duration = Duration::from_secs(5);
+const TCP_WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+// pipelined queries of one connection waiting for their reply
+const MAX_PIPELINED_QUERIES: usize = 64;
+// RFC 7858 section 3.2 ALPN protocol ID
+const DOT_ALPN: &[u8] = b"dot";
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+// how often the certificate files are checked for changes
+const TLS_RELOAD_EVERY: Duration = Duration::from_secs(60);
 
+static TCP_SESSIONS: Lazy<Arc<TcpSessions>> = Lazy::new(|| Arc::new(TcpSessions::default()));
+
+/// Open TCP connections per client address, shared by every acceptor.
+#[derive(Debug, Default)]
+pub(crate) struct TcpSessions {
+    by_ip: DashMap<IpAddr, usize>,
+}
+
+impl TcpSessions {
+    /// Count one more connection from `ip`, unless it already has `max`
+    /// (0 means no limit).
+    pub(crate) fn open(self: &Arc<Self>, ip: IpAddr, max: usize) -> Option<TcpSession> {
+        let mut count = self.by_ip.entry(ip).or_insert(0);
+        if max > 0 && *count >= max {
+            return None;
+        }
+        *count += 1;
+        Some(TcpSession {
+            sessions: self.clone(),
+            ip,
+        })
+    }
+
+    #[allow(unused)]
+    pub(crate) fn count(&self, ip: &IpAddr) -> usize {
+        self.by_ip.get(ip).map(|c| *c).unwrap_or(0)
+    }
+}
+
+/// Limits applied to every TCP connection.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TcpLimits {
+    pub max_sessions_per_ip: usize,
+    pub idle_timeout: Duration,
+}
+
+impl TcpLimits {
+    pub(crate) fn from_config(cfg: &Config) -> TcpLimits {
+        TcpLimits {
+            max_sessions_per_ip: cfg.limits.max_tcp_sessions_per_ip,
+            idle_timeout: Duration::from_secs(
+                cfg.server
+                    .tcp_idle_timeout_secs
+                    .unwrap_or(DEFAULT_TCP_IDLE_TIMEOUT_SECS),
+            ),
+        }
+    }
+}
+
+/// One open connection; it is uncounted when dropped.
+pub(crate) struct TcpSession {
+    sessions: Arc<TcpSessions>,
+    ip: IpAddr,
+}
+
+impl Drop for TcpSession {
+    fn drop(&mut self) {
+        self.sessions.by_ip.remove_if_mut(&self.ip, |_, count| {
+            *count -= 1;
+            *count == 0
+        });
+    }
+}
+
+/// Serve the listener assigned to `worker`: plain DNS over UDP and/or TCP
+/// on its address and port, according to its `protocols`, or DNS over TLS
+/// when the listener has `enable_tls`.
+///
+/// Every acceptor of a listener binds the same address with SO_REUSEPORT,
+/// so the kernel spreads the clients over them.
 pub async fn run_dns_tcp_acceptor(
     worker: Arc<SCloudWorker>,
     tx: Vec<mpsc::Sender<InFlightTask>>,
 ) -> Result<(), SCloudException> {
-    #[cfg(not(target_os = "windows"))]
-    {
-        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))
-            .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_CREATION_FAILED)?;
+    let cfg = Config::from_file(Path::new("./config/config.json"))?;
+    let listener = worker
+        .get_listener()
+        .ok_or(SCloudException::SCLOUD_WORKER_TCPA_NO_LISTENER)?;
+    let addr = listener.socket_addr()?;
 
+    if listener.is_tls() {
+        let certs = Arc::new(TlsCertStore::load(
+            listener.tls_cert_path.as_deref().unwrap_or_default(),
+            listener.tls_key_path.as_deref().unwrap_or_default(),
+        )?);
+        let tls = TlsAcceptor::from(certs.server_config(&[DOT_ALPN])?);
+        let socket = bind_tcp(addr)?;
+        log_info!("listener {} on {} (tls)", listener.name, addr);
+
+        let dot = run_dns_dot_listener(
+            worker,
+            socket,
+            tls,
+            TCP_SESSIONS.clone(),
+            TcpLimits::from_config(&cfg),
+            tx,
+        );
+        return tokio::select! {
+            res = dot => res,
+            res = certs.watch(TLS_RELOAD_EVERY) => res,
+        };
+    }
+
+    let udp = match listener.has_protocol(ListenerProtocol::UDP) {
+        true => Some(bind_udp(addr)?),
+        false => None,
+    };
+    let tcp = match listener.has_protocol(ListenerProtocol::TCP) && cfg.server.enable_tcp {
+        true => Some(bind_tcp(addr)?),
+        false => None,
+    };
+    if udp.is_none() && tcp.is_none() {
+        return Err(SCloudException::SCLOUD_CONFIG_INVALID_LISTENER_PROTOCOLS);
+    }
+    log_info!(
+        "listener {} on {} (udp: {}, tcp: {})",
+        listener.name,
+        addr,
+        udp.is_some(),
+        tcp.is_some()
+    );
+
+    let udp = async {
+        match udp {
+            Some(socket) => {
+                run_dns_listener_with_socket(worker.clone(), socket, vec![], tx.clone()).await
+            }
+            None => std::future::pending().await,
+        }
+    };
+    let tcp = async {
+        match tcp {
+            Some(socket) => {
+                run_dns_tcp_listener(
+                    worker.clone(),
+                    socket,
+                    TCP_SESSIONS.clone(),
+                    TcpLimits::from_config(&cfg),
+                    tx.clone(),
+                )
+                .await
+            }
+            None => std::future::pending().await,
+        }
+    };
+
+    tokio::select! {
+        res = udp => res,
+        res = tcp => res,
+    }
+}
+
+/// Bind a UDP socket shared with the other acceptors of the listener.
+pub(crate) fn bind_udp(addr: SocketAddr) -> Result<UdpSocket, SCloudException> {
+    let socket = Socket::new(Domain::for_address(addr), Type::DGRAM, Some(Protocol::UDP))
+        .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_CREATION_FAILED)?;
+    // an IPv6 listener only takes IPv6, so `::` and `0.0.0.0` can share a port
+    if addr.is_ipv6() {
         socket
-            .set_reuse_port(true)
-            .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_CREATION_FAILED)?;
-        socket
-            .set_reuse_address(true)
-            .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_CREATION_FAILED)?;
-        socket
-            .set_nonblocking(true)
-            .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_CREATION_FAILED)?;
-        socket
-            .set_recv_buffer_size(16 * 1024 * 1024)
+            .set_only_v6(true)
             .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_CREATION_FAILED)?;
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    socket
+        .set_reuse_port(true)
+        .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_CREATION_FAILED)?;
+    socket
+        .set_reuse_address(true)
+        .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_CREATION_FAILED)?;
+    socket
+        .set_nonblocking(true)
+        .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_CREATION_FAILED)?;
+    socket
+        .set_recv_buffer_size(16 * 1024 * 1024)
+        .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_CREATION_FAILED)?;
+    socket
+        .set_send_buffer_size(16 * 1024 * 1024)
+        .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_CREATION_FAILED)?;
+    socket
+        .bind(&addr.into())
+        .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_BIND_FAILED)?;
+
+    let std_socket: std::net::UdpSocket = socket.into();
+    UdpSocket::from_std(std_socket)
+        .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_CREATION_FAILED)
+}
+
+/// Bind a TCP socket shared with the other acceptors of the listener.
+pub(crate) fn bind_tcp(addr: SocketAddr) -> Result<TcpListener, SCloudException> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))
+        .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_CREATION_FAILED)?;
+    if addr.is_ipv6() {
         socket
-            .set_send_buffer_size(16 * 1024 * 1024)
+            .set_only_v6(true)
             .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_CREATION_FAILED)?;
+    }
 
-        let addr: SocketAddr = "0.0.0.0:5353".parse().unwrap();
-        socket
-            .bind(&addr.into())
-            .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_BIND_FAILED)?;
+    #[cfg(not(target_os = "windows"))]
+    socket
+        .set_reuse_port(true)
+        .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_CREATION_FAILED)?;
+    socket
+        .set_reuse_address(true)
+        .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_CREATION_FAILED)?;
+    socket
+        .set_nonblocking(true)
+        .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_CREATION_FAILED)?;
+    socket
+        .bind(&addr.into())
+        .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_BIND_FAILED)?;
+    socket
+        .listen(1024)
+        .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_BIND_FAILED)?;
 
-        let std_socket: std::net::UdpSocket = socket.into();
-        let udp = UdpSocket::from_std(std_socket)
-            .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_CREATION_FAILED)?;
+    let std_listener: std::net::TcpListener = socket.into();
+    TcpListener::from_std(std_listener)
+        .map_err(|_| SCloudException::SCLOUD_WORKER_TCPA_SOCKET_CREATION_FAILED)
+}
+
+/// Accept DNS over TCP connections (RFC 7766) on `listener`.
+///
+/// Every connection gets its own task. A client already holding
+/// `max_sessions_per_ip` connections has the new one closed right away.
+pub(crate) async fn run_dns_tcp_listener(
+    worker: Arc<SCloudWorker>,
+    listener: TcpListener,
+    sessions: Arc<TcpSessions>,
+    limits: TcpLimits,
+    tx: Vec<mpsc::Sender<InFlightTask>>,
+) -> Result<(), SCloudException> {
+    accept_connections(worker, listener, None, sessions, limits, tx).await
+}
+
+/// Accept DNS over TLS connections (RFC 7858) on `listener`.
+///
+/// Once the handshake is done, a connection is served like a plain TCP one
+/// and counts in the same per-client session limit.
+pub(crate) async fn run_dns_dot_listener(
+    worker: Arc<SCloudWorker>,
+    listener: TcpListener,
+    tls: TlsAcceptor,
+    sessions: Arc<TcpSessions>,
+    limits: TcpLimits,
+    tx: Vec<mpsc::Sender<InFlightTask>>,
+) -> Result<(), SCloudException> {
+    accept_connections(worker, listener, Some(tls), sessions, limits, tx).await
+}
 
-        run_dns_listener_with_socket(worker, udp, vec![], tx).await
+async fn accept_connections(
+    worker: Arc<SCloudWorker>,
+    listener: TcpListener,
+    tls: Option<TlsAcceptor>,
+    sessions: Arc<TcpSessions>,
+    limits: TcpLimits,
+    tx: Vec<mpsc::Sender<InFlightTask>>,
+) -> Result<(), SCloudException> {
+    if tx.is_empty() {
+        return Err(SCloudException::SCLOUD_WORKER_TX_NOT_SET);
     }
 
-    #[cfg(target_os = "windows")]
-    {
-        run_dns_listener_with_shared_socket(worker, tx).await
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(v) => v,
+            Err(e) => {
+                log_error!("tcp accept failed: {}", e);
+                sleep(ACCEPT_ERROR_BACKOFF).await;
+                continue;
+            }
+        };
+        let Some(session) = sessions.open(peer.ip(), limits.max_sessions_per_ip) else {
+            log_debug!(
+                "tcp: too many sessions from {}, connection closed",
+                peer.ip()
+            );
+            continue;
+        };
+
+        let worker = worker.clone();
+        let tls = tls.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            match tls {
+                None => serve_connection(worker, stream, peer, limits.idle_timeout, tx).await,
+                Some(tls) => match timeout(TLS_HANDSHAKE_TIMEOUT, tls.accept(stream)).await {
+                    Ok(Ok(stream)) => {
+                        serve_connection(worker, stream, peer, limits.idle_timeout, tx).await
+                    }
+                    Ok(Err(e)) => {
+                        log_debug!("dot: handshake with {} failed: {}", peer, e);
+                    }
+                    Err(_) => {
+                        log_debug!("dot: handshake with {} timed out", peer);
+                    }
+                },
+            }
+            drop(session);
+        });
+    }
+}
+
+/// Serve the queries of one connection until it is closed or idle.
+///
+/// Queries are pipelined: each one is dispatched as soon as it is read, and
+/// replies are written back in the order they come out of the pipeline. The
+/// client matches them with the message ID (RFC 7766 section 6.2.1.1). The
+/// connection is idle once no query is pending and none has come in for
+/// `idle_timeout`.
+pub(crate) async fn serve_connection<S>(
+    worker: Arc<SCloudWorker>,
+    stream: S,
+    peer: SocketAddr,
+    idle_timeout: Duration,
+    tx: Vec<mpsc::Sender<InFlightTask>>,
+) where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    // reading runs in its own task, as a half-read frame cannot be resumed
+    let (frames_tx, mut frames_rx) = mpsc::channel::<Bytes>(MAX_PIPELINED_QUERIES);
+    let reader = tokio::spawn(read_frames(reader, frames_tx));
+
+    let mut pending = FuturesUnordered::new();
+    let mut reading = true;
+    let mut idle_deadline = Instant::now() + idle_timeout;
+
+    while reading || !pending.is_empty() {
+        tokio::select! {
+            frame = frames_rx.recv(), if reading && pending.len() < MAX_PIPELINED_QUERIES => {
+                match frame {
+                    Some(query) => {
+                        if let Some(reply) = dispatch(&worker, peer, query, &tx).await {
+                            pending.push(reply);
+                        }
+                    }
+                    None => reading = false,
+                }
+            }
+            Some(reply) = pending.next() => {
+                let Some(reply) = reply else { continue };
+                if let Err(e) = write_frame(&mut writer, &reply).await {
+                    log_debug!("tcp: write to {} failed: {}", peer, e);
+                    break;
+                }
+            }
+            _ = sleep_until(idle_deadline), if pending.is_empty() => {
+                log_debug!("tcp: idle connection from {} closed", peer);
+                break;
+            }
+        }
+        idle_deadline = Instant::now() + idle_timeout;
+    }
+
+    reader.abort();
+    let _ = writer.shutdown().await;
+}
+
+/// Read length-prefixed messages until the connection is closed, a message
+/// is cut short, or `frames` is dropped.
+async fn read_frames<R>(mut reader: R, frames: mpsc::Sender<Bytes>)
+where
+    R: AsyncRead + Unpin,
+{
+    loop {
+        let Ok(len) = reader.read_u16().await else {
+            return;
+        };
+        if len == 0 {
+            return;
+        }
+        let mut buf = vec![0u8; len as usize];
+        match timeout(TCP_READ_TIMEOUT, reader.read_exact(&mut buf)).await {
+            Ok(Ok(_)) => {}
+            _ => return,
+        }
+        if frames.send(Bytes::from(buf)).await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn write_frame<W>(writer: &mut W, message: &[u8]) -> std::io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let Ok(len) = u16::try_from(message.len()) else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "dns message longer than 65535 bytes",
+        ));
+    };
+    let mut frame = Vec::with_capacity(message.len() + 2);
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(message);
+    match timeout(TCP_WRITE_TIMEOUT, writer.write_all(&frame)).await {
+        Ok(res) => res,
+        Err(_) => Err(std::io::ErrorKind::TimedOut.into()),
+    }
+}
+
+/// Hand one query to the pipeline and return the future of its reply.
+///
+/// Returns `None` when the query is dropped: worker at capacity, or the
+/// pipeline is closed.
+async fn dispatch(
+    worker: &Arc<SCloudWorker>,
+    peer: SocketAddr,
+    query: Bytes,
+    tx: &[mpsc::Sender<InFlightTask>],
+) -> Option<impl Future<Output = Option<Bytes>> + use<>> {
+    let Ok(permit) = worker.in_flight_sem.clone().try_acquire_owned() else {
+        log_debug!("tcp: worker at capacity, query from {} dropped", peer);
+        return None;
+    };
+    let id = query.get(..2).map(|id| [id[0], id[1]]);
+
+    let task_id = utils::uuid::generate_uuid();
+    let task = SCloudWorkerTask {
+        task_id,
+        for_type: WorkerType::TCP_ACCEPTOR,
+        for_who: peer,
+        payload: query,
+        attempts: 0,
+        max_attempts: 0,
+        created_at: SystemTime::now(),
+        deadline_timeout: None,
+        priority: 0,
+        reply_to: Some(reply_registry::REPLY_TAG_TCP.to_string()),
+        correlation_id: None,
+        udp_socket: None,
+        query: None,
+        response: None,
+        forwarder: None,
+        listener: worker.get_listener(),
+    };
+    let in_flight = InFlightTask {
+        task,
+        _permit: permit,
+    };
+
+    let rx = reply_registry::register(task_id);
+    let pending = PendingReply(task_id);
+    if !forward_task(in_flight, tx).await {
+        return None;
+    }
+    Some(wait_reply(pending, id, rx))
+}
+
+/// Reply channel of a dispatched query, unregistered when dropped: the
+/// pipeline may drop the task, and the connection may close before the
+/// reply comes.
+struct PendingReply(Uuid);
+
+impl Drop for PendingReply {
+    fn drop(&mut self) {
+        reply_registry::drop_entry(&self.0);
+    }
+}
+
+/// Wait for the reply of the `pending` task, and check it carries the
+/// message ID of the query.
+async fn wait_reply(
+    pending: PendingReply,
+    id: Option<[u8; 2]>,
+    rx: oneshot::Receiver<Bytes>,
+) -> Option<Bytes> {
+    match timeout(REPLY_TIMEOUT, rx).await {
+        Ok(Ok(reply)) if reply.get(..2) == id.as_ref().map(|id| &id[..]) => Some(reply),
+        Ok(Ok(_)) => {
+            log_debug!("tcp: reply to task {} has another message ID", pending.0);
+            None
+        }
+        _ => None,
     }
 }
@@ -1,37 +1,67 @@
+use crate::config::Config;
+use crate::dns::packet::DNSPacket;
+use crate::dns::packet::header::Header;
+use crate::dns::zones::manager::ZoneManager;
 use crate::exceptions::SCloudException;
 use crate::workers::SCloudWorker;
+use crate::workers::queue::{forward_task, recv_any};
 use crate::workers::task::InFlightTask;
+use crate::{log_debug, log_error};
+use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::Ordering;
 use tokio::sync::mpsc;
 
 pub async fn run_dns_zone_manager(
     worker: Arc<SCloudWorker>,
-    mut rx: Vec<mpsc::Receiver<InFlightTask>>,
+    rx: Vec<mpsc::Receiver<InFlightTask>>,
     tx: Vec<mpsc::Sender<InFlightTask>>,
 ) -> Result<(), SCloudException> {
-    loop {
-        for rx_channel in rx.iter_mut() {
-            while let Some(msg) = rx_channel.recv().await {
-                let mut current = Some(msg);
+    let cfg = Config::from_file(Path::new("./config/config.json"))?;
+    let zones = ZoneManager::from_config(&cfg.zone);
+    run_dns_zone_manager_with_zones(worker, Arc::new(zones), rx, tx).await
+}
 
-                for tx_channel in tx.iter() {
-                    match tx_channel.try_send(current.take().unwrap()) {
-                        Ok(_) => break,
-                        Err(mpsc::error::TrySendError::Full(returned)) => {
-                            current = Some(returned);
-                        }
-                        Err(mpsc::error::TrySendError::Closed(_)) => {
-                            return Ok(());
-                        }
-                    }
+/// Answer every query of `rx` from `zones` and hand the response over to `tx`.
+///
+/// A task without a decoded query is dropped: its payload is the query
+/// itself, which must never be sent back as a response.
+pub async fn run_dns_zone_manager_with_zones(
+    worker: Arc<SCloudWorker>,
+    zones: Arc<ZoneManager>,
+    mut rx: Vec<mpsc::Receiver<InFlightTask>>,
+    tx: Vec<mpsc::Sender<InFlightTask>>,
+) -> Result<(), SCloudException> {
+    while let Some(mut msg) = recv_any(&mut rx).await {
+        if let Some(query) = msg.task.query.as_ref() {
+            let response = match zones.answer(query) {
+                Ok(response) => {
+                    worker.jobs_done.fetch_add(1, Ordering::Relaxed);
+                    response
                 }
-
-                if let Some(unsent) = current {
-                    if tx[0].send(unsent).await.is_err() {
-                        return Ok(());
-                    }
+                Err(e) => {
+                    log_error!(
+                        "zone manager: task {} failed: {}",
+                        msg.task.task_id,
+                        e.to_str()
+                    );
+                    worker.jobs_failed.fetch_add(1, Ordering::Relaxed);
+                    DNSPacket::new_response(query, Header::RCODE_SERVFAIL)
                 }
-            }
+            };
+            msg.task.response = Some(response);
+        } else {
+            log_debug!(
+                "zone manager: task {} has no decoded query, dropped",
+                msg.task.task_id
+            );
+            worker.jobs_failed.fetch_add(1, Ordering::Relaxed);
+            continue;
+        }
+
+        if !forward_task(msg, &tx).await {
+            return Ok(());
         }
     }
+    Ok(())
 }